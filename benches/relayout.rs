@@ -0,0 +1,73 @@
+//! Compares the full layouting to the re-layouting on window resizes
+//!
+//! Run with `cargo bench --features testing`.
+#[macro_use]
+extern crate pugl_ui;
+
+use std::time::{Duration, Instant};
+
+use pugl_sys::*;
+use pugl_ui::layout::stacklayout::*;
+use pugl_ui::ui::*;
+use pugl_ui::widget::*;
+
+const ROWS: usize = 30;
+const COLUMNS: usize = 20;
+const ROUNDS: u32 = 200;
+
+#[derive(Default)]
+struct RootWidget {
+    stub: WidgetStub
+}
+
+impl Widget for RootWidget {
+    widget_stub!();
+}
+
+#[derive(Default)]
+struct Cell {
+    stub: WidgetStub
+}
+
+impl Widget for Cell {
+    widget_stub!();
+    fn min_size(&self) -> Size { Size { w: 12., h: 8. } }
+}
+
+fn build_ui(ui: &mut UI<RootWidget>) {
+    for _ in 0..ROWS {
+        let row = ui.new_layouter::<HorizontalLayouter>();
+        ui.pack_to_layout(row.widget(), ui.root_layout(), StackDirection::Back);
+        for _ in 0..COLUMNS {
+            let cell = ui.new_widget(Box::new(Cell::default()));
+            ui.pack_to_layout(cell, row, StackDirection::Back);
+        }
+    }
+    ui.do_layout();
+}
+
+fn measure<F: FnMut(&mut UI<RootWidget>, u32)>(ui: &mut UI<RootWidget>, mut f: F) -> Duration {
+    let start = Instant::now();
+    for round in 0..ROUNDS {
+        f(ui, round);
+    }
+    start.elapsed() / ROUNDS
+}
+
+fn main() {
+    let rw = Box::new(RootWidget::default());
+    let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new(pv, rw));
+    let ui = view.handle();
+    build_ui(ui);
+
+    let size = |round: u32| Size { w: 400. + f64::from(round % 50), h: 300. };
+    let full = measure(ui, |ui, round| {
+        ui.root_widget().set_size(&size(round));
+        ui.do_layout();
+    });
+    let partial = measure(ui, |ui, round| ui.resize(size(round)));
+
+    println!("{} widgets", ROWS * COLUMNS + ROWS);
+    println!("full layouting:       {:?} per layout", full);
+    println!("layouting on resize:  {:?} per layout", partial);
+}
@@ -16,7 +16,7 @@ pub struct Button {
 impl Widget for Button {
     widget_stub!();
 
-    fn exposed (&mut self, _expose: &ExposeArea, cr: &cairo::Context) {
+    fn exposed (&mut self, ctx: &PaintContext, cr: &cairo::Context) {
         let (r, g, b) = (0.7, 0.7, 0.7);
         let size = self.size();
         let pos = self.pos();
@@ -30,8 +30,8 @@ impl Widget for Button {
         cr.save();
         cr.translate(pos.x, pos.y);
 
-        let ctx = pangocairo::functions::create_context (&cr).unwrap();
-        let lyt = pango::Layout::new (&ctx);
+        let pctx = pangocairo::functions::create_context (&cr).unwrap();
+        let lyt = pango::Layout::new (&pctx);
 
         let font_desc = pango::FontDescription::from_string ("Sans 24px");
 
@@ -43,9 +43,7 @@ impl Widget for Button {
         cr.restore();
 
         if self.has_focus() {
-            cr.set_source_rgb (1., 1., 1.);
-            cr.rectangle(pos.x, pos.y, size.w, size.h);
-            cr.stroke();
+            ctx.draw_focus_ring(cr, pos, size);
         }
     }
     fn event (&mut self, ev: Event) -> Option<Event> {
@@ -76,20 +74,8 @@ impl Widget for Button {
 }
 
 impl Button {
-    pub fn new (text: &str) -> Box<Button> {
-        let sf = cairo::ImageSurface::create (cairo::Format::ARgb32, 8, 8).unwrap();
-        let cr = cairo::Context::new (&sf);
-
-        let ctx = pangocairo::functions::create_context (&cr).unwrap();
-        let lyt = pango::Layout::new (&ctx);
-
-        let font_desc = pango::FontDescription::from_string ("Sans 24px");
-
-        lyt.set_font_description (Some(&font_desc));
-        lyt.set_text (text);
-
-        let (w, h) = lyt.get_pixel_size();
-        let min_size: Size = Size { w: w.into(), h: h.into() };
+    pub fn new (ctx: &UiContext, text: &str) -> Box<Button> {
+        let min_size = ctx.text_metrics().measure(text, "Sans 24px");
 
         Box::new(Button {
             stub: WidgetStub::default(),
@@ -29,7 +29,7 @@ impl Dial {
 impl Widget for Dial {
     widget_stub!();
 
-    fn exposed (&mut self, _exposed: &ExposeArea, cr: &cairo::Context) {
+    fn exposed (&mut self, _ctx: &PaintContext, cr: &cairo::Context) {
 
         let pos = self.pos() + Coord { x: self.radius, y: self.radius };
 
@@ -76,7 +76,8 @@ impl Widget for Dial {
     fn event(&mut self, ev: Event) -> Option<Event> {
         match ev.data {
             EventType::Scroll (sc) => {
-                let nv = self.value + sc.dy.signum() * self.step;
+                let step = self.step * self.interaction_settings().scroll_step;
+                let nv = self.value + sc.dy.signum() * step;
                 let new_value = match nv {
                     v if v > self.max_value => self.max_value,
                     v if v < self.min_value => self.min_value,
@@ -22,7 +22,7 @@ struct RootWidget {
 impl Widget for RootWidget {
     widget_stub!();
 
-    fn exposed (&mut self, _expose: &ExposeArea, cr: &cairo::Context) {
+    fn exposed (&mut self, _ctx: &PaintContext, cr: &cairo::Context) {
         cr.set_source_rgb (0., 1., 0.);
         let size = self.size();
         cr.rectangle (0., 0., size.w, size.h);
@@ -63,7 +63,7 @@ fn main() {
     let dial2 = ui.new_widget(dial::Dial::new(0.0, 1.0, 0.1));
     let dial3 = ui.new_widget(dial::Dial::new(0.0, 1.0, 0.1));
 
-    let reset_button = ui.new_widget(button::Button::new("Reset"));
+    let reset_button = ui.new_widget_with_ctx(|ctx| button::Button::new(ctx, "Reset"));
 
     println!("starting layouts");
 
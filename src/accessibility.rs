@@ -0,0 +1,168 @@
+//! Describing the UI to assistive technologies
+//!
+//! Widgets describe themselves by
+//! [`Widget::accessible()`](../widget/trait.Widget.html#method.accessible)
+//! with a [`Role`](enum.Role.html), a name, a value and the
+//! [`Action`](enum.Action.html)s they support. The `UI` assembles
+//! the descriptions of the shown widgets to a tree of
+//! [`AccessibleNode`](struct.AccessibleNode.html)s by
+//! [`UI::accessibility_tree()`](../ui/struct.UI.html#method.accessibility_tree)
+//! and performs actions by
+//! [`UI::perform_accessible_action()`](../ui/struct.UI.html#method.perform_accessible_action).
+//!
+//! These are the interface for platform bridges, which poll the tree
+//! after every event loop cycle and pass the requests of screen
+//! readers on to the `UI`. With the feature `atspi` the bridge
+//! [`AtspiBridge`](../atspi/struct.AtspiBridge.html) exposes the tree
+//! over AT-SPI on Linux. It is behind a feature, as it pulls a D-Bus
+//! implementation into the plugin GUI.
+use crate::widget::{Id, Layout};
+
+/// The role of a widget for assistive technologies
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// The window, the role of the root of the tree
+    Window,
+    /// A container grouping related widgets, like a frame
+    Group,
+    /// A push button
+    Button,
+    /// A button switching between on and off
+    ToggleButton,
+    /// A check box
+    CheckBox,
+    /// A linear slider or fader
+    Slider,
+    /// A rotary knob
+    Dial,
+    /// A field to enter a number, usually with up/down buttons
+    SpinButton,
+    /// A static text
+    Label,
+    /// A field to enter text
+    TextEntry,
+    /// A selection out of a list of options
+    ComboBox,
+    /// A display of a value that the user can not change, like a meter
+    Indicator,
+    /// A widget not fitting into any of the other roles
+    Other
+}
+
+/// An action assistive technologies can perform on a widget
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Clicks a button or toggles a switch
+    Activate,
+    /// Increases a value by a step
+    Increment,
+    /// Decreases a value by a step
+    Decrement,
+    /// Moves the keyboard focus to the widget
+    Focus
+}
+
+/// The description of a widget for assistive technologies
+#[derive(Clone, Debug, PartialEq)]
+pub struct Accessible {
+    /// The role of the widget
+    pub role: Role,
+    /// The name of the widget as read out by the screen reader, like "Cutoff"
+    pub name: String,
+    /// The current value of the widget in a human readable form, like "440 Hz"
+    pub value: Option<String>,
+    /// The actions the widget supports
+    pub actions: Vec<Action>
+}
+
+impl Accessible {
+    /// Creates a description with the role `role` and the name `name`
+    /// without a value and without actions.
+    pub fn new(role: Role, name: &str) -> Accessible {
+        Accessible {
+            role,
+            name: String::from(name),
+            value: None,
+            actions: Vec::new()
+        }
+    }
+
+    /// Sets the value of the description.
+    pub fn with_value(mut self, value: &str) -> Accessible {
+        self.value = Some(String::from(value));
+        self
+    }
+
+    /// Adds the action `action` to the description.
+    pub fn with_action(mut self, action: Action) -> Accessible {
+        self.actions.push(action);
+        self
+    }
+}
+
+/// A node of the tree returned by
+/// [`UI::accessibility_tree()`](../ui/struct.UI.html#method.accessibility_tree)
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccessibleNode {
+    /// The id of the described widget
+    pub id: Id,
+    /// The description of the widget
+    pub accessible: Accessible,
+    /// The geometry of the widget in window pixels
+    pub bounds: Layout,
+    /// `true` iff the widget has the keyboard focus
+    pub focused: bool,
+    /// `true` iff the widget is sensitive
+    pub sensitive: bool,
+    /// The accessible widgets inside the widget
+    pub children: Vec<AccessibleNode>
+}
+
+impl AccessibleNode {
+    /// Returns the node of the widget `id` in this subtree, if any.
+    pub fn find(&self, id: Id) -> Option<&AccessibleNode> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.children.iter().find_map(|c| c.find(id))
+    }
+
+    /// Returns the number of nodes in this subtree.
+    pub fn count(&self) -> usize {
+        1 + self.children.iter().map(AccessibleNode::count).sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: Id, children: Vec<AccessibleNode>) -> AccessibleNode {
+        AccessibleNode {
+            id,
+            accessible: Accessible::new(Role::Group, "group"),
+            bounds: Layout::default(),
+            focused: false,
+            sensitive: true,
+            children
+        }
+    }
+
+    #[test]
+    fn find_in_tree() {
+        let tree = node(0, vec![node(1, vec![node(3, vec![])]), node(2, vec![])]);
+        assert_eq!(tree.count(), 4);
+        assert_eq!(tree.find(3).map(|n| n.id), Some(3));
+        assert!(tree.find(4).is_none());
+    }
+
+    #[test]
+    fn build_description() {
+        let acc = Accessible::new(Role::Dial, "Cutoff")
+            .with_value("440 Hz")
+            .with_action(Action::Increment)
+            .with_action(Action::Decrement);
+        assert_eq!(acc.value.as_deref(), Some("440 Hz"));
+        assert_eq!(acc.actions, vec![Action::Increment, Action::Decrement]);
+    }
+}
@@ -0,0 +1,387 @@
+//! Loading images once and caching them per scale factor
+//!
+//! Only available with the feature `cairo-helpers`.
+//!
+//! Knob strips, background images and icons are registered with the
+//! `UI`'s [`Assets`](struct.Assets.html) under a key, see
+//! [`UI::assets()`](../ui/struct.UI.html#method.assets). They are
+//! decoded once and rasterized once for every scale factor the `UI` is
+//! drawn at. Widgets get the rasterized images by the key from the
+//! [`PaintContext`](../widget/struct.PaintContext.html):
+//!
+//! ```ignore
+//! ui.assets().add_png_file("knob", "assets/knob@2x.png", 2.0)?;
+//!
+//! // in Widget::exposed()
+//! ctx.paint_image(cr, "knob", self.pos());
+//! ```
+//!
+//! PNG images are decoded by cairo. SVG images are rendered by
+//! `librsvg` and need the feature `svg`, as do the
+//! [`SvgSkin`](../skin/struct.SvgSkin.html)s rendering elements of
+//! SVG documents.
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use cairo::ImageSurface;
+use pugl_sys::*;
+
+use crate::panel::{self, Insets};
+#[cfg(feature = "svg")]
+use crate::skin::SvgSkin;
+use crate::widget::Layout;
+
+/// The key of a composed nine-patch: the image key, the insets, the
+/// size and the scale factor
+type NinePatchKey = (String, [u64; 4], [u64; 2], u64);
+
+/// Error returned when an image can not be added to the
+/// [`Assets`](struct.Assets.html)
+#[derive(Debug)]
+pub enum AssetError {
+    /// The image file could not be read.
+    Io(io::Error),
+    /// The PNG image could not be decoded.
+    Png(cairo::IoError),
+    /// The SVG document could not be loaded.
+    #[cfg(feature = "svg")]
+    Svg(String),
+    /// The image could not be rasterized at the current scale factor.
+    Raster
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetError::Io(err) => write!(f, "image not accessible: {}", err),
+            AssetError::Png(err) => write!(f, "PNG image not decodable: {}", err),
+            #[cfg(feature = "svg")]
+            AssetError::Svg(err) => write!(f, "SVG document not loadable: {}", err),
+            AssetError::Raster => write!(f, "image not rasterizable")
+        }
+    }
+}
+
+impl Error for AssetError {}
+
+impl From<io::Error> for AssetError {
+    fn from(err: io::Error) -> AssetError {
+        AssetError::Io(err)
+    }
+}
+
+impl From<cairo::IoError> for AssetError {
+    fn from(err: cairo::IoError) -> AssetError {
+        AssetError::Png(err)
+    }
+}
+
+/// A decoded image, to be rasterized at the scale factors
+enum Source {
+    Png { image: ImageSurface, native_scale: f64 },
+    #[cfg(feature = "svg")]
+    Svg { handle: librsvg::SvgHandle, size: Size }
+}
+
+impl Source {
+    fn size(&self) -> Size {
+        match self {
+            Source::Png { image, native_scale } => Size {
+                w: f64::from(image.get_width()) / native_scale,
+                h: f64::from(image.get_height()) / native_scale
+            },
+            #[cfg(feature = "svg")]
+            Source::Svg { size, .. } => *size
+        }
+    }
+
+    fn rasterize(&self, scale_factor: f64) -> Option<ImageSurface> {
+        match self {
+            Source::Png { image, native_scale } if *native_scale == scale_factor => Some(image.clone()),
+            Source::Png { image, native_scale } => {
+                let factor = scale_factor / native_scale;
+                let surface = new_surface(self.size(), scale_factor)?;
+                let cr = cairo::Context::new(&surface);
+                cr.scale(factor, factor);
+                cr.set_source_surface(image, 0., 0.);
+                cr.paint();
+                Some(surface)
+            }
+            #[cfg(feature = "svg")]
+            Source::Svg { handle, size } => {
+                let surface = new_surface(*size, scale_factor)?;
+                let cr = cairo::Context::new(&surface);
+                let viewport = cairo::Rectangle { x: 0., y: 0., width: size.w * scale_factor, height: size.h * scale_factor };
+                librsvg::CairoRenderer::new(handle).render_document(&cr, &viewport).ok()?;
+                Some(surface)
+            }
+        }
+    }
+}
+
+fn new_surface(size: Size, scale_factor: f64) -> Option<ImageSurface> {
+    ImageSurface::create(cairo::Format::ARgb32,
+                         ((size.w * scale_factor).ceil() as i32).max(1),
+                         ((size.h * scale_factor).ceil() as i32).max(1)).ok()
+}
+
+/// The images of a `UI`, rasterized per scale factor
+pub struct Assets {
+    sources: HashMap<String, Source>,
+    cache: HashMap<u64, HashMap<String, ImageSurface>>,
+    #[cfg(feature = "svg")]
+    skins: HashMap<String, SvgSkin>,
+    nine_patches: RefCell<HashMap<NinePatchKey, ImageSurface>>,
+    scale_factor: f64
+}
+
+impl Assets {
+    pub(crate) fn new(scale_factor: f64) -> Assets {
+        Assets {
+            sources: HashMap::new(),
+            cache: HashMap::new(),
+            #[cfg(feature = "svg")]
+            skins: HashMap::new(),
+            nine_patches: RefCell::new(HashMap::new()),
+            scale_factor
+        }
+    }
+
+    /// Adds the PNG image `data` under the key `key`.
+    ///
+    /// `native_scale` is the scale factor the image is made for, like
+    /// `2.0` for an image of twice the size of the widget in unscaled
+    /// pixels. An image already added under `key` is replaced.
+    pub fn add_png(&mut self, key: &str, mut data: &[u8], native_scale: f64) -> Result<(), AssetError> {
+        let image = ImageSurface::create_from_png(&mut data)?;
+        self.insert(key, Source::Png { image, native_scale })
+    }
+
+    /// Adds the PNG image file `path` under the key `key`, see
+    /// [`add_png()`](#method.add_png).
+    pub fn add_png_file<P: AsRef<Path>>(&mut self, key: &str, path: P, native_scale: f64) -> Result<(), AssetError> {
+        let image = ImageSurface::create_from_png(&mut File::open(path)?)?;
+        self.insert(key, Source::Png { image, native_scale })
+    }
+
+    /// Adds the SVG document file `path` under the key `key`, rendered
+    /// to the unscaled size `size`.
+    ///
+    /// Only available with the feature `svg`.
+    #[cfg(feature = "svg")]
+    pub fn add_svg_file<P: AsRef<Path>>(&mut self, key: &str, path: P, size: Size) -> Result<(), AssetError> {
+        let handle = librsvg::Loader::new().read_path(path).map_err(|err| AssetError::Svg(err.to_string()))?;
+        self.insert(key, Source::Svg { handle, size })
+    }
+
+    /// Adds the SVG document file `path` as skin under the key `key`.
+    ///
+    /// Only available with the feature `svg`.
+    #[cfg(feature = "svg")]
+    pub fn add_svg_skin<P: AsRef<Path>>(&mut self, key: &str, path: P) -> Result<(), AssetError> {
+        let skin = SvgSkin::from_file(path)?;
+        self.skins.insert(key.to_string(), skin);
+        Ok(())
+    }
+
+    /// Returns the skin `key`.
+    ///
+    /// Only available with the feature `svg`.
+    #[cfg(feature = "svg")]
+    pub fn skin(&self, key: &str) -> Option<&SvgSkin> {
+        self.skins.get(key)
+    }
+
+    /// Rasterizes the image at the current scale factor, so that
+    /// failures are reported when the image is added.
+    fn insert(&mut self, key: &str, source: Source) -> Result<(), AssetError> {
+        let surface = source.rasterize(self.scale_factor).ok_or(AssetError::Raster)?;
+        for images in self.cache.values_mut() {
+            images.remove(key);
+        }
+        self.nine_patches.borrow_mut().retain(|(image, ..), _| image != key);
+        self.cache.entry(self.scale_factor.to_bits()).or_default().insert(key.to_string(), surface);
+        self.sources.insert(key.to_string(), source);
+        Ok(())
+    }
+
+    /// Removes the image or the skin `key`.
+    ///
+    /// Returns `false` if there is no image or skin `key`.
+    pub fn remove(&mut self, key: &str) -> bool {
+        for images in self.cache.values_mut() {
+            images.remove(key);
+        }
+        self.nine_patches.borrow_mut().retain(|(image, ..), _| image != key);
+        #[cfg(feature = "svg")]
+        {
+            if self.skins.remove(key).is_some() {
+                return true;
+            }
+        }
+        self.sources.remove(key).is_some()
+    }
+
+    /// Returns `true` iff there is an image `key`.
+    pub fn contains(&self, key: &str) -> bool {
+        self.sources.contains_key(key)
+    }
+
+    /// Returns the unscaled size of the image `key`.
+    pub fn size(&self, key: &str) -> Option<Size> {
+        self.sources.get(key).map(Source::size)
+    }
+
+    /// Returns the image `key` rasterized at the scale factor
+    /// `scale_factor`, if it has been drawn at that scale factor.
+    ///
+    /// The size of the surface is the unscaled size multiplied by
+    /// `scale_factor`.
+    pub fn surface(&self, key: &str, scale_factor: f64) -> Option<&ImageSurface> {
+        self.cache.get(&scale_factor.to_bits())?.get(key)
+    }
+
+    /// Draws the image `key` rasterized at the scale factor
+    /// `scale_factor` to `cr` with its upper left corner at `pos`.
+    ///
+    /// `cr` is expected to be scaled by `scale_factor`, like the
+    /// context passed to `Widget::exposed()`. Returns `false` if there
+    /// is no such image.
+    pub fn paint(&self, cr: &cairo::Context, key: &str, pos: Coord, scale_factor: f64) -> bool {
+        let surface = match self.surface(key, scale_factor) {
+            Some(surface) => surface,
+            None => return false
+        };
+        cr.save();
+        cr.scale(1./scale_factor, 1./scale_factor);
+        cr.set_source_surface(surface, (pos.x * scale_factor).round(), (pos.y * scale_factor).round());
+        cr.paint();
+        cr.restore();
+        true
+    }
+
+    /// Draws the image `key` as nine-patch sliced by `insets` into
+    /// `layout`, see the [`panel`](../panel/index.html) module.
+    ///
+    /// The composed nine-patch is cached for the size of `layout` and
+    /// `scale_factor`. Returns `false` if there is no image `key` at
+    /// `scale_factor`.
+    pub fn paint_nine_patch(&self, cr: &cairo::Context, key: &str, insets: Insets, layout: Layout, scale_factor: f64) -> bool {
+        let cache_key = (key.to_string(),
+                         [insets.left.to_bits(), insets.top.to_bits(), insets.right.to_bits(), insets.bottom.to_bits()],
+                         [layout.size.w.to_bits(), layout.size.h.to_bits()],
+                         scale_factor.to_bits());
+        let mut nine_patches = self.nine_patches.borrow_mut();
+        if !nine_patches.contains_key(&cache_key) {
+            let patch = match self.surface(key, scale_factor).and_then(|image| panel::nine_patch(image, insets, layout.size, scale_factor)) {
+                Some(patch) => patch,
+                None => return false
+            };
+            nine_patches.insert(cache_key.clone(), patch);
+        }
+        cr.save();
+        cr.scale(1./scale_factor, 1./scale_factor);
+        cr.set_source_surface(&nine_patches[&cache_key], (layout.pos.x * scale_factor).round(), (layout.pos.y * scale_factor).round());
+        cr.paint();
+        cr.restore();
+        true
+    }
+
+    /// Drops the rasterizations of all the images and skins but those
+    /// at the current scale factor.
+    pub fn clear_cache(&mut self) {
+        let current = self.scale_factor.to_bits();
+        self.cache.retain(|scale, _| *scale == current);
+        self.nine_patches.borrow_mut().retain(|(.., scale), _| *scale == current);
+        #[cfg(feature = "svg")]
+        {
+            for skin in self.skins.values() {
+                skin.retain_scale(self.scale_factor);
+            }
+        }
+    }
+
+    /// Rasterizes the images missing at the scale factor `scale_factor`.
+    ///
+    /// Called by the `UI` before the widgets are drawn.
+    pub(crate) fn prepare(&mut self, scale_factor: f64) {
+        self.scale_factor = scale_factor;
+        let images = self.cache.entry(scale_factor.to_bits()).or_default();
+        if images.len() == self.sources.len() {
+            return;
+        }
+        for (key, source) in self.sources.iter() {
+            if !images.contains_key(key) {
+                if let Some(surface) = source.rasterize(scale_factor) {
+                    images.insert(key.clone(), surface);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(w: i32, h: i32) -> Vec<u8> {
+        let surface = ImageSurface::create(cairo::Format::ARgb32, w, h).unwrap();
+        {
+            let cr = cairo::Context::new(&surface);
+            cr.set_source_rgb(1., 0., 0.);
+            cr.paint();
+        }
+        let mut data = Vec::new();
+        surface.write_to_png(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn rasterized_per_scale_factor() {
+        let mut assets = Assets::new(1.);
+        assets.add_png("knob", &png(40, 20), 2.).unwrap();
+        assert_eq!(assets.size("knob"), Some(Size { w: 20., h: 10. }));
+
+        let surface = assets.surface("knob", 1.).unwrap();
+        assert_eq!((surface.get_width(), surface.get_height()), (20, 10));
+        assert!(assets.surface("knob", 2.).is_none());
+
+        assets.prepare(2.);
+        let surface = assets.surface("knob", 2.).unwrap();
+        assert_eq!((surface.get_width(), surface.get_height()), (40, 20));
+        assert!(assets.surface("knob", 1.).is_some());
+
+        assets.clear_cache();
+        assert!(assets.surface("knob", 1.).is_none());
+        assert!(assets.surface("knob", 2.).is_some());
+    }
+
+    #[test]
+    fn replace_and_remove() {
+        let mut assets = Assets::new(1.);
+        assets.add_png("icon", &png(8, 8), 1.).unwrap();
+        assets.prepare(2.);
+        assets.add_png("icon", &png(16, 16), 1.).unwrap();
+        assert!(assets.surface("icon", 1.).is_none());
+        assert_eq!(assets.surface("icon", 2.).map(|s| s.get_width()), Some(32));
+
+        assert!(assets.remove("icon"));
+        assert!(!assets.contains("icon"));
+        assert!(!assets.remove("icon"));
+    }
+
+    #[test]
+    fn invalid_png() {
+        let mut assets = Assets::new(1.);
+        match assets.add_png("broken", b"no png", 1.) {
+            Err(AssetError::Png(_)) => {}
+            other => panic!("broken PNG accepted: {:?}", other.map(|_| ()))
+        }
+        assert!(!assets.contains("broken"));
+    }
+}
@@ -0,0 +1,240 @@
+//! Exposing the `UI` to screen readers over AT-SPI
+//!
+//! Only available with the feature `atspi`, which pulls in `accesskit`
+//! and `accesskit_unix`, the latter talking AT-SPI over D-Bus.
+//!
+//! An [`AtspiBridge`](struct.AtspiBridge.html) publishes the
+//! [`UI::accessibility_tree()`](../ui/struct.UI.html#method.accessibility_tree)
+//! on the accessibility bus and passes the actions requested by
+//! screen readers on to
+//! [`UI::perform_accessible_action()`](../ui/struct.UI.html#method.perform_accessible_action).
+//! The requests arrive on the D-Bus thread, so they are queued and
+//! only performed by [`update()`](struct.AtspiBridge.html#method.update),
+//! which is to be called after every event loop cycle.
+//!
+//! ```ignore
+//! let mut bridge = AtspiBridge::new(view.handle(), "My Synth");
+//! loop {
+//!     view.handle().next_event(-1.0);
+//!     bridge.update(view.handle());
+//! }
+//! ```
+use std::sync::{Arc, Mutex};
+
+use accesskit::{ActionHandler, ActionRequest, NodeBuilder, NodeClassSet, NodeId, Rect, Tree, TreeUpdate};
+use accesskit_unix::Adapter;
+
+use crate::accessibility::{Action, AccessibleNode, Role};
+use crate::ui::UI;
+use crate::widget::{Id, Widget};
+
+/// Publishes the accessibility tree of a `UI` over AT-SPI
+pub struct AtspiBridge {
+    adapter: Option<Adapter>,
+    requests: Arc<Mutex<Vec<(Id, Action)>>>,
+    classes: NodeClassSet,
+    last_tree: Option<AccessibleNode>
+}
+
+struct QueueingHandler {
+    requests: Arc<Mutex<Vec<(Id, Action)>>>
+}
+
+impl ActionHandler for QueueingHandler {
+    fn do_action(&mut self, request: ActionRequest) {
+        if let Some(action) = action_from_accesskit(request.action) {
+            if let Ok(mut requests) = self.requests.lock() {
+                requests.push((request.target.0 as Id, action));
+            }
+        }
+    }
+}
+
+impl AtspiBridge {
+    /// Registers the `UI` `ui` as the application `app_name` on the
+    /// accessibility bus.
+    ///
+    /// If there is no accessibility bus, the bridge does nothing, see
+    /// [`is_active()`](#method.is_active).
+    pub fn new<RW: Widget>(ui: &UI<RW>, app_name: &str) -> AtspiBridge {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let tree = ui.accessibility_tree();
+        let initial = tree_update(&tree, &mut NodeClassSet::new(), true);
+        let adapter = Adapter::new(
+            String::from(app_name),
+            String::from(env!("CARGO_PKG_NAME")),
+            String::from(env!("CARGO_PKG_VERSION")),
+            move || initial,
+            Box::new(QueueingHandler { requests: requests.clone() })
+        );
+        AtspiBridge {
+            adapter,
+            requests,
+            classes: NodeClassSet::new(),
+            last_tree: Some(tree)
+        }
+    }
+
+    /// Returns `true` iff the bridge is connected to an accessibility bus.
+    pub fn is_active(&self) -> bool {
+        self.adapter.is_some()
+    }
+
+    /// Performs the actions requested by screen readers since the last
+    /// call and publishes the accessibility tree if it has changed.
+    pub fn update<RW: Widget>(&mut self, ui: &mut UI<RW>) {
+        let requests = match self.requests.lock() {
+            Ok(mut requests) => std::mem::take(&mut *requests),
+            Err(_) => Vec::new()
+        };
+        for (id, action) in requests {
+            ui.perform_accessible_action(id, action);
+        }
+        let adapter = match self.adapter.as_ref() {
+            Some(adapter) => adapter,
+            None => return
+        };
+        let tree = ui.accessibility_tree();
+        if self.last_tree.as_ref() == Some(&tree) {
+            return;
+        }
+        let classes = &mut self.classes;
+        adapter.update_if_active(|| tree_update(&tree, classes, false));
+        self.last_tree = Some(tree);
+    }
+
+    /// Tells the screen readers whether the window of the `UI` has the
+    /// keyboard focus.
+    pub fn set_window_focus(&self, focused: bool) {
+        if let Some(adapter) = self.adapter.as_ref() {
+            adapter.update_window_focus_state(focused);
+        }
+    }
+}
+
+fn role_to_accesskit(role: Role) -> accesskit::Role {
+    match role {
+        Role::Window => accesskit::Role::Window,
+        Role::Group => accesskit::Role::Group,
+        Role::Button => accesskit::Role::Button,
+        Role::ToggleButton => accesskit::Role::ToggleButton,
+        Role::CheckBox => accesskit::Role::CheckBox,
+        Role::Slider | Role::Dial => accesskit::Role::Slider,
+        Role::SpinButton => accesskit::Role::SpinButton,
+        Role::Label => accesskit::Role::StaticText,
+        Role::TextEntry => accesskit::Role::TextInput,
+        Role::ComboBox => accesskit::Role::ComboBox,
+        Role::Indicator => accesskit::Role::Meter,
+        Role::Other => accesskit::Role::Unknown
+    }
+}
+
+fn action_to_accesskit(action: Action) -> accesskit::Action {
+    match action {
+        Action::Activate => accesskit::Action::Default,
+        Action::Increment => accesskit::Action::Increment,
+        Action::Decrement => accesskit::Action::Decrement,
+        Action::Focus => accesskit::Action::Focus
+    }
+}
+
+fn action_from_accesskit(action: accesskit::Action) -> Option<Action> {
+    match action {
+        accesskit::Action::Default => Some(Action::Activate),
+        accesskit::Action::Increment => Some(Action::Increment),
+        accesskit::Action::Decrement => Some(Action::Decrement),
+        accesskit::Action::Focus => Some(Action::Focus),
+        _ => None
+    }
+}
+
+/// Converts the tree `tree` to an update of all its nodes. The
+/// `Tree` itself is only set by the `initial` update.
+fn tree_update(tree: &AccessibleNode, classes: &mut NodeClassSet, initial: bool) -> TreeUpdate {
+    let mut nodes = Vec::new();
+    let mut focus = NodeId(tree.id as u64);
+    push_nodes(tree, classes, &mut nodes, &mut focus);
+    TreeUpdate {
+        nodes,
+        tree: if initial { Some(Tree::new(NodeId(tree.id as u64))) } else { None },
+        focus
+    }
+}
+
+fn push_nodes(node: &AccessibleNode, classes: &mut NodeClassSet, nodes: &mut Vec<(NodeId, accesskit::Node)>, focus: &mut NodeId) {
+    let acc = &node.accessible;
+    let mut builder = NodeBuilder::new(role_to_accesskit(acc.role));
+    if !acc.name.is_empty() {
+        builder.set_name(acc.name.clone());
+    }
+    if let Some(value) = acc.value.as_ref() {
+        builder.set_value(value.clone());
+    }
+    for &action in acc.actions.iter() {
+        builder.add_action(action_to_accesskit(action));
+    }
+    if !node.sensitive {
+        builder.set_disabled();
+    }
+    let b = node.bounds;
+    builder.set_bounds(Rect { x0: b.pos.x, y0: b.pos.y, x1: b.pos.x + b.size.w, y1: b.pos.y + b.size.h });
+    builder.set_children(node.children.iter().map(|c| NodeId(c.id as u64)).collect::<Vec<_>>());
+    if node.focused {
+        *focus = NodeId(node.id as u64);
+    }
+    nodes.push((NodeId(node.id as u64), builder.build(classes)));
+    for c in node.children.iter() {
+        push_nodes(c, classes, nodes, focus);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accessibility::Accessible;
+    use crate::widget::Layout;
+
+    fn node(id: Id, accessible: Accessible, children: Vec<AccessibleNode>) -> AccessibleNode {
+        AccessibleNode {
+            id,
+            accessible,
+            bounds: Layout::default(),
+            focused: false,
+            sensitive: true,
+            children
+        }
+    }
+
+    #[test]
+    fn tree_converted() {
+        let mut dial = node(3, Accessible::new(Role::Dial, "Cutoff").with_value("440 Hz").with_action(Action::Increment), vec![]);
+        dial.focused = true;
+        let tree = node(0, Accessible::new(Role::Window, ""), vec![
+            node(1, Accessible::new(Role::Group, "Filter"), vec![dial]),
+            node(2, Accessible::new(Role::Button, "Mute"), vec![])
+        ]);
+
+        let update = tree_update(&tree, &mut NodeClassSet::new(), true);
+        assert_eq!(update.nodes.len(), 4);
+        assert_eq!(update.focus, NodeId(3));
+        assert_eq!(update.tree.map(|t| t.root), Some(NodeId(0)));
+        assert_eq!(update.nodes[0].1.children(), &[NodeId(1), NodeId(2)]);
+
+        let (_, dial) = update.nodes.iter().find(|(id, _)| *id == NodeId(3)).unwrap();
+        assert_eq!(dial.role(), accesskit::Role::Slider);
+        assert_eq!(dial.name(), Some("Cutoff"));
+        assert_eq!(dial.value(), Some("440 Hz"));
+        assert!(dial.supports_action(accesskit::Action::Increment));
+        assert!(!dial.supports_action(accesskit::Action::Default));
+
+        assert!(tree_update(&tree, &mut NodeClassSet::new(), false).tree.is_none());
+    }
+
+    #[test]
+    fn actions_mapped_both_ways() {
+        for &action in [Action::Activate, Action::Increment, Action::Decrement, Action::Focus].iter() {
+            assert_eq!(action_from_accesskit(action_to_accesskit(action)), Some(action));
+        }
+        assert_eq!(action_from_accesskit(accesskit::Action::ScrollIntoView), None);
+    }
+}
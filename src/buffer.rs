@@ -0,0 +1,189 @@
+//! Double buffering for widgets with expensive drawing
+//!
+//! Only available with the feature `cairo-helpers`.
+//!
+//! Meters, scopes and spectrograms are expensive to draw and usually
+//! updated by a timer rather than by events. If such a widget draws
+//! directly in [`Widget::exposed()`](../widget/trait.Widget.html#method.exposed),
+//! the drawing costs are paid at the time of the expose and an expose
+//! during an update can show a half drawn frame.
+//!
+//! A [`DoubleBuffer`](struct.DoubleBuffer.html) decouples the
+//! drawing from the exposes. The widget draws a new frame into the
+//! back surface when its data changes, for example in
+//! [`Widget::reminder_handler()`](../widget/trait.Widget.html#method.reminder_handler),
+//! and `exposed()` only blits the last complete frame.
+//!
+//! ```
+//! # use pugl_sys::*;
+//! # #[macro_use] extern crate pugl_ui;
+//! # use pugl_ui::widget::*;
+//! use pugl_ui::buffer::DoubleBuffer;
+//!
+//! #[derive(Default)]
+//! struct Meter {
+//!     stub: WidgetStub,
+//!     level: f64,
+//!     buffer: DoubleBuffer
+//! }
+//!
+//! impl Widget for Meter {
+//!     widget_stub!();
+//!
+//!     fn exposed(&mut self, ctx: &PaintContext, cr: &cairo::Context) {
+//!         self.buffer.blit(ctx, cr, self.pos());
+//!     }
+//!
+//!     fn reminder_handler(&mut self) -> bool {
+//!         let level = self.level;
+//!         self.buffer.render(self.size(), |cr, size| {
+//!             cr.rectangle(0., size.h * (1. - level), size.w, size.h * level);
+//!             cr.fill();
+//!         });
+//!         self.ask_for_repaint();
+//!         true
+//!     }
+//! }
+//! # fn main() {}
+//! ```
+use pugl_sys::*;
+
+use crate::widget::PaintContext;
+
+/// A pair of surfaces, the front one holding the last complete frame
+pub struct DoubleBuffer {
+    front: Option<cairo::ImageSurface>,
+    back: Option<cairo::ImageSurface>,
+    scale_factor: f64
+}
+
+impl Default for DoubleBuffer {
+    fn default() -> DoubleBuffer {
+        DoubleBuffer {
+            front: None,
+            back: None,
+            scale_factor: 1.0
+        }
+    }
+}
+
+impl DoubleBuffer {
+    /// Draws a new frame of size `size` by `draw` and makes it the
+    /// frame to be blitted.
+    ///
+    /// The closure gets a `cairo::Context` whose origin is the top
+    /// left corner of the frame and the size of the frame. The
+    /// surface's resolution follows the scale factor of the last
+    /// [`blit()`](#method.blit). The back surface is reused if its
+    /// size did not change.
+    pub fn render<F>(&mut self, size: Size, draw: F)
+    where F: FnOnce(&cairo::Context, Size) {
+        let w = (size.w * self.scale_factor).ceil() as i32;
+        let h = (size.h * self.scale_factor).ceil() as i32;
+        let surface = match self.back.take() {
+            Some(sf) if sf.get_width() == w && sf.get_height() == h => sf,
+            _ => cairo::ImageSurface::create(cairo::Format::ARgb32, w, h)
+                .expect("Creation of back buffer surface failed")
+        };
+        {
+            let cr = cairo::Context::new(&surface);
+            cr.set_operator(cairo::Operator::Clear);
+            cr.paint();
+            cr.set_operator(cairo::Operator::Over);
+            cr.scale(self.scale_factor, self.scale_factor);
+            draw(&cr, size);
+        }
+        self.back = self.front.take();
+        self.front = Some(surface);
+    }
+
+    /// Paints the last complete frame to `cr` with its top left
+    /// corner at `pos`.
+    ///
+    /// Returns `false` if there has not been a frame rendered yet.
+    /// If the scale factor of `ctx` differs from the one of the frame,
+    /// the frame is dropped and the next
+    /// [`render()`](#method.render) uses the new scale factor.
+    pub fn blit(&mut self, ctx: &PaintContext, cr: &cairo::Context, pos: Coord) -> bool {
+        let sf = ctx.scale_factor();
+        if sf != self.scale_factor {
+            self.scale_factor = sf;
+            self.clear();
+            return false;
+        }
+        self.blit_surface(cr, pos)
+    }
+
+    fn blit_surface(&self, cr: &cairo::Context, pos: Coord) -> bool {
+        let surface = match &self.front {
+            Some(sf) => sf,
+            None => return false
+        };
+        let sf = self.scale_factor;
+        cr.save();
+        cr.translate(pos.x, pos.y);
+        cr.scale(1./sf, 1./sf);
+        cr.set_source_surface(surface, 0., 0.);
+        cr.paint();
+        cr.restore();
+        true
+    }
+
+    /// Returns `true` iff there is a frame to be blitted.
+    pub fn has_frame(&self) -> bool {
+        self.front.is_some()
+    }
+
+    /// Drops the frames, for example when the widget is hidden.
+    pub fn clear(&mut self) {
+        self.front = None;
+        self.back = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(surface: &mut cairo::ImageSurface, x: usize, y: usize) -> [u8; 4] {
+        let stride = surface.get_stride() as usize;
+        let data = surface.get_data().expect("surface data borrowed");
+        let i = y * stride + x * 4;
+        [data[i], data[i+1], data[i+2], data[i+3]]
+    }
+
+    #[test]
+    fn blit_last_complete_frame() {
+        let mut buffer = DoubleBuffer::default();
+        let mut target = cairo::ImageSurface::create(cairo::Format::ARgb32, 8, 8).unwrap();
+        {
+            let cr = cairo::Context::new(&target);
+            assert!(!buffer.blit_surface(&cr, Coord::default()));
+        }
+        assert!(!buffer.has_frame());
+
+        buffer.render(Size { w: 4., h: 4. }, |cr, size| {
+            cr.set_source_rgb(1., 0., 0.);
+            cr.rectangle(0., 0., size.w, size.h);
+            cr.fill();
+        });
+        assert!(buffer.has_frame());
+        {
+            let cr = cairo::Context::new(&target);
+            assert!(buffer.blit_surface(&cr, Coord { x: 2., y: 2. }));
+        }
+        assert_eq!(pixel(&mut target, 0, 0), [0, 0, 0, 0]);
+        assert_eq!(pixel(&mut target, 3, 3), [0, 0, 255, 255]);
+
+        buffer.render(Size { w: 4., h: 4. }, |_cr, _size| {});
+        let mut target = cairo::ImageSurface::create(cairo::Format::ARgb32, 8, 8).unwrap();
+        {
+            let cr = cairo::Context::new(&target);
+            assert!(buffer.blit_surface(&cr, Coord { x: 2., y: 2. }));
+        }
+        assert_eq!(pixel(&mut target, 3, 3), [0, 0, 0, 0]);
+
+        buffer.clear();
+        assert!(!buffer.has_frame());
+    }
+}
@@ -0,0 +1,293 @@
+//! Constructing the widget tree from a declarative description
+//!
+//! Only available with the feature `builder`.
+//!
+//! Instead of creating and packing every layouter by hand, the layout
+//! tree can be described by a [`Node`](enum.Node.html) which can be
+//! deserialized by `serde` from any format, like RON or JSON. The
+//! widgets themselves are named placeholders in the description.
+//! The application registers a factory for each of them at the
+//! [`Builder`](struct.Builder.html). The widgets are registered as
+//! named widgets, so that the application can find them by
+//! [`UI::widget_by_name()`](../ui/struct.UI.html#method.widget_by_name).
+//!
+//! ```
+//! # use pugl_ui::builder::*;
+//! let description: Node = serde_json::from_str(r#"
+//!     {"vertical": {
+//!         "padding": 5.0,
+//!         "children": [
+//!             {"widget": "title"},
+//!             {"horizontal": {"children": [
+//!                 {"stretch": {"factor": 2.0, "child": {"widget": "gain_dial"}}},
+//!                 "spacer",
+//!                 {"widget": "mute"}
+//!             ]}}
+//!         ]
+//!     }}
+//! "#).unwrap();
+//!
+//! assert_eq!(description.placeholders(), vec!["title", "gain_dial", "mute"]);
+//! ```
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::layout::*;
+use crate::layout::stacklayout::*;
+use crate::ui::UI;
+use crate::widget::*;
+
+/// A node of the layout tree description
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Node {
+    /// A horizontal stack layout
+    Horizontal(Stack),
+    /// A vertical stack layout
+    Vertical(Stack),
+    /// A [`Spacer`](../layout/stacklayout/struct.Spacer.html) taking
+    /// the extra space of the surrounding stack layout
+    Spacer,
+    /// A placeholder for the widget created by the factory of this name
+    Widget(String),
+    /// The node `child` packed with the stretch factor `factor`, see
+    /// [`Constraints::stretch()`](../layout/struct.Constraints.html#method.stretch)
+    Stretch { factor: f64, child: Box<Node> }
+}
+
+/// The description of a stack layout
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct Stack {
+    /// The padding of the layout, the layouter's default if `None`
+    #[serde(default)]
+    pub padding: Option<f64>,
+    /// The spacing of the layout, the layouter's default if `None`
+    #[serde(default)]
+    pub spacing: Option<f64>,
+    /// The children stacked from the front to the back
+    #[serde(default)]
+    pub children: Vec<Node>
+}
+
+impl Node {
+    /// Returns the names of all the widget placeholders in the order
+    /// they appear in the description.
+    pub fn placeholders(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.collect_placeholders(&mut names);
+        names
+    }
+
+    fn collect_placeholders<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Node::Horizontal(stack) | Node::Vertical(stack) => {
+                for c in stack.children.iter() {
+                    c.collect_placeholders(names);
+                }
+            }
+            Node::Spacer => {}
+            Node::Widget(name) => names.push(name),
+            Node::Stretch { child, .. } => child.collect_placeholders(names)
+        }
+    }
+}
+
+/// Error returned by [`Builder::build()`](struct.Builder.html#method.build)
+#[derive(Clone, Debug, PartialEq)]
+pub enum BuildError {
+    /// There is no factory for the placeholder of this name.
+    UnknownWidget(String),
+    /// The placeholder of this name appears more than once.
+    DuplicateWidget(String)
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::UnknownWidget(name) => write!(f, "no factory for widget {}", name),
+            BuildError::DuplicateWidget(name) => write!(f, "widget {} used more than once", name)
+        }
+    }
+}
+
+impl Error for BuildError {}
+
+type Factory<RW> = Box<dyn FnMut(&mut UI<RW>, &str) -> Id>;
+
+/// Builds widget trees from descriptions using widget factories
+pub struct Builder<RW: Widget + 'static> {
+    factories: HashMap<String, Factory<RW>>
+}
+
+impl<RW: Widget + 'static> Default for Builder<RW> {
+    fn default() -> Builder<RW> {
+        Builder {
+            factories: HashMap::new()
+        }
+    }
+}
+
+impl<RW: Widget + 'static> Builder<RW> {
+    /// Registers `factory` to create the widget for the placeholder `name`
+    pub fn factory<W, F>(&mut self, name: &str, mut factory: F) -> &mut Builder<RW>
+    where W: Widget,
+          F: FnMut() -> Box<W> + 'static {
+        self.factories.insert(String::from(name), Box::new(move |ui, name| {
+            ui.new_named_widget(name, factory()).id()
+        }));
+        self
+    }
+
+    /// Creates the widgets and layouts described by `description` and
+    /// packs them into the root layout of `ui`.
+    ///
+    /// Nothing is created if a placeholder has no factory or appears
+    /// more than once. The layouting is not performed, so that more
+    /// widgets can be added before calling
+    /// [`UI::do_layout()`](../ui/struct.UI.html#method.do_layout).
+    pub fn build(&mut self, ui: &mut UI<RW>, description: &Node) -> Result<(), BuildError> {
+        let mut seen = Vec::new();
+        for name in description.placeholders() {
+            if !self.factories.contains_key(name) {
+                return Err(BuildError::UnknownWidget(String::from(name)));
+            }
+            if seen.contains(&name) {
+                return Err(BuildError::DuplicateWidget(String::from(name)));
+            }
+            seen.push(name);
+        }
+        let root = ui.root_layout();
+        self.build_node(ui, description, root, Constraints::default());
+        Ok(())
+    }
+
+    fn build_node<L, PW>(&mut self, ui: &mut UI<RW>, node: &Node, parent: LayoutWidgetHandle<L, PW>,
+                         constraints: Constraints)
+    where L: Layouter<Target = StackDirection>,
+          PW: Widget {
+        let id = match node {
+            Node::Horizontal(stack) => {
+                let lw = ui.new_layouter::<HorizontalLayouter>();
+                if let Some(padding) = stack.padding {
+                    ui.layouter(lw).set_padding(padding);
+                }
+                if let Some(spacing) = stack.spacing {
+                    ui.layouter(lw).set_spacing(spacing);
+                }
+                ui.pack_id_to_layout(lw.widget().id(), parent, StackDirection::Back, constraints);
+                for c in stack.children.iter() {
+                    self.build_node(ui, c, lw, Constraints::default());
+                }
+                return;
+            }
+            Node::Vertical(stack) => {
+                let lw = ui.new_layouter::<VerticalLayouter>();
+                if let Some(padding) = stack.padding {
+                    ui.layouter(lw).set_padding(padding);
+                }
+                if let Some(spacing) = stack.spacing {
+                    ui.layouter(lw).set_spacing(spacing);
+                }
+                ui.pack_id_to_layout(lw.widget().id(), parent, StackDirection::Back, constraints);
+                for c in stack.children.iter() {
+                    self.build_node(ui, c, lw, Constraints::default());
+                }
+                return;
+            }
+            Node::Spacer => ui.new_widget(Box::new(Spacer::new(L::expandable()))).id(),
+            Node::Widget(name) => {
+                let factory = self.factories.get_mut(name).expect("factory vanished");
+                factory(ui, name)
+            }
+            Node::Stretch { factor, child } => {
+                self.build_node(ui, child, parent, Constraints { stretch_factor: *factor, ..constraints });
+                return;
+            }
+        };
+        ui.pack_id_to_layout(id, parent, StackDirection::Back, constraints);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "testing")]
+    use pugl_sys::*;
+
+    #[cfg(feature = "testing")]
+    #[derive(Default)]
+    struct RootWidget {
+        stub: WidgetStub
+    }
+
+    #[cfg(feature = "testing")]
+    impl Widget for RootWidget {
+        widget_stub!();
+    }
+
+    #[derive(Default)]
+    struct Knob {
+        stub: WidgetStub
+    }
+
+    impl Widget for Knob {
+        widget_stub!();
+        fn min_size(&self) -> pugl_sys::Size { pugl_sys::Size { w: 20., h: 30. } }
+    }
+
+    fn description() -> Node {
+        serde_json::from_str(r#"
+            {"horizontal": {
+                "padding": 0.0,
+                "spacing": 10.0,
+                "children": [{"widget": "gain"}, {"vertical": {"children": ["spacer", {"widget": "pan"}]}}]
+            }}
+        "#).unwrap()
+    }
+
+    #[test]
+    fn deserialize_description() {
+        assert_eq!(description(), Node::Horizontal(Stack {
+            padding: Some(0.0),
+            spacing: Some(10.0),
+            children: vec![
+                Node::Widget(String::from("gain")),
+                Node::Vertical(Stack {
+                    children: vec![Node::Spacer, Node::Widget(String::from("pan"))],
+                    ..Default::default()
+                })
+            ]
+        }));
+    }
+
+    #[test]
+    fn deserialize_stretch() {
+        let node: Node = serde_json::from_str(r#"{"stretch": {"factor": 2.0, "child": "spacer"}}"#).unwrap();
+        assert_eq!(node, Node::Stretch { factor: 2.0, child: Box::new(Node::Spacer) });
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn build_description() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+        let ui = view.handle();
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+
+        let mut builder = Builder::default();
+        builder.factory("gain", || Box::new(Knob::default()));
+        assert_eq!(builder.build(ui, &description()), Err(BuildError::UnknownWidget(String::from("pan"))));
+
+        builder.factory("pan", || Box::new(Knob::default()));
+        assert_eq!(builder.build(ui, &description()), Ok(()));
+        ui.do_layout();
+
+        assert_eq!(ui.root_widget().size(), Size { w: 50., h: 30. });
+        let pan = ui.widget_handle_by_name::<Knob>("pan").expect("no pan widget");
+        assert_eq!(ui.widget(pan).pos(), Coord { x: 30., y: 0. });
+    }
+}
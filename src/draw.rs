@@ -0,0 +1,40 @@
+//! The drawing backends widgets draw themselves with
+//!
+//! The `UI` hands the widgets a [`DrawBackend`](trait.DrawBackend.html)
+//! by [`Widget::draw()`](../widget/trait.Widget.html#method.draw),
+//! which by default passes the cairo context of the backend on to
+//! [`Widget::exposed()`](../widget/trait.Widget.html#method.exposed).
+//! Widgets only need to implement `draw()` themselves if they can make
+//! use of other backends.
+//!
+//! Currently the only backend is [`CairoBackend`](struct.CairoBackend.html),
+//! as `pugl-sys` creates the views with pugl's cairo backend. A
+//! backend not drawing with cairo is to implement `DrawBackend` with
+//! [`cairo()`](trait.DrawBackend.html#tymethod.cairo) returning
+//! `None`, so that widgets drawing by cairo only are skipped rather
+//! than drawing into nowhere.
+
+/// A backend widgets draw themselves with
+pub trait DrawBackend {
+    /// Returns the cairo context to draw on, if the backend is
+    /// drawing with cairo.
+    fn cairo(&self) -> Option<&cairo::Context>;
+}
+
+/// The default backend, drawing on a `cairo::Context`
+pub struct CairoBackend<'a> {
+    cr: &'a cairo::Context
+}
+
+impl<'a> CairoBackend<'a> {
+    /// Creates the backend drawing on `cr`.
+    pub fn new(cr: &'a cairo::Context) -> CairoBackend<'a> {
+        CairoBackend { cr }
+    }
+}
+
+impl<'a> DrawBackend for CairoBackend<'a> {
+    fn cairo(&self) -> Option<&cairo::Context> {
+        Some(self.cr)
+    }
+}
@@ -0,0 +1,112 @@
+//! Embedding the `UI` into windows of plugin hosts
+//!
+//! Plugin standards like CLAP, VST3 and LV2 pass the native handle of
+//! the window the plugin GUI is to be embedded into. A
+//! [`ParentWindow`](struct.ParentWindow.html) wraps it in a typed way,
+//! and [`UI::embed_into()`](../ui/struct.UI.html#method.embed_into)
+//! creates the view as a child of it.
+//!
+//! With the feature `raw-window-handle` the handles of the
+//! [`raw-window-handle`](https://docs.rs/raw-window-handle) crate can
+//! be used by
+//! [`ParentWindow::from_raw_window_handle()`](struct.ParentWindow.html#method.from_raw_window_handle).
+use std::os::raw::{c_ulong, c_void};
+
+#[cfg(feature = "raw-window-handle")]
+use raw_window_handle::RawWindowHandle;
+
+/// The native handle of the window a `UI` is embedded into
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ParentWindow(*mut c_void);
+
+impl ParentWindow {
+    /// No parent window, the view becomes a top level window.
+    pub fn none() -> ParentWindow {
+        ParentWindow(std::ptr::null_mut())
+    }
+
+    /// The X11 window `window`, as passed by hosts on Linux.
+    pub fn from_x11(window: c_ulong) -> ParentWindow {
+        ParentWindow(window as usize as *mut c_void)
+    }
+
+    /// The window `hwnd`, as passed by hosts on Windows.
+    pub fn from_hwnd(hwnd: *mut c_void) -> ParentWindow {
+        ParentWindow(hwnd)
+    }
+
+    /// The `NSView` `view`, as passed by hosts on macOS.
+    pub fn from_ns_view(view: *mut c_void) -> ParentWindow {
+        ParentWindow(view)
+    }
+
+    /// The handle of a window from the `raw-window-handle` crate.
+    ///
+    /// Returns `None` for kinds of windows pugl can not embed into,
+    /// like Wayland surfaces. Only available with the feature
+    /// `raw-window-handle`.
+    #[cfg(feature = "raw-window-handle")]
+    pub fn from_raw_window_handle(handle: RawWindowHandle) -> Option<ParentWindow> {
+        match handle {
+            #[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd",
+                      target_os = "netbsd", target_os = "openbsd"))]
+            RawWindowHandle::Xlib(h) => Some(ParentWindow::from_x11(h.window)),
+            #[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd",
+                      target_os = "netbsd", target_os = "openbsd"))]
+            RawWindowHandle::Xcb(h) => Some(ParentWindow::from_x11(c_ulong::from(h.window))),
+            #[cfg(target_os = "windows")]
+            RawWindowHandle::Windows(h) => Some(ParentWindow::from_hwnd(h.hwnd)),
+            #[cfg(target_os = "macos")]
+            RawWindowHandle::MacOS(h) => Some(ParentWindow::from_ns_view(h.ns_view)),
+            _ => None
+        }
+    }
+
+    /// The native window `handle` as pugl reports it.
+    pub(crate) fn from_ptr(handle: *mut c_void) -> ParentWindow {
+        ParentWindow(handle)
+    }
+
+    /// Returns `true` iff there is no parent window.
+    pub fn is_none(&self) -> bool {
+        self.0.is_null()
+    }
+
+    /// Returns the handle as pugl expects it.
+    pub fn as_ptr(&self) -> *mut c_void {
+        self.0
+    }
+}
+
+impl Default for ParentWindow {
+    fn default() -> ParentWindow {
+        ParentWindow::none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn native_handles() {
+        assert!(ParentWindow::none().is_none());
+        assert!(ParentWindow::default().is_none());
+        let x11 = ParentWindow::from_x11(0x0340_0007);
+        assert!(!x11.is_none());
+        assert_eq!(x11.as_ptr() as usize, 0x0340_0007);
+    }
+
+    #[cfg(all(feature = "raw-window-handle", target_os = "linux"))]
+    #[test]
+    fn raw_window_handles() {
+        use raw_window_handle::unix::{WaylandHandle, XcbHandle, XlibHandle};
+
+        let xlib = RawWindowHandle::Xlib(XlibHandle { window: 0x0340_0007, ..XlibHandle::empty() });
+        assert_eq!(ParentWindow::from_raw_window_handle(xlib), Some(ParentWindow::from_x11(0x0340_0007)));
+        let xcb = RawWindowHandle::Xcb(XcbHandle { window: 42, ..XcbHandle::empty() });
+        assert_eq!(ParentWindow::from_raw_window_handle(xcb), Some(ParentWindow::from_x11(42)));
+        let wayland = RawWindowHandle::Wayland(WaylandHandle::empty());
+        assert_eq!(ParentWindow::from_raw_window_handle(wayland), None);
+    }
+}
@@ -0,0 +1,218 @@
+//! Recognizing long presses and swipes from the pointer events
+//!
+//! Widgets returning `true` by
+//! [`Widget::recognizes_gestures()`](../widget/trait.Widget.html#method.recognizes_gestures)
+//! are notified by
+//! [`Widget::pointer_gesture()`](../widget/trait.Widget.html#method.pointer_gesture)
+//! when the `UI` has recognized a [`PointerGesture`](enum.PointerGesture.html)
+//! made with the primary button starting on them. The raw pointer
+//! events are delivered as usual in addition.
+//!
+//! The thresholds are set by
+//! [`UI::set_gesture_settings()`](../ui/struct.UI.html#method.set_gesture_settings).
+//! Multi touch gestures like pinching are not recognized, as the
+//! windowing system interface does not report touch points.
+use pugl_sys::*;
+
+use crate::widget::Id;
+
+/// The timer id the `UI` uses for the long press delay.
+///
+/// Next to the tooltip timer, at the upper end of the timer ids.
+pub(crate) const LONG_PRESS_TIMER: usize = usize::MAX - 1;
+
+/// The direction of a swipe
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SwipeDirection {
+    Up,
+    Down,
+    Left,
+    Right
+}
+
+/// A gesture recognized from the pointer events
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PointerGesture {
+    /// The button has been held down at `pos` without moving for the
+    /// long press delay. Reported while the button is still down.
+    LongPress { pos: Coord },
+    /// The pointer has been moved quickly while the button was down,
+    /// and released. `velocity` is in unscaled pixels per second.
+    Swipe { direction: SwipeDirection, velocity: Coord }
+}
+
+/// The thresholds of the gesture recognition
+///
+/// Lengths are unscaled like the widget geometries.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GestureSettings {
+    /// The time in seconds the button needs to be held for a long press
+    pub long_press_delay: f64,
+    /// The distance the pointer may move without cancelling a long press
+    pub move_tolerance: f64,
+    /// The minimal distance between press and release of a swipe
+    pub swipe_min_distance: f64,
+    /// The minimal velocity in pixels per second at the release of a swipe
+    pub swipe_min_velocity: f64
+}
+
+impl Default for GestureSettings {
+    fn default() -> GestureSettings {
+        GestureSettings {
+            long_press_delay: 0.5,
+            move_tolerance: 8.0,
+            swipe_min_distance: 40.0,
+            swipe_min_velocity: 600.0
+        }
+    }
+}
+
+/// The velocity is measured over the motion of this last period in seconds
+const VELOCITY_WINDOW: f64 = 0.1;
+
+struct Press {
+    widget: Id,
+    origin: Coord,
+    moved: bool,
+    samples: Vec<(Coord, f64)>
+}
+
+/// Keeps track of the pointer to recognize gestures
+pub(crate) struct GestureRecognizer {
+    settings: GestureSettings,
+    press: Option<Press>
+}
+
+impl GestureRecognizer {
+    pub(crate) fn new() -> GestureRecognizer {
+        GestureRecognizer {
+            settings: GestureSettings::default(),
+            press: None
+        }
+    }
+
+    pub(crate) fn settings(&self) -> GestureSettings {
+        self.settings
+    }
+
+    pub(crate) fn set_settings(&mut self, settings: GestureSettings) {
+        self.settings = settings;
+    }
+
+    /// Starts tracking a press on `widget` at `pos` at `time` seconds.
+    pub(crate) fn press(&mut self, widget: Id, pos: Coord, time: f64) {
+        self.press = Some(Press { widget, origin: pos, moved: false, samples: vec![(pos, time)] });
+    }
+
+    /// Tracks the motion to `pos` while pressed.
+    ///
+    /// Returns `true` iff the motion cancels the long press.
+    pub(crate) fn motion(&mut self, pos: Coord, time: f64) -> bool {
+        let tolerance = self.settings.move_tolerance;
+        let press = match self.press.as_mut() {
+            Some(press) => press,
+            None => return false
+        };
+        press.samples.retain(|(_, t)| time - t <= VELOCITY_WINDOW);
+        press.samples.push((pos, time));
+        if !press.moved && distance(press.origin, pos) > tolerance {
+            press.moved = true;
+            return true;
+        }
+        false
+    }
+
+    /// Returns the long press to be reported as the delay has passed,
+    /// if the pointer has not moved.
+    pub(crate) fn long_press(&mut self) -> Option<(Id, PointerGesture)> {
+        let press = self.press.as_mut()?;
+        if press.moved {
+            return None;
+        }
+        press.moved = true;
+        Some((press.widget, PointerGesture::LongPress { pos: press.origin }))
+    }
+
+    /// Ends the press by the release at `pos` and returns the swipe
+    /// to be reported, if any.
+    pub(crate) fn release(&mut self, pos: Coord, time: f64) -> Option<(Id, PointerGesture)> {
+        let mut press = self.press.take()?;
+        if distance(press.origin, pos) < self.settings.swipe_min_distance {
+            return None;
+        }
+        press.samples.retain(|(_, t)| time - t <= VELOCITY_WINDOW);
+        let (start, start_time) = *press.samples.first().unwrap_or(&(press.origin, time));
+        let dt = time - start_time;
+        if dt <= 0. {
+            return None;
+        }
+        let velocity = Coord { x: (pos.x - start.x) / dt, y: (pos.y - start.y) / dt };
+        if (velocity.x * velocity.x + velocity.y * velocity.y).sqrt() < self.settings.swipe_min_velocity {
+            return None;
+        }
+        let direction = if velocity.x.abs() > velocity.y.abs() {
+            if velocity.x > 0. { SwipeDirection::Right } else { SwipeDirection::Left }
+        } else if velocity.y > 0. {
+            SwipeDirection::Down
+        } else {
+            SwipeDirection::Up
+        };
+        Some((press.widget, PointerGesture::Swipe { direction, velocity }))
+    }
+
+    /// Forgets the ongoing press, for example if the widget is gone.
+    pub(crate) fn cancel(&mut self) {
+        self.press = None;
+    }
+}
+
+fn distance(a: Coord, b: Coord) -> f64 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_press() {
+        let mut rec = GestureRecognizer::new();
+        rec.press(3, Coord { x: 10., y: 10. }, 0.);
+        assert!(!rec.motion(Coord { x: 13., y: 12. }, 0.1));
+        assert_eq!(rec.long_press(), Some((3, PointerGesture::LongPress { pos: Coord { x: 10., y: 10. } })));
+        assert_eq!(rec.long_press(), None);
+        assert_eq!(rec.release(Coord { x: 13., y: 12. }, 0.6), None);
+    }
+
+    #[test]
+    fn motion_cancels_long_press() {
+        let mut rec = GestureRecognizer::new();
+        rec.press(3, Coord { x: 10., y: 10. }, 0.);
+        assert!(rec.motion(Coord { x: 20., y: 10. }, 0.1));
+        assert!(!rec.motion(Coord { x: 30., y: 10. }, 0.2));
+        assert_eq!(rec.long_press(), None);
+    }
+
+    #[test]
+    fn swipe() {
+        let mut rec = GestureRecognizer::new();
+        rec.press(2, Coord { x: 100., y: 10. }, 0.);
+        rec.motion(Coord { x: 80., y: 12. }, 0.02);
+        rec.motion(Coord { x: 40., y: 14. }, 0.06);
+        match rec.release(Coord { x: 20., y: 14. }, 0.08) {
+            Some((2, PointerGesture::Swipe { direction, velocity })) => {
+                assert_eq!(direction, SwipeDirection::Left);
+                assert!((velocity.x + 1000.).abs() < 1e-6);
+            }
+            other => panic!("no swipe recognized: {:?}", other)
+        }
+    }
+
+    #[test]
+    fn slow_drag_is_no_swipe() {
+        let mut rec = GestureRecognizer::new();
+        rec.press(2, Coord { x: 10., y: 10. }, 0.);
+        rec.motion(Coord { x: 10., y: 60. }, 0.9);
+        assert_eq!(rec.release(Coord { x: 10., y: 70. }, 1.0), None);
+    }
+}
@@ -0,0 +1,208 @@
+//! Comparing rendered UIs against reference images
+//!
+//! Only available with the feature `testing`.
+//!
+//! Widget crates can write visual regression tests without a display
+//! server: the UI is set up on the mock view, rendered offscreen by
+//! [`render_ui()`](fn.render_ui.html) and compared to a reference PNG
+//! by [`assert_matches_png()`](fn.assert_matches_png.html).
+//!
+//! If the environment variable `PUGL_UI_UPDATE_GOLDEN` is set, the
+//! reference images are written rather than compared, in order to
+//! create them initially or to update them after an intended change.
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use cairo::ImageSurface;
+
+use crate::ui::UI;
+use crate::widget::Widget;
+
+/// The environment variable requesting to write the reference images.
+pub const UPDATE_VARIABLE: &str = "PUGL_UI_UPDATE_GOLDEN";
+
+/// The result of comparing two images of the same size
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ImageDiff {
+    /// The number of pixels with a channel deviating by more than the tolerance
+    pub differing_pixels: usize,
+    /// The largest deviation of a channel of any pixel
+    pub max_deviation: u8
+}
+
+/// Error returned by [`assert_matches_png()`](fn.assert_matches_png.html)
+#[derive(Debug)]
+pub enum GoldenError {
+    /// The reference image could not be read or written.
+    Io(io::Error),
+    /// The reference image could not be decoded or encoded.
+    Png(cairo::IoError),
+    /// The size `(width, height)` of the rendered image differs from the reference.
+    SizeMismatch { expected: (i32, i32), actual: (i32, i32) },
+    /// The rendered image differs from the reference.
+    Mismatch(ImageDiff)
+}
+
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GoldenError::Io(err) => write!(f, "reference image not accessible: {}", err),
+            GoldenError::Png(err) => write!(f, "reference image not processable: {}", err),
+            GoldenError::SizeMismatch { expected, actual } =>
+                write!(f, "image size {}x{} differs from the reference size {}x{}",
+                       actual.0, actual.1, expected.0, expected.1),
+            GoldenError::Mismatch(diff) =>
+                write!(f, "{} pixels differ from the reference, by up to {}",
+                       diff.differing_pixels, diff.max_deviation)
+        }
+    }
+}
+
+impl Error for GoldenError {}
+
+impl From<io::Error> for GoldenError {
+    fn from(err: io::Error) -> GoldenError {
+        GoldenError::Io(err)
+    }
+}
+
+impl From<cairo::IoError> for GoldenError {
+    fn from(err: cairo::IoError) -> GoldenError {
+        GoldenError::Png(err)
+    }
+}
+
+/// Renders the whole `ui` into an image surface.
+///
+/// See [`UI::render_to_surface()`](../ui/struct.UI.html#method.render_to_surface).
+pub fn render_ui<RW: Widget + 'static>(ui: &mut UI<RW>) -> ImageSurface {
+    let root = ui.root_layout().widget();
+    ui.render_to_surface(root)
+}
+
+/// Compares the images `actual` and `expected` pixel by pixel.
+///
+/// A pixel differs if any of its channels deviates by more than
+/// `tolerance`. Returns `Err(GoldenError::SizeMismatch)` if the
+/// sizes of the images differ.
+pub fn compare_images(actual: &ImageSurface, expected: &ImageSurface, tolerance: u8) -> Result<ImageDiff, GoldenError> {
+    let size = (actual.get_width(), actual.get_height());
+    let expected_size = (expected.get_width(), expected.get_height());
+    if size != expected_size {
+        return Err(GoldenError::SizeMismatch { expected: expected_size, actual: size });
+    }
+    let mut actual = to_argb32(actual);
+    let mut expected = to_argb32(expected);
+    let stride = actual.get_stride() as usize;
+    let actual = actual.get_data().expect("image data borrowed");
+    let expected = expected.get_data().expect("image data borrowed");
+
+    let mut diff = ImageDiff::default();
+    for row in 0..size.1 as usize {
+        let start = row * stride;
+        let end = start + 4 * size.0 as usize;
+        for (a, e) in actual[start..end].chunks(4).zip(expected[start..end].chunks(4)) {
+            let deviation = a.iter().zip(e.iter())
+                .map(|(a, e)| if a > e { a - e } else { e - a })
+                .max()
+                .unwrap_or(0);
+            diff.max_deviation = diff.max_deviation.max(deviation);
+            if deviation > tolerance {
+                diff.differing_pixels += 1;
+            }
+        }
+    }
+    Ok(diff)
+}
+
+/// Compares the image `surface` to the reference PNG at `path`.
+///
+/// Returns `Err(GoldenError::Mismatch)` if any pixel differs by more
+/// than `tolerance` in any channel. If the environment variable
+/// [`UPDATE_VARIABLE`](constant.UPDATE_VARIABLE.html) is set, the
+/// reference is written from `surface` instead.
+pub fn assert_matches_png<P: AsRef<Path>>(surface: &ImageSurface, path: P, tolerance: u8) -> Result<(), GoldenError> {
+    if std::env::var_os(UPDATE_VARIABLE).is_some() {
+        surface.write_to_png(&mut File::create(path)?)?;
+        return Ok(());
+    }
+    let expected = ImageSurface::create_from_png(&mut File::open(path)?)?;
+    let diff = compare_images(surface, &expected, tolerance)?;
+    if diff.differing_pixels > 0 {
+        return Err(GoldenError::Mismatch(diff));
+    }
+    Ok(())
+}
+
+/// Copies `surface` to a new surface of the format `ARgb32`.
+fn to_argb32(surface: &ImageSurface) -> ImageSurface {
+    let copy = ImageSurface::create(cairo::Format::ARgb32, surface.get_width(), surface.get_height())
+        .expect("Creation of image surface failed");
+    {
+        let cr = cairo::Context::new(&copy);
+        cr.set_source_surface(surface, 0., 0.);
+        cr.set_operator(cairo::Operator::Source);
+        cr.paint();
+    }
+    copy.flush();
+    copy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(w: i32, h: i32, (r, g, b): (f64, f64, f64)) -> ImageSurface {
+        let surface = ImageSurface::create(cairo::Format::ARgb32, w, h).unwrap();
+        {
+            let cr = cairo::Context::new(&surface);
+            cr.set_source_rgb(r, g, b);
+            cr.paint();
+        }
+        surface
+    }
+
+    #[test]
+    fn identical_images() {
+        let diff = compare_images(&filled(4, 3, (0.5, 0., 1.)), &filled(4, 3, (0.5, 0., 1.)), 0).unwrap();
+        assert_eq!(diff, ImageDiff::default());
+    }
+
+    #[test]
+    fn deviation_within_tolerance() {
+        let actual = filled(4, 3, (0.5, 0., 1.));
+        let expected = filled(4, 3, (0.51, 0., 1.));
+        let diff = compare_images(&actual, &expected, 2).unwrap();
+        assert_eq!(diff.differing_pixels, 0);
+        assert!(diff.max_deviation > 0);
+        assert_eq!(compare_images(&actual, &expected, 0).unwrap().differing_pixels, 12);
+    }
+
+    #[test]
+    fn size_mismatch() {
+        match compare_images(&filled(4, 3, (0., 0., 0.)), &filled(3, 4, (0., 0., 0.)), 0) {
+            Err(GoldenError::SizeMismatch { expected, actual }) => {
+                assert_eq!(expected, (3, 4));
+                assert_eq!(actual, (4, 3));
+            }
+            _ => panic!("size mismatch not detected")
+        }
+    }
+
+    #[test]
+    fn png_reference() {
+        let path = std::env::temp_dir().join(format!("pugl-ui-golden-{}.png", std::process::id()));
+        filled(4, 3, (1., 0., 0.)).write_to_png(&mut File::create(&path).unwrap()).unwrap();
+
+        assert!(assert_matches_png(&filled(4, 3, (1., 0., 0.)), &path, 0).is_ok());
+        let result = assert_matches_png(&filled(4, 3, (0., 1., 0.)), &path, 16);
+        std::fs::remove_file(&path).unwrap();
+        match result {
+            Err(GoldenError::Mismatch(diff)) => assert_eq!(diff.differing_pixels, 12),
+            _ => panic!("mismatch not detected")
+        }
+    }
+}
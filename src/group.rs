@@ -0,0 +1,207 @@
+//! Running several `UI` windows in one event loop
+//!
+//! An application with detachable windows, like a meter or an editor
+//! window next to the main window, has a `UI` with its own view for
+//! each window. A [`UiGroup`](struct.UiGroup.html) owns the views and
+//! pumps the events for all of them, so that the application only
+//! calls [`UiGroup::next_event()`](struct.UiGroup.html#method.next_event)
+//! in its event loop.
+//!
+//! ```ignore
+//! let mut group = UiGroup::new();
+//! let main = group.add(PuglView::new(std::ptr::null_mut(), |pv| UI::new(pv, Box::new(MainRoot::default()))));
+//! let meters = group.add(PuglView::new(std::ptr::null_mut(), |pv| UI::new(pv, Box::new(MeterRoot::default()))));
+//!
+//! while !group.ui::<MainRoot>(main).unwrap().close_request_issued() {
+//!     group.next_event_auto();
+//!     if group.close_request_issued(meters) {
+//!         group.remove(meters);
+//!     }
+//! }
+//! ```
+//!
+//! The widgets of the windows are separate, as each `UI` has its own
+//! widgets. Widgets of different windows communicate through the
+//! application, for example by
+//! [`UI::send_message()`](../ui/struct.UI.html#method.send_message).
+use std::any::Any;
+use std::time::Duration;
+
+use pugl_sys::*;
+
+use crate::ui::UI;
+use crate::widget::Widget;
+
+/// The identifier of a view in a [`UiGroup`](struct.UiGroup.html)
+pub type ViewId = usize;
+
+/// The longest time in seconds a `UiGroup` of several views waits
+/// without looking for new events of the other views.
+pub(crate) const POLL_PERIOD: f64 = 1. / 60.;
+
+/// A view in the group, independent of the root widget type
+trait Member {
+    fn next_event(&mut self, timeout: f64);
+    fn auto_timeout(&mut self) -> f64;
+    fn close_request_issued(&mut self) -> bool;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<RW: Widget + 'static> Member for Box<PuglView<UI<RW>>> {
+    fn next_event(&mut self, timeout: f64) { self.handle().next_event(timeout) }
+    fn auto_timeout(&mut self) -> f64 { self.handle().auto_timeout() }
+    fn close_request_issued(&mut self) -> bool { self.handle().close_request_issued() }
+    fn as_any_mut(&mut self) -> &mut dyn Any { self }
+}
+
+/// Pumps the events of several views in one loop
+#[derive(Default)]
+pub struct UiGroup {
+    views: Vec<Option<Box<dyn Member>>>
+}
+
+impl UiGroup {
+    /// Creates an empty group.
+    pub fn new() -> UiGroup {
+        UiGroup::default()
+    }
+
+    /// Adds the view `view` of a `UI` to the group and returns its id.
+    pub fn add<RW: Widget + 'static>(&mut self, view: Box<PuglView<UI<RW>>>) -> ViewId {
+        let member: Box<dyn Member> = Box::new(view);
+        match self.views.iter().position(Option::is_none) {
+            Some(id) => {
+                self.views[id] = Some(member);
+                id
+            }
+            None => {
+                self.views.push(Some(member));
+                self.views.len() - 1
+            }
+        }
+    }
+
+    /// Removes the view `id` from the group and closes its window.
+    ///
+    /// Returns `false` if there is no view `id`. The id can be handed
+    /// out again by [`add()`](#method.add).
+    pub fn remove(&mut self, id: ViewId) -> bool {
+        self.views.get_mut(id).and_then(Option::take).is_some()
+    }
+
+    /// Returns the `UI` of the view `id`.
+    ///
+    /// Returns `None` if there is no view `id` or if the root widget
+    /// of its `UI` is not of type `RW`.
+    pub fn ui<RW: Widget + 'static>(&mut self, id: ViewId) -> Option<&mut UI<RW>> {
+        let member = self.views.get_mut(id)?.as_mut()?;
+        member.as_any_mut().downcast_mut::<Box<PuglView<UI<RW>>>>().map(|view| view.handle())
+    }
+
+    /// Returns the ids of the views in the group.
+    pub fn view_ids(&self) -> Vec<ViewId> {
+        self.views.iter().enumerate().filter_map(|(id, v)| v.as_ref().map(|_| id)).collect()
+    }
+
+    /// Returns the number of views in the group.
+    pub fn len(&self) -> usize {
+        self.views.iter().filter(|v| v.is_some()).count()
+    }
+
+    /// Returns `true` iff there are no views in the group.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` iff the window of the view `id` has been
+    /// requested to close, see
+    /// [`UI::close_request_issued()`](../ui/struct.UI.html#method.close_request_issued).
+    pub fn close_request_issued(&mut self, id: ViewId) -> bool {
+        match self.views.get_mut(id).and_then(Option::as_mut) {
+            Some(member) => member.close_request_issued(),
+            None => false
+        }
+    }
+
+    /// Initiates the next cycle of the event loop of all the views.
+    ///
+    /// `timeout` is as in
+    /// [`UI::next_event()`](../ui/struct.UI.html#method.next_event).
+    /// A single view waits for its events as usual. With several views
+    /// all of them are polled without blocking, and the group then
+    /// sleeps for `timeout` seconds, but not longer than a 60th of a
+    /// second to keep all windows responsive.
+    pub fn next_event(&mut self, timeout: f64) {
+        let mut members: Vec<&mut Box<dyn Member>> = self.views.iter_mut().filter_map(Option::as_mut).collect();
+        if members.len() == 1 {
+            members[0].next_event(timeout);
+            return;
+        }
+        for member in members {
+            member.next_event(0.0);
+        }
+        if timeout != 0.0 && !self.is_empty() {
+            let wait = if timeout < 0.0 { POLL_PERIOD } else { timeout.min(POLL_PERIOD) };
+            std::thread::sleep(Duration::from_secs_f64(wait));
+        }
+    }
+
+    /// Initiates the next cycle of the event loop of all the views
+    /// with the shortest timeout chosen by their
+    /// [`IdlePolicy`](../ui/enum.IdlePolicy.html)s.
+    pub fn next_event_auto(&mut self) {
+        let timeout = self.views.iter_mut()
+            .filter_map(Option::as_mut)
+            .map(|member| member.auto_timeout())
+            .fold(-1.0, |shortest: f64, t| if shortest < 0.0 { t } else if t < 0.0 { shortest } else { shortest.min(t) });
+        self.next_event(timeout);
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct MainRoot {
+        stub: crate::widget::WidgetStub
+    }
+
+    impl Widget for MainRoot {
+        widget_stub!();
+    }
+
+    #[derive(Default)]
+    struct MeterRoot {
+        stub: crate::widget::WidgetStub,
+        messages: usize
+    }
+
+    impl Widget for MeterRoot {
+        widget_stub!();
+        fn message(&mut self, _msg: Box<dyn Any>) {
+            self.messages += 1;
+        }
+    }
+
+    #[test]
+    fn pump_several_views() {
+        let mut group = UiGroup::new();
+        let main = group.add(PuglView::new(std::ptr::null_mut(), |pv| UI::new(pv, Box::new(MainRoot::default()))));
+        let meters = group.add(PuglView::new(std::ptr::null_mut(), |pv| UI::new(pv, Box::new(MeterRoot::default()))));
+        assert_eq!(group.view_ids(), vec![main, meters]);
+
+        assert!(group.ui::<MeterRoot>(main).is_none());
+        let ui = group.ui::<MeterRoot>(meters).unwrap();
+        ui.send_message(crate::widget::WidgetHandle::<MeterRoot>::new(0), Box::new(()));
+
+        group.next_event(0.0);
+        assert_eq!(group.ui::<MeterRoot>(meters).unwrap().root_widget().messages, 1);
+        assert!(!group.close_request_issued(meters));
+
+        assert!(group.remove(meters));
+        assert!(!group.remove(meters));
+        assert_eq!(group.len(), 1);
+        assert!(group.ui::<MainRoot>(main).is_some());
+    }
+}
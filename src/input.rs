@@ -0,0 +1,354 @@
+//! Editing state for text entry widgets
+//!
+//! An [`EditableText`](struct.EditableText.html) keeps the text, the
+//! cursor and the selection of a text entry and implements the usual
+//! editing operations. Text entry widgets embed it, pass their key
+//! presses to [`EditableText::handle_key()`](struct.EditableText.html#method.handle_key)
+//! and draw the text, the cursor and the selection.
+//!
+//! The widget should return `true` from
+//! [`Widget::takes_text_input()`](../widget/trait.Widget.html#method.takes_text_input),
+//! so that the UI passes the key events only to it while it is
+//! focused, and delivers the clipboard content to
+//! [`Widget::paste()`](../widget/trait.Widget.html#method.paste) on
+//! `Ctrl-V`. Copied and cut text is passed to the UI's clipboard by
+//! [`Widget::copy_to_clipboard()`](../widget/trait.Widget.html#method.copy_to_clipboard).
+//!
+//! All positions are byte indices into the text and are always at
+//! character boundaries.
+use std::ops::Range;
+
+use pugl_sys::*;
+
+/// The result of [`EditableText::handle_key()`](struct.EditableText.html#method.handle_key)
+#[derive(Clone, Debug, PartialEq)]
+pub enum EditOutcome {
+    /// The key is not an editing key.
+    Ignored,
+    /// The cursor or the selection has moved, the text is unchanged.
+    Moved,
+    /// The text has changed.
+    Edited,
+    /// The text is to be copied to the clipboard. The text has
+    /// changed, if the key was the one for cutting.
+    Copy { text: String, cut: bool }
+}
+
+/// The text, the cursor and the selection of a text entry
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EditableText {
+    text: String,
+    cursor: usize,
+    anchor: Option<usize>
+}
+
+impl EditableText {
+    /// Creates a new instance containing `text` with the cursor at its end.
+    pub fn new(text: &str) -> EditableText {
+        EditableText {
+            text: String::from(text),
+            cursor: text.len(),
+            anchor: None
+        }
+    }
+
+    /// Returns the text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text by `text` moving the cursor to its end.
+    pub fn set_text(&mut self, text: &str) {
+        *self = EditableText::new(text);
+    }
+
+    /// Returns the position of the cursor.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Returns the selected range, if any.
+    pub fn selection(&self) -> Option<Range<usize>> {
+        match self.anchor {
+            Some(a) if a < self.cursor => Some(a..self.cursor),
+            Some(a) if a > self.cursor => Some(self.cursor..a),
+            _ => None
+        }
+    }
+
+    /// Returns the selected text, if any.
+    pub fn selected_text(&self) -> Option<&str> {
+        self.selection().map(|sel| &self.text[sel])
+    }
+
+    /// Selects the whole text.
+    pub fn select_all(&mut self) {
+        self.anchor = Some(0);
+        self.cursor = self.text.len();
+    }
+
+    /// Inserts `s` at the cursor replacing the selection.
+    ///
+    /// Also used for text coming from input methods or the clipboard.
+    pub fn insert(&mut self, s: &str) {
+        self.delete_selection();
+        self.text.insert_str(self.cursor, s);
+        self.cursor += s.len();
+    }
+
+    /// Deletes the selection or, if there is none, the character before the cursor.
+    pub fn delete_backward(&mut self) {
+        if !self.delete_selection() && self.cursor > 0 {
+            let pos = self.prev_char(self.cursor);
+            self.text.replace_range(pos..self.cursor, "");
+            self.cursor = pos;
+        }
+    }
+
+    /// Deletes the selection or, if there is none, the character after the cursor.
+    pub fn delete_forward(&mut self) {
+        if !self.delete_selection() && self.cursor < self.text.len() {
+            let pos = self.next_char(self.cursor);
+            self.text.replace_range(self.cursor..pos, "");
+        }
+    }
+
+    /// Moves the cursor one character to the left, extending the
+    /// selection if `select` is `true`.
+    pub fn move_left(&mut self, select: bool) {
+        let pos = match self.selection() {
+            Some(sel) if !select => sel.start,
+            _ => self.prev_char(self.cursor)
+        };
+        self.move_to(pos, select);
+    }
+
+    /// Moves the cursor one character to the right, extending the
+    /// selection if `select` is `true`.
+    pub fn move_right(&mut self, select: bool) {
+        let pos = match self.selection() {
+            Some(sel) if !select => sel.end,
+            _ => self.next_char(self.cursor)
+        };
+        self.move_to(pos, select);
+    }
+
+    /// Moves the cursor to the beginning of the current or previous
+    /// word, extending the selection if `select` is `true`.
+    pub fn move_word_left(&mut self, select: bool) {
+        let mut pos = self.cursor;
+        while pos > 0 && !is_word_char(self.char_before(pos)) {
+            pos = self.prev_char(pos);
+        }
+        while pos > 0 && is_word_char(self.char_before(pos)) {
+            pos = self.prev_char(pos);
+        }
+        self.move_to(pos, select);
+    }
+
+    /// Moves the cursor to the end of the current or next word,
+    /// extending the selection if `select` is `true`.
+    pub fn move_word_right(&mut self, select: bool) {
+        let mut pos = self.cursor;
+        while pos < self.text.len() && !is_word_char(self.char_after(pos)) {
+            pos = self.next_char(pos);
+        }
+        while pos < self.text.len() && is_word_char(self.char_after(pos)) {
+            pos = self.next_char(pos);
+        }
+        self.move_to(pos, select);
+    }
+
+    /// Moves the cursor to the beginning of the text, extending the
+    /// selection if `select` is `true`.
+    pub fn move_home(&mut self, select: bool) {
+        self.move_to(0, select);
+    }
+
+    /// Moves the cursor to the end of the text, extending the
+    /// selection if `select` is `true`.
+    pub fn move_end(&mut self, select: bool) {
+        self.move_to(self.text.len(), select);
+    }
+
+    /// Performs the editing operation of the key press `key`.
+    ///
+    /// * printable characters are inserted
+    /// * `Backspace` and `Delete` delete
+    /// * `Left`, `Right`, `Home` and `End` move the cursor, by words
+    ///   with `Ctrl`, extending the selection with `Shift`
+    /// * `Ctrl-A` selects all
+    /// * `Ctrl-C` and `Ctrl-X` copy and cut the selection
+    ///
+    /// Pasting is not handled here, as the clipboard content is
+    /// delivered by [`Widget::paste()`](../widget/trait.Widget.html#method.paste).
+    pub fn handle_key(&mut self, key: &Key) -> EditOutcome {
+        let ctrl = key.modifiers.ctrl();
+        let shift = key.modifiers.shift();
+        match key.key {
+            KeyVal::Character('\u{8}') => {
+                self.delete_backward();
+                EditOutcome::Edited
+            }
+            KeyVal::Character('\u{7f}') => {
+                self.delete_forward();
+                EditOutcome::Edited
+            }
+            KeyVal::Character(c) if ctrl => match ctrl_letter(c) {
+                'a' => {
+                    self.select_all();
+                    EditOutcome::Moved
+                }
+                'c' | 'x' => match self.selected_text().map(String::from) {
+                    Some(text) => {
+                        let cut = ctrl_letter(c) == 'x';
+                        if cut {
+                            self.delete_selection();
+                        }
+                        EditOutcome::Copy { text, cut }
+                    }
+                    None => EditOutcome::Ignored
+                },
+                _ => EditOutcome::Ignored
+            },
+            KeyVal::Character(c) if !c.is_control() => {
+                let mut buf = [0; 4];
+                self.insert(c.encode_utf8(&mut buf));
+                EditOutcome::Edited
+            }
+            KeyVal::Special(SpecialKey::Left) if ctrl => { self.move_word_left(shift); EditOutcome::Moved }
+            KeyVal::Special(SpecialKey::Right) if ctrl => { self.move_word_right(shift); EditOutcome::Moved }
+            KeyVal::Special(SpecialKey::Left) => { self.move_left(shift); EditOutcome::Moved }
+            KeyVal::Special(SpecialKey::Right) => { self.move_right(shift); EditOutcome::Moved }
+            KeyVal::Special(SpecialKey::Home) => { self.move_home(shift); EditOutcome::Moved }
+            KeyVal::Special(SpecialKey::End) => { self.move_end(shift); EditOutcome::Moved }
+            _ => EditOutcome::Ignored
+        }
+    }
+
+    fn move_to(&mut self, pos: usize, select: bool) {
+        if select {
+            if self.anchor.is_none() {
+                self.anchor = Some(self.cursor);
+            }
+        } else {
+            self.anchor = None;
+        }
+        self.cursor = pos;
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        let sel = self.selection();
+        self.anchor = None;
+        match sel {
+            Some(sel) => {
+                self.cursor = sel.start;
+                self.text.replace_range(sel, "");
+                true
+            }
+            None => false
+        }
+    }
+
+    fn prev_char(&self, pos: usize) -> usize {
+        self.text[..pos].char_indices().next_back().map_or(0, |(i, _)| i)
+    }
+
+    fn next_char(&self, pos: usize) -> usize {
+        self.text[pos..].chars().next().map_or(pos, |c| pos + c.len_utf8())
+    }
+
+    fn char_before(&self, pos: usize) -> char {
+        self.text[..pos].chars().next_back().unwrap_or(' ')
+    }
+
+    fn char_after(&self, pos: usize) -> char {
+        self.text[pos..].chars().next().unwrap_or(' ')
+    }
+}
+
+/// Returns the lower case letter of `c`, which might be reported as
+/// a control character if `Ctrl` is pressed.
+pub(crate) fn ctrl_letter(c: char) -> char {
+    match c as u32 {
+        1..=26 => (b'a' + (c as u8) - 1) as char,
+        _ => c.to_ascii_lowercase()
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_and_delete() {
+        let mut et = EditableText::new("ac");
+        et.move_left(false);
+        et.insert("bä");
+        assert_eq!(et.text(), "abäc");
+        assert_eq!(et.cursor(), 4);
+        et.delete_backward();
+        assert_eq!(et.text(), "abc");
+        et.delete_forward();
+        assert_eq!(et.text(), "ab");
+        et.delete_forward();
+        assert_eq!(et.text(), "ab");
+        et.move_home(false);
+        et.delete_backward();
+        assert_eq!(et.text(), "ab");
+    }
+
+    #[test]
+    fn selection_replaced() {
+        let mut et = EditableText::new("gain level");
+        et.move_word_left(true);
+        assert_eq!(et.selected_text(), Some("level"));
+        et.insert("knob");
+        assert_eq!(et.text(), "gain knob");
+        assert_eq!(et.selection(), None);
+
+        et.move_home(false);
+        et.move_right(true);
+        et.move_right(true);
+        assert_eq!(et.selection(), Some(0..2));
+        et.move_right(false);
+        assert_eq!(et.cursor(), 2);
+        assert_eq!(et.selection(), None);
+
+        et.select_all();
+        et.delete_backward();
+        assert_eq!(et.text(), "");
+    }
+
+    #[test]
+    fn word_movement() {
+        let mut et = EditableText::new("one, two_2  three");
+        et.move_home(false);
+        et.move_word_right(false);
+        assert_eq!(et.cursor(), 3);
+        et.move_word_right(false);
+        assert_eq!(et.cursor(), 10);
+        et.move_word_right(false);
+        assert_eq!(et.cursor(), 17);
+        et.move_word_left(false);
+        assert_eq!(et.cursor(), 12);
+        et.move_word_left(false);
+        assert_eq!(et.cursor(), 5);
+    }
+
+    #[test]
+    fn keys_edit() {
+        let key = |key| Key { key, modifiers: Modifiers::default(), code: 0 };
+        let mut et = EditableText::default();
+        assert_eq!(et.handle_key(&key(KeyVal::Character('h'))), EditOutcome::Edited);
+        assert_eq!(et.handle_key(&key(KeyVal::Character('i'))), EditOutcome::Edited);
+        assert_eq!(et.handle_key(&key(KeyVal::Special(SpecialKey::Left))), EditOutcome::Moved);
+        assert_eq!(et.handle_key(&key(KeyVal::Character('\u{8}'))), EditOutcome::Edited);
+        assert_eq!(et.text(), "i");
+        assert_eq!(et.handle_key(&key(KeyVal::Character('\t'))), EditOutcome::Ignored);
+    }
+}
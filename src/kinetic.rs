@@ -0,0 +1,178 @@
+//! Kinetic scrolling after drags in scroll containers
+use pugl_sys::*;
+
+use crate::widget::Id;
+
+/// The timer id the `UI` uses for the steps of the kinetic scrolling.
+pub(crate) const KINETIC_TIMER: usize = usize::MAX - 2;
+
+/// The period of the kinetic scrolling steps in seconds
+pub(crate) const KINETIC_PERIOD: f64 = 1. / 60.;
+
+/// The velocity is measured over the motion of this last period in seconds
+const VELOCITY_WINDOW: f64 = 0.1;
+
+/// The scrolling stops below this velocity in pixels per second
+const MIN_VELOCITY: f64 = 20.0;
+
+/// Keeps track of the scroll position of a dragged scroll container
+/// and continues the scrolling after the release
+pub(crate) struct KineticScroller {
+    widget: Option<Id>,
+    samples: Vec<(Coord, f64)>,
+    position: Coord,
+    velocity: Coord,
+    last_step: f64,
+    active: bool
+}
+
+impl KineticScroller {
+    pub(crate) fn new() -> KineticScroller {
+        KineticScroller {
+            widget: None,
+            samples: Vec::new(),
+            position: Coord::default(),
+            velocity: Coord::default(),
+            last_step: 0.,
+            active: false
+        }
+    }
+
+    /// Starts tracking the scroll position `pos` of the dragged `widget`.
+    pub(crate) fn begin(&mut self, widget: Id, pos: Coord, time: f64) {
+        self.widget = Some(widget);
+        self.samples = vec![(pos, time)];
+        self.active = false;
+    }
+
+    /// Returns the widget the scroll position of which is tracked or
+    /// continued, if any.
+    pub(crate) fn widget(&self) -> Option<Id> {
+        self.widget
+    }
+
+    /// Records the scroll position `pos` during the drag.
+    pub(crate) fn track(&mut self, pos: Coord, time: f64) {
+        if self.widget.is_none() || self.active {
+            return;
+        }
+        self.samples.retain(|(_, t)| time - t <= VELOCITY_WINDOW);
+        self.samples.push((pos, time));
+    }
+
+    /// Ends the drag with the scroll position `pos`.
+    ///
+    /// Returns `true` iff the scrolling is to be continued.
+    pub(crate) fn release(&mut self, pos: Coord, time: f64) -> bool {
+        self.track(pos, time);
+        let (start, start_time) = match self.samples.first() {
+            Some(sample) => *sample,
+            None => return false
+        };
+        let dt = time - start_time;
+        if self.widget.is_none() || dt <= 0. {
+            self.cancel();
+            return false;
+        }
+        self.velocity = Coord { x: (pos.x - start.x) / dt, y: (pos.y - start.y) / dt };
+        if speed(self.velocity) < MIN_VELOCITY {
+            self.cancel();
+            return false;
+        }
+        self.position = pos;
+        self.last_step = time;
+        self.active = true;
+        true
+    }
+
+    /// Returns the next scroll position at `time` with the velocity
+    /// decaying by `friction` per second, or `None` if the scrolling
+    /// has come to a halt.
+    pub(crate) fn step(&mut self, time: f64, friction: f64) -> Option<(Id, Coord)> {
+        if !self.active {
+            return None;
+        }
+        let dt = time - self.last_step;
+        self.last_step = time;
+        let decay = (-friction * dt).exp();
+        self.velocity = Coord { x: self.velocity.x * decay, y: self.velocity.y * decay };
+        if speed(self.velocity) < MIN_VELOCITY {
+            self.cancel();
+            return None;
+        }
+        self.position = self.position + Coord { x: self.velocity.x * dt, y: self.velocity.y * dt };
+        self.widget.map(|id| (id, self.position))
+    }
+
+    /// Corrects the scroll position to `pos` as set by the widget.
+    ///
+    /// If the widget has clamped the position along an axis, the
+    /// scrolling along that axis stops.
+    pub(crate) fn clamped(&mut self, pos: Coord) {
+        if pos.x != self.position.x {
+            self.velocity.x = 0.;
+        }
+        if pos.y != self.position.y {
+            self.velocity.y = 0.;
+        }
+        self.position = pos;
+    }
+
+    /// Stops tracking and scrolling.
+    pub(crate) fn cancel(&mut self) {
+        self.widget = None;
+        self.samples.clear();
+        self.active = false;
+    }
+}
+
+fn speed(v: Coord) -> f64 {
+    (v.x * v.x + v.y * v.y).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scrolling_continues_and_decays() {
+        let mut ks = KineticScroller::new();
+        ks.begin(4, Coord::default(), 0.);
+        ks.track(Coord { x: 0., y: 10. }, 0.02);
+        assert!(ks.release(Coord { x: 0., y: 40. }, 0.08));
+
+        let (id, pos) = ks.step(0.1, 0.).unwrap();
+        assert_eq!(id, 4);
+        assert!((pos.y - 50.).abs() < 1e-9);
+
+        let mut last = pos.y;
+        let mut time = 0.1;
+        while let Some((_, pos)) = ks.step(time + KINETIC_PERIOD, 4.) {
+            time += KINETIC_PERIOD;
+            assert!(pos.y > last);
+            last = pos.y;
+        }
+        assert!(ks.widget().is_none());
+    }
+
+    #[test]
+    fn slow_release_stops() {
+        let mut ks = KineticScroller::new();
+        ks.begin(4, Coord::default(), 0.);
+        ks.track(Coord { x: 0., y: 10. }, 0.5);
+        assert!(!ks.release(Coord { x: 0., y: 10. }, 1.0));
+        assert_eq!(ks.step(1.1, 4.), None);
+    }
+
+    #[test]
+    fn clamping_stops_axis() {
+        let mut ks = KineticScroller::new();
+        ks.begin(1, Coord::default(), 0.);
+        assert!(ks.release(Coord { x: 50., y: 50. }, 0.05));
+        let (_, pos) = ks.step(0.06, 0.).unwrap();
+        ks.clamped(Coord { x: pos.x, y: 50. });
+        let (_, next) = ks.step(0.07, 0.).unwrap();
+        assert!(next.x > pos.x);
+        assert_eq!(next.y, 50.);
+    }
+}
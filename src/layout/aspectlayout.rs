@@ -0,0 +1,178 @@
+//! Layouting a single child with a fixed aspect ratio
+use pugl_sys::*;
+
+use crate::layout::*;
+use crate::ui;
+use crate::widget::*;
+
+/// Layouter giving its single child the largest rectangle of a fixed
+/// aspect ratio inside the space of the layout
+///
+/// The `Target` is `()`. The child is centered inside the space. If
+/// the child is expandable in both directions, it grows with the
+/// layout without being distorted, which is what scopes, spectrograms
+/// and meter displays need. The aspect ratio, width divided by
+/// height, is set by
+/// [`AspectFrameImpl::set_aspect_ratio()`](struct.AspectFrameImpl.html#method.set_aspect_ratio).
+#[derive(Clone, Copy, Default, Debug)]
+pub struct AspectFrame;
+
+pub struct AspectFrameImpl {
+    ratio: f64,
+    child: Option<Id>
+}
+
+impl Default for AspectFrameImpl {
+    fn default() -> AspectFrameImpl {
+        AspectFrameImpl {
+            ratio: 1.0,
+            child: None
+        }
+    }
+}
+
+impl AspectFrameImpl {
+    /// Sets the aspect ratio, width divided by height, of the
+    /// child. Default is `1.0`.
+    pub fn set_aspect_ratio(&mut self, ratio: f64) -> &mut AspectFrameImpl {
+        self.ratio = ratio;
+        self
+    }
+
+    /// Returns the largest size of the aspect ratio fitting into `size`
+    fn fit(&self, size: Size) -> Size {
+        if size.w > size.h * self.ratio {
+            Size { w: size.h * self.ratio, h: size.h }
+        } else {
+            Size { w: size.w, h: size.w / self.ratio }
+        }
+    }
+
+    fn pack(&mut self, subnode_id: Id) {
+        if self.child.is_some() {
+            panic!("AspectFrame can only take one child");
+        }
+        self.child = Some(subnode_id);
+    }
+}
+
+impl LayouterImpl for AspectFrameImpl {
+    fn apply_layouts(&self, widgets: &mut Vec<Box<dyn Widget>>, children: &[ui::WidgetNode],
+                     orig_pos: Coord, size_avail: Size) {
+        let node = match self.child {
+            Some(sn) if widgets[children[sn].id].is_visible() => &children[sn],
+            _ => return
+        };
+        let widget = &mut widgets[node.id];
+        let size = if widget.width_expandable() && widget.height_expandable() {
+            self.fit(size_avail)
+        } else {
+            widget.size()
+        };
+        let pos = orig_pos + Coord { x: (size_avail.w - size.w) / 2., y: (size_avail.h - size.h) / 2. };
+        widget.set_layout(&Layout { pos, size });
+        node.apply_sizes(widgets, pos);
+    }
+
+    fn calc_size(&self, widgets: &mut Vec<Box<dyn Widget>>, children: &[ui::WidgetNode]) -> Size {
+        let node = match self.child {
+            Some(sn) if widgets[children[sn].id].is_visible() => &children[sn],
+            _ => return Size::default()
+        };
+        let min = node.calc_widget_sizes(widgets);
+        let size = if min.w < min.h * self.ratio {
+            Size { w: min.h * self.ratio, h: min.h }
+        } else {
+            Size { w: min.w, h: min.w / self.ratio }
+        };
+        widgets[node.id].set_size(&size);
+        size
+    }
+}
+
+impl Layouter for AspectFrame {
+    type Target = ();
+    type Implementor = AspectFrameImpl;
+
+    fn new_implementor() -> Box<dyn LayouterImpl> {
+        Box::new(AspectFrameImpl::default())
+    }
+    fn pack(&mut self, layout_impl: &mut Self::Implementor, subnode_id: Id, _target: Self::Target) {
+        layout_impl.pack(subnode_id);
+    }
+    fn expandable() -> (bool, bool) {
+        (true, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::*;
+
+    #[derive(Default)]
+    struct RootWidget {
+        stub: WidgetStub
+    }
+
+    impl Widget for RootWidget {
+        widget_stub!();
+    }
+
+    #[derive(Default)]
+    struct Scope {
+        stub: WidgetStub
+    }
+
+    impl Widget for Scope {
+        widget_stub!();
+
+        fn min_size(&self) -> Size {
+            Size { w: 40., h: 40. }
+        }
+
+        fn width_expandable(&self) -> bool {
+            true
+        }
+        fn height_expandable(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn child_keeps_aspect_ratio() {
+        let mut root = WidgetNode::root::<AspectFrame>();
+        let mut widgets: Vec<Box<dyn Widget>> = vec![Box::new(RootWidget::default()), Box::new(Scope::default())];
+        root.children.push(WidgetNode::new_leaf(1));
+        let root_widget_handle = LayoutWidgetHandle::<AspectFrame, RootWidget>::new(WidgetHandle::new(0));
+        root.pack(1, root_widget_handle, ());
+        root.layouter_impl::<AspectFrame>().set_aspect_ratio(2.0);
+
+        let size = root.layouter.as_ref().unwrap().calc_size(&mut widgets, root.children.as_slice());
+        assert_eq!(size, Size { w: 80., h: 40. });
+
+        root.layouter.as_ref().unwrap().apply_layouts(
+            &mut widgets,
+            root.children.as_slice(),
+            Coord { x: 10., y: 10. },
+            Size { w: 100., h: 100. }
+        );
+        assert_eq!(widgets[1].layout(), Layout { pos: Coord { x: 10., y: 35. }, size: Size { w: 100., h: 50. } });
+
+        root.layouter.as_ref().unwrap().apply_layouts(
+            &mut widgets,
+            root.children.as_slice(),
+            Coord::default(),
+            Size { w: 300., h: 100. }
+        );
+        assert_eq!(widgets[1].layout(), Layout { pos: Coord { x: 50., y: 0. }, size: Size { w: 200., h: 100. } });
+    }
+
+    #[test]
+    #[should_panic(expected = "AspectFrame can only take one child")]
+    fn only_one_child() {
+        let mut frame = AspectFrameImpl::default();
+        frame.pack(0);
+        frame.pack(1);
+    }
+}
@@ -0,0 +1,141 @@
+//! Placing widgets at fixed positions
+use pugl_sys::*;
+
+use crate::layout::*;
+use crate::ui;
+use crate::widget::*;
+
+/// Layouter placing its children at fixed positions
+///
+/// The `Target` is the position of the child relative to the
+/// position of the layout. The children keep their minimal size, they
+/// are not expanded. The size of the layout is the bounding box of
+/// its children. Useful for canvas like UIs where knobs are placed
+/// over a background image drawn by the layout's parent.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct FixedLayouter;
+
+#[derive(Default)]
+pub struct FixedLayouterImpl {
+    subnodes: Vec<(Id, Coord)>
+}
+
+impl FixedLayouterImpl {
+    fn pack(&mut self, subnode_id: Id, pos: Coord) {
+        self.subnodes.push((subnode_id, pos));
+    }
+}
+
+impl LayouterImpl for FixedLayouterImpl {
+    fn apply_layouts(&self, widgets: &mut Vec<Box<dyn Widget>>, children: &[ui::WidgetNode],
+                     orig_pos: Coord, _size_avail: Size) {
+        for (sn, offset) in self.subnodes.iter() {
+            let node = &children[*sn];
+            if !widgets[node.id].is_visible() {
+                continue;
+            }
+            let pos = orig_pos + *offset;
+            widgets[node.id].set_pos(&pos);
+            node.apply_sizes(widgets, pos);
+        }
+    }
+
+    fn calc_size(&self, widgets: &mut Vec<Box<dyn Widget>>, children: &[ui::WidgetNode]) -> Size {
+        self.subnodes.iter().fold(Size::default(), |size, (sn, offset)| {
+            if !widgets[children[*sn].id].is_visible() {
+                return size;
+            }
+            let child = children[*sn].calc_widget_sizes(widgets);
+            Size {
+                w: size.w.max(offset.x + child.w),
+                h: size.h.max(offset.y + child.h)
+            }
+        })
+    }
+
+    fn children_may_overlap(&self) -> bool {
+        true
+    }
+}
+
+impl Layouter for FixedLayouter {
+    type Target = Coord;
+    type Implementor = FixedLayouterImpl;
+
+    fn new_implementor() -> Box<dyn LayouterImpl> {
+        Box::new(FixedLayouterImpl::default())
+    }
+    fn pack(&mut self, layout_impl: &mut Self::Implementor, subnode_id: Id, target: Self::Target) {
+        layout_impl.pack(subnode_id, target);
+    }
+    fn expandable() -> (bool, bool) {
+        (false, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::*;
+
+    #[derive(Default)]
+    struct RootWidget {
+        stub: WidgetStub
+    }
+
+    impl Widget for RootWidget {
+        widget_stub!();
+    }
+
+    #[derive(Default)]
+    struct BothExpandable {
+        stub: WidgetStub
+    }
+
+    impl Widget for BothExpandable {
+        widget_stub!();
+
+        fn min_size(&self) -> Size {
+            Size { w: 23., h: 42. }
+        }
+
+        fn width_expandable(&self) -> bool {
+            true
+        }
+        fn height_expandable(&self) -> bool {
+            true
+        }
+    }
+
+    fn new_widget<W: Widget + Default>(widgets: &mut Vec<Box<dyn Widget>>, node: &mut WidgetNode) -> Id {
+        let id = widgets.len();
+        widgets.push(Box::new(W::default()));
+        node.children.push(WidgetNode::new_leaf(id));
+        id
+    }
+
+    #[test]
+    fn layout_two_widgets_fixed() {
+        let mut root = WidgetNode::root::<FixedLayouter>();
+        let mut widgets: Vec<Box<dyn Widget>> = vec![Box::new(RootWidget::default())];
+        let root_widget_handle = LayoutWidgetHandle::<FixedLayouter, RootWidget>::new(WidgetHandle::new(0));
+
+        let w1 = new_widget::<BothExpandable>(&mut widgets, &mut root);
+        root.pack(w1, root_widget_handle, Coord { x: 10., y: 100. });
+        let w2 = new_widget::<BothExpandable>(&mut widgets, &mut root);
+        root.pack(w2, root_widget_handle, Coord { x: 50., y: 5. });
+
+        let size = root.layouter.as_ref().unwrap().calc_size(&mut widgets, root.children.as_slice());
+        assert_eq!(size, Size { w: 50.+23., h: 100.+42. });
+
+        root.layouter.as_ref().unwrap().apply_layouts(
+            &mut widgets,
+            root.children.as_slice(),
+            Coord { x: 3., y: 4. },
+            Size { w: 200., h: 200. }
+        );
+
+        assert_eq!(widgets[w1].layout(), Layout { pos: Coord { x: 13., y: 104. }, size: Size { w: 23., h: 42. } });
+        assert_eq!(widgets[w2].layout(), Layout { pos: Coord { x: 53., y: 9. }, size: Size { w: 23., h: 42. } });
+    }
+}
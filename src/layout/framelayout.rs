@@ -0,0 +1,181 @@
+//! Layouting a single child inside a titled border
+use pugl_sys::*;
+
+use crate::layout::*;
+use crate::ui;
+use crate::widget::*;
+
+/// Layouter placing its single child inside a border with a caption
+///
+/// The `Target` is `()`. Frames group sections of a UI, like the
+/// filter, envelope or LFO panel of a synth. Create them by
+/// [`UI::new_frame()`](../../ui/struct.UI.html#method.new_frame),
+/// which reserves the space for the caption and makes the frame's
+/// [`LayoutWidget`](../struct.LayoutWidget.html) draw the border and
+/// the caption.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct FrameLayouter;
+
+pub struct FrameLayouterImpl {
+    child: Option<Id>,
+    caption_height: f64,
+    padding: f64
+}
+
+impl Default for FrameLayouterImpl {
+    fn default() -> FrameLayouterImpl {
+        FrameLayouterImpl {
+            child: None,
+            caption_height: 0.,
+            padding: 6.
+        }
+    }
+}
+
+impl FrameLayouterImpl {
+    /// Sets the padding between the border and the child. Default is `6.0`.
+    pub fn set_padding(&mut self, padding: f64) -> &mut FrameLayouterImpl {
+        self.padding = padding;
+        self
+    }
+
+    /// Sets the height of the space reserved for the caption above
+    /// the child.
+    pub fn set_caption_height(&mut self, height: f64) -> &mut FrameLayouterImpl {
+        self.caption_height = height;
+        self
+    }
+
+    /// Returns the height of the space reserved for the caption.
+    pub fn caption_height(&self) -> f64 {
+        self.caption_height
+    }
+
+    fn top(&self) -> f64 {
+        self.caption_height.max(self.padding)
+    }
+
+    fn pack(&mut self, subnode_id: Id) {
+        if self.child.is_some() {
+            panic!("FrameLayouter can only take one child");
+        }
+        self.child = Some(subnode_id);
+    }
+}
+
+impl LayouterImpl for FrameLayouterImpl {
+    fn apply_layouts(&self, widgets: &mut Vec<Box<dyn Widget>>, children: &[ui::WidgetNode],
+                     orig_pos: Coord, size_avail: Size) {
+        let node = match self.child {
+            Some(sn) if widgets[children[sn].id].is_visible() => &children[sn],
+            _ => return
+        };
+        let widget = &mut widgets[node.id];
+        if widget.width_expandable() {
+            let w = size_avail.w - 2. * self.padding;
+            let w = node.constraints.max_w.map_or(w, |max| w.min(max.max(widget.size().w)));
+            widget.set_width(w);
+        }
+        if widget.height_expandable() {
+            let h = size_avail.h - self.top() - self.padding;
+            let h = node.constraints.max_h.map_or(h, |max| h.min(max.max(widget.size().h)));
+            widget.set_height(h);
+        }
+        let pos = orig_pos + Coord { x: self.padding, y: self.top() };
+        widget.set_pos(&pos);
+        node.apply_sizes(widgets, pos);
+    }
+
+    fn calc_size(&self, widgets: &mut Vec<Box<dyn Widget>>, children: &[ui::WidgetNode]) -> Size {
+        let size = match self.child {
+            Some(sn) if widgets[children[sn].id].is_visible() => children[sn].calc_widget_sizes(widgets),
+            _ => Size::default()
+        };
+        Size {
+            w: size.w + 2. * self.padding,
+            h: size.h + self.top() + self.padding
+        }
+    }
+}
+
+impl Layouter for FrameLayouter {
+    type Target = ();
+    type Implementor = FrameLayouterImpl;
+
+    fn new_implementor() -> Box<dyn LayouterImpl> {
+        Box::new(FrameLayouterImpl::default())
+    }
+    fn pack(&mut self, layout_impl: &mut Self::Implementor, subnode_id: Id, _target: Self::Target) {
+        layout_impl.pack(subnode_id);
+    }
+    fn expandable() -> (bool, bool) {
+        (true, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::*;
+
+    #[derive(Default)]
+    struct RootWidget {
+        stub: WidgetStub
+    }
+
+    impl Widget for RootWidget {
+        widget_stub!();
+    }
+
+    #[derive(Default)]
+    struct Panel {
+        stub: WidgetStub
+    }
+
+    impl Widget for Panel {
+        widget_stub!();
+
+        fn min_size(&self) -> Size {
+            Size { w: 40., h: 30. }
+        }
+
+        fn width_expandable(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn child_inside_border() {
+        let mut root = WidgetNode::root::<FrameLayouter>();
+        let mut widgets: Vec<Box<dyn Widget>> = vec![Box::new(RootWidget::default()), Box::new(Panel::default())];
+        root.children.push(WidgetNode::new_leaf(1));
+        let root_widget_handle = LayoutWidgetHandle::<FrameLayouter, RootWidget>::new(WidgetHandle::new(0));
+        root.pack(1, root_widget_handle, ());
+        root.layouter_impl::<FrameLayouter>().set_caption_height(14.).set_padding(4.);
+
+        let size = root.layouter.as_ref().unwrap().calc_size(&mut widgets, root.children.as_slice());
+        assert_eq!(size, Size { w: 48., h: 48. });
+
+        root.layouter.as_ref().unwrap().apply_layouts(
+            &mut widgets,
+            root.children.as_slice(),
+            Coord { x: 10., y: 10. },
+            Size { w: 100., h: 100. }
+        );
+        assert_eq!(widgets[1].layout(), Layout { pos: Coord { x: 14., y: 24. }, size: Size { w: 92., h: 30. } });
+    }
+
+    #[test]
+    fn padding_without_caption() {
+        let frame = FrameLayouterImpl::default();
+        assert_eq!(frame.top(), 6.);
+    }
+
+    #[test]
+    #[should_panic(expected = "FrameLayouter can only take one child")]
+    fn only_one_child() {
+        let mut frame = FrameLayouterImpl::default();
+        frame.pack(0);
+        frame.pack(1);
+    }
+}
@@ -18,9 +18,57 @@ pub struct LayoutWidget {
 
     width_locked: bool,
     height_locked: bool,
+
+    caption: Option<String>,
+    panel: bool
 }
 
+/// The distance of the caption from the left edge of a frame
+const CAPTION_INDENT: f64 = 10.;
+
 impl LayoutWidget {
+    /// Makes the widget draw a border with the caption `caption`,
+    /// see [`FrameLayouter`](framelayout/struct.FrameLayouter.html).
+    pub(crate) fn set_caption(&mut self, caption: &str) {
+        self.caption = Some(String::from(caption));
+    }
+
+    /// Returns the caption of the frame drawn by the widget, if any.
+    pub fn caption(&self) -> Option<&str> {
+        self.caption.as_deref()
+    }
+
+    /// Draws the caption at `(x, y)` and returns its size.
+    #[cfg(feature = "text")]
+    fn draw_caption(&self, ctx: &widget::PaintContext, cr: &cairo::Context, x: f64, y: f64) -> (f64, f64) {
+        let (caption, pctx) = match (&self.caption, pangocairo::functions::create_context(cr)) {
+            (Some(caption), Some(pctx)) => (caption, pctx),
+            _ => return (0., 0.)
+        };
+        let lyt = pango::Layout::new(&pctx);
+        lyt.set_font_description(Some(&pango::FontDescription::from_string(&ctx.style().font)));
+        lyt.set_text(caption);
+        cr.move_to(x, y);
+        pangocairo::functions::show_layout(cr, &lyt);
+        let (w, h) = lyt.get_pixel_size();
+        (f64::from(w), f64::from(h))
+    }
+
+    /// Makes the widget draw the panel of the theme as background
+    /// of the layout, see
+    /// [`PaintContext::draw_panel()`](../../widget/struct.PaintContext.html#method.draw_panel).
+    pub fn set_panel(&mut self, panel: bool) {
+        if self.panel != panel {
+            self.panel = panel;
+            widget::Widget::ask_for_repaint(self);
+        }
+    }
+
+    /// Returns `true` iff the widget draws a panel as background.
+    pub fn has_panel(&self) -> bool {
+        self.panel
+    }
+
     pub(crate) fn set_expandable(&mut self, we: bool, he: bool) {
         self.width_expandable = we && !self.width_locked;
         self.height_expandable = he && !self.height_locked;
@@ -59,6 +107,42 @@ impl widget::Widget for LayoutWidget {
     fn width_expandable(&self) -> bool { self.width_expandable }
     fn height_expandable(&self) -> bool { self.height_expandable }
 
+    fn exposed(&mut self, ctx: &widget::PaintContext, cr: &cairo::Context) {
+        if self.panel {
+            ctx.draw_panel(cr, widget::Widget::layout(self));
+        }
+        if self.caption.is_none() {
+            return;
+        }
+        let (x, y, w, h) = widget::Widget::rect(self);
+        let (r, g, b) = ctx.style().foreground;
+        cr.save();
+        cr.set_source_rgb(r, g, b);
+        cr.set_line_width(1.);
+
+        #[cfg(feature = "text")]
+        let (caption_w, caption_h) = self.draw_caption(ctx, cr, x + CAPTION_INDENT, y);
+        #[cfg(not(feature = "text"))]
+        let (caption_w, caption_h) = (0., 0.);
+
+        let top = (y + caption_h / 2.).floor() + 0.5;
+        let (left, right, bottom) = (x + 0.5, x + w - 0.5, y + h - 0.5);
+        if caption_w > 0. {
+            cr.move_to(x + CAPTION_INDENT - 2., top);
+        } else {
+            cr.move_to(right, top);
+        }
+        cr.line_to(left, top);
+        cr.line_to(left, bottom);
+        cr.line_to(right, bottom);
+        cr.line_to(right, top);
+        if caption_w > 0. {
+            cr.line_to(x + CAPTION_INDENT + caption_w + 2., top);
+        }
+        cr.stroke();
+        cr.restore();
+    }
+
     fn sized_width(&self) -> bool { true }
     fn sized_height(&self) -> bool { true }
     fn pointer_enter_wrap(&mut self) {}
@@ -1,8 +1,12 @@
 //! Facilities for widget layouting
 //!
 //! So far there is the classical box stacking layout (like Gtk's
-//! HBox/Vbox) implemented. Other layouting algorithms can be
-//! implemented later.
+//! HBox/Vbox), a tab layout showing one of its children at a time, a
+//! layout placing its children at fixed positions, a frame keeping
+//! the aspect ratio of its child, a split layout of two panes with
+//! a draggable divider and a titled frame around its child
+//! implemented.
+//! Other layouting algorithms can be implemented later.
 //!
 //! This module contains the items, that are needed to layout widgets.
 //!
@@ -43,6 +47,11 @@ use crate::ui;
 use crate::widget;
 
 pub mod stacklayout;
+pub mod tablayout;
+pub mod fixedlayout;
+pub mod aspectlayout;
+pub mod panedlayout;
+pub mod framelayout;
 
 #[doc(hidden)]
 pub mod layoutwidget;
@@ -50,6 +59,81 @@ pub mod layoutwidget;
 #[doc(inline)]
 pub use layoutwidget::*;
 
+/// Constraints for the size of a widget packed into a layout.
+///
+/// Passed to
+/// [`UI::pack_to_layout_with()`](../ui/struct.UI.html#method.pack_to_layout_with).
+/// The minima are applied when the sizes are determined, so the
+/// widget gets at least the minimal size. The maxima limit how far an
+/// expandable widget is expanded by the layouter. They never shrink
+/// a widget below its minimal size.
+///
+/// ```
+/// # use pugl_ui::layout::Constraints;
+/// let constraints = Constraints { max_w: Some(120.), stretch_factor: 2., ..Default::default() };
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Constraints {
+    /// The minimal width of the widget
+    pub min_w: Option<f64>,
+    /// The minimal height of the widget
+    pub min_h: Option<f64>,
+    /// The maximal width the widget is expanded to
+    pub max_w: Option<f64>,
+    /// The maximal height the widget is expanded to
+    pub max_h: Option<f64>,
+    /// The share of the available extra space the widget gets
+    /// relative to the stretch factors of its expandable siblings.
+    pub stretch_factor: f64
+}
+
+impl Default for Constraints {
+    fn default() -> Constraints {
+        Constraints {
+            min_w: None,
+            min_h: None,
+            max_w: None,
+            max_h: None,
+            stretch_factor: 1.0
+        }
+    }
+}
+
+impl Constraints {
+    /// Constraints to give the widget the fixed size `size`
+    ///
+    /// If the widget's minimal size is bigger than `size` the minimal
+    /// size is used.
+    pub fn fixed(size: sys::Size) -> Constraints {
+        Constraints {
+            min_w: Some(size.w),
+            min_h: Some(size.h),
+            max_w: Some(size.w),
+            max_h: Some(size.h),
+            ..Default::default()
+        }
+    }
+
+    /// Constraints to give the widget a share of the extra space
+    /// proportional to `factor`
+    ///
+    /// So packing widgets with `stretch(2.)` and `stretch(1.)` to a
+    /// stack layout distributes the extra space 2:1 among them.
+    pub fn stretch(factor: f64) -> Constraints {
+        Constraints {
+            stretch_factor: factor,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn apply_min(&self, size: sys::Size) -> sys::Size {
+        sys::Size {
+            w: self.min_w.map_or(size.w, |w| size.w.max(w)),
+            h: self.min_h.map_or(size.h, |h| size.h.max(h))
+        }
+    }
+}
+
 /// A trait describing layouters in order to assign them to a
 /// [`LayoutWidget`](struct.LayoutWidget.html).
 ///
@@ -102,5 +186,25 @@ pub trait LayouterImpl: DowncastSync {
         &self,
         widgets: &mut Vec<Box<dyn widget::Widget>>,
         children: &[ui::WidgetNode]) -> sys::Size;
+
+    /// Supposed to return `false` if the child `index` of the layout
+    /// is not shown at the moment.
+    ///
+    /// Children not shown are treated as if they were hidden: they
+    /// are not drawn and do not receive events or the focus. The
+    /// default implementation shows all the children.
+    fn shows_child(&self, _index: usize) -> bool {
+        true
+    }
+
+    /// Supposed to return `true` if the children of the layout can
+    /// overlap each other.
+    ///
+    /// The `UI` relies on non overlapping children when it reuses
+    /// the widgets hit by the last pointer event. The default
+    /// implementation returns `false`.
+    fn children_may_overlap(&self) -> bool {
+        false
+    }
 }
 impl_downcast!(sync LayouterImpl);
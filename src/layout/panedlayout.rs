@@ -0,0 +1,307 @@
+//! Layouting two panes separated by a draggable divider
+use pugl_sys::*;
+
+use crate::layout::*;
+use crate::ui;
+use crate::widget::*;
+
+/// The pane a widget is packed to in a
+/// [`PanedLayouter`](struct.PanedLayouter.html)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pane {
+    /// The left or, if vertical, the top pane
+    First,
+    /// The right or, if vertical, the bottom pane
+    Second
+}
+
+/// Layouter splitting its space between exactly two children
+///
+/// The `Target` is the [`Pane`](enum.Pane.html). The children are
+/// separated by a divider the user can drag with the mouse to change
+/// the split ratio. The UI re-applies the layout while the divider is
+/// dragged. The ratio is kept by the
+/// [`PanedLayouterImpl`](struct.PanedLayouterImpl.html) and can be
+/// read and set by the application, for example to persist it.
+///
+/// The divider is the space between the panes, so the children
+/// should be expandable in the direction of the split to fill their
+/// panes.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct PanedLayouter;
+
+pub struct PanedLayouterImpl {
+    panes: [Option<Id>; 2],
+    ratio: f64,
+    vertical: bool,
+    divider_width: f64
+}
+
+impl Default for PanedLayouterImpl {
+    fn default() -> PanedLayouterImpl {
+        PanedLayouterImpl {
+            panes: [None, None],
+            ratio: 0.5,
+            vertical: false,
+            divider_width: 6.
+        }
+    }
+}
+
+impl PanedLayouterImpl {
+    /// Sets the share of the first pane of the space available for
+    /// both panes. Clamped to `0.0..=1.0`, default is `0.5`.
+    ///
+    /// The panes never shrink below the minimal sizes of their
+    /// children. Takes effect with the next
+    /// [`UI::do_layout()`](../../ui/struct.UI.html#method.do_layout).
+    pub fn set_split_ratio(&mut self, ratio: f64) -> &mut PanedLayouterImpl {
+        self.ratio = ratio.max(0.).min(1.);
+        self
+    }
+
+    /// Returns the share of the first pane of the space available
+    /// for both panes.
+    pub fn split_ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    /// Stacks the panes vertically rather than horizontally.
+    pub fn set_vertical(&mut self, yn: bool) -> &mut PanedLayouterImpl {
+        self.vertical = yn;
+        self
+    }
+
+    /// Sets the width of the divider between the panes. Default is `6.0`.
+    pub fn set_divider_width(&mut self, width: f64) -> &mut PanedLayouterImpl {
+        self.divider_width = width;
+        self
+    }
+
+    /// Returns the cursor to be shown over the divider.
+    pub(crate) fn cursor(&self) -> Cursor {
+        if self.vertical { Cursor::UpDown } else { Cursor::LeftRight }
+    }
+
+    /// Returns `true` iff `pos`, which is supposed to be inside the
+    /// layout, is on the divider.
+    pub(crate) fn divider_hit(&self, widgets: &[Box<dyn Widget>], children: &[ui::WidgetNode], pos: Coord) -> bool {
+        let first = match self.panes[0] {
+            Some(sn) => widgets[children[sn].id].layout(),
+            None => return false
+        };
+        let (start, p) = if self.vertical {
+            (first.pos.y + first.size.h, pos.y)
+        } else {
+            (first.pos.x + first.size.w, pos.x)
+        };
+        p >= start && p < start + self.divider_width
+    }
+
+    /// Sets the split ratio so that the divider of the layout covering
+    /// `layout` is centered at `pos`.
+    pub(crate) fn drag_divider(&mut self, layout: Layout, pos: Coord) {
+        let (offset, extent) = if self.vertical {
+            (pos.y - layout.pos.y, layout.size.h)
+        } else {
+            (pos.x - layout.pos.x, layout.size.w)
+        };
+        let space = extent - self.divider_width;
+        if space > 0. {
+            self.set_split_ratio((offset - self.divider_width / 2.) / space);
+        }
+    }
+
+    fn pack(&mut self, subnode_id: Id, pane: Pane) {
+        let index = pane as usize;
+        if self.panes[index].is_some() {
+            panic!("PanedLayouter pane {:?} already taken", pane);
+        }
+        self.panes[index] = Some(subnode_id);
+    }
+
+    fn extent(&self, size: Size) -> f64 {
+        if self.vertical { size.h } else { size.w }
+    }
+
+    fn breadth(&self, size: Size) -> f64 {
+        if self.vertical { size.w } else { size.h }
+    }
+
+    fn visible_pane<'a>(&self, index: usize, widgets: &[Box<dyn Widget>],
+                        children: &'a [ui::WidgetNode]) -> Option<&'a ui::WidgetNode> {
+        self.panes[index].map(|sn| &children[sn]).filter(|node| widgets[node.id].is_visible())
+    }
+}
+
+impl LayouterImpl for PanedLayouterImpl {
+    fn apply_layouts(&self, widgets: &mut Vec<Box<dyn Widget>>, children: &[ui::WidgetNode],
+                     orig_pos: Coord, size_avail: Size) {
+        let nodes = [self.visible_pane(0, widgets, children), self.visible_pane(1, widgets, children)];
+        let mins: Vec<f64> = nodes.iter()
+            .map(|n| n.map_or(0., |node| self.extent(widgets[node.id].size())))
+            .collect();
+        let space = (self.extent(size_avail) - self.divider_width).max(mins[0] + mins[1]);
+        let first = (space * self.ratio).max(mins[0]).min(space - mins[1]);
+        let extents = [first, space - first];
+        let offsets = [0., first + self.divider_width];
+        let breadth = self.breadth(size_avail);
+
+        for (i, node) in nodes.iter().enumerate() {
+            let node = match node {
+                Some(node) => node,
+                None => continue
+            };
+            let widget = &mut widgets[node.id];
+            let (w, h, pos) = if self.vertical {
+                (breadth, extents[i], orig_pos + Coord { x: 0., y: offsets[i] })
+            } else {
+                (extents[i], breadth, orig_pos + Coord { x: offsets[i], y: 0. })
+            };
+            if widget.width_expandable() {
+                let w = node.constraints.max_w.map_or(w, |max| w.min(max.max(widget.size().w)));
+                widget.set_width(w);
+            }
+            if widget.height_expandable() {
+                let h = node.constraints.max_h.map_or(h, |max| h.min(max.max(widget.size().h)));
+                widget.set_height(h);
+            }
+            widget.set_pos(&pos);
+            node.apply_sizes(widgets, pos);
+        }
+    }
+
+    fn calc_size(&self, widgets: &mut Vec<Box<dyn Widget>>, children: &[ui::WidgetNode]) -> Size {
+        let mut extent = self.divider_width;
+        let mut breadth: f64 = 0.;
+        for i in 0..2 {
+            if let Some(node) = self.visible_pane(i, widgets, children) {
+                let size = node.calc_widget_sizes(widgets);
+                extent += self.extent(size);
+                breadth = breadth.max(self.breadth(size));
+            }
+        }
+        if self.vertical {
+            Size { w: breadth, h: extent }
+        } else {
+            Size { w: extent, h: breadth }
+        }
+    }
+}
+
+impl Layouter for PanedLayouter {
+    type Target = Pane;
+    type Implementor = PanedLayouterImpl;
+
+    fn new_implementor() -> Box<dyn LayouterImpl> {
+        Box::new(PanedLayouterImpl::default())
+    }
+    fn pack(&mut self, layout_impl: &mut Self::Implementor, subnode_id: Id, target: Self::Target) {
+        layout_impl.pack(subnode_id, target);
+    }
+    fn expandable() -> (bool, bool) {
+        (true, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::*;
+
+    #[derive(Default)]
+    struct RootWidget {
+        stub: WidgetStub
+    }
+
+    impl Widget for RootWidget {
+        widget_stub!();
+    }
+
+    #[derive(Default)]
+    struct PaneWidget {
+        stub: WidgetStub
+    }
+
+    impl Widget for PaneWidget {
+        widget_stub!();
+
+        fn min_size(&self) -> Size {
+            Size { w: 20., h: 10. }
+        }
+
+        fn width_expandable(&self) -> bool {
+            true
+        }
+        fn height_expandable(&self) -> bool {
+            true
+        }
+    }
+
+    fn paned() -> (WidgetNode, Vec<Box<dyn Widget>>) {
+        let mut root = WidgetNode::root::<PanedLayouter>();
+        let widgets: Vec<Box<dyn Widget>> = vec![
+            Box::new(RootWidget::default()), Box::new(PaneWidget::default()), Box::new(PaneWidget::default())
+        ];
+        root.children.push(WidgetNode::new_leaf(1));
+        root.children.push(WidgetNode::new_leaf(2));
+        let root_widget_handle = LayoutWidgetHandle::<PanedLayouter, RootWidget>::new(WidgetHandle::new(0));
+        root.pack(1, root_widget_handle, Pane::First);
+        root.pack(2, root_widget_handle, Pane::Second);
+        (root, widgets)
+    }
+
+    #[test]
+    fn split_by_ratio() {
+        let (mut root, mut widgets) = paned();
+        root.layouter_impl::<PanedLayouter>().set_split_ratio(0.25);
+
+        let size = root.layouter.as_ref().unwrap().calc_size(&mut widgets, root.children.as_slice());
+        assert_eq!(size, Size { w: 46., h: 10. });
+
+        root.layouter.as_ref().unwrap().apply_layouts(
+            &mut widgets, root.children.as_slice(), Coord::default(), Size { w: 206., h: 50. }
+        );
+        assert_eq!(widgets[1].layout(), Layout { pos: Coord::default(), size: Size { w: 50., h: 50. } });
+        assert_eq!(widgets[2].layout(), Layout { pos: Coord { x: 56., y: 0. }, size: Size { w: 150., h: 50. } });
+    }
+
+    #[test]
+    fn panes_keep_min_size() {
+        let (mut root, mut widgets) = paned();
+        root.layouter_impl::<PanedLayouter>().set_split_ratio(1.0);
+        root.layouter.as_ref().unwrap().calc_size(&mut widgets, root.children.as_slice());
+        root.layouter.as_ref().unwrap().apply_layouts(
+            &mut widgets, root.children.as_slice(), Coord::default(), Size { w: 106., h: 50. }
+        );
+        assert_eq!(widgets[1].size().w, 80.);
+        assert_eq!(widgets[2].layout().pos.x, 86.);
+        assert_eq!(widgets[2].size().w, 20.);
+    }
+
+    #[test]
+    fn divider_dragged() {
+        let (mut root, mut widgets) = paned();
+        root.layouter.as_ref().unwrap().calc_size(&mut widgets, root.children.as_slice());
+        let layout = Layout { pos: Coord { x: 10., y: 0. }, size: Size { w: 106., h: 50. } };
+        root.layouter.as_ref().unwrap().apply_layouts(&mut widgets, root.children.as_slice(), layout.pos, layout.size);
+
+        let paned = root.layouter.as_ref().unwrap().downcast_ref::<PanedLayouterImpl>().unwrap();
+        assert!(paned.divider_hit(&widgets, root.children.as_slice(), Coord { x: 62., y: 20. }));
+        assert!(!paned.divider_hit(&widgets, root.children.as_slice(), Coord { x: 58., y: 20. }));
+
+        let paned = root.layouter_impl::<PanedLayouter>();
+        paned.drag_divider(layout, Coord { x: 38., y: 20. });
+        assert_eq!(paned.split_ratio(), 0.25);
+        paned.drag_divider(layout, Coord { x: 0., y: 20. });
+        assert_eq!(paned.split_ratio(), 0.);
+    }
+
+    #[test]
+    #[should_panic(expected = "PanedLayouter pane First already taken")]
+    fn pane_taken() {
+        let mut paned = PanedLayouterImpl::default();
+        paned.pack(0, Pane::First);
+        paned.pack(1, Pane::First);
+    }
+}
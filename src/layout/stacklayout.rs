@@ -111,6 +111,8 @@ trait LengthCrossExpander {
     fn cross(size: Size) -> f64;
     fn length(size: Size) -> f64;
     fn length_expandable(widget: &Box<dyn Widget>) -> bool;
+    fn max_length(constraints: &Constraints) -> Option<f64>;
+    fn max_cross(constraints: &Constraints) -> Option<f64>;
     fn real_coord(len_pos: f64, cross: f64) -> Coord;
     fn len_cross_pos(pos: Coord) -> (f64, f64);
     fn real_size(length: f64, cross: f64) -> Size;
@@ -147,6 +149,14 @@ impl LengthCrossExpander for HorizontalExpander {
         widget.width_expandable()
     }
 
+    fn max_length(constraints: &Constraints) -> Option<f64> {
+        constraints.max_w
+    }
+
+    fn max_cross(constraints: &Constraints) -> Option<f64> {
+        constraints.max_h
+    }
+
     fn real_coord(len_pos: f64, cross: f64) -> Coord {
         Coord { x: len_pos, y: cross }
     }
@@ -191,6 +201,14 @@ impl LengthCrossExpander for VerticalExpander {
         widget.height_expandable()
     }
 
+    fn max_length(constraints: &Constraints) -> Option<f64> {
+        constraints.max_h
+    }
+
+    fn max_cross(constraints: &Constraints) -> Option<f64> {
+        constraints.max_w
+    }
+
     fn real_coord(len_pos: f64, cross: f64) -> Coord {
         Coord { x: cross, y: len_pos }
     }
@@ -208,6 +226,7 @@ struct LayoutApplyer<'a, E: LengthCrossExpander> {
     d: &'a StackLayoutData,
     widgets: &'a mut Vec<Box<dyn Widget>>,
     children: &'a [ui::WidgetNode],
+    subnodes: Vec<Id>,
     size_avail: Size,
 
     expander_type: PhantomData<E>
@@ -218,25 +237,34 @@ impl<'a, E: LengthCrossExpander> LayoutApplyer<'a, E> {
            widgets: &'a mut Vec<Box<dyn Widget>>,
            children: &'a [ui::WidgetNode],
            size_avail: Size) -> Self {
-        Self { d, widgets, children, size_avail, expander_type: PhantomData::<E> }
+        let subnodes = d.subnodes.iter()
+            .filter(|&&sn| widgets[children[sn].id].is_visible())
+            .cloned()
+            .collect();
+        Self { d, widgets, children, subnodes, size_avail, expander_type: PhantomData::<E> }
     }
 
     fn apply_cross(&mut self) {
         let avail = E::cross(self.size_avail) - 2.*self.d.padding;
 
-        for sn in self.d.subnodes.iter() {
-            let widget = &mut self.widgets[self.children[*sn].id];
-            E::set_cross(widget, avail);
+        for sn in self.subnodes.iter() {
+            let node = &self.children[*sn];
+            let widget = &mut self.widgets[node.id];
+            let cross = match E::max_cross(&node.constraints) {
+                Some(max) => avail.min(max.max(E::cross(widget.size()))),
+                None => avail
+            };
+            E::set_cross(widget, cross);
         }
     }
 
     fn expandable_length(&self) -> f64 {
-        let sized_widgets = self.d.subnodes.iter()
+        let sized_widgets = self.subnodes.iter()
             .filter(|&&sn| E::sized_length(&self.widgets[self.children[sn].id]))
             .count();
-        let needed_spacing = self.d.spacing * (sized_widgets - 1) as f64;
+        let needed_spacing = self.d.spacing * sized_widgets.saturating_sub(1) as f64;
         let available_length = E::length(self.size_avail) - needed_spacing - 2.*self.d.padding;
-        let natural_length = self.d.subnodes.iter().fold(0.0, |total_length, sn| {
+        let natural_length = self.subnodes.iter().fold(0.0, |total_length, sn| {
             total_length + E::length(self.widgets[self.children[*sn].id].size())
         });
 
@@ -244,24 +272,18 @@ impl<'a, E: LengthCrossExpander> LayoutApplyer<'a, E> {
     }
 
     fn count_spacers(&self) -> usize {
-        self.d.subnodes.iter()
+        self.subnodes.iter()
             .filter(|&&sn| self.widgets[self.children[sn].id].downcast_ref::<Spacer>().is_some())
             .count()
     }
 
-    fn count_expandables(&self) -> usize {
-        self.d.subnodes.iter()
-            .filter(|&&sn| E::length_expandable(&self.widgets[self.children[sn].id]))
-            .count()
-    }
-
     fn expand_spacers(&mut self) -> bool {
         let spacers = self.count_spacers();
         if spacers == 0 {
             return false
         }
         let expand_each = self.expandable_length() / spacers as f64;
-        for sn in self.d.subnodes.iter() {
+        for sn in self.subnodes.iter() {
             let widget = &mut self.widgets[self.children[*sn].id];
             if widget.downcast_ref::<Spacer>().is_some() {
                 E::expand_length(widget, expand_each);
@@ -270,25 +292,60 @@ impl<'a, E: LengthCrossExpander> LayoutApplyer<'a, E> {
         true
     }
 
-    fn expand_expandable_widgets(&mut self) {
-        let expandable_widgets = self.count_expandables();
-        if expandable_widgets == 0 {
-            return;
+    /// The length the subnode `sn` can be expanded until it reaches its maximum
+    fn room(&self, sn: Id) -> f64 {
+        let node = &self.children[sn];
+        match E::max_length(&node.constraints) {
+            Some(max) => (max - E::length(self.widgets[node.id].size())).max(0.0),
+            None => f64::INFINITY
         }
-        let expand_each = self.expandable_length() / expandable_widgets as f64;
+    }
 
-        for sn in self.d.subnodes.iter() {
-            let widget = &mut self.widgets[self.children[*sn].id];
-            if widget.downcast_ref::<Spacer>().is_none() {
-                E::expand_length(widget, expand_each)
+    /// Distributes the extra length to the expandable widgets
+    /// according to their stretch factors.
+    ///
+    /// Widgets that would exceed their maximal length are expanded to
+    /// it and the remaining length is distributed among the others.
+    fn expand_expandable_widgets(&mut self) {
+        let mut open: Vec<Id> = self.subnodes.iter()
+            .filter(|&&sn| E::length_expandable(&self.widgets[self.children[sn].id]))
+            .cloned()
+            .collect();
+        let mut extra = self.expandable_length();
+
+        while !open.is_empty() {
+            let total_stretch = open.iter()
+                .fold(0.0, |total, sn| total + self.children[*sn].constraints.stretch_factor);
+            if total_stretch <= 0.0 {
+                return;
+            }
+
+            let (clamped, unclamped): (Vec<Id>, Vec<Id>) = open.iter().cloned().partition(|&sn| {
+                let share = extra * self.children[sn].constraints.stretch_factor / total_stretch;
+                extra > 0.0 && self.room(sn) <= share
+            });
+
+            if clamped.is_empty() {
+                for sn in unclamped {
+                    let share = extra * self.children[sn].constraints.stretch_factor / total_stretch;
+                    E::expand_length(&mut self.widgets[self.children[sn].id], share);
+                }
+                return;
+            }
+
+            for sn in clamped {
+                let room = self.room(sn);
+                E::expand_length(&mut self.widgets[self.children[sn].id], room);
+                extra -= room;
             }
+            open = unclamped;
         }
     }
 
     fn apply_positions(&mut self, start: f64, cross: f64) {
         let mut len_pos = start + self.d.padding;
         let mut spacing = 0.0;
-        for sn in self.d.subnodes.iter() {
+        for sn in self.subnodes.iter() {
             let (length, pos) = {
                 let widget = &mut self.widgets[self.children[*sn].id];
 
@@ -338,6 +395,9 @@ trait StackLayouterImpl : LayouterImpl {
         let mut needed_length = padding;
         let mut needed_cross = 0.0;
         for subnode in self.stack_layout_data().subnodes.iter() {
+            if !widgets[children[*subnode].id].is_visible() {
+                continue;
+            }
 
             let size = children[*subnode].calc_widget_sizes(widgets);
             let length = Self::Expander::length(size);
@@ -441,6 +501,7 @@ impl LayouterImpl for VerticalLayouterImpl {
 
 impl VerticalLayouterImpl {
     fn pack(&mut self, subnode_id: Id, target: StackDirection) { self.d.pack(subnode_id, target) }
+    pub(crate) fn clear(&mut self) { self.d.subnodes.clear() }
 }
 
 impl Layouter for VerticalLayouter {
@@ -896,6 +957,98 @@ mod tests {
         assert_eq!(widgets[w3].pos(), Coord { x: 0., y: 17.+42.+17. });
     }
 
+    #[test]
+    fn layout_two_width_expandable_stretch_factors_horizontally() {
+        let mut root = WidgetNode::root::<HorizontalLayouter>();
+        let mut widgets: Vec<Box<dyn Widget>> = vec![Box::new(RootWidget::default())];
+
+        root.layouter_impl::<HorizontalLayouter>().set_spacing(5.).set_padding(17.);
+        let root_widget_handle = LayoutWidgetHandle::<HorizontalLayouter, RootWidget>::new(WidgetHandle::new(0));
+
+        let w1 = new_widget::<WidthExpandable>(&mut widgets, &mut root);
+        root.pack(w1, root_widget_handle, StackDirection::Back);
+        root.children[0].constraints.stretch_factor = 2.;
+
+        let w2 = new_widget::<WidthExpandable>(&mut widgets, &mut root);
+        root.pack(w2, root_widget_handle, StackDirection::Back);
+
+        let size = root.layouter.as_ref().unwrap().calc_size(&mut widgets, root.children.as_slice());
+
+        root.layouter.unwrap().apply_layouts(
+            &mut widgets,
+            root.children.as_slice(),
+            Coord::default(),
+            size + Size { w: 30., h: 0. }
+        );
+
+        assert_eq!(widgets[w1].size(), Size { w: 12.+20., h: 42.});
+        assert_eq!(widgets[w2].size(), Size { w: 12.+10., h: 42.});
+        assert_eq!(widgets[w2].pos(), Coord { x: 17.+12.+20.+5., y: 17.});
+    }
+
+    #[test]
+    fn layout_width_expandable_max_width_horizontally() {
+        let mut root = WidgetNode::root::<HorizontalLayouter>();
+        let mut widgets: Vec<Box<dyn Widget>> = vec![Box::new(RootWidget::default())];
+
+        root.layouter_impl::<HorizontalLayouter>().set_spacing(5.).set_padding(17.);
+        let root_widget_handle = LayoutWidgetHandle::<HorizontalLayouter, RootWidget>::new(WidgetHandle::new(0));
+
+        let w1 = new_widget::<WidthExpandable>(&mut widgets, &mut root);
+        root.pack(w1, root_widget_handle, StackDirection::Back);
+        root.children[0].constraints.max_w = Some(17.);
+
+        let w2 = new_widget::<BothExpandable>(&mut widgets, &mut root);
+        root.pack(w2, root_widget_handle, StackDirection::Back);
+        root.children[1].constraints.max_h = Some(50.);
+
+        let size = root.layouter.as_ref().unwrap().calc_size(&mut widgets, root.children.as_slice());
+
+        root.layouter.unwrap().apply_layouts(
+            &mut widgets,
+            root.children.as_slice(),
+            Coord::default(),
+            size + Size { w: 30., h: 20. }
+        );
+
+        assert_eq!(widgets[w1].size(), Size { w: 17., h: 42.});
+        assert_eq!(widgets[w2].size(), Size { w: 23.+25., h: 50.});
+    }
+
+    #[test]
+    fn layout_skip_invisible_widget_horizontally() {
+        let mut root = WidgetNode::root::<HorizontalLayouter>();
+        let mut widgets: Vec<Box<dyn Widget>> = vec![Box::new(RootWidget::default())];
+
+        root.layouter_impl::<HorizontalLayouter>().set_spacing(5.).set_padding(17.);
+        let root_widget_handle = LayoutWidgetHandle::<HorizontalLayouter, RootWidget>::new(WidgetHandle::new(0));
+
+        let w1 = new_widget::<NotExpandable>(&mut widgets, &mut root);
+        root.pack(w1, root_widget_handle, StackDirection::Front);
+
+        let w2 = new_widget::<NotExpandableLow>(&mut widgets, &mut root);
+        root.pack(w2, root_widget_handle, StackDirection::Front);
+
+        let w3 = new_widget::<NotExpandableNarrow>(&mut widgets, &mut root);
+        root.pack(w3, root_widget_handle, StackDirection::Front);
+
+        widgets[w2].set_visible(false);
+
+        let size = root.layouter.as_ref().unwrap().calc_size(&mut widgets, root.children.as_slice());
+
+        assert_eq!(size, Size { w: 17.+12.+5.+23.+17., h: 17.+42.+17. });
+
+        root.layouter.unwrap().apply_layouts(
+            &mut widgets,
+            root.children.as_slice(),
+            Coord::default(),
+            size
+        );
+
+        assert_eq!(widgets[w3].pos(), Coord { x: 17., y: 17.});
+        assert_eq!(widgets[w1].pos(), Coord { x: 17.+12.+5., y: 17.});
+    }
+
     #[test]
     fn layout_two_not_expandable_widgets_vertically() {
         let mut root = WidgetNode::root::<VerticalLayouter>();
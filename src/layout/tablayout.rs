@@ -0,0 +1,207 @@
+//! Tab layouting showing one of several pages at a time
+use pugl_sys::*;
+
+use crate::layout::*;
+use crate::ui;
+use crate::widget::*;
+
+/// Layouter showing only one of its children, the active tab
+///
+/// The `Target` is the label of the tab. Only the active tab is laid
+/// out, drawn and receives events, the others are treated as if they
+/// were hidden. The first tab packed is the active one until
+/// [`TabLayouterImpl::set_active_tab()`](struct.TabLayouterImpl.html#method.set_active_tab)
+/// is called.
+///
+/// The layouter does not draw a tab bar. The labels, for example to
+/// be shown by buttons switching the tabs, are available by
+/// [`TabLayouterImpl::labels()`](struct.TabLayouterImpl.html#method.labels).
+#[derive(Clone, Copy, Default, Debug)]
+pub struct TabLayouter;
+
+pub struct TabLayouterImpl {
+    tabs: Vec<(Id, String)>,
+    active: usize
+}
+
+impl Default for TabLayouterImpl {
+    fn default() -> TabLayouterImpl {
+        TabLayouterImpl {
+            tabs: Vec::new(),
+            active: 0
+        }
+    }
+}
+
+impl TabLayouterImpl {
+    /// Makes the tab `index` the active tab.
+    ///
+    /// The change takes effect with the next
+    /// [`UI::do_layout()`](../../ui/struct.UI.html#method.do_layout),
+    /// which is when the widgets of the tabs are notified that they
+    /// are shown or hidden. Use
+    /// [`UI::set_active_tab()`](../../ui/struct.UI.html#method.set_active_tab)
+    /// to switch tabs immediately. Indices out of range are ignored.
+    pub fn set_active_tab(&mut self, index: usize) -> &mut TabLayouterImpl {
+        if index < self.tabs.len() {
+            self.active = index;
+        }
+        self
+    }
+
+    /// Returns the index of the active tab.
+    pub fn active_tab(&self) -> usize {
+        self.active
+    }
+
+    /// Returns the labels of the tabs in the order they were packed.
+    pub fn labels(&self) -> Vec<&str> {
+        self.tabs.iter().map(|(_, label)| label.as_str()).collect()
+    }
+
+    fn pack(&mut self, subnode_id: Id, label: String) {
+        self.tabs.push((subnode_id, label));
+    }
+
+    fn active_subnode(&self) -> Option<Id> {
+        self.tabs.get(self.active).map(|(sn, _)| *sn)
+    }
+}
+
+impl LayouterImpl for TabLayouterImpl {
+    fn apply_layouts(&self, widgets: &mut Vec<Box<dyn Widget>>, children: &[ui::WidgetNode],
+                     orig_pos: Coord, size_avail: Size) {
+        let node = match self.active_subnode() {
+            Some(sn) => &children[sn],
+            None => return
+        };
+        let widget = &mut widgets[node.id];
+        if widget.width_expandable() {
+            let w = node.constraints.max_w.map_or(size_avail.w, |max| size_avail.w.min(max.max(widget.size().w)));
+            widget.set_width(w);
+        }
+        if widget.height_expandable() {
+            let h = node.constraints.max_h.map_or(size_avail.h, |max| size_avail.h.min(max.max(widget.size().h)));
+            widget.set_height(h);
+        }
+        widget.set_pos(&orig_pos);
+        node.apply_sizes(widgets, orig_pos);
+    }
+
+    fn calc_size(&self, widgets: &mut Vec<Box<dyn Widget>>, children: &[ui::WidgetNode]) -> Size {
+        match self.active_subnode() {
+            Some(sn) if widgets[children[sn].id].is_visible() => children[sn].calc_widget_sizes(widgets),
+            _ => Size::default()
+        }
+    }
+
+    fn shows_child(&self, index: usize) -> bool {
+        self.active_subnode() == Some(index)
+    }
+}
+
+impl Layouter for TabLayouter {
+    type Target = String;
+    type Implementor = TabLayouterImpl;
+
+    fn new_implementor() -> Box<dyn LayouterImpl> {
+        Box::new(TabLayouterImpl::default())
+    }
+    fn pack(&mut self, layout_impl: &mut Self::Implementor, subnode_id: Id, target: Self::Target) {
+        layout_impl.pack(subnode_id, target);
+    }
+    fn expandable() -> (bool, bool) {
+        (true, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::*;
+
+    #[derive(Default)]
+    struct RootWidget {
+        stub: WidgetStub
+    }
+
+    impl Widget for RootWidget {
+        widget_stub!();
+    }
+
+    #[derive(Default)]
+    struct NotExpandable {
+        stub: WidgetStub
+    }
+
+    impl Widget for NotExpandable {
+        widget_stub!();
+
+        fn min_size(&self) -> Size {
+            Size { w: 23., h: 42. }
+        }
+    }
+
+    #[derive(Default)]
+    struct BothExpandable {
+        stub: WidgetStub
+    }
+
+    impl Widget for BothExpandable {
+        widget_stub!();
+
+        fn min_size(&self) -> Size {
+            Size { w: 12., h: 12. }
+        }
+
+        fn width_expandable(&self) -> bool {
+            true
+        }
+        fn height_expandable(&self) -> bool {
+            true
+        }
+    }
+
+    fn new_widget<W: Widget + Default>(widgets: &mut Vec<Box<dyn Widget>>, node: &mut WidgetNode) -> Id {
+        let id = widgets.len();
+        widgets.push(Box::new(W::default()));
+        node.children.push(WidgetNode::new_leaf(id));
+        id
+    }
+
+    #[test]
+    fn only_active_tab_layouted() {
+        let mut root = WidgetNode::root::<TabLayouter>();
+        let mut widgets: Vec<Box<dyn Widget>> = vec![Box::new(RootWidget::default())];
+        let root_widget_handle = LayoutWidgetHandle::<TabLayouter, RootWidget>::new(WidgetHandle::new(0));
+
+        let w1 = new_widget::<NotExpandable>(&mut widgets, &mut root);
+        root.pack(w1, root_widget_handle, String::from("One"));
+        let w2 = new_widget::<BothExpandable>(&mut widgets, &mut root);
+        root.pack(w2, root_widget_handle, String::from("Two"));
+
+        assert_eq!(root.layouter_impl::<TabLayouter>().labels(), vec!["One", "Two"]);
+        assert_eq!(root.layouter_impl::<TabLayouter>().active_tab(), 0);
+
+        let size = root.layouter.as_ref().unwrap().calc_size(&mut widgets, root.children.as_slice());
+        assert_eq!(size, Size { w: 23., h: 42. });
+        assert!(root.layouter.as_ref().unwrap().shows_child(0));
+        assert!(!root.layouter.as_ref().unwrap().shows_child(1));
+
+        root.layouter_impl::<TabLayouter>().set_active_tab(1).set_active_tab(5);
+        assert_eq!(root.layouter_impl::<TabLayouter>().active_tab(), 1);
+
+        let size = root.layouter.as_ref().unwrap().calc_size(&mut widgets, root.children.as_slice());
+        assert_eq!(size, Size { w: 12., h: 12. });
+
+        root.layouter.as_ref().unwrap().apply_layouts(
+            &mut widgets,
+            root.children.as_slice(),
+            Coord { x: 5., y: 7. },
+            Size { w: 50., h: 60. }
+        );
+        assert_eq!(widgets[w2].layout(), Layout { pos: Coord { x: 5., y: 7. }, size: Size { w: 50., h: 60. } });
+        assert!(!root.layouter.as_ref().unwrap().shows_child(0));
+        assert!(root.layouter.as_ref().unwrap().shows_child(1));
+    }
+}
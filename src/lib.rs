@@ -10,6 +10,56 @@
 //!
 //! It does not feature actual widgets, though.
 //!
+//! # Cargo features
+//!
+//! * `text` (default): text measuring by [`TextMetrics`](text/struct.TextMetrics.html),
+//!   locales and the drawing of tooltips. Pulls in `pango` and `pangocairo`.
+//!
+//! * `builder`: constructing layouts from declarative descriptions,
+//!   see [`builder`](builder/index.html). Pulls in `serde`.
+//!
+//! * `serde`: (de)serialization of
+//!   [`LayoutSnapshot`](ui/struct.LayoutSnapshot.html)s. Also enabled
+//!   by `builder`.
+//!
+//! * `frame-stats`: timing statistics of the frames, see
+//!   [`UI::frame_stats()`](ui/struct.UI.html#method.frame_stats).
+//!
+//! * `widget-stats`: per widget counters of events and repaints and
+//!   the time spent in the widgets, see
+//!   [`UI::widget_stats()`](ui/struct.UI.html#method.widget_stats).
+//!
+//! * `cairo-helpers` (default): the images of the
+//!   [`assets`](assets/index.html), nine-patch [`panel`](panel/index.html)s
+//!   and the double buffering of [`buffer`](buffer/index.html).
+//!   Enables the PNG support of `cairo-rs`.
+//!
+//! * `svg`: SVG images in the [`assets`](assets/index.html) and
+//!   widget skins from SVG documents, see [`skin`](skin/index.html).
+//!   Pulls in `librsvg`, implies `cairo-helpers`.
+//!
+//! * `raw-window-handle`: embedding the `UI` into windows given by
+//!   the [`raw-window-handle`](https://docs.rs/raw-window-handle)
+//!   crate, see [`embed`](embed/index.html).
+//!
+//! * `lv2`: driving the `UI` by the idle and the show interface of
+//!   LV2 hosts, see [`lv2`](lv2/index.html).
+//!
+//! * `atspi`: exposing the `UI` to screen readers over AT-SPI, see
+//!   [`atspi`](atspi/index.html). Pulls in `accesskit` and
+//!   `accesskit_unix`.
+//!
+//! * `testing`: a mock of the windowing system for unit testing and
+//!   the comparison of rendered UIs against reference images, see
+//!   [`golden`](golden/index.html), and scripted user interaction, see
+//!   [`script`](script/index.html).
+//!
+//! Without any feature only `cairo` is needed besides `pugl-sys`. The
+//! [`CairoBackend`](draw/struct.CairoBackend.html) and the focus rings
+//! and highlights of the [`theme`](theme/index.html) are not behind a
+//! feature, as `pugl-sys` hands the `UI` a `cairo::Context` to draw
+//! on, so that `cairo` can not be left out anyway. The crate does not
+//! come with a widget set to strip.
 //!
 //! # API principles
 //!
@@ -84,7 +134,7 @@
 //!
 //! impl Widget for RootWidget {
 //!     widget_stub!();
-//!     fn exposed (&mut self, _expose: &ExposeArea, cr: &cairo::Context) {
+//!     fn exposed (&mut self, _ctx: &PaintContext, cr: &cairo::Context) {
 //!         cr.set_source_rgb(0.2, 0.2, 0.2);
 //!         let size = self.size();
 //!         cr.rectangle(0., 0., size.w, size.h);
@@ -114,7 +164,7 @@
 //!     widget_stub!();
 //!
 //!     // rendering the button
-//!     fn exposed(&mut self, _expose: &ExposeArea, cr: &cairo::Context) {
+//!     fn exposed(&mut self, _ctx: &PaintContext, cr: &cairo::Context) {
 //!         cr.set_source_rgb(0.7, 0.7, 0.7);
 //!         let (x, y, w, h) = self.rect();
 //!         cr.rectangle(x, y, w, h);
@@ -177,6 +227,7 @@
 //! }
 //! ```
 extern crate cairo;
+#[cfg(feature = "text")]
 extern crate pango;
 
 extern crate pugl_sys;
@@ -186,10 +237,49 @@ extern crate downcast_rs;
 
 #[macro_use]
 pub mod widget;
+pub mod accessibility;
 
 #[macro_use]
 pub mod ui;
 pub mod layout;
+pub mod theme;
+pub mod sanitizer;
+#[cfg(feature = "text")]
+pub mod text;
+pub mod pool;
+#[cfg(feature = "cairo-helpers")]
+pub mod buffer;
+pub mod region;
+pub mod input;
+pub mod value;
+pub mod recording;
+pub mod gestures;
+#[cfg(feature = "cairo-helpers")]
+pub mod assets;
+pub mod draw;
+pub mod panel;
+#[cfg(feature = "svg")]
+pub mod skin;
+pub mod embed;
+pub mod group;
+pub mod window;
+pub mod popup;
+#[cfg(feature = "builder")]
+pub mod builder;
+#[cfg(feature = "lv2")]
+pub mod lv2;
+#[cfg(feature = "atspi")]
+pub mod atspi;
+#[cfg(feature = "testing")]
+pub mod golden;
+#[cfg(feature = "testing")]
+pub mod script;
+
+mod tooltip;
+mod prediction;
+mod kinetic;
+mod routing;
+mod spatial;
 
 #[macro_export]
 macro_rules! event_processed { () => (Some($crate::ui::EventState::Processed)) }
@@ -201,25 +291,35 @@ mod tests {
     use pugl_sys::*;
     use crate::ui::*;
     use crate::layout::stacklayout::*;
+    use crate::layout::tablayout::*;
     use crate::widget::*;
+    use crate::theme::*;
+    use crate::pool::*;
+    use crate::region::*;
+    use crate::input::*;
     use cairo;
 
     #[derive(Default)]
     struct RootWidget {
         stub: WidgetStub,
         wants_quit: bool,
-        focus_next: bool
+        focus_next: bool,
+        scroll_container: bool,
+        scrolls: usize
     }
 
     impl Widget for RootWidget {
         widget_stub!();
-        fn exposed (&mut self, _expose: &ExposeArea, cr: &cairo::Context) {
+        fn exposed (&mut self, _ctx: &PaintContext, cr: &cairo::Context) {
             cr.set_source_rgb (0.2, 0.2, 0.2);
             let size = self.size();
             cr.rectangle (0., 0., size.w, size.h);
             cr.fill ();
         }
         fn event(&mut self, ev: Event) -> Option<Event> {
+            if let EventType::Scroll(_) = ev.data {
+                self.scrolls += 1;
+            }
             ev.try_keypress()
                 .and_then(|kp| kp.try_char())
                 .and_then(|c| {
@@ -237,6 +337,8 @@ mod tests {
                 })
                 .or(event_not_processed!()).and_then (|p| p.pass_event (ev))
         }
+
+        fn scroll_container(&self) -> bool { self.scroll_container }
     }
 
     impl RootWidget {
@@ -257,6 +359,10 @@ mod tests {
         color: (f64, f64, f64),
         min_size: Size,
         name: &'static str,
+        cursor: Option<Cursor>,
+        tooltip: Option<&'static str>,
+        local_coordinates: bool,
+        last_press_pos: Coord,
 
         width_expandable: bool,
         height_expandable: bool,
@@ -266,12 +372,16 @@ mod tests {
         recently_clicked: bool,
 
         clicked: bool,
-        pointer_entered: bool
+        pointer_entered: bool,
+        shown: Option<bool>,
+        reports_gestures: bool,
+        wants_all_motion_events: bool,
+        motion_events: usize
     }
 
     impl Widget for RectWidget {
         widget_stub!();
-        fn exposed (&mut self, _expose: &ExposeArea, cr: &cairo::Context) {
+        fn exposed (&mut self, _ctx: &PaintContext, cr: &cairo::Context) {
             let (r, g, b) = self.color;
             let size = self.size();
             let pos = self.pos();
@@ -312,6 +422,7 @@ mod tests {
         fn event (&mut self, ev: Event) -> Option<Event> {
             match ev.data {
                 EventType::MouseMove (_mm) => {
+                    self.motion_events += 1;
                     if self.drag_ongoing {
                         println!("drag to {}:{} {}", ev.context.pos.x, ev.context.pos.y, self.name);
                     }
@@ -321,6 +432,7 @@ mod tests {
                     if btn.num == 1 {
                         self.drag_ongoing = true;
                     }
+                    self.last_press_pos = ev.pos();
                     event_processed!()
                 }
                 EventType::MouseButtonRelease (btn) => {
@@ -371,6 +483,16 @@ mod tests {
 
         fn takes_focus(&self) -> bool { true }
 
+        fn cursor(&self) -> Option<Cursor> { self.cursor }
+
+        fn tooltip(&self) -> Option<String> { self.tooltip.map(String::from) }
+
+        fn local_coordinates(&self) -> bool { self.local_coordinates }
+
+        fn reports_gestures(&self) -> bool { self.reports_gestures }
+
+        fn wants_all_motion_events(&self) -> bool { self.wants_all_motion_events }
+
         fn pointer_enter(&mut self) {
             self.pointer_entered = true;
             println!("pointer enter {}", self.name);
@@ -380,6 +502,14 @@ mod tests {
             self.pointer_entered = false;
             println!("pointer leave {}", self.name);
         }
+
+        fn shown(&mut self) {
+            self.shown = Some(true);
+        }
+
+        fn hidden(&mut self) {
+            self.shown = Some(false);
+        }
     }
 
     impl RectWidget {
@@ -398,6 +528,35 @@ mod tests {
         }
     }
 
+    #[derive(Default)]
+    struct TextEntry {
+        stub: WidgetStub,
+        text: EditableText
+    }
+
+    impl Widget for TextEntry {
+        widget_stub!();
+        fn event(&mut self, ev: Event) -> Option<Event> {
+            match ev.data {
+                EventType::KeyPress(key) => match self.text.handle_key(&key) {
+                    EditOutcome::Ignored => event_not_processed!(),
+                    EditOutcome::Copy { text, .. } => {
+                        self.copy_to_clipboard(text);
+                        event_processed!()
+                    }
+                    _ => event_processed!()
+                },
+                _ => event_not_processed!()
+            }.and_then(|es| es.pass_event(ev))
+        }
+        fn min_size(&self) -> Size { Size { w: 50., h: 20. } }
+        fn takes_focus(&self) -> bool { true }
+        fn takes_text_input(&self) -> bool { true }
+        fn paste(&mut self, text: &str) {
+            self.text.insert(text);
+        }
+    }
+
     #[cfg(feature = "testing")]
     #[test]
     fn root_widget_quit_event() {
@@ -419,6 +578,31 @@ mod tests {
         assert!(ui.root_widget().wants_quit());
     }
 
+    #[cfg(feature = "testing")]
+    #[test]
+    fn window_shortcuts() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let key_press = |key| Event {
+            data: EventType::KeyPress(Key { key, modifiers: Modifiers::default(), code: 0 }),
+            context: EventContext::default()
+        };
+
+        assert_eq!(view.handle().window_shortcuts(), WindowShortcuts::default());
+        view.queue_event(key_press(KeyVal::Special(SpecialKey::F11)));
+        view.handle().update(-1.0);
+        assert!(!view.handle().fullscreen_toggle_requested());
+
+        view.handle().set_window_shortcuts(WindowShortcuts { close: true, fullscreen: true });
+        view.queue_event(key_press(KeyVal::Special(SpecialKey::F11)));
+        view.queue_event(key_press(KeyVal::Character('w')));
+        view.handle().update(-1.0);
+        assert!(view.handle().fullscreen_toggle_requested());
+        assert!(!view.handle().fullscreen_toggle_requested());
+        assert!(!view.handle().close_request_issued());
+    }
+
     #[cfg(feature = "testing")]
     #[test]
     fn layout_single_widget_unscaled() {
@@ -528,6 +712,59 @@ mod tests {
         assert_eq!(view.mock_instance().min_size(), expected_size);
     }
 
+    #[cfg(feature = "testing")]
+    #[test]
+    fn layout_single_widget_fractional_scale() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.25));
+
+        let widget_size = Size { w: 42., h: 23. };
+        let expected_size = Size { w: 53., h: 29. };
+        let widget = {
+            let ui = view.handle();
+            let widget = ui.new_widget(Box::new(RectWidget {
+                min_size: widget_size,
+                ..Default::default()
+            }));
+            ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+            ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Front);
+            ui.do_layout();
+            ui.fit_window_size();
+            ui.fit_window_min_size();
+            ui.show_window();
+
+            assert_eq!(ui.get_frame().size, expected_size);
+            widget
+        };
+        assert_eq!(view.mock_instance().min_size(), expected_size);
+
+        view.fake_resize(expected_size);
+        let ui = view.handle();
+        assert_eq!(ui.widget(widget).size(), widget_size);
+        assert!(ui.root_widget().size().w >= widget_size.w);
+        assert!(ui.root_widget().size().h >= widget_size.h);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn layout_single_widget_fractional_scale_integral_size_not_rounded_up() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.5));
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 42., h: 22. },
+            ..Default::default()
+        }));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Front);
+        ui.do_layout();
+        ui.fit_window_size();
+        ui.show_window();
+
+        assert_eq!(ui.get_frame().size, Size { w: 63., h: 33. });
+    }
+
     #[cfg(feature = "testing")]
     #[test]
     #[should_panic(expected = "Root window size zero. Have you forgotten ui::UI::do_layout()?")]
@@ -872,7 +1109,7 @@ mod tests {
 
     #[cfg(feature = "testing")]
     #[test]
-    fn two_widgets_clicks() {
+    fn group_visibility_relayout() {
         let rw = Box::new(RootWidget::default());
         let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
 
@@ -883,16 +1120,6 @@ mod tests {
             context: EventContext { pos: Coord{ x: 21., y: 11.5 }, ..Default::default() }
         });
 
-        view.queue_event(Event {
-            data: EventType::MouseButtonRelease(MouseButton { num: 1, modifiers: Modifiers::default() }),
-            context: EventContext { pos: Coord{ x: 21., y: 28. }, ..Default::default() }
-        });
-
-        view.queue_event(Event {
-            data: EventType::MouseButtonRelease(MouseButton { num: 1, modifiers: Modifiers::default() }),
-            context: EventContext { pos: Coord{ x: 21., y: 46. }, ..Default::default() }
-        });
-
         let ui = view.handle();
         let widget_1 = ui.new_widget(Box::new(RectWidget {
             min_size: widget_size,
@@ -902,38 +1129,52 @@ mod tests {
             min_size: widget_size,
             ..Default::default()
         }));
-        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(10.);
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
         ui.pack_to_layout(widget_1, ui.root_layout(), StackDirection::Front);
         ui.pack_to_layout(widget_2, ui.root_layout(), StackDirection::Front);
+        ui.add_to_group("advanced", widget_2);
         ui.do_layout();
         ui.fit_window_size();
         ui.fit_window_min_size();
         ui.show_window();
 
-        assert!(!ui.widget(widget_1).clicked());
-        assert!(!ui.widget(widget_2).clicked());
-
-        ui.update(-1.0);
-        assert!(!ui.widget(widget_1).clicked());
-        assert!(ui.widget(widget_2).clicked());
+        assert_eq!(ui.widget(widget_1).pos(), Coord { x: 0.0, y: 23.0 });
 
-        ui.update(-1.0);
-        assert!(!ui.widget(widget_1).clicked());
-        assert!(!ui.widget(widget_2).clicked());
+        ui.set_group_visible("advanced", false);
+        assert!(!ui.widget(widget_2).is_visible());
+        assert_eq!(ui.widget(widget_1).pos(), Coord::default());
 
         ui.update(-1.0);
         assert!(ui.widget(widget_1).clicked());
         assert!(!ui.widget(widget_2).clicked());
+
+        ui.set_group_visible("advanced", true);
+        assert_eq!(ui.widget(widget_1).pos(), Coord { x: 0.0, y: 23.0 });
+
+        ui.set_group_sensitive("advanced", false);
+        assert!(ui.widget(widget_1).is_sensitive());
+        assert!(!ui.widget(widget_2).is_sensitive());
+
+        ui.set_group_highlighted("advanced", true);
+        assert!(!ui.is_highlighted(widget_1));
+        assert!(ui.is_highlighted(widget_2));
+        ui.set_group_highlighted("advanced", false);
+        assert!(!ui.is_highlighted(widget_2));
     }
 
     #[cfg(feature = "testing")]
     #[test]
-    fn focus_two_widgets() {
+    fn insensitive_widget_skipped() {
         let rw = Box::new(RootWidget::default());
         let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
 
         let widget_size = Size { w: 42., h: 23. };
 
+        view.queue_event(Event {
+            data: EventType::MouseButtonRelease(MouseButton { num: 1, modifiers: Modifiers::default() }),
+            context: EventContext { pos: Coord{ x: 21., y: 11.5 }, ..Default::default() }
+        });
+
         let ui = view.handle();
         let widget_1 = ui.new_widget(Box::new(RectWidget {
             min_size: widget_size,
@@ -944,111 +1185,40 @@ mod tests {
             ..Default::default()
         }));
         ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
-        ui.pack_to_layout(widget_1, ui.root_layout(), StackDirection::Front);
-        ui.pack_to_layout(widget_2, ui.root_layout(), StackDirection::Front);
+        ui.pack_to_layout(widget_1, ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(widget_2, ui.root_layout(), StackDirection::Back);
         ui.do_layout();
-        ui.fit_window_size();
-        ui.fit_window_min_size();
-        ui.show_window();
-
-        assert!(!ui.widget(widget_1).has_focus());
-        assert!(!ui.widget(widget_2).has_focus());
-
-        ui.focus_next_widget();
-
-        assert!(ui.widget(widget_1).has_focus());
-        assert!(!ui.widget(widget_2).has_focus());
-
-        ui.focus_next_widget();
 
+        ui.focus_widget(widget_1);
+        ui.set_sensitive(widget_1, false);
+        assert!(!ui.widget(widget_1).is_sensitive());
         assert!(!ui.widget(widget_1).has_focus());
-        assert!(ui.widget(widget_2).has_focus());
-
-        ui.focus_next_widget();
-
-        assert!(ui.widget(widget_1).has_focus());
-        assert!(!ui.widget(widget_2).has_focus());
-    }
-
-
-    #[cfg(feature = "testing")]
-    #[test]
-    fn test_focus() {
-        let rw = Box::new(RootWidget::default());
-        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
-
-        assert!(!view.handle().has_focus());
-        view.fake_focus_in();
-        assert!(view.handle().has_focus());
-        view.fake_focus_out();
-        assert!(!view.handle().has_focus());
-    }
-
-    #[cfg(feature = "testing")]
-    #[test]
-    fn two_widgets_focus() {
-        let rw = Box::new(RootWidget::default());
-        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
-
-        let space_event = Event {
-            data: EventType::KeyRelease(Key {
-                key: KeyVal::Character(' '),
-                modifiers: Modifiers::default(),
-                code: 0
-            }),
-            context: EventContext::default()
-        };
-
-        view.queue_event(space_event);
-        view.queue_event(space_event);
-        view.queue_event(space_event);
-
-        let ui = view.handle();
-        let widget_1 = ui.new_widget(Box::new(RectWidget::default()));
-        let widget_2 = ui.new_widget(Box::new(RectWidget::default()));
-
-        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(10.);
-
-        assert!(!ui.widget(widget_1).clicked());
-        assert!(!ui.widget(widget_2).clicked());
 
         ui.update(-1.0);
         assert!(!ui.widget(widget_1).clicked());
-        assert!(!ui.widget(widget_2).clicked());
 
+        ui.focus_widget(widget_1);
+        assert!(!ui.widget(widget_1).has_focus());
         ui.focus_next_widget();
-        ui.update(-1.0);
-        assert!(ui.widget(widget_1).clicked());
-        assert!(!ui.widget(widget_2).clicked());
+        assert!(ui.widget(widget_2).has_focus());
+        ui.focus_next_widget();
+        assert!(ui.widget(widget_2).has_focus());
 
+        ui.set_sensitive(widget_1, true);
         ui.focus_next_widget();
-        ui.update(-1.0);
-        assert!(!ui.widget(widget_1).clicked());
-        assert!(ui.widget(widget_2).clicked());
+        assert!(ui.widget(widget_1).has_focus());
     }
 
     #[cfg(feature = "testing")]
     #[test]
-    fn two_widgets_hover() {
+    fn hide_layout_widget() {
         let rw = Box::new(RootWidget::default());
         let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
 
-        let widget_1_hover_event = Event {
-            data: EventType::MouseMove(MotionContext::default()),
-            context: EventContext { pos: Coord{ x: 21., y: 36. }, ..Default::default() }
-        };
-        let widget_2_hover_event = Event {
-            data: EventType::MouseMove(MotionContext::default()),
-            context: EventContext { pos: Coord{ x: 21., y: 11.5 }, ..Default::default() }
-        };
-
-        view.queue_event(widget_1_hover_event);
-        view.queue_event(widget_2_hover_event);
-        view.queue_event(widget_1_hover_event);
-
         let widget_size = Size { w: 42., h: 23. };
 
         let ui = view.handle();
+        let panel = ui.new_layouter::<HorizontalLayouter>();
         let widget_1 = ui.new_widget(Box::new(RectWidget {
             min_size: widget_size,
             ..Default::default()
@@ -1058,48 +1228,3771 @@ mod tests {
             ..Default::default()
         }));
         ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
-        ui.pack_to_layout(widget_1, ui.root_layout(), StackDirection::Front);
-        ui.pack_to_layout(widget_2, ui.root_layout(), StackDirection::Front);
+        ui.pack_to_layout(panel.widget(), ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(widget_1, panel, StackDirection::Back);
+        ui.pack_to_layout(widget_2, ui.root_layout(), StackDirection::Back);
         ui.do_layout();
-        ui.fit_window_size();
-        ui.fit_window_min_size();
-        ui.show_window();
 
-        assert!(!ui.widget(widget_1).is_hovered());
-        assert!(!ui.widget(widget_1).pointer_in());
-        assert!(!ui.widget(widget_2).is_hovered());
-        assert!(!ui.widget(widget_2).pointer_in());
+        assert_eq!(ui.widget(widget_2).pos(), Coord { x: 0.0, y: 23.0 });
 
-        ui.update(1.0);
+        ui.focus_widget(widget_1);
+        assert!(ui.widget(widget_1).has_focus());
 
-        assert!(ui.widget(widget_1).is_hovered());
-        assert!(ui.widget(widget_1).pointer_in());
-        assert!(!ui.widget(widget_2).is_hovered());
-        assert!(!ui.widget(widget_2).pointer_in());
+        ui.set_visible(panel.widget(), false);
+        assert_eq!(ui.widget(widget_2).pos(), Coord::default());
+        assert!(!ui.widget(widget_1).has_focus());
 
-        ui.update(1.0);
+        ui.focus_next_widget();
+        assert!(ui.widget(widget_2).has_focus());
+        ui.focus_next_widget();
+        assert!(ui.widget(widget_2).has_focus());
 
-        assert!(!ui.widget(widget_1).is_hovered());
-        assert!(!ui.widget(widget_1).pointer_in());
-        assert!(ui.widget(widget_2).is_hovered());
-        assert!(ui.widget(widget_2).pointer_in());
+        ui.focus_widget(widget_1);
+        assert!(!ui.widget(widget_1).has_focus());
 
-        ui.update(1.0);
+        ui.set_visible(panel.widget(), true);
+        assert_eq!(ui.widget(widget_2).pos(), Coord { x: 0.0, y: 23.0 });
+        ui.focus_next_widget();
+        assert!(ui.widget(widget_1).has_focus());
+    }
 
-        assert!(ui.widget(widget_1).is_hovered());
-        assert!(ui.widget(widget_1).pointer_in());
-        assert!(!ui.widget(widget_2).is_hovered());
-        assert!(!ui.widget(widget_2).pointer_in());
+    #[derive(Default)]
+    struct RowWidget {
+        stub: WidgetStub,
+        text: String,
+        unbound: usize
+    }
+
+    impl Widget for RowWidget {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 100., h: 20. } }
+    }
+
+    impl Recyclable for RowWidget {
+        type Item = String;
+        fn bind(&mut self, item: &String) {
+            self.text = item.clone();
+        }
+        fn unbind(&mut self) {
+            self.unbound += 1;
+        }
     }
 
     #[cfg(feature = "testing")]
     #[test]
-    fn hover_pointer_enter_leave_window() {
+    fn widget_pool_recycling() {
         let rw = Box::new(RootWidget::default());
         let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
 
-        let enter_event = Event {
-            data: EventType::PointerIn,
+        let ui = view.handle();
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        let list = ui.new_layouter::<VerticalLayouter>();
+        ui.layouter(list).set_spacing(0.);
+        ui.pack_to_layout(list.widget(), ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        let items: Vec<String> = (0..1000).map(|i| format!("row {}", i)).collect();
+        let mut pool = WidgetPool::new(|| Box::new(RowWidget::default()));
+        let mut packed = 0;
+
+        pool.show_rows(ui, &items, 0..3, |ui, w| {
+            ui.pack_to_layout(w, list, StackDirection::Back);
+            packed += 1;
+        });
+        assert_eq!(packed, 3);
+        assert_eq!(pool.widget_count(), 3);
+        assert_eq!(ui.root_widget().size(), Size { w: 100., h: 60. });
+        let first = pool.handle(0).unwrap();
+        assert_eq!(ui.widget(first).text, "row 0");
+
+        pool.show_rows(ui, &items, 2..5, |ui, w| {
+            ui.pack_to_layout(w, list, StackDirection::Back);
+            packed += 1;
+        });
+        assert_eq!(packed, 3);
+        assert_eq!(pool.widget_count(), 3);
+        assert!(pool.handle(0).is_none());
+        assert_eq!(pool.row_of(first), Some(2));
+        assert_eq!(ui.widget(first).unbound, 1);
+        assert_eq!(ui.widget(pool.handle(4).unwrap()).text, "row 4");
+        assert_eq!(pool.row_of(pool.handle(2).unwrap()), Some(2));
+        for row in 2..5 {
+            let widget = pool.handle(row).unwrap();
+            assert_eq!(ui.widget(widget).pos(), Coord { x: 0., y: 20. * (row - 2) as f64 });
+        }
+
+        pool.show_rows(ui, &items, 998..1003, |ui, w| {
+            ui.pack_to_layout(w, list, StackDirection::Back);
+            packed += 1;
+        });
+        assert_eq!(packed, 3);
+        assert!(pool.handle(1000).is_none());
+        assert_eq!(ui.widget(pool.handle(999).unwrap()).text, "row 999");
+        assert_eq!(ui.widget(first).unbound, 2);
+        assert_eq!(pool.row_of(first), Some(998));
+        assert_eq!(ui.widget(pool.handle(998).unwrap()).pos(), Coord { x: 0., y: 0. });
+        assert_eq!(ui.widget(pool.handle(999).unwrap()).pos(), Coord { x: 0., y: 20. });
+    }
+
+    #[derive(Default)]
+    struct LogWidget {
+        stub: WidgetStub,
+        log: Vec<String>
+    }
+
+    impl Widget for LogWidget {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 10., h: 10. } }
+        fn event(&mut self, ev: Event) -> Option<Event> {
+            if let EventType::MouseButtonPress(_) = ev.data {
+                self.log.push(String::from("press"));
+            }
+            Some(ev)
+        }
+        fn message(&mut self, msg: Box<dyn std::any::Any>) {
+            if let Ok(text) = msg.downcast::<&str>() {
+                self.log.push(String::from(*text));
+            }
+        }
+    }
+
+    #[derive(Default)]
+    struct LifecycleWidget {
+        stub: WidgetStub,
+        log: Vec<&'static str>
+    }
+
+    impl Widget for LifecycleWidget {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 10., h: 10. } }
+        fn on_added(&mut self) { self.log.push("added"); }
+        fn on_realize(&mut self) { self.log.push("realized"); }
+        fn on_layout(&mut self) { self.log.push("layouted"); }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn lifecycle_hooks() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(LifecycleWidget::default()));
+        assert_eq!(ui.widget(widget).log, vec!["added"]);
+
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+        assert_eq!(ui.widget(widget).log, vec!["added", "layouted"]);
+
+        ui.do_layout();
+        assert_eq!(ui.widget(widget).log, vec!["added", "layouted"]);
+
+        ui.layouter(ui.root_layout()).set_padding(20.);
+        ui.do_layout();
+        assert_eq!(ui.widget(widget).log, vec!["added", "layouted", "layouted"]);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn introspection() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 2.));
+
+        let ui = view.handle();
+        let widget = ui.new_named_widget("gain", Box::new(RectWidget {
+            min_size: Size { w: 50., h: 20. },
+            ..Default::default()
+        }));
+        let unpacked = ui.new_widget(Box::new(LogWidget::default()));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        let infos = ui.introspect();
+        assert_eq!(infos.len(), 3);
+        assert!(infos[0].type_name.ends_with("RootWidget"));
+        assert!(infos[0].shown);
+
+        let info = &infos[widget.id()];
+        assert!(info.type_name.ends_with("RectWidget"));
+        assert_eq!(info.name.as_deref(), Some("gain"));
+        assert_eq!(info.parent, Some(0));
+        assert_eq!(info.bounds, Layout { pos: Coord::default(), size: Size { w: 100., h: 40. } });
+        assert!(info.sensitive && info.shown);
+
+        let info = &infos[unpacked.id()];
+        assert_eq!(info.name, None);
+        assert_eq!(info.parent, None);
+        assert!(!info.shown);
+
+        assert_eq!(ui.widget_handle::<RectWidget>(widget.id()).map(|h| h.id()), Some(widget.id()));
+        assert!(ui.widget_handle::<LogWidget>(widget.id()).is_none());
+        assert!(ui.widget_handle::<RectWidget>(17).is_none());
+    }
+
+    #[derive(Default)]
+    struct MuteButton {
+        stub: WidgetStub,
+        muted: bool
+    }
+
+    impl Widget for MuteButton {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 30., h: 20. } }
+        fn takes_focus(&self) -> bool { true }
+        fn accessible(&self) -> Option<crate::accessibility::Accessible> {
+            use crate::accessibility::*;
+            Some(Accessible::new(Role::ToggleButton, "Mute")
+                 .with_value(if self.muted { "on" } else { "off" })
+                 .with_action(Action::Activate)
+                 .with_action(Action::Focus))
+        }
+        fn accessible_action(&mut self, action: crate::accessibility::Action) -> bool {
+            self.muted = !self.muted;
+            action == crate::accessibility::Action::Activate
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn accessibility_tree() {
+        use crate::accessibility::*;
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let row = ui.new_layouter::<HorizontalLayouter>();
+        let button = ui.new_widget(Box::new(MuteButton::default()));
+        let plain = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 30., h: 20. },
+            ..Default::default()
+        }));
+        ui.pack_to_layout(row.widget(), ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(button, row, StackDirection::Back);
+        ui.pack_to_layout(plain, row, StackDirection::Back);
+        ui.do_layout();
+
+        let tree = ui.accessibility_tree();
+        assert_eq!(tree.accessible.role, Role::Window);
+        assert_eq!(tree.count(), 2);
+        let node = &tree.children[0];
+        assert_eq!(node.id, button.id());
+        assert_eq!(node.accessible.name, "Mute");
+        assert_eq!(node.accessible.value.as_deref(), Some("off"));
+        assert_eq!(node.bounds, ui.widget(button).layout());
+
+        assert!(ui.perform_accessible_action(button.id(), Action::Activate));
+        assert_eq!(ui.accessibility_tree().children[0].accessible.value.as_deref(), Some("on"));
+        assert!(!ui.perform_accessible_action(button.id(), Action::Increment));
+        assert!(!ui.perform_accessible_action(plain.id(), Action::Activate));
+
+        assert!(ui.perform_accessible_action(button.id(), Action::Focus));
+        assert!(ui.accessibility_tree().children[0].focused);
+    }
+
+    #[derive(Default)]
+    struct TimerWidget {
+        stub: WidgetStub,
+        expired: Vec<&'static str>
+    }
+
+    impl Widget for TimerWidget {
+        widget_stub!();
+        fn timer(&mut self, tag: &'static str) {
+            self.expired.push(tag);
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn tagged_timers() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(TimerWidget::default()));
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        ui.widget(widget).request_timer("blink", 0.5, true);
+        ui.widget(widget).request_timer("fade", 0.1, false);
+        ui.next_event(0.0);
+        assert!(ui.auto_timeout() > 0.0);
+
+        let (blink, fade) = (crate::ui::WIDGET_TIMER_BASE, crate::ui::WIDGET_TIMER_BASE + 1);
+
+        ui.timer_event(fade);
+        ui.timer_event(blink);
+        ui.timer_event(fade);
+        ui.timer_event(blink);
+        assert_eq!(ui.widget(widget).expired, vec!["fade", "blink", "blink"]);
+
+        ui.widget(widget).cancel_timer("blink");
+        ui.next_event(0.0);
+        ui.timer_event(blink);
+        assert_eq!(ui.widget(widget).expired.len(), 3);
+        assert_eq!(ui.auto_timeout(), -1.0);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn paned_divider_dragged() {
+        use crate::layout::panedlayout::*;
+
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let pane = || Box::new(RectWidget {
+            min_size: Size { w: 20., h: 10. },
+            width_expandable: true,
+            height_expandable: true,
+            ..Default::default()
+        });
+        let left = ui.new_widget(pane());
+        let right = ui.new_widget(pane());
+        let paned = ui.new_layouter::<PanedLayouter>();
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(paned.widget(), ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(left, paned, Pane::First);
+        ui.pack_to_layout(right, paned, Pane::Second);
+        ui.root_widget().set_size(&Size { w: 206., h: 50. });
+        ui.do_layout();
+        assert_eq!(ui.widget(left).size().w, 100.);
+        assert_eq!(ui.widget(right).pos().x, 106.);
+
+        let at = |data, x| Event {
+            data,
+            context: EventContext { pos: Coord { x, y: 5. }, ..Default::default() }
+        };
+        let button = MouseButton { num: 1, modifiers: Modifiers::default() };
+        view.queue_event(at(EventType::MouseButtonPress(button), 103.));
+        view.queue_event(at(EventType::MouseMove(MotionContext::default()), 53.));
+        view.queue_event(at(EventType::MouseButtonRelease(button), 53.));
+        view.handle().update(-1.0);
+
+        let ui = view.handle();
+        assert_eq!(ui.layouter_ref(paned).split_ratio(), 0.25);
+        assert_eq!(ui.widget(left).size().w, 50.);
+        assert_eq!(ui.widget(right).pos().x, 56.);
+        assert_eq!(ui.widget(right).size().w, 150.);
+        assert!(!ui.widget(left).clicked());
+
+        view.queue_event(at(EventType::MouseMove(MotionContext::default()), 150.));
+        view.handle().update(-1.0);
+        assert_eq!(view.handle().layouter_ref(paned).split_ratio(), 0.25);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn visual_states_follow_events() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 20., h: 20. },
+            ..Default::default()
+        }));
+        ui.layouter(ui.root_layout()).set_padding(0.);
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+        assert!(ui.widget(widget).state().is_normal());
+
+        let at = |data| Event {
+            data,
+            context: EventContext { pos: Coord { x: 10., y: 10. }, ..Default::default() }
+        };
+        let button = MouseButton { num: 1, modifiers: Modifiers::default() };
+        view.queue_event(at(EventType::MouseMove(MotionContext::default())));
+        view.queue_event(at(EventType::MouseButtonPress(button)));
+        view.handle().update(-1.0);
+        let ui = view.handle();
+        assert_eq!(ui.widget(widget).state(), VisualState { hovered: true, pressed: true, armed: true, ..Default::default() });
+
+        view.queue_event(at(EventType::MouseButtonRelease(button)));
+        view.handle().update(-1.0);
+        let ui = view.handle();
+        assert_eq!(ui.widget(widget).state(), VisualState { hovered: true, ..Default::default() });
+
+        ui.set_sensitive(widget, false);
+        view.queue_event(at(EventType::MouseButtonPress(button)));
+        view.handle().update(-1.0);
+        let ui = view.handle();
+        assert!(!ui.widget(widget).state().pressed);
+        assert!(ui.widget(widget).state().disabled);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn hover_follows_cached_event_path() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let new_rect = |ui: &mut UI<RootWidget>| ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 20., h: 20. },
+            ..Default::default()
+        }));
+        let upper = new_rect(ui);
+        let lower = new_rect(ui);
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(upper, ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(lower, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        let move_to = |view: &mut Box<PuglView<UI<RootWidget>>>, y| {
+            view.queue_event(Event {
+                data: EventType::MouseMove(MotionContext::default()),
+                context: EventContext { pos: Coord { x: 10., y }, ..Default::default() }
+            });
+            view.handle().update(-1.0);
+        };
+
+        move_to(&mut view, 10.);
+        assert!(view.handle().widget(upper).state().hovered);
+        move_to(&mut view, 15.);
+        assert!(view.handle().widget(upper).state().hovered);
+        move_to(&mut view, 30.);
+        assert!(!view.handle().widget(upper).state().hovered);
+        assert!(view.handle().widget(lower).state().hovered);
+
+        let ui = view.handle();
+        ui.set_visible(upper, false);
+        move_to(&mut view, 10.);
+        assert!(view.handle().widget(lower).state().hovered);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn cursor_follows_hovered_widget() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let link = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 20., h: 20. },
+            cursor: Some(Cursor::Hand),
+            ..Default::default()
+        }));
+        let plain = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 20., h: 20. },
+            ..Default::default()
+        }));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(link, ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(plain, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+        assert!(matches!(ui.cursor(), Cursor::Arrow));
+
+        let move_to = |view: &mut Box<PuglView<UI<RootWidget>>>, y| {
+            view.queue_event(Event {
+                data: EventType::MouseMove(MotionContext::default()),
+                context: EventContext { pos: Coord { x: 10., y }, ..Default::default() }
+            });
+            view.handle().update(-1.0);
+        };
+
+        move_to(&mut view, 10.);
+        assert!(matches!(view.handle().cursor(), Cursor::Hand));
+        move_to(&mut view, 30.);
+        assert!(matches!(view.handle().cursor(), Cursor::Arrow));
+        move_to(&mut view, 5.);
+        assert!(matches!(view.handle().cursor(), Cursor::Hand));
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn spatial_index_hit_testing() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let row = ui.new_layouter::<HorizontalLayouter>();
+        ui.layouter(ui.root_layout()).set_padding(0.);
+        ui.layouter(row).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(row.widget(), ui.root_layout(), StackDirection::Back);
+        let steps: Vec<_> = (0..16).map(|i| {
+            let step = ui.new_widget(Box::new(RectWidget {
+                min_size: Size { w: 10., h: 10. },
+                color: if i == 5 { (0., 0., 1.) } else { (0., 1., 0.) },
+                ..Default::default()
+            }));
+            ui.pack_to_layout(step, row, StackDirection::Back);
+            step
+        }).collect();
+        ui.set_spatial_index(Some(25.));
+        ui.do_layout();
+
+        let move_to = |view: &mut Box<PuglView<UI<RootWidget>>>, x| {
+            view.queue_event(Event {
+                data: EventType::MouseMove(MotionContext::default()),
+                context: EventContext { pos: Coord { x, y: 5. }, ..Default::default() }
+            });
+            view.handle().update(-1.0);
+        };
+        move_to(&mut view, 55.);
+        assert!(view.handle().widget(steps[5]).state().hovered);
+        move_to(&mut view, 128.);
+        assert!(!view.handle().widget(steps[5]).state().hovered);
+        assert!(view.handle().widget(steps[12]).state().hovered);
+
+        let ui = view.handle();
+        let mut surface = cairo::ImageSurface::create(cairo::Format::ARgb32, 160, 10).unwrap();
+        {
+            let cr = cairo::Context::new(&surface);
+            let expose = ExposeArea { pos: Coord { x: 50., y: 0. }, size: Size { w: 10., h: 10. } };
+            ui.exposed(&expose, &cr);
+        }
+        let stride = surface.get_stride() as usize;
+        let data = surface.get_data().expect("surface data borrowed");
+        let pixel = |x: usize| u32::from_ne_bytes([data[5 * stride + 4 * x], data[5 * stride + 4 * x + 1],
+                                                   data[5 * stride + 4 * x + 2], data[5 * stride + 4 * x + 3]]);
+        assert_eq!(pixel(55), 0xff0000ff);
+        assert_ne!(pixel(15), 0xff00ff00);
+    }
+
+    #[derive(Default)]
+    struct CoverWidget {
+        stub: WidgetStub,
+        opaque: bool,
+        exposes: usize
+    }
+
+    impl Widget for CoverWidget {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 20., h: 20. } }
+        fn opaque(&self) -> bool { self.opaque }
+        fn exposed(&mut self, _ctx: &PaintContext, _cr: &cairo::Context) {
+            self.exposes += 1;
+        }
+    }
+
+    #[derive(Default)]
+    struct CachedRoot {
+        stub: WidgetStub,
+        exposes: usize
+    }
+
+    impl Widget for CachedRoot {
+        widget_stub!();
+        fn cacheable(&self) -> bool { true }
+        fn exposed(&mut self, _ctx: &PaintContext, _cr: &cairo::Context) {
+            self.exposes += 1;
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn render_cache_invalidation() {
+        let rw = Box::new(CachedRoot::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let child = ui.new_widget(Box::new(CoverWidget::default()));
+        ui.layouter(ui.root_layout()).set_padding(0.);
+        ui.pack_to_layout(child, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, 40, 40).unwrap();
+        let cr = cairo::Context::new(&surface);
+        let expose = ExposeArea { pos: Coord { x: 0., y: 0. }, size: Size { w: 20., h: 20. } };
+        let exposes = |ui: &mut UI<CachedRoot>| {
+            ui.exposed(&expose, &cr);
+            (ui.root_widget().exposes, ui.widget(child).exposes)
+        };
+
+        assert_eq!(exposes(ui), (1, 1));
+        assert_eq!(exposes(ui), (1, 1));
+
+        ui.widget(child).ask_for_repaint();
+        ui.next_event(0.0);
+        assert_eq!(exposes(ui), (2, 2));
+        assert_eq!(exposes(ui), (2, 2));
+
+        ui.set_style(Style { padding: 2., ..Default::default() });
+        assert_eq!(exposes(ui), (3, 3));
+
+        ui.set_style_override(child, StyleOverride { foreground: Some((1., 0., 0.)), ..Default::default() });
+        assert_eq!(exposes(ui), (4, 4));
+
+        ui.set_scale_factor(2.);
+        assert_eq!(exposes(ui), (5, 5));
+        assert_eq!(exposes(ui), (5, 5));
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn covered_widgets_not_exposed() {
+        use crate::layout::fixedlayout::*;
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let canvas = ui.new_layouter::<FixedLayouter>();
+        let background = ui.new_widget(Box::new(CoverWidget::default()));
+        let overlay = ui.new_widget(Box::new(CoverWidget { opaque: true, ..Default::default() }));
+        let beside = ui.new_widget(Box::new(CoverWidget::default()));
+        ui.layouter(ui.root_layout()).set_padding(0.);
+        ui.pack_to_layout(canvas.widget(), ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(background, canvas, Coord { x: 0., y: 0. });
+        ui.pack_to_layout(overlay, canvas, Coord { x: 0., y: 0. });
+        ui.pack_to_layout(beside, canvas, Coord { x: 30., y: 0. });
+        ui.do_layout();
+
+        let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, 50, 20).unwrap();
+        let cr = cairo::Context::new(&surface);
+        ui.exposed(&ExposeArea { pos: Coord { x: 0., y: 0. }, size: Size { w: 50., h: 20. } }, &cr);
+        assert_eq!(ui.widget(background).exposes, 0);
+        assert_eq!(ui.widget(overlay).exposes, 1);
+        assert_eq!(ui.widget(beside).exposes, 1);
+
+        ui.widget(overlay).opaque = false;
+        ui.exposed(&ExposeArea { pos: Coord { x: 5., y: 5. }, size: Size { w: 10., h: 10. } }, &cr);
+        assert_eq!(ui.widget(background).exposes, 1);
+        assert_eq!(ui.widget(overlay).exposes, 2);
+        assert_eq!(ui.widget(beside).exposes, 1);
+    }
+
+    #[derive(Default)]
+    struct FrameWidget {
+        stub: WidgetStub,
+        deltas: Vec<f64>
+    }
+
+    impl Widget for FrameWidget {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 10., h: 10. } }
+        fn exposed(&mut self, ctx: &PaintContext, _cr: &cairo::Context) {
+            self.deltas.push(ctx.frame_delta());
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn frame_rate_limit() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(FrameWidget::default()));
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+        assert_eq!(ui.max_fps(), None);
+        ui.set_max_fps(Some(0.));
+        assert_eq!(ui.max_fps(), None);
+        ui.set_max_fps(Some(30.));
+        assert_eq!(ui.max_fps(), Some(30.));
+
+        let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, 20, 20).unwrap();
+        let cr = cairo::Context::new(&surface);
+        let expose = ExposeArea { pos: Coord { x: 0., y: 0. }, size: Size { w: 20., h: 20. } };
+        ui.exposed(&expose, &cr);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        ui.exposed(&expose, &cr);
+        ui.widget(widget).ask_for_repaint();
+        ui.next_event(0.0);
+
+        let deltas = &ui.widget(widget).deltas;
+        assert_eq!(deltas.len(), 2);
+        assert_eq!(deltas[0], 0.0);
+        assert!(deltas[1] >= 0.005);
+    }
+
+    #[derive(Default)]
+    struct ContextWidget {
+        stub: WidgetStub,
+        highlight_width: f64,
+        scale_factor: f64,
+        frame_time: f64,
+        frame_delta: f64,
+        damage: Option<Layout>,
+        clip: Option<(f64, f64, f64, f64)>
+    }
+
+    impl Widget for ContextWidget {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 20., h: 20. } }
+        fn exposed(&mut self, ctx: &PaintContext, cr: &cairo::Context) {
+            self.highlight_width = ctx.theme().highlight_width;
+            self.scale_factor = ctx.scale_factor();
+            self.frame_time = ctx.frame_time();
+            self.frame_delta = ctx.frame_delta();
+            self.damage = Some(ctx.damage());
+            self.clip = Some(cr.clip_extents());
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn paint_context_passed_to_exposed() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 2.));
+
+        let ui = view.handle();
+        let top = ui.new_widget(Box::new(ContextWidget::default()));
+        let bottom = ui.new_widget(Box::new(ContextWidget::default()));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(top, ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(bottom, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+        ui.theme().highlight_width = 7.;
+
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, 40, 80).unwrap();
+        let cr = cairo::Context::new(&surface);
+        ui.exposed(&ExposeArea { pos: Coord { x: 10., y: 50. }, size: Size { w: 10., h: 20. } }, &cr);
+
+        assert_eq!(ui.widget(top).damage, None);
+        let w = ui.widget(bottom);
+        assert_eq!(w.highlight_width, 7.);
+        assert_eq!(w.scale_factor, 2.);
+        assert!(w.frame_time > 0.);
+        assert_eq!(w.frame_delta, 0.);
+        assert_eq!(w.damage, Some(Layout { pos: Coord { x: 5., y: 5. }, size: Size { w: 5., h: 10. } }));
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn partial_repaint_clipped_to_dirty_rect() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(ContextWidget::default()));
+        ui.layouter(ui.root_layout()).set_padding(10.);
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        let rect = Layout { pos: Coord { x: 2., y: 4. }, size: Size { w: 6., h: 8. } };
+        ui.widget(widget).ask_for_repaint_rect(rect);
+        ui.next_event(0.0);
+
+        let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, 40, 40).unwrap();
+        let cr = cairo::Context::new(&surface);
+        ui.exposed(&ExposeArea { pos: Coord { x: 12., y: 14. }, size: Size { w: 6., h: 8. } }, &cr);
+
+        let w = ui.widget(widget);
+        assert_eq!(w.damage, Some(rect));
+        assert_eq!(w.clip, Some((12., 14., 18., 22.)));
+    }
+
+    #[derive(Default)]
+    struct BufferedWidget {
+        stub: WidgetStub,
+        exposes: usize
+    }
+
+    impl Widget for BufferedWidget {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 10., h: 10. } }
+        fn double_buffered(&self) -> bool { true }
+        fn exposed(&mut self, _ctx: &PaintContext, cr: &cairo::Context) {
+            self.exposes += 1;
+            let layout = self.layout();
+            cr.set_source_rgb(0., 0., 1.);
+            cr.rectangle(layout.pos.x, layout.pos.y, layout.size.w, layout.size.h);
+            cr.fill();
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn double_buffered_widget_drawn_once() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(BufferedWidget::default()));
+        ui.layouter(ui.root_layout()).set_padding(0.);
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        let mut surface = cairo::ImageSurface::create(cairo::Format::ARgb32, 10, 10).unwrap();
+        let expose = ExposeArea { pos: Coord { x: 0., y: 0. }, size: Size { w: 10., h: 10. } };
+        {
+            let cr = cairo::Context::new(&surface);
+            ui.exposed(&expose, &cr);
+            ui.exposed(&expose, &cr);
+        }
+        assert_eq!(ui.widget(widget).exposes, 1);
+        {
+            let data = surface.get_data().expect("surface data borrowed");
+            let p = &data[5 * 40 + 4 * 5..5 * 40 + 4 * 5 + 4];
+            assert_eq!(u32::from_ne_bytes([p[0], p[1], p[2], p[3]]), 0xff0000ff);
+        }
+
+        ui.widget(widget).ask_for_repaint();
+        ui.next_event(0.0);
+        let cr = cairo::Context::new(&surface);
+        ui.exposed(&expose, &cr);
+        assert_eq!(ui.widget(widget).exposes, 1);
+
+        ui.widget(widget).invalidate_buffer();
+        ui.next_event(0.0);
+        ui.exposed(&expose, &cr);
+        assert_eq!(ui.widget(widget).exposes, 2);
+    }
+
+    #[derive(Default)]
+    struct CachedWidget {
+        stub: WidgetStub,
+        value: Option<u64>,
+        exposes: usize
+    }
+
+    impl Widget for CachedWidget {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 10., h: 10. } }
+        fn cacheable(&self) -> bool { true }
+        fn state_hash(&self) -> Option<u64> { self.value }
+        fn exposed(&mut self, _ctx: &PaintContext, _cr: &cairo::Context) {
+            self.exposes += 1;
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn unchanged_state_hash_skips_repaint() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(CachedWidget { value: Some(1), ..Default::default() }));
+        ui.layouter(ui.root_layout()).set_padding(0.);
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, 10, 10).unwrap();
+        let cr = cairo::Context::new(&surface);
+        let expose = ExposeArea { pos: Coord { x: 0., y: 0. }, size: Size { w: 10., h: 10. } };
+        ui.exposed(&expose, &cr);
+        assert_eq!(ui.widget(widget).exposes, 1);
+
+        ui.widget(widget).ask_for_repaint();
+        ui.next_event(0.0);
+        ui.exposed(&expose, &cr);
+        assert_eq!(ui.widget(widget).exposes, 1);
+
+        ui.widget(widget).value = Some(2);
+        ui.widget(widget).ask_for_repaint();
+        ui.next_event(0.0);
+        ui.exposed(&expose, &cr);
+        assert_eq!(ui.widget(widget).exposes, 2);
+
+        ui.widget(widget).ask_for_repaint();
+        ui.next_event(0.0);
+        ui.exposed(&expose, &cr);
+        assert_eq!(ui.widget(widget).exposes, 2);
+    }
+
+    #[derive(Default)]
+    struct HashedWidget {
+        stub: WidgetStub,
+        exposes: usize
+    }
+
+    impl Widget for HashedWidget {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 10., h: 10. } }
+        fn state_hash(&self) -> Option<u64> { Some(1) }
+        fn exposed(&mut self, _ctx: &PaintContext, _cr: &cairo::Context) {
+            self.exposes += 1;
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn partial_paint_keeps_state_hash_unpainted() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(HashedWidget::default()));
+        ui.layouter(ui.root_layout()).set_padding(0.);
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, 10, 10).unwrap();
+        let cr = cairo::Context::new(&surface);
+        let partial = ExposeArea { pos: Coord { x: 0., y: 0. }, size: Size { w: 5., h: 5. } };
+        let whole = ExposeArea { pos: Coord { x: 0., y: 0. }, size: Size { w: 10., h: 10. } };
+        ui.exposed(&partial, &cr);
+        assert_eq!(ui.widget(widget).exposes, 1);
+
+        ui.widget(widget).ask_for_repaint();
+        ui.next_event(0.0);
+        ui.exposed(&whole, &cr);
+        assert_eq!(ui.widget(widget).exposes, 2);
+
+        ui.widget(widget).ask_for_repaint();
+        ui.next_event(0.0);
+        ui.exposed(&whole, &cr);
+        assert_eq!(ui.widget(widget).exposes, 2);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn layouter_changes_relayout() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let row = ui.new_layouter::<HorizontalLayouter>();
+        let first = ui.new_widget(Box::new(LogWidget::default()));
+        let second = ui.new_widget(Box::new(LogWidget::default()));
+        ui.layouter(ui.root_layout()).set_padding(0.);
+        ui.layouter(row).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(row.widget(), ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(first, row, StackDirection::Back);
+        ui.pack_to_layout(second, row, StackDirection::Back);
+        ui.do_layout();
+        assert_eq!(ui.widget(second).pos(), Coord { x: 10., y: 0. });
+        view.mock_instance().take_redisplay_rects();
+
+        let ui = view.handle();
+        let _ = ui.layouter_ref(row);
+        ui.next_event(0.0);
+        assert!(view.mock_instance().take_redisplay_rects().is_empty());
+
+        let ui = view.handle();
+        ui.layouter(row).set_spacing(5.);
+        assert_eq!(ui.widget(second).pos(), Coord { x: 10., y: 0. });
+        ui.next_event(0.0);
+        assert_eq!(ui.widget(second).pos(), Coord { x: 15., y: 0. });
+        let damage = view.mock_instance().take_redisplay_rects();
+        assert!(damage.iter().any(|&(pos, size)| pos.x <= 10. && pos.x + size.w >= 25. && size.h >= 10.));
+
+        let ui = view.handle();
+        ui.layouter(row).set_padding(2.);
+        ui.do_layout();
+        assert_eq!(ui.widget(second).pos(), Coord { x: 17., y: 2. });
+        ui.next_event(0.0);
+        assert_eq!(ui.widget(second).pos(), Coord { x: 17., y: 2. });
+    }
+
+    #[derive(Default)]
+    struct GrowingLabel {
+        stub: WidgetStub,
+        text: String
+    }
+
+    impl GrowingLabel {
+        fn set_text(&mut self, text: &str) {
+            self.text = String::from(text);
+            self.request_resize();
+        }
+    }
+
+    impl Widget for GrowingLabel {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 10. * self.text.len() as f64, h: 10. } }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn relayout_on_resize_request() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let row = ui.new_layouter::<HorizontalLayouter>();
+        let label = ui.new_widget(Box::new(GrowingLabel { text: String::from("ab"), ..Default::default() }));
+        let next = ui.new_widget(Box::new(LogWidget::default()));
+        ui.layouter(ui.root_layout()).set_padding(0.);
+        ui.layouter(row).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(row.widget(), ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(label, row, StackDirection::Back);
+        ui.pack_to_layout(next, row, StackDirection::Back);
+        ui.do_layout();
+        ui.set_fit_window_on_resize(true);
+        assert_eq!(ui.widget(next).pos().x, 20.);
+
+        ui.widget(label).set_text("abcd");
+        ui.next_event(0.0);
+        assert_eq!(ui.widget(label).size().w, 40.);
+        assert_eq!(ui.widget(next).pos().x, 40.);
+        assert!(ui.root_widget().size().w >= 50.);
+    }
+
+    #[derive(Default)]
+    struct ClickWidget {
+        stub: WidgetStub,
+        clicks: usize,
+        armed: Vec<bool>
+    }
+
+    impl Widget for ClickWidget {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 20., h: 20. } }
+        fn clicked(&mut self) {
+            self.clicks += 1;
+        }
+        fn pressed_changed(&mut self, armed: bool) {
+            self.armed.push(armed);
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn click_semantics() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(ClickWidget::default()));
+        ui.layouter(ui.root_layout()).set_padding(0.);
+        ui.root_widget().set_size(&Size { w: 100., h: 100. });
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        let at = |data, x| Event {
+            data,
+            context: EventContext { pos: Coord { x, y: 10. }, ..Default::default() }
+        };
+        let button = MouseButton { num: 1, modifiers: Modifiers::default() };
+        let motion = EventType::MouseMove(MotionContext::default());
+
+        view.queue_event(at(EventType::MouseButtonPress(button), 10.));
+        view.queue_event(at(EventType::MouseButtonRelease(button), 12.));
+        view.handle().update(-1.0);
+        assert_eq!(view.handle().widget(widget).clicks, 1);
+
+        view.queue_event(at(EventType::MouseButtonPress(button), 10.));
+        view.queue_event(at(motion, 50.));
+        view.queue_event(at(EventType::MouseButtonRelease(button), 50.));
+        view.handle().update(-1.0);
+        assert_eq!(view.handle().widget(widget).clicks, 1);
+
+        view.queue_event(at(EventType::MouseButtonPress(button), 10.));
+        view.queue_event(at(motion, 50.));
+        view.queue_event(at(motion, 15.));
+        view.queue_event(at(EventType::MouseButtonRelease(button), 15.));
+        view.handle().update(-1.0);
+        let ui = view.handle();
+        assert_eq!(ui.widget(widget).clicks, 2);
+        assert_eq!(ui.widget(widget).armed, vec![true, false, true, false, true, false, true, false]);
+        assert!(!ui.widget(widget).is_pressed());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn focus_follows_clicks() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let first = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 20., h: 20. },
+            ..Default::default()
+        }));
+        let second = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 20., h: 20. },
+            ..Default::default()
+        }));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(first, ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(second, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+        assert_eq!(ui.focus_policy(), FocusPolicy::Manual);
+
+        let click = |view: &mut PuglView<UI<RootWidget>>, y| {
+            let at = |data| Event {
+                data,
+                context: EventContext { pos: Coord { x: 10., y }, ..Default::default() }
+            };
+            let button = MouseButton { num: 1, modifiers: Modifiers::default() };
+            view.queue_event(at(EventType::MouseButtonPress(button)));
+            view.queue_event(at(EventType::MouseButtonRelease(button)));
+            view.handle().update(-1.0);
+        };
+
+        click(&mut view, 30.);
+        assert!(!view.handle().widget(second).has_focus());
+
+        view.handle().set_focus_policy(FocusPolicy::ClickToFocus);
+        click(&mut view, 30.);
+        assert!(view.handle().widget(second).has_focus());
+        click(&mut view, 10.);
+        let ui = view.handle();
+        assert!(ui.widget(first).has_focus());
+        assert!(!ui.widget(second).has_focus());
+    }
+
+    #[derive(Default)]
+    struct ObservingRoot {
+        stub: WidgetStub,
+        block: bool,
+        log: Vec<&'static str>
+    }
+
+    impl Widget for ObservingRoot {
+        widget_stub!();
+        fn captures_events(&self) -> bool { true }
+        fn capture_event(&mut self, ev: Event) -> Option<Event> {
+            if let EventType::MouseButtonPress(_) = ev.data {
+                self.log.push("capture");
+                if self.block {
+                    return None;
+                }
+            }
+            Some(ev)
+        }
+        fn observe_event(&mut self, ev: Event) {
+            if let EventType::MouseButtonPress(_) = ev.data {
+                self.log.push("observe");
+            }
+        }
+    }
+
+    #[derive(Default)]
+    struct HandlingWidget {
+        stub: WidgetStub,
+        presses: usize
+    }
+
+    impl Widget for HandlingWidget {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 20., h: 20. } }
+        fn event(&mut self, ev: Event) -> Option<Event> {
+            if let EventType::MouseButtonPress(_) = ev.data {
+                self.presses += 1;
+                self.mark_event_handled();
+            }
+            Some(ev)
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn capture_and_observe_events() {
+        let rw = Box::new(ObservingRoot::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(HandlingWidget::default()));
+        ui.layouter(ui.root_layout()).set_padding(0.);
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        let press = Event {
+            data: EventType::MouseButtonPress(MouseButton { num: 1, modifiers: Modifiers::default() }),
+            context: EventContext { pos: Coord { x: 10., y: 10. }, ..Default::default() }
+        };
+        view.queue_event(press);
+        view.handle().update(-1.0);
+        let ui = view.handle();
+        assert_eq!(ui.widget(widget).presses, 1);
+        assert_eq!(ui.root_widget().log, vec!["capture", "observe"]);
+
+        ui.root_widget().block = true;
+        ui.root_widget().log.clear();
+        view.queue_event(press);
+        view.handle().update(-1.0);
+        let ui = view.handle();
+        assert_eq!(ui.widget(widget).presses, 1);
+        assert_eq!(ui.root_widget().log, vec!["capture"]);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn titled_frame() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let panel = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 40., h: 30. },
+            ..Default::default()
+        }));
+        let frame = ui.new_frame("Filter");
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(frame.widget(), ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(panel, frame, ());
+        ui.do_layout();
+
+        assert_eq!(ui.widget(frame.widget()).caption(), Some("Filter"));
+        let top = ui.layouter_ref(frame).caption_height().max(6.);
+        assert_eq!(ui.widget(panel).pos(), Coord { x: 6., y: top });
+        assert_eq!(ui.root_widget().size(), Size { w: 52., h: 36. + top });
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn layout_snapshot_restored() {
+        use crate::layout::panedlayout::*;
+
+        fn build(ui: &mut UI<RootWidget>) -> (WidgetHandle<RectWidget>, LayoutWidgetHandle<PanedLayouter, LayoutWidget>) {
+            let pane = || Box::new(RectWidget {
+                min_size: Size { w: 20., h: 10. },
+                width_expandable: true,
+                height_expandable: true,
+                ..Default::default()
+            });
+            let left = ui.new_widget(pane());
+            let right = ui.new_widget(pane());
+            let paned = ui.new_layouter::<PanedLayouter>();
+            ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+            ui.pack_to_layout(paned.widget(), ui.root_layout(), StackDirection::Back);
+            ui.pack_to_layout(left, paned, Pane::First);
+            ui.pack_to_layout(right, paned, Pane::Second);
+            ui.do_layout();
+            (left, paned)
+        }
+
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+        let ui = view.handle();
+        let (left, paned) = build(ui);
+        ui.layouter(paned).set_split_ratio(0.25);
+        ui.root_widget().set_size(&Size { w: 206., h: 50. });
+        ui.do_layout();
+        ui.focus_widget(left);
+        let snapshot = ui.snapshot_layout();
+        assert_eq!(snapshot.size, (206., 50.));
+        assert_eq!(snapshot.focused, Some(left.id()));
+        assert_eq!(snapshot.split_ratios, vec![(paned.widget().id(), 0.25)]);
+        assert!(snapshot.geometries.contains(&(left.id(), (0., 0., 50., 50.))));
+
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+        let ui = view.handle();
+        let (left, paned) = build(ui);
+        assert_eq!(ui.widget(left).size().w, 20.);
+
+        ui.restore_layout(&snapshot);
+        assert_eq!(ui.layouter_ref(paned).split_ratio(), 0.25);
+        assert_eq!(ui.widget(left).size().w, 50.);
+        assert!(ui.widget(left).has_focus());
+        assert_eq!(ui.snapshot_layout(), snapshot);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn size_hints_constrain_resize() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 2.));
+
+        let ui = view.handle();
+        ui.do_layout();
+        ui.set_size_hints(Some(Size { w: 20., h: 10. }), Some(Size { w: 300., h: 300. }), Some(2.), Some(1.));
+
+        ui.resize(Size { w: 401., h: 150. });
+        assert_eq!(ui.root_widget().size(), Size { w: 150., h: 75. });
+
+        ui.resize(Size { w: 1000., h: 1000. });
+        assert_eq!(ui.root_widget().size(), Size { w: 300., h: 150. });
+
+        ui.resize(Size { w: 10., h: 10. });
+        assert_eq!(ui.root_widget().size(), Size { w: 20., h: 10. });
+
+        ui.set_size_hints(Some(Size { w: 200., h: 100. }), None, Some(1.), None);
+        ui.resize(Size { w: 600., h: 200. });
+        assert_eq!(ui.root_widget().size(), Size { w: 200., h: 100. });
+
+        ui.set_size_hints(Some(Size { w: 25., h: 15. }), None, None, Some(10.));
+        ui.resize(Size { w: 52., h: 34. });
+        assert_eq!(ui.root_widget().size(), Size { w: 30., h: 20. });
+        ui.resize(Size { w: 90., h: 70. });
+        assert_eq!(ui.root_widget().size(), Size { w: 40., h: 30. });
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    #[should_panic(expected = "aspect ratio Some(0.0) not positive")]
+    fn size_hints_zero_aspect_ratio() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+        view.handle().set_size_hints(None, None, Some(0.), None);
+    }
+
+    #[derive(Default)]
+    struct ScaledWidget {
+        stub: WidgetStub,
+        scale_factors: Vec<f64>
+    }
+
+    impl Widget for ScaledWidget {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 40., h: 20. } }
+        fn scale_changed(&mut self, scale_factor: f64) {
+            self.scale_factors.push(scale_factor);
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn scale_factor_changed() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(ScaledWidget::default()));
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+        assert_eq!(ui.scale_factor(), 1.);
+
+        ui.set_scale_factor(1.5);
+        ui.set_scale_factor(1.5);
+        assert_eq!(ui.scale_factor(), 1.5);
+        assert_eq!(ui.widget(widget).scale_factors, vec![1.5]);
+        assert_eq!(ui.widget(widget).size(), Size { w: 40., h: 20. });
+        assert_eq!(ui.host_region(widget).map(|l| l.size), Some(Size { w: 60., h: 30. }));
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn scale_follows_monitor() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(ScaledWidget::default()));
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        let monitor_scale = Rc::new(Cell::new(None));
+        let query_scale = monitor_scale.clone();
+        ui.set_scale_query(move || query_scale.get());
+
+        ui.resize(Size { w: 200., h: 100. });
+        assert_eq!(ui.scale_factor(), 1.);
+        assert_eq!(ui.root_widget().size(), Size { w: 200., h: 100. });
+
+        monitor_scale.set(Some(2.));
+        ui.resize(Size { w: 400., h: 200. });
+        assert_eq!(ui.scale_factor(), 2.);
+        assert_eq!(ui.widget(widget).scale_factors, vec![2.]);
+        assert_eq!(ui.root_widget().size(), Size { w: 200., h: 100. });
+
+        ui.resize(Size { w: 300., h: 200. });
+        assert_eq!(ui.widget(widget).scale_factors, vec![2.]);
+        assert_eq!(ui.root_widget().size(), Size { w: 150., h: 100. });
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn auto_scale_follows_pugl() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_auto_scaled(pv, rw));
+        view.handle().do_layout();
+
+        view.fake_scale_factor(2.);
+        view.fake_resize(Size { w: 400., h: 200. });
+        let ui = view.handle();
+        assert_eq!(ui.scale_factor(), 2.);
+        assert_eq!(ui.root_widget().size(), Size { w: 200., h: 100. });
+
+        ui.set_scale_factor(1.5);
+        view.fake_resize(Size { w: 300., h: 150. });
+        assert_eq!(view.handle().scale_factor(), 1.5);
+        assert_eq!(view.handle().root_widget().size(), Size { w: 200., h: 100. });
+    }
+
+    #[derive(Default)]
+    struct MeasuredWidget {
+        stub: WidgetStub,
+        width: f64,
+        measurements: std::sync::atomic::AtomicUsize
+    }
+
+    impl MeasuredWidget {
+        fn measurements(&self) -> usize {
+            self.measurements.load(std::sync::atomic::Ordering::Relaxed)
+        }
+    }
+
+    impl Widget for MeasuredWidget {
+        widget_stub!();
+        fn min_size(&self) -> Size {
+            self.measurements.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Size { w: self.width.max(10.), h: 10. }
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn resize_measures_invalidated_widgets_only() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let hlayout = ui.new_layouter::<HorizontalLayouter>();
+        ui.pack_to_layout(hlayout.widget(), ui.root_layout(), StackDirection::Back);
+        let first = ui.new_widget(Box::new(MeasuredWidget::default()));
+        ui.pack_to_layout(first, hlayout, StackDirection::Back);
+        let second = ui.new_widget(Box::new(MeasuredWidget::default()));
+        ui.pack_to_layout(second, hlayout, StackDirection::Back);
+        ui.do_layout();
+
+        let (m1, m2) = (ui.widget(first).measurements(), ui.widget(second).measurements());
+        assert!(m1 > 0 && m2 > 0);
+
+        ui.resize(Size { w: 300., h: 200. });
+        assert_eq!(ui.widget(first).measurements(), m1);
+        assert_eq!(ui.widget(second).measurements(), m2);
+        assert_eq!(ui.root_widget().size(), Size { w: 300., h: 200. });
+        assert_eq!(ui.widget(first).size(), Size { w: 10., h: 10. });
+
+        ui.widget(first).width = 50.;
+        ui.widget(first).ask_for_relayout();
+        ui.resize(Size { w: 320., h: 200. });
+        assert!(ui.widget(first).measurements() > m1);
+        assert_eq!(ui.widget(second).measurements(), m2);
+        assert_eq!(ui.widget(first).size(), Size { w: 50., h: 10. });
+        assert_eq!(ui.widget(second).size(), Size { w: 10., h: 10. });
+
+        ui.do_layout();
+        assert!(ui.widget(second).measurements() > m2);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn widget_rendered_to_surface() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 2.));
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(RectWidget {
+            color: (1., 0., 0.),
+            min_size: Size { w: 40., h: 20. },
+            ..Default::default()
+        }));
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        let mut surface = ui.render_to_surface(widget);
+        assert_eq!((surface.get_width(), surface.get_height()), (80, 40));
+        let stride = surface.get_stride() as usize;
+        let data = surface.get_data().expect("surface data borrowed");
+        let pixel = &data[stride + 4..stride + 8];
+        assert_eq!(u32::from_ne_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]), 0xffff0000);
+    }
+
+    #[derive(Default)]
+    struct BackendWidget {
+        stub: WidgetStub
+    }
+
+    impl Widget for BackendWidget {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 10., h: 10. } }
+        fn draw(&mut self, _ctx: &PaintContext, backend: &dyn crate::draw::DrawBackend) {
+            let cr = backend.cairo().unwrap();
+            cr.set_source_rgb(0., 1., 0.);
+            cr.paint();
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn widget_drawn_by_backend() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(BackendWidget::default()));
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        let mut surface = ui.render_to_surface(widget);
+        let data = surface.get_data().expect("surface data borrowed");
+        assert_eq!(u32::from_ne_bytes([data[0], data[1], data[2], data[3]]), 0xff00ff00);
+    }
+
+    #[cfg(feature = "cairo-helpers")]
+    #[derive(Default)]
+    struct ImageWidget {
+        stub: WidgetStub
+    }
+
+    #[cfg(feature = "cairo-helpers")]
+    impl Widget for ImageWidget {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 10., h: 10. } }
+        fn exposed(&mut self, ctx: &PaintContext, cr: &cairo::Context) {
+            assert!(ctx.image("background").is_some());
+            assert!(ctx.paint_image(cr, "background", self.pos()));
+            assert!(!ctx.paint_image(cr, "missing", self.pos()));
+        }
+    }
+
+    #[cfg(all(feature = "testing", feature = "cairo-helpers"))]
+    #[test]
+    fn widget_drawn_from_assets() {
+        let image = cairo::ImageSurface::create(cairo::Format::ARgb32, 10, 10).unwrap();
+        {
+            let cr = cairo::Context::new(&image);
+            cr.set_source_rgb(0., 0., 1.);
+            cr.paint();
+        }
+        let mut png = Vec::new();
+        image.write_to_png(&mut png).unwrap();
+
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 2.));
+
+        let ui = view.handle();
+        ui.assets().add_png("background", &png, 1.).unwrap();
+        let widget = ui.new_widget(Box::new(ImageWidget::default()));
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        let mut surface = ui.render_to_surface(widget);
+        assert_eq!(surface.get_width(), 20);
+        let stride = surface.get_stride() as usize;
+        let data = surface.get_data().expect("surface data borrowed");
+        let pixel = &data[10 * stride + 40..10 * stride + 44];
+        assert_eq!(u32::from_ne_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]), 0xff0000ff);
+    }
+
+    #[cfg(all(feature = "testing", feature = "cairo-helpers"))]
+    #[test]
+    fn layout_panel_drawn_as_nine_patch() {
+        let pixel = |surface: &mut cairo::ImageSurface, x: usize, y: usize| {
+            let stride = surface.get_stride() as usize;
+            let data = surface.get_data().expect("surface data borrowed");
+            let p = &data[y * stride + 4 * x..y * stride + 4 * x + 4];
+            u32::from_ne_bytes([p[0], p[1], p[2], p[3]])
+        };
+        let image = cairo::ImageSurface::create(cairo::Format::ARgb32, 6, 6).unwrap();
+        {
+            let cr = cairo::Context::new(&image);
+            cr.set_source_rgb(1., 0., 0.);
+            cr.paint();
+            cr.set_source_rgb(0., 0., 1.);
+            cr.rectangle(2., 2., 2., 2.);
+            cr.fill();
+        }
+        let mut png = Vec::new();
+        image.write_to_png(&mut png).unwrap();
+
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let panel = ui.new_layouter::<HorizontalLayouter>();
+        ui.pack_to_layout(panel.widget(), ui.root_layout(), StackDirection::Back);
+        ui.layouter(panel).set_padding(10.);
+        ui.widget(panel.widget()).set_panel(true);
+        ui.do_layout();
+        ui.set_style(crate::theme::Style { background: (0., 1., 0.), ..Default::default() });
+
+        let mut surface = ui.render_to_surface(panel.widget());
+        assert_eq!(pixel(&mut surface, 10, 10), 0xff00ff00);
+
+        ui.assets().add_png("panel", &png, 1.).unwrap();
+        ui.theme().panel = crate::panel::Panel::NinePatch {
+            image: String::from("panel"),
+            insets: crate::panel::Insets::uniform(2.)
+        };
+        let mut surface = ui.render_to_surface(panel.widget());
+        assert_eq!(pixel(&mut surface, 0, 0), 0xffff0000);
+        assert_eq!(pixel(&mut surface, 10, 10), 0xff0000ff);
+        assert_eq!(pixel(&mut surface, 19, 10), 0xffff0000);
+    }
+
+    #[test]
+    fn scale_from_environment() {
+        use crate::ui::scale_from_env;
+        let env = |vars: &'static [(&'static str, &'static str)]| move |name: &str| {
+            vars.iter().find(|(n, _)| *n == name).map(|(_, v)| String::from(*v))
+        };
+        assert_eq!(scale_from_env(env(&[])), None);
+        assert_eq!(scale_from_env(env(&[("GDK_SCALE", "2")])), Some(2.));
+        assert_eq!(scale_from_env(env(&[("GDK_SCALE", "2"), ("GDK_DPI_SCALE", "0.75")])), Some(1.5));
+        assert_eq!(scale_from_env(env(&[("QT_SCALE_FACTOR", "1.25")])), Some(1.25));
+        assert_eq!(scale_from_env(env(&[("GDK_SCALE", "0"), ("QT_SCALE_FACTOR", "x")])), None);
+    }
+
+    #[test]
+    fn merge_overlapping_rects() {
+        use crate::ui::merge_rects;
+        let rect = |x, y, w, h| Layout { pos: Coord { x, y }, size: Size { w, h } };
+        let merged = |rects| {
+            let mut merged = merge_rects(rects);
+            merged.sort_by(|a, b| a.pos.x.partial_cmp(&b.pos.x).unwrap());
+            merged
+        };
+
+        assert!(merged(vec![]).is_empty());
+        assert_eq!(merged(vec![rect(0., 0., 10., 10.), rect(5., 5., 10., 10.)]),
+                   vec![rect(0., 0., 15., 15.)]);
+        assert_eq!(merged(vec![rect(0., 0., 5., 5.), rect(10., 10., 5., 5.)]),
+                   vec![rect(0., 0., 5., 5.), rect(10., 10., 5., 5.)]);
+        assert_eq!(merged(vec![rect(0., 0., 20., 20.), rect(5., 5., 5., 5.)]),
+                   vec![rect(0., 0., 20., 20.)]);
+        assert_eq!(merged(vec![rect(0., 0., 10., 10.), rect(20., 0., 10., 10.), rect(8., 0., 14., 5.)]),
+                   vec![rect(0., 0., 30., 10.)]);
+    }
+
+    #[test]
+    fn damage_accumulation() {
+        use crate::ui::Damage;
+        let rect = |x, y, w, h| Layout { pos: Coord { x, y }, size: Size { w, h } };
+
+        let mut damage = Damage::default();
+        assert!(damage.is_empty());
+        damage.add(rect(0., 0., 10., 10.));
+        damage.add(rect(5., 5., 10., 10.));
+        assert!(!damage.is_empty());
+        assert_eq!(damage.take(), Some(vec![rect(0., 0., 15., 15.)]));
+        assert!(damage.is_empty());
+
+        damage.add_full();
+        damage.add(rect(0., 0., 10., 10.));
+        assert_eq!(damage.take(), None);
+        assert_eq!(damage.take(), Some(vec![]));
+    }
+
+    #[derive(Default)]
+    struct AnimatedWidget {
+        stub: WidgetStub,
+        frames: Vec<f64>
+    }
+
+    impl Widget for AnimatedWidget {
+        widget_stub!();
+        fn frame(&mut self, dt: f64) -> bool {
+            self.frames.push(dt);
+            self.frames.len() < 3
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn frame_callbacks() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(AnimatedWidget::default()));
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        ui.next_event(0.0);
+        assert!(ui.widget(widget).frames.is_empty());
+
+        ui.request_frame_callbacks(widget);
+        assert!(ui.is_animating(widget));
+        assert!(ui.auto_timeout() > 0.0);
+
+        ui.next_event(0.0);
+        view.queue_event(Event { data: EventType::Unmap, context: Default::default() });
+        view.handle().next_event(0.0);
+        view.handle().next_event(0.0);
+        view.queue_event(Event { data: EventType::Map, context: Default::default() });
+        view.handle().next_event(0.0);
+        view.handle().next_event(0.0);
+        view.handle().next_event(0.0);
+
+        let ui = view.handle();
+        let frames = &ui.widget(widget).frames;
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0], 0.0);
+        assert_eq!(frames[1], 0.0);
+        assert!(!ui.is_animating(widget));
+        assert_eq!(ui.auto_timeout(), -1.0);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn messages_delivered_before_events() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let widget = {
+            let ui = view.handle();
+            let widget = ui.new_widget(Box::new(LogWidget::default()));
+            ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+            ui.do_layout();
+            ui.send_message(widget, Box::new("first"));
+            ui.send_message(widget, Box::new(42));
+            ui.send_message(widget, Box::new("second"));
+            widget
+        };
+        view.queue_event(Event {
+            data: EventType::MouseButtonPress(MouseButton { num: 1, modifiers: Modifiers::default() }),
+            context: EventContext { pos: Coord { x: 5., y: 5. }, ..Default::default() }
+        });
+        view.handle().send_message(widget, Box::new("third"));
+        view.handle().next_event(-1.0);
+
+        assert_eq!(view.handle().widget(widget).log, vec!["first", "second", "third", "press"]);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn commands_from_other_threads() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(LogWidget::default()));
+        let rect = ui.new_widget(Box::new(RectWidget::default()));
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(rect, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        let sender = ui.command_sender();
+        let colors = ui.command_channel(move |ui: &mut UI<RootWidget>, color: (f64, f64, f64)| {
+            ui.widget(rect).color = color;
+        });
+        std::thread::spawn(move || {
+            sender.send(UiCommand::message(widget, Box::new("from dsp"))).unwrap();
+            sender.send(UiCommand::Redisplay).unwrap();
+            colors.send((0., 1., 0.)).unwrap();
+        }).join().unwrap();
+        ui.command_sender().send(UiCommand::message(widget, Box::new("from gui"))).unwrap();
+
+        assert!(ui.widget(widget).log.is_empty());
+        ui.next_event(-1.0);
+        assert_eq!(ui.widget(widget).log, vec!["from dsp", "from gui"]);
+        assert_eq!(ui.widget(rect).color, (0., 1., 0.));
+
+        let removed = ui.new_widget(Box::new(LogWidget::default()));
+        let command = UiCommand::message(removed, Box::new("too late"));
+        ui.remove_widget(removed);
+        let reused = ui.new_widget(Box::new(LogWidget::default()));
+        ui.command_sender().send(command).unwrap();
+        ui.next_event(0.0);
+        assert!(ui.widget(reused).log.is_empty());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn embedded_view() {
+        let rw = Box::new(RootWidget::default());
+        let parent = crate::embed::ParentWindow::from_x11(0x0340_0007);
+        let mut view = UI::embed_into(parent, rw);
+        assert_eq!(view.mock_instance().parent(), parent.as_ptr());
+
+        let ui = view.handle();
+        let rect = ui.new_widget(Box::new(RectWidget::default()));
+        ui.pack_to_layout(rect, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+        assert!(ui.is_alive(rect));
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn window_opened_by_config() {
+        use crate::window::WindowConfig;
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let rect = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 20., h: 20. },
+            ..Default::default()
+        }));
+        ui.pack_to_layout(rect, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+        assert_eq!(ui.window_config(), None);
+
+        let config = WindowConfig::new("Synth").class_name("synth").resizable(true);
+        ui.open_window(config.clone());
+        assert_eq!(ui.window_config(), Some(&config));
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn popup_windows() {
+        use crate::window::WindowConfig;
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let rect = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 20., h: 20. },
+            ..Default::default()
+        }));
+        ui.pack_to_layout(rect, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        let popup = ui.open_popup(Box::new(RootWidget::default()), WindowConfig::new("list"), |popup| {
+            let item = popup.new_widget(Box::new(LogWidget::default()));
+            popup.pack_to_layout(item, popup.root_layout(), StackDirection::Back);
+        });
+        assert!(ui.is_popup_open(popup));
+        assert_eq!(ui.popup::<RootWidget>(popup).unwrap().window_config().map(|c| c.decorated), Some(false));
+        let main_window = crate::embed::ParentWindow::from_ptr(ui.get_native_window());
+        assert_eq!(ui.popup::<RootWidget>(popup).unwrap().window_config().and_then(|c| c.transient_for),
+                   Some(main_window));
+        assert!(ui.popup::<RectWidget>(popup).is_none());
+
+        view.queue_event(Event {
+            data: EventType::KeyPress(Key { key: KeyVal::Character('q'), modifiers: Modifiers::default(), code: 0 }),
+            context: EventContext::default()
+        });
+        view.handle().update(-1.0);
+        let ui = view.handle();
+        assert!(!ui.root_widget().wants_quit());
+        assert!(ui.popup::<RootWidget>(popup).unwrap().root_widget().wants_quit());
+        ui.next_event(0.0);
+        assert!(ui.is_popup_open(popup));
+        assert!(ui.dismissed_popups().is_empty());
+
+        view.queue_event(Event {
+            data: EventType::MouseButtonPress(MouseButton { num: 1, modifiers: Modifiers::default() }),
+            context: EventContext { pos: Coord { x: 10., y: 10. }, ..Default::default() }
+        });
+        view.handle().update(-1.0);
+        let ui = view.handle();
+        assert!(!ui.is_popup_open(popup));
+        assert_eq!(ui.dismissed_popups(), vec![popup]);
+        assert!(ui.dismissed_popups().is_empty());
+
+        let other = ui.open_popup(Box::new(RootWidget::default()), WindowConfig::new("list"), |_| {});
+        assert_ne!(other, popup);
+        ui.add_event_filter(Box::new(|ev| match ev.data {
+            EventType::KeyPress(_) => FilterResult::Consume,
+            _ => FilterResult::Pass
+        }));
+        view.queue_event(Event {
+            data: EventType::KeyPress(Key { key: KeyVal::Character('q'), modifiers: Modifiers::default(), code: 0 }),
+            context: EventContext::default()
+        });
+        view.handle().update(-1.0);
+        let ui = view.handle();
+        assert!(!ui.popup::<RootWidget>(other).unwrap().root_widget().wants_quit());
+        assert!(ui.close_popup(other));
+        assert!(!ui.close_popup(other));
+        assert!(ui.dismissed_popups().is_empty());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn named_widgets() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let gain = ui.new_named_widget("gain_dial", Box::new(RectWidget {
+            name: "gain",
+            ..Default::default()
+        }));
+        ui.pack_to_layout(gain, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        assert_eq!(ui.widget_by_name::<RectWidget>("gain_dial").map(|w| w.name), Some("gain"));
+        assert_eq!(ui.widget_handle_by_name::<RectWidget>("gain_dial").map(|h| h.id()), Some(gain.id()));
+        assert!(ui.widget_by_name::<RectWidget>("volume_dial").is_none());
+        assert!(ui.widget_by_name::<RootWidget>("gain_dial").is_none());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    #[should_panic(expected = "widget name gain_dial already taken")]
+    fn named_widgets_unique() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        ui.new_named_widget("gain_dial", Box::new(RectWidget::default()));
+        ui.new_named_widget("gain_dial", Box::new(RectWidget::default()));
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn tab_layout_switching() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let tabs = ui.new_layouter::<TabLayouter>();
+        let widget_1 = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 42., h: 23. },
+            ..Default::default()
+        }));
+        let widget_2 = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 23., h: 42. },
+            ..Default::default()
+        }));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(tabs.widget(), ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(widget_1, tabs, String::from("First"));
+        ui.pack_to_layout(widget_2, tabs, String::from("Second"));
+        ui.do_layout();
+
+        assert_eq!(ui.layouter_ref(tabs).labels(), vec!["First", "Second"]);
+        assert_eq!(ui.root_widget().size(), Size { w: 42., h: 23. });
+        ui.focus_widget(widget_2);
+        assert!(!ui.widget(widget_2).has_focus());
+        ui.focus_widget(widget_1);
+        assert!(ui.widget(widget_1).has_focus());
+        assert_eq!(ui.widget(widget_1).shown, None);
+
+        ui.set_active_tab(tabs, 1);
+        assert_eq!(ui.layouter_ref(tabs).active_tab(), 1);
+        assert!(!ui.widget(widget_1).has_focus());
+        assert_eq!(ui.widget(widget_1).shown, Some(false));
+        assert_eq!(ui.widget(widget_2).shown, Some(true));
+        assert_eq!(ui.widget(widget_2).size(), Size { w: 23., h: 42. });
+        ui.focus_widget(widget_2);
+        assert!(ui.widget(widget_2).has_focus());
+
+        ui.set_active_tab(tabs, 0);
+        assert_eq!(ui.widget(widget_1).shown, Some(true));
+        assert_eq!(ui.widget(widget_2).shown, Some(false));
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn deferred_construction() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.do_layout();
+        ui.set_deferred_budget(0.0);
+        for _ in 0..2 {
+            ui.defer(|ui| {
+                let widget = ui.new_widget(Box::new(RectWidget {
+                    min_size: Size { w: 42., h: 23. },
+                    ..Default::default()
+                }));
+                ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+            });
+        }
+        assert!(ui.is_loading());
+
+        ui.next_event(0.0);
+        assert!(ui.is_loading());
+        assert_eq!(ui.root_widget().size(), Size::default());
+
+        ui.next_event(0.0);
+        assert!(!ui.is_loading());
+        assert_eq!(ui.root_widget().size(), Size { w: 42., h: 46. });
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn swap_root_layout_pages() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let page_1 = ui.new_layouter::<HorizontalLayouter>();
+        let page_2 = ui.new_layouter::<VerticalLayouter>();
+        let widget_1 = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 42., h: 23. },
+            ..Default::default()
+        }));
+        let widget_2 = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 23., h: 42. },
+            ..Default::default()
+        }));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(page_1.widget(), ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(widget_1, page_1, StackDirection::Back);
+        ui.pack_to_layout(widget_2, page_2, StackDirection::Back);
+        ui.do_layout();
+
+        ui.focus_widget(widget_1);
+        assert!(ui.widget(widget_1).has_focus());
+
+        ui.swap_root_layout(page_2);
+        assert!(!ui.widget(widget_1).has_focus());
+        ui.focus_widget(widget_1);
+        assert!(!ui.widget(widget_1).has_focus());
+        ui.focus_widget(widget_2);
+        assert!(ui.widget(widget_2).has_focus());
+        assert_eq!(ui.widget(widget_2).pos(), Coord::default());
+
+        ui.swap_root_layout(page_2);
+        assert!(ui.widget(widget_2).has_focus());
+
+        ui.swap_root_layout(page_1);
+        assert!(!ui.widget(widget_2).has_focus());
+        ui.focus_next_widget();
+        assert!(ui.widget(widget_1).has_focus());
+        assert_eq!(ui.widget(widget_1).pos(), Coord::default());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn style_override_inheritance() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let strip = ui.new_layouter::<VerticalLayouter>();
+        let widget_1 = ui.new_widget(Box::new(RectWidget::default()));
+        let widget_2 = ui.new_widget(Box::new(RectWidget::default()));
+        let widget_3 = ui.new_widget(Box::new(RectWidget::default()));
+        ui.pack_to_layout(strip.widget(), ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(widget_1, strip, StackDirection::Back);
+        ui.pack_to_layout(widget_2, strip, StackDirection::Back);
+        ui.pack_to_layout(widget_3, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        ui.set_style(Style { padding: 2.0, ..Default::default() });
+        ui.set_style_override(strip.widget(), StyleOverride {
+            accent: Some((0.0, 1.0, 0.0)),
+            ..Default::default()
+        });
+        ui.set_style_override(widget_2, StyleOverride {
+            border_radius: Some(3.0),
+            ..Default::default()
+        });
+
+        assert_eq!(ui.widget_style(widget_1), Style { padding: 2.0, accent: (0.0, 1.0, 0.0), ..Default::default() });
+        assert_eq!(ui.widget_style(widget_2), Style {
+            padding: 2.0,
+            accent: (0.0, 1.0, 0.0),
+            border_radius: 3.0,
+            ..Default::default()
+        });
+        assert_eq!(ui.widget_style(widget_3), Style { padding: 2.0, ..Default::default() });
+
+        ui.clear_style_override(strip.widget());
+        assert_eq!(ui.widget_style(widget_1), Style { padding: 2.0, ..Default::default() });
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn removed_widget_slots_reused() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new(pv, rw));
+
+        let ui = view.handle();
+        let page = ui.new_layouter::<VerticalLayouter>();
+        let widget = ui.new_named_widget("old", Box::new(RectWidget::default()));
+        ui.pack_to_layout(widget, page, StackDirection::Back);
+        ui.add_to_group("page", widget);
+
+        ui.remove_widget(page.widget());
+        assert!(!ui.is_alive(widget));
+        assert!(!ui.is_alive(page.widget()));
+        assert!(ui.widget_handle_by_name::<RectWidget>("old").is_none());
+        assert!(ui.group_members("page").is_empty());
+
+        let new_widget = ui.new_widget(Box::new(TextEntry::default()));
+        assert!(ui.is_alive(new_widget));
+        assert!(!ui.is_alive(widget));
+        ui.pack_to_layout(new_widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+        assert!(ui.widget(new_widget).text.text().is_empty());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn fallible_widget_borrows() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new(pv, rw));
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(RectWidget::default()));
+        let removed = ui.new_widget(Box::new(RectWidget::default()));
+        ui.remove_widget(removed);
+
+        assert!(ui.try_root_widget().is_some());
+        assert!(ui.try_widget(widget).is_some());
+        assert!(ui.try_widget(removed).is_none());
+
+        let reused = ui.new_widget(Box::new(TextEntry::default()));
+        assert!(ui.try_widget(removed).is_none());
+        assert!(ui.try_widget(reused).is_some());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn borrow_several_widgets() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new(pv, rw));
+
+        let ui = view.handle();
+        let rects: Vec<WidgetHandle<RectWidget>> = (0..3)
+            .map(|_| ui.new_widget(Box::new(RectWidget::default())))
+            .collect();
+        let entry = ui.new_widget(Box::new(TextEntry::default()));
+
+        let (entry_widget, rect) = ui.widgets2(entry, rects[1]);
+        rect.color = (1., 0., 0.);
+        entry_widget.text.set_text("copied");
+        assert_eq!(ui.widget(rects[1]).color, (1., 0., 0.));
+
+        let (first, last) = ui.widgets2(rects[2], rects[0]);
+        first.color = last.color;
+
+        let mut all = ui.widgets_n(&[rects[2], rects[0], rects[1]]);
+        all[1].color = (0., 1., 0.);
+        assert_eq!(all[2].color, (1., 0., 0.));
+        assert_eq!(ui.widget(rects[0]).color, (0., 1., 0.));
+        assert_eq!(ui.widget(entry).text.text(), "copied");
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn batched_updates_layout_once() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new(pv, rw));
+
+        let ui = view.handle();
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        let widgets: Vec<WidgetHandle<RectWidget>> = (0..3)
+            .map(|_| ui.new_widget(Box::new(RectWidget {
+                min_size: Size { w: 20., h: 10. },
+                ..Default::default()
+            })))
+            .collect();
+        for w in widgets.iter() {
+            ui.pack_to_layout(*w, ui.root_layout(), StackDirection::Back);
+        }
+        ui.do_layout();
+        assert_eq!(ui.widget(widgets[2]).pos(), Coord { x: 0., y: 20. });
+
+        ui.batch(|ui| {
+            assert!(ui.in_batch());
+            ui.set_visible(widgets[0], false);
+            ui.batch(|ui| ui.set_highlighted(widgets[1], true));
+            ui.set_visible(widgets[1], false);
+            assert_eq!(ui.widget(widgets[2]).pos(), Coord { x: 0., y: 20. });
+        });
+        assert!(!ui.in_batch());
+        assert_eq!(ui.widget(widgets[2]).pos(), Coord { x: 0., y: 0. });
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn iterate_widgets_of_type() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new(pv, rw));
+
+        let ui = view.handle();
+        let first = ui.new_widget(Box::new(RectWidget::default()));
+        let _entry = ui.new_widget(Box::new(TextEntry::default()));
+        let second = ui.new_widget(Box::new(RectWidget::default()));
+
+        for (rect, _) in ui.widgets_of_type::<RectWidget>() {
+            rect.color = (0., 0., 1.);
+        }
+        let ids: Vec<Id> = ui.widgets_of_type::<RectWidget>().map(|(_, id)| id).collect();
+        assert_eq!(ids, vec![first.id(), second.id()]);
+        assert_eq!(ui.widget(first).color, (0., 0., 1.));
+        assert_eq!(ui.widget(second).color, (0., 0., 1.));
+        assert_eq!(ui.widgets_of_type::<TextEntry>().count(), 1);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    #[should_panic(expected = "needs two different widgets")]
+    fn borrow_same_widget_twice_panics() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new(pv, rw));
+
+        let ui = view.handle();
+        let rect = ui.new_widget(Box::new(RectWidget::default()));
+        ui.widgets2(rect, rect);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    #[should_panic(expected = "stale widget handle")]
+    fn stale_widget_handle_panics() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new(pv, rw));
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(RectWidget::default()));
+        ui.remove_widget(widget);
+        let _reused = ui.new_widget(Box::new(RectWidget::default()));
+        ui.widget(widget);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    #[should_panic(expected = "stale widget handle")]
+    fn stale_widget_handle_queries_and_setters() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new(pv, rw));
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(RectWidget::default()));
+        ui.remove_widget(widget);
+        let reused = ui.new_widget(Box::new(RectWidget::default()));
+        ui.set_highlighted(reused, true);
+        assert!(ui.is_highlighted(reused));
+        assert!(!ui.is_highlighted(widget));
+        assert!(!ui.is_modal(widget));
+        assert!(!ui.is_animating(widget));
+        ui.set_visible(widget, false);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn nodes_found_after_packing_unlayouted_subtrees() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new(pv, rw));
+
+        let ui = view.handle();
+        let outer = ui.new_layouter::<VerticalLayouter>();
+        let inner = ui.new_layouter::<HorizontalLayouter>();
+        let widget = ui.new_widget(Box::new(RectWidget::default()));
+        ui.pack_to_layout(widget, inner, StackDirection::Back);
+        ui.pack_to_layout(inner.widget(), outer, StackDirection::Back);
+        ui.layouter(inner).set_padding(7.);
+        ui.pack_to_layout(outer.widget(), ui.root_layout(), StackDirection::Back);
+        ui.layouter(outer).set_padding(0.);
+        ui.layouter(ui.root_layout()).set_padding(0.);
+        ui.do_layout();
+
+        ui.set_style_override(inner.widget(), StyleOverride {
+            accent: Some((0.0, 1.0, 0.0)),
+            ..Default::default()
+        });
+        assert_eq!(ui.widget_style(widget).accent, (0.0, 1.0, 0.0));
+        assert_eq!(ui.widget(widget).pos(), Coord { x: 7., y: 7. });
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn interaction_settings_distributed() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let widget_1 = ui.new_widget(Box::new(RectWidget::default()));
+        assert_eq!(ui.widget(widget_1).interaction_settings(), InteractionSettings::default());
+
+        let settings = InteractionSettings { scroll_step: 0.5, ..Default::default() };
+        ui.set_interaction_settings(settings);
+        let widget_2 = ui.new_widget(Box::new(RectWidget::default()));
+        assert_eq!(ui.widget(widget_1).interaction_settings(), settings);
+        assert_eq!(ui.widget(widget_2).interaction_settings(), settings);
+        assert_eq!(ui.root_widget().interaction_settings(), settings);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn right_to_left_layout_mirrored() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let widget_size = Size { w: 42., h: 23. };
+
+        let ui = view.handle();
+        let row = ui.new_layouter::<HorizontalLayouter>();
+        let widget_1 = ui.new_widget(Box::new(RectWidget {
+            min_size: widget_size,
+            ..Default::default()
+        }));
+        let widget_2 = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 10., h: 23. },
+            ..Default::default()
+        }));
+        let hidden = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 10., h: 23. },
+            ..Default::default()
+        }));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.layouter(row).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(row.widget(), ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(widget_1, row, StackDirection::Back);
+        ui.pack_to_layout(widget_2, row, StackDirection::Back);
+        ui.pack_to_layout(hidden, row, StackDirection::Front);
+        ui.set_visible(hidden, false);
+        ui.do_layout();
+
+        assert_eq!(ui.widget(widget_1).pos(), Coord { x: 0., y: 0. });
+        assert_eq!(ui.widget(widget_2).pos(), Coord { x: 42., y: 0. });
+        let hidden_pos = ui.widget(hidden).pos();
+
+        ui.set_layout_direction(LayoutDirection::RightToLeft);
+        assert_eq!(ui.layout_direction(), LayoutDirection::RightToLeft);
+        assert_eq!(ui.widget(widget_1).pos(), Coord { x: 10., y: 0. });
+        assert_eq!(ui.widget(widget_2).pos(), Coord { x: 0., y: 0. });
+        assert_eq!(ui.widget(hidden).pos(), hidden_pos);
+
+        ui.set_layout_direction(LayoutDirection::RightToLeft);
+        assert_eq!(ui.widget(widget_1).pos(), Coord { x: 10., y: 0. });
+        assert_eq!(ui.widget(hidden).pos(), hidden_pos);
+
+        ui.set_layout_direction(LayoutDirection::LeftToRight);
+        assert_eq!(ui.widget(widget_1).pos(), Coord { x: 0., y: 0. });
+        assert_eq!(ui.widget(hidden).pos(), hidden_pos);
+
+        #[cfg(feature = "text")]
+        {
+            ui.set_locale(crate::text::Locale { direction: crate::text::TextDirection::RightToLeft, ..Default::default() });
+            assert_eq!(ui.layout_direction(), LayoutDirection::RightToLeft);
+            assert_eq!(ui.widget(widget_1).pos(), Coord { x: 10., y: 0. });
+            assert_eq!(ui.widget(widget_2).pos(), Coord { x: 0., y: 0. });
+
+            ui.set_locale(Default::default());
+            assert_eq!(ui.layout_direction(), LayoutDirection::LeftToRight);
+            assert_eq!(ui.widget(widget_1).pos(), Coord { x: 0., y: 0. });
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn highlight_widgets() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let widget_1 = ui.new_widget(Box::new(RectWidget::default()));
+        let widget_2 = ui.new_widget(Box::new(RectWidget::default()));
+        ui.pack_to_layout(widget_1, ui.root_layout(), StackDirection::Front);
+        ui.pack_to_layout(widget_2, ui.root_layout(), StackDirection::Front);
+        ui.add_to_group("learn", widget_1);
+        ui.add_to_group("learn", widget_2);
+        ui.do_layout();
+
+        ui.set_group_highlighted("learn", true);
+        assert!(ui.is_highlighted(widget_1));
+        assert!(ui.is_highlighted(widget_2));
+        ui.set_highlighted(widget_1, false);
+        assert!(!ui.is_highlighted(widget_1));
+        assert!(ui.is_highlighted(widget_2));
+        ui.set_group_highlighted("learn", false);
+
+        ui.flash_highlight(widget_2, 0.5);
+        assert!(ui.is_highlighted(widget_2));
+        ui.timer_event(crate::ui::FLASH_TIMER_BASE + widget_2.id());
+        assert!(!ui.is_highlighted(widget_2));
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn two_widgets_clicks() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let widget_size = Size { w: 42., h: 23. };
+
+        view.queue_event(Event {
+            data: EventType::MouseButtonRelease(MouseButton { num: 1, modifiers: Modifiers::default() }),
+            context: EventContext { pos: Coord{ x: 21., y: 11.5 }, ..Default::default() }
+        });
+
+        view.queue_event(Event {
+            data: EventType::MouseButtonRelease(MouseButton { num: 1, modifiers: Modifiers::default() }),
+            context: EventContext { pos: Coord{ x: 21., y: 28. }, ..Default::default() }
+        });
+
+        view.queue_event(Event {
+            data: EventType::MouseButtonRelease(MouseButton { num: 1, modifiers: Modifiers::default() }),
+            context: EventContext { pos: Coord{ x: 21., y: 46. }, ..Default::default() }
+        });
+
+        let ui = view.handle();
+        let widget_1 = ui.new_widget(Box::new(RectWidget {
+            min_size: widget_size,
+            ..Default::default()
+        }));
+        let widget_2 = ui.new_widget(Box::new(RectWidget {
+            min_size: widget_size,
+            ..Default::default()
+        }));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(10.);
+        ui.pack_to_layout(widget_1, ui.root_layout(), StackDirection::Front);
+        ui.pack_to_layout(widget_2, ui.root_layout(), StackDirection::Front);
+        ui.do_layout();
+        ui.fit_window_size();
+        ui.fit_window_min_size();
+        ui.show_window();
+
+        assert!(!ui.widget(widget_1).clicked());
+        assert!(!ui.widget(widget_2).clicked());
+
+        ui.update(-1.0);
+        assert!(!ui.widget(widget_1).clicked());
+        assert!(ui.widget(widget_2).clicked());
+
+        ui.update(-1.0);
+        assert!(!ui.widget(widget_1).clicked());
+        assert!(!ui.widget(widget_2).clicked());
+
+        ui.update(-1.0);
+        assert!(ui.widget(widget_1).clicked());
+        assert!(!ui.widget(widget_2).clicked());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn focus_two_widgets() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let widget_size = Size { w: 42., h: 23. };
+
+        let ui = view.handle();
+        let widget_1 = ui.new_widget(Box::new(RectWidget {
+            min_size: widget_size,
+            ..Default::default()
+        }));
+        let widget_2 = ui.new_widget(Box::new(RectWidget {
+            min_size: widget_size,
+            ..Default::default()
+        }));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(widget_1, ui.root_layout(), StackDirection::Front);
+        ui.pack_to_layout(widget_2, ui.root_layout(), StackDirection::Front);
+        ui.do_layout();
+        ui.fit_window_size();
+        ui.fit_window_min_size();
+        ui.show_window();
+
+        assert!(!ui.widget(widget_1).has_focus());
+        assert!(!ui.widget(widget_2).has_focus());
+
+        ui.focus_next_widget();
+
+        assert!(ui.widget(widget_1).has_focus());
+        assert!(!ui.widget(widget_2).has_focus());
+
+        ui.focus_next_widget();
+
+        assert!(!ui.widget(widget_1).has_focus());
+        assert!(ui.widget(widget_2).has_focus());
+
+        ui.focus_next_widget();
+
+        assert!(ui.widget(widget_1).has_focus());
+        assert!(!ui.widget(widget_2).has_focus());
+    }
+
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn test_focus() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        assert!(!view.handle().has_focus());
+        view.fake_focus_in();
+        assert!(view.handle().has_focus());
+        view.fake_focus_out();
+        assert!(!view.handle().has_focus());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn remap_repaints_pending_widgets() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(RectWidget::default()));
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Front);
+        ui.do_layout();
+
+        assert!(ui.is_mapped());
+        view.queue_event(Event { data: EventType::Unmap, context: Default::default() });
+        view.handle().update(-1.0);
+        assert!(!view.handle().is_mapped());
+
+        view.mock_instance().take_redisplay_rects();
+        view.handle().widget(widget).ask_for_repaint();
+        view.handle().next_event(0.0);
+        assert!(view.mock_instance().take_redisplay_rects().is_empty());
+
+        view.queue_event(Event { data: EventType::Map, context: Default::default() });
+        view.handle().update(-1.0);
+        assert!(view.handle().is_mapped());
+        view.handle().next_event(0.0);
+        let layout = view.handle().widget(widget).layout();
+        let damage = view.mock_instance().take_redisplay_rects();
+        assert!(damage.iter().any(|&(pos, size)| {
+            pos.x <= layout.pos.x && pos.y <= layout.pos.y
+                && pos.x + size.w >= layout.pos.x + layout.size.w
+                && pos.y + size.h >= layout.pos.y + layout.size.h
+        }));
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn window_focus_change_notification() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        assert_eq!(view.handle().window_focus_changed(), None);
+        view.fake_focus_in();
+        assert_eq!(view.handle().window_focus_changed(), Some(true));
+        assert_eq!(view.handle().window_focus_changed(), None);
+        view.fake_focus_in();
+        assert_eq!(view.handle().window_focus_changed(), None);
+        view.fake_focus_out();
+        assert_eq!(view.handle().window_focus_changed(), Some(false));
+        assert_eq!(view.handle().window_focus_changed(), None);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn release_synthesized_on_focus_out() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        view.queue_event(Event {
+            data: EventType::MouseButtonPress(MouseButton { num: 1, modifiers: Modifiers::default() }),
+            context: EventContext { pos: Coord{ x: 21., y: 11.5 }, ..Default::default() }
+        });
+
+        let widget = {
+            let ui = view.handle();
+            ui.input_sanitizer().set_release_on_focus_out(true);
+            let widget = ui.new_widget(Box::new(RectWidget {
+                min_size: Size { w: 42., h: 23. },
+                ..Default::default()
+            }));
+            ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+            ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Front);
+            ui.do_layout();
+            ui.update(-1.0);
+            assert!(!ui.widget(widget).clicked());
+            widget
+        };
+
+        view.fake_focus_in();
+        view.fake_focus_out();
+        assert!(view.handle().widget(widget).clicked());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn focus_visible_after_keyboard_interaction() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        assert!(!view.handle().focus_visible());
+
+        view.queue_event(Event {
+            data: EventType::KeyPress(Key {
+                key: KeyVal::Character('\t'),
+                modifiers: Modifiers::default(),
+                code: 0
+            }),
+            context: EventContext::default()
+        });
+        view.handle().update(-1.0);
+        assert!(view.handle().focus_visible());
+
+        view.queue_event(Event {
+            data: EventType::MouseButtonPress(MouseButton { num: 1, modifiers: Modifiers::default() }),
+            context: EventContext::default()
+        });
+        view.handle().update(-1.0);
+        assert!(!view.handle().focus_visible());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn gestures_reported() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let press = Event {
+            data: EventType::MouseButtonPress(MouseButton { num: 1, modifiers: Modifiers::default() }),
+            context: EventContext { pos: Coord { x: 10., y: 10. }, ..Default::default() }
+        };
+        let release = Event {
+            data: EventType::MouseButtonRelease(MouseButton { num: 1, modifiers: Modifiers::default() }),
+            context: EventContext { pos: Coord { x: 100., y: 10. }, ..Default::default() }
+        };
+
+        let ui = view.handle();
+        let bound = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 50., h: 20. },
+            reports_gestures: true,
+            ..Default::default()
+        }));
+        let unbound = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 50., h: 20. },
+            ..Default::default()
+        }));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(bound, ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(unbound, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        view.queue_event(press);
+        view.handle().update(-1.0);
+        assert!(view.handle().widget(bound).in_gesture());
+        assert_eq!(view.handle().widget(bound).gestures(), vec![Gesture::Begin]);
+        assert_eq!(view.handle().widget(bound).gestures(), vec![]);
+
+        view.queue_event(release);
+        view.handle().update(-1.0);
+        assert!(!view.handle().widget(bound).in_gesture());
+        assert_eq!(view.handle().widget(bound).gestures(), vec![Gesture::End]);
+
+        view.queue_event(press);
+        view.queue_event(release);
+        view.handle().update(-1.0);
+        assert_eq!(view.handle().widget(bound).gestures(), vec![Gesture::Begin, Gesture::End]);
+
+        let press_unbound = Event {
+            context: EventContext { pos: Coord { x: 10., y: 30. }, ..Default::default() },
+            ..press
+        };
+        view.queue_event(press_unbound);
+        view.queue_event(release);
+        view.handle().update(-1.0);
+        assert!(!view.handle().widget(unbound).in_gesture());
+        assert_eq!(view.handle().widget(unbound).gestures(), vec![]);
+        assert_eq!(view.handle().widget(bound).gestures(), vec![]);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn modal_widget_routing() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let press = Event {
+            data: EventType::MouseButtonPress(MouseButton { num: 1, modifiers: Modifiers::default() }),
+            context: EventContext { pos: Coord { x: 10., y: 10. }, ..Default::default() }
+        };
+        let release = Event {
+            data: EventType::MouseButtonRelease(MouseButton { num: 1, modifiers: Modifiers::default() }),
+            context: EventContext { pos: Coord { x: 10., y: 10. }, ..Default::default() }
+        };
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 50., h: 20. },
+            ..Default::default()
+        }));
+        let popup = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 50., h: 20. },
+            ..Default::default()
+        }));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(popup, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+        ui.focus_widget(widget);
+
+        ui.set_modal(popup);
+        assert!(ui.is_modal(popup));
+        assert!(!ui.is_modal(widget));
+
+        view.queue_event(press);
+        view.queue_event(release);
+        view.handle().update(-1.0);
+        assert!(!view.handle().widget(widget).clicked());
+        assert!(view.handle().widget(popup).clicked());
+        assert_eq!(view.handle().widget(popup).last_press_pos, Coord { x: 10., y: 10. });
+
+        view.queue_event(Event {
+            data: EventType::KeyRelease(Key {
+                key: KeyVal::Character(' '),
+                modifiers: Modifiers::default(),
+                code: 0
+            }),
+            context: EventContext::default()
+        });
+        view.handle().update(-1.0);
+        assert!(!view.handle().widget(widget).clicked());
+        assert!(view.handle().widget(popup).clicked());
+
+        view.handle().release_modal();
+        assert!(!view.handle().is_modal(popup));
+        view.queue_event(press);
+        view.queue_event(release);
+        view.handle().update(-1.0);
+        assert!(view.handle().widget(widget).clicked());
+        assert!(!view.handle().widget(popup).clicked());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn removing_capturing_widget_keeps_modal() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let press = Event {
+            data: EventType::MouseButtonPress(MouseButton { num: 1, modifiers: Modifiers::default() }),
+            context: EventContext { pos: Coord { x: 10., y: 10. }, ..Default::default() }
+        };
+
+        let ui = view.handle();
+        let page = ui.new_layouter::<VerticalLayouter>();
+        let other_page = ui.new_layouter::<VerticalLayouter>();
+        let button = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 50., h: 20. },
+            ..Default::default()
+        }));
+        let popup = ui.new_widget(Box::new(RectWidget::default()));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.layouter(page).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(button, page, StackDirection::Back);
+        ui.swap_root_layout(page);
+        ui.do_layout();
+
+        view.queue_event(press);
+        view.handle().update(-1.0);
+
+        let ui = view.handle();
+        ui.set_modal(popup);
+        ui.swap_root_layout(other_page);
+        ui.remove_widget(page.widget());
+        assert!(!ui.is_alive(button));
+        assert!(ui.is_modal(popup));
+
+        ui.remove_widget(popup);
+        assert!(!ui.is_modal(popup));
+    }
+
+    #[derive(Default)]
+    struct DragWidget {
+        stub: WidgetStub,
+        source: bool,
+        dropped: Vec<String>,
+        clicks: usize
+    }
+
+    impl Widget for DragWidget {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 20., h: 20. } }
+        fn clicked(&mut self) {
+            self.clicks += 1;
+        }
+        fn drag_data(&mut self) -> Option<Box<dyn std::any::Any>> {
+            if self.source {
+                Some(Box::new(String::from("preset")))
+            } else {
+                None
+            }
+        }
+        fn accepts_drop(&self, data: &dyn std::any::Any) -> bool {
+            !self.source && data.is::<String>()
+        }
+        fn dropped(&mut self, data: Box<dyn std::any::Any>) {
+            if let Ok(text) = data.downcast::<String>() {
+                self.dropped.push(*text);
+            }
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn drag_and_drop() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let at = |data, y| Event {
+            data,
+            context: EventContext { pos: Coord { x: 10., y }, ..Default::default() }
+        };
+        let button = MouseButton { num: 1, modifiers: Modifiers::default() };
+        let escape = Key { key: KeyVal::Character('\u{1b}'), modifiers: Modifiers::default(), code: 0 };
+
+        let ui = view.handle();
+        let source = ui.new_widget(Box::new(DragWidget { source: true, ..Default::default() }));
+        let target = ui.new_widget(Box::new(DragWidget::default()));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(source, ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(target, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        view.queue_event(at(EventType::MouseButtonPress(button), 10.));
+        view.queue_event(at(EventType::MouseMove(MotionContext::default()), 30.));
+        view.queue_event(at(EventType::MouseButtonRelease(button), 30.));
+        view.handle().update(-1.0);
+        assert_eq!(view.handle().widget(target).dropped, vec!["preset"]);
+        assert_eq!(view.handle().widget(source).clicks, 0);
+        assert_eq!(view.handle().widget(target).clicks, 0);
+
+        view.queue_event(at(EventType::MouseButtonPress(button), 10.));
+        view.queue_event(at(EventType::MouseMove(MotionContext::default()), 12.));
+        view.queue_event(at(EventType::MouseButtonRelease(button), 10.));
+        view.handle().update(-1.0);
+        assert_eq!(view.handle().widget(target).dropped.len(), 1);
+        assert_eq!(view.handle().widget(source).clicks, 0);
+
+        view.queue_event(at(EventType::MouseButtonPress(button), 10.));
+        view.queue_event(at(EventType::MouseMove(MotionContext::default()), 30.));
+        view.queue_event(at(EventType::KeyPress(escape), 30.));
+        view.queue_event(at(EventType::MouseButtonRelease(button), 30.));
+        view.handle().update(-1.0);
+        assert_eq!(view.handle().widget(target).dropped.len(), 1);
+        assert_eq!(view.handle().widget(target).clicks, 0);
+
+        view.queue_event(at(EventType::MouseButtonPress(button), 30.));
+        view.queue_event(at(EventType::MouseButtonRelease(button), 30.));
+        view.handle().update(-1.0);
+        assert_eq!(view.handle().widget(target).clicks, 1);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn host_drawn_regions() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 2.));
+        let ui = view.handle();
+
+        let widget = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 40., h: 10. },
+            ..Default::default()
+        }));
+        let region = ui.new_widget(Box::new(HostRegion::new(Size { w: 20., h: 30. }, (true, false))));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(region, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        let expected = Layout { pos: Coord { x: 0., y: 20. }, size: Size { w: 80., h: 60. } };
+        assert_eq!(ui.host_regions(), vec![expected]);
+        assert_eq!(ui.host_region(region), Some(expected));
+        assert!(!ui.widget(widget).host_drawn());
+
+        ui.set_visible(region, false);
+        assert_eq!(ui.host_regions(), vec![]);
+        assert_eq!(ui.host_region(region), None);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn scroll_offered_to_scroll_containers() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let scroll = Event {
+            data: EventType::Scroll(Scroll { dx: 0., dy: 1., modifiers: Modifiers::default() }),
+            context: EventContext { pos: Coord { x: 10., y: 10. }, ..Default::default() }
+        };
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 50., h: 20. },
+            ..Default::default()
+        }));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        // the root widget filters every event before the event path
+        view.queue_event(scroll);
+        view.handle().update(-1.0);
+        assert_eq!(view.handle().root_widget().scrolls, 1);
+
+        view.handle().root_widget().scroll_container = true;
+        view.queue_event(scroll);
+        view.handle().update(-1.0);
+        assert_eq!(view.handle().root_widget().scrolls, 3);
+    }
+
+    #[derive(Default)]
+    struct SlowWidget {
+        stub: WidgetStub
+    }
+
+    impl Widget for SlowWidget {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 10., h: 10. } }
+        fn exposed(&mut self, _ctx: &PaintContext, _cr: &cairo::Context) {
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+    }
+
+    #[cfg(all(feature = "testing", feature = "frame-stats"))]
+    #[test]
+    fn frame_stats_counted() {
+        use std::time::Duration;
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let fast = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 10., h: 10. },
+            ..Default::default()
+        }));
+        let slow = ui.new_widget(Box::new(SlowWidget::default()));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(fast, ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(slow, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+        assert!(ui.frame_stats().widget_exposes.is_empty());
+
+        view.queue_event(Event {
+            data: EventType::MouseButtonPress(MouseButton { num: 1, modifiers: Modifiers::default() }),
+            context: EventContext { pos: Coord { x: 5., y: 5. }, ..Default::default() }
+        });
+        view.handle().update(-1.0);
+
+        let ui = view.handle();
+        let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, 10, 20).unwrap();
+        let cr = cairo::Context::new(&surface);
+        let expose = ExposeArea { pos: Coord { x: 0., y: 0. }, size: Size { w: 10., h: 20. } };
+        ui.exposed(&expose, &cr);
+
+        let stats = ui.frame_stats().clone();
+        assert!(stats.layouting > Duration::default());
+        assert!(stats.event_dispatch > Duration::default());
+        let ids: Vec<Id> = stats.widget_exposes.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![0, fast.id(), slow.id()]);
+        assert!(stats.widget_exposes[2].1 >= Duration::from_millis(2));
+        assert!(stats.exposing >= stats.widget_exposes.iter().map(|(_, t)| *t).sum());
+
+        ui.exposed(&expose, &cr);
+        let stats = ui.frame_stats();
+        assert_eq!(stats.layouting, Duration::default());
+        assert_eq!(stats.event_dispatch, Duration::default());
+        assert_eq!(stats.widget_exposes.len(), 3);
+    }
+
+    #[cfg(all(feature = "testing", feature = "widget-stats"))]
+    #[test]
+    fn widget_stats_counted() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let widget = ui.new_named_widget("gain", Box::new(RectWidget {
+            min_size: Size { w: 50., h: 20. },
+            ..Default::default()
+        }));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+        assert_eq!(ui.widget_stats(widget), WidgetStats::default());
+
+        view.queue_event(Event {
+            data: EventType::MouseButtonPress(MouseButton { num: 1, modifiers: Modifiers::default() }),
+            context: EventContext { pos: Coord { x: 10., y: 10. }, ..Default::default() }
+        });
+        view.queue_event(Event {
+            data: EventType::KeyPress(Key { key: KeyVal::Character('x'), modifiers: Modifiers::default(), code: 0 }),
+            context: EventContext { pos: Coord { x: 10., y: 10. }, ..Default::default() }
+        });
+        view.handle().update(-1.0);
+
+        let ui = view.handle();
+        let stats = ui.widget_stats(widget);
+        assert_eq!(stats.events_received, 2);
+        assert_eq!(stats.events_processed, 1);
+        assert!(ui.dump_widget_stats().lines().any(|l| l.starts_with("gain ")));
+
+        ui.reset_widget_stats();
+        assert_eq!(ui.widget_stats(widget), WidgetStats::default());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn text_input_routed_to_focused_entry() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let key_press = |c| Event {
+            data: EventType::KeyPress(Key { key: KeyVal::Character(c), modifiers: Modifiers::default(), code: 0 }),
+            context: EventContext::default()
+        };
+
+        let ui = view.handle();
+        let entry = ui.new_widget(Box::new(TextEntry::default()));
+        ui.pack_to_layout(entry, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+        ui.focus_widget(entry);
+
+        view.queue_event(key_press('q'));
+        view.queue_event(key_press('\t'));
+        view.handle().update(-1.0);
+
+        let ui = view.handle();
+        assert_eq!(ui.widget(entry).text.text(), "q");
+        assert!(!ui.root_widget().wants_quit());
+        assert!(ui.root_widget().focus_next);
+
+        ui.set_clipboard_text(String::from("gain"));
+        assert_eq!(ui.clipboard_text(), Some(String::from("gain")));
+        ui.widget(entry).copy_to_clipboard(String::from("level"));
+        assert_eq!(ui.widget(entry).clipboard_request(), Some(String::from("level")));
+        assert_eq!(ui.widget(entry).clipboard_request(), None);
+    }
+
+    #[derive(Default)]
+    struct SwipeWidget {
+        stub: WidgetStub,
+        gestures: Vec<crate::gestures::PointerGesture>
+    }
+
+    impl Widget for SwipeWidget {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 200., h: 50. } }
+        fn recognizes_gestures(&self) -> bool { true }
+        fn pointer_gesture(&mut self, gesture: crate::gestures::PointerGesture) {
+            self.gestures.push(gesture);
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn pointer_gestures_recognized() {
+        use crate::gestures::*;
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        let widget = ui.new_widget(Box::new(SwipeWidget::default()));
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        let at = |data, x| Event {
+            data,
+            context: EventContext { pos: Coord { x, y: 20. }, ..Default::default() }
+        };
+        let button = MouseButton { num: 1, modifiers: Modifiers::default() };
+
+        view.queue_event(at(EventType::MouseButtonPress(button), 20.));
+        view.handle().update(-1.0);
+        view.handle().timer_event(LONG_PRESS_TIMER);
+        view.queue_event(at(EventType::MouseButtonRelease(button), 20.));
+        view.handle().update(-1.0);
+        assert_eq!(std::mem::take(&mut view.handle().widget(widget).gestures),
+                   vec![PointerGesture::LongPress { pos: Coord { x: 20., y: 20. } }]);
+
+        view.queue_event(at(EventType::MouseButtonPress(button), 20.));
+        view.queue_event(at(EventType::MouseMove(MotionContext::default()), 80.));
+        view.queue_event(at(EventType::MouseMove(MotionContext::default()), 140.));
+        view.queue_event(at(EventType::MouseButtonRelease(button), 180.));
+        view.handle().update(-1.0);
+        view.handle().timer_event(LONG_PRESS_TIMER);
+        let gestures = std::mem::take(&mut view.handle().widget(widget).gestures);
+        assert_eq!(gestures.len(), 1);
+        assert!(matches!(gestures[0], PointerGesture::Swipe { direction: SwipeDirection::Right, .. }));
+    }
+
+    #[derive(Default)]
+    struct MirroredWidget {
+        stub: WidgetStub,
+        presses: Vec<Coord>
+    }
+
+    impl Widget for MirroredWidget {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 100., h: 50. } }
+        fn transform(&self) -> Option<Transform> {
+            Some(Transform { mirror_horizontally: true, ..Default::default() })
+        }
+        fn event(&mut self, ev: Event) -> Option<Event> {
+            if let EventType::MouseButtonPress(_) = ev.data {
+                self.presses.push(ev.pos());
+                return None;
+            }
+            Some(ev)
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn transformed_widget_events() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        let widget = ui.new_widget(Box::new(MirroredWidget::default()));
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        let button = MouseButton { num: 1, modifiers: Modifiers::default() };
+        view.queue_event(Event {
+            data: EventType::MouseButtonPress(button),
+            context: EventContext { pos: Coord { x: 10., y: 20. }, ..Default::default() }
+        });
+        view.handle().update(-1.0);
+        assert_eq!(view.handle().widget(widget).presses, vec![Coord { x: 90., y: 20. }]);
+    }
+
+    #[derive(Default)]
+    struct ScrollList {
+        stub: WidgetStub,
+        offset: f64,
+        drag: Option<(f64, f64)>
+    }
+
+    impl Widget for ScrollList {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 100., h: 200. } }
+        fn scroll_container(&self) -> bool { true }
+        fn kinetic_scrolling(&self) -> bool { true }
+        fn scroll_position(&self) -> Option<Coord> { Some(Coord { x: 0., y: self.offset }) }
+        fn set_scroll_position(&mut self, pos: Coord) {
+            self.offset = pos.y.max(0.).min(1000.);
+        }
+        fn event(&mut self, ev: Event) -> Option<Event> {
+            match ev.data {
+                EventType::MouseButtonPress(_) => self.drag = Some((ev.pos().y, self.offset)),
+                EventType::MouseButtonRelease(_) => self.drag = None,
+                EventType::MouseMove(_) => if let Some((start, offset)) = self.drag {
+                    self.set_scroll_position(Coord { x: 0., y: offset + start - ev.pos().y });
+                }
+                _ => return Some(ev)
+            }
+            None
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn kinetic_scrolling() {
+        use crate::kinetic::KINETIC_TIMER;
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        let list = ui.new_widget(Box::new(ScrollList::default()));
+        ui.pack_to_layout(list, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        let at = |data, y| Event {
+            data,
+            context: EventContext { pos: Coord { x: 50., y }, ..Default::default() }
+        };
+        let button = MouseButton { num: 1, modifiers: Modifiers::default() };
+        view.handle().set_motion_coalescing(false);
+        view.queue_event(at(EventType::MouseButtonPress(button), 150.));
+        for y in [130., 110., 90.].iter() {
+            view.queue_event(at(EventType::MouseMove(MotionContext::default()), *y));
+        }
+        view.queue_event(at(EventType::MouseButtonRelease(button), 90.));
+        view.handle().update(-1.0);
+        assert_eq!(view.handle().widget(list).offset, 60.);
+
+        view.handle().timer_event(KINETIC_TIMER);
+        let offset = view.handle().widget(list).offset;
+        assert!(offset > 60. && offset <= 1000.);
+
+        view.queue_event(at(EventType::MouseButtonPress(button), 150.));
+        view.handle().update(-1.0);
+        view.handle().timer_event(KINETIC_TIMER);
+        assert_eq!(view.handle().widget(list).offset, offset);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn event_filters() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        let first = ui.new_widget(Box::new(RectWidget { min_size: Size { w: 50., h: 20. }, ..Default::default() }));
+        let second = ui.new_widget(Box::new(RectWidget { min_size: Size { w: 50., h: 20. }, ..Default::default() }));
+        ui.pack_to_layout(first, ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(second, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        let click = |data| Event {
+            data,
+            context: EventContext { pos: Coord { x: 10., y: 10. }, ..Default::default() }
+        };
+        let press = click(EventType::MouseButtonPress(MouseButton { num: 1, modifiers: Modifiers::default() }));
+        let release = click(EventType::MouseButtonRelease(MouseButton { num: 1, modifiers: Modifiers::default() }));
+
+        let consume = ui.add_event_filter(Box::new(|ev| match ev.data {
+            EventType::MouseButtonRelease(_) => FilterResult::Consume,
+            _ => FilterResult::Pass
+        }));
+        view.queue_event(press);
+        view.queue_event(release);
+        view.handle().update(-1.0);
+        assert!(!view.handle().widget(first).clicked());
+
+        let ui = view.handle();
+        assert!(ui.remove_event_filter(consume));
+        assert!(!ui.remove_event_filter(consume));
+        ui.add_event_filter(Box::new(|ev| FilterResult::Replace(Event {
+            context: EventContext { pos: Coord { x: 10., y: 30. }, ..ev.context },
+            ..*ev
+        })));
+        view.queue_event(press);
+        view.queue_event(release);
+        view.handle().update(-1.0);
+        assert!(!view.handle().widget(first).clicked());
+        assert!(view.handle().widget(second).clicked());
+
+        view.handle().add_event_filter(Box::new(move |_| FilterResult::redirect(first)));
+        view.queue_event(release);
+        view.handle().update(-1.0);
+        assert!(view.handle().widget(first).clicked());
+        assert!(!view.handle().widget(second).clicked());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn group_handles() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let attack = ui.new_widget(Box::new(RectWidget { min_size: Size { w: 20., h: 20. }, ..Default::default() }));
+        let release = ui.new_widget(Box::new(RectWidget { min_size: Size { w: 20., h: 20. }, ..Default::default() }));
+        ui.pack_to_layout(attack, ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(release, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        assert!(ui.group("envelope").is_none());
+        let envelope = ui.new_group("envelope");
+        assert_eq!(envelope.name(), "envelope");
+        assert_eq!(ui.group("envelope"), Some(envelope.clone()));
+        assert!(ui.group_members(&envelope).is_empty());
+
+        ui.add_to_group(&envelope, attack);
+        ui.add_to_group("envelope", release);
+        assert_eq!(ui.group_members(&envelope), vec![attack.id(), release.id()]);
+
+        ui.set_group_sensitive(&envelope, false);
+        assert!(!ui.widget(attack).is_sensitive());
+        assert!(!ui.widget(release).is_sensitive());
+        ui.redisplay_group(&envelope);
+
+        ui.remove_from_group(&envelope, attack);
+        ui.set_group_sensitive(&envelope, true);
+        assert!(!ui.widget(attack).is_sensitive());
+        assert!(ui.widget(release).is_sensitive());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn spatial_focus_navigation() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        let mut cells = Vec::new();
+        for _ in 0..2 {
+            let row = ui.new_layouter::<HorizontalLayouter>();
+            ui.pack_to_layout(row.widget(), ui.root_layout(), StackDirection::Back);
+            for _ in 0..2 {
+                let cell = ui.new_widget(Box::new(RectWidget {
+                    min_size: Size { w: 40., h: 20. },
+                    ..Default::default()
+                }));
+                ui.pack_to_layout(cell, row, StackDirection::Back);
+                cells.push(cell);
+            }
+        }
+        ui.do_layout();
+        ui.focus_widget(cells[0]);
+
+        assert!(!ui.focus_direction(FocusDirection::Up));
+        assert!(!ui.focus_direction(FocusDirection::Left));
+        assert!(ui.focus_direction(FocusDirection::Right));
+        assert!(ui.widget(cells[1]).has_focus());
+        assert!(ui.focus_direction(FocusDirection::Down));
+        assert!(ui.widget(cells[3]).has_focus());
+        assert!(!ui.widget(cells[1]).has_focus());
+
+        let arrow = |sk| Event {
+            data: EventType::KeyPress(Key { key: KeyVal::Special(sk), modifiers: Modifiers::default(), code: 0 }),
+            context: EventContext::default()
+        };
+        view.queue_event(arrow(SpecialKey::Left));
+        view.handle().update(-1.0);
+        assert!(view.handle().widget(cells[3]).has_focus());
+
+        view.handle().set_arrow_key_navigation(true);
+        view.handle().set_focus_overlay(true);
+        view.queue_event(arrow(SpecialKey::Left));
+        view.queue_event(arrow(SpecialKey::Up));
+        view.handle().update(-1.0);
+        assert!(view.handle().widget(cells[0]).has_focus());
+        assert!(view.handle().focus_visible());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn scripted_interaction() {
+        use crate::script::EventScript;
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let ui = view.handle();
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        let button = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 50., h: 20. },
+            ..Default::default()
+        }));
+        let entry = ui.new_widget(Box::new(TextEntry::default()));
+        ui.pack_to_layout(button, ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(entry, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+
+        EventScript::new().click_at(Coord { x: 10., y: 10. }).play(&mut view);
+        assert!(view.handle().widget(button).clicked());
+
+        view.handle().focus_widget(entry);
+        EventScript::new().type_text("440").play(&mut view);
+        assert_eq!(view.handle().widget(entry).text.text(), "440");
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn events_recorded_and_replayed() {
+        use crate::recording::EventRecording;
+        let new_view = || {
+            let rw = Box::new(RootWidget::default());
+            let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+            let ui = view.handle();
+            let button = ui.new_widget(Box::new(RectWidget {
+                min_size: Size { w: 50., h: 20. },
+                ..Default::default()
+            }));
+            ui.pack_to_layout(button, ui.root_layout(), StackDirection::Back);
+            ui.do_layout();
+            (view, button)
+        };
+        let click = |data| Event {
+            data,
+            context: EventContext { pos: Coord { x: 20., y: 15. }, ..Default::default() }
+        };
+
+        let (mut view, button) = new_view();
+        assert!(view.handle().stop_recording().is_none());
+        view.handle().start_recording();
+        assert!(view.handle().is_recording());
+        view.queue_event(click(EventType::MouseButtonPress(MouseButton { num: 1, modifiers: Modifiers::default() })));
+        view.queue_event(click(EventType::MouseButtonRelease(MouseButton { num: 1, modifiers: Modifiers::default() })));
+        view.handle().update(-1.0);
+        assert!(view.handle().widget(button).clicked());
+
+        let recording = view.handle().stop_recording().expect("no recording");
+        assert!(!view.handle().is_recording());
+        assert_eq!(recording.events().len(), 2);
+
+        let mut file = Vec::new();
+        recording.write_to(&mut file).unwrap();
+        let recording = EventRecording::read_from(file.as_slice()).unwrap();
+
+        let (mut view, button) = new_view();
+        recording.replay(&mut view, false);
+        assert!(view.handle().widget(button).clicked());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn widget_constructed_with_context() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.5));
+        let ui = view.handle();
+
+        let widget = ui.new_widget_with_ctx(|ctx| {
+            assert_eq!(ctx.scale_factor(), 1.5);
+            Box::new(RectWidget {
+                min_size: Size { w: 10. * ctx.scale_factor(), h: 10. },
+                ..Default::default()
+            })
+        });
+        assert_eq!(ui.widget(widget).min_size(), Size { w: 15., h: 10. });
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn close_request_veto() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+        let ui = view.handle();
+
+        ui.set_close_veto(2);
+        ui.close_request();
+        assert!(ui.close_request_pending());
+        assert!(!ui.close_request_issued());
+        ui.veto_close();
+        assert!(!ui.close_request_pending());
+        assert!(!ui.close_request_issued());
+
+        ui.close_request();
+        ui.confirm_close();
+        assert!(!ui.close_request_pending());
+        assert!(ui.close_request_issued());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn unanswered_close_request_confirmed() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+        let ui = view.handle();
+
+        ui.set_close_veto(2);
+        ui.close_request();
+        ui.next_event(0.0);
+        ui.next_event(0.0);
+        assert!(ui.close_request_pending());
+        ui.next_event(0.0);
+        assert!(!ui.close_request_pending());
+        assert!(ui.close_request_issued());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_clipboard_accessors() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+        let ui = view.handle();
+
+        assert_eq!(ui.clipboard(), "");
+        ui.set_clipboard("preset");
+        assert_eq!(ui.clipboard(), "preset");
+        assert_eq!(ui.clipboard_text(), Some(String::from("preset")));
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn adaptive_timeout() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+        let ui = view.handle();
+
+        assert_eq!(ui.auto_timeout(), -1.0);
+
+        ui.defer(|_ui| {});
+        assert_eq!(ui.auto_timeout(), 0.0);
+        ui.next_event_auto();
+        assert_eq!(ui.auto_timeout(), -1.0);
+
+        ui.set_idle_policy(IdlePolicy::NonBlocking);
+        assert_eq!(ui.auto_timeout(), 0.0);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn high_contrast_mode() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+        let ui = view.handle();
+        let buffered = ui.new_widget(Box::new(BufferedWidget::default()));
+        let cached = ui.new_widget(Box::new(CachedWidget::default()));
+        let highlighted = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 20., h: 20. },
+            ..Default::default()
+        }));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(buffered, ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(cached, ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(highlighted, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+        ui.set_highlighted(highlighted, true);
+
+        let mut surface = cairo::ImageSurface::create(cairo::Format::ARgb32, 20, 40).unwrap();
+        let expose = ExposeArea { pos: Coord { x: 0., y: 0. }, size: Size { w: 20., h: 40. } };
+        let mut draw = |ui: &mut UI<RootWidget>| {
+            {
+                let cr = cairo::Context::new(&surface);
+                ui.exposed(&expose, &cr);
+            }
+            let stride = surface.get_stride() as usize;
+            let data = surface.get_data().expect("surface data borrowed");
+            let p = &data[30 * stride + 4..30 * stride + 8];
+            (u32::from_ne_bytes([p[0], p[1], p[2], p[3]]), ui.widget(buffered).exposes, ui.widget(cached).exposes)
+        };
+
+        assert!(!ui.high_contrast());
+        assert_eq!(draw(ui), (0xffff9900, 1, 1));
+        assert_eq!(draw(ui), (0xffff9900, 1, 1));
+
+        ui.set_high_contrast(true);
+        assert!(ui.high_contrast());
+        assert_eq!(draw(ui), (0xffffff00, 2, 2));
+        assert_eq!(draw(ui), (0xffffff00, 2, 2));
+
+        let theme = Theme::default();
+        assert_eq!(theme.tooltip_colors(true), ((0., 0., 0.), (1., 1., 1.)));
+        assert_eq!(theme.tooltip_colors(false), (theme.tooltip_background, theme.tooltip_foreground));
+
+        ui.set_high_contrast(false);
+        assert!(!ui.high_contrast());
+        assert_eq!(draw(ui), (0xffff9900, 3, 3));
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn two_widgets_focus() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let space_event = Event {
+            data: EventType::KeyRelease(Key {
+                key: KeyVal::Character(' '),
+                modifiers: Modifiers::default(),
+                code: 0
+            }),
+            context: EventContext::default()
+        };
+
+        view.queue_event(space_event);
+        view.queue_event(space_event);
+        view.queue_event(space_event);
+
+        let ui = view.handle();
+        let widget_1 = ui.new_widget(Box::new(RectWidget::default()));
+        let widget_2 = ui.new_widget(Box::new(RectWidget::default()));
+
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(10.);
+
+        assert!(!ui.widget(widget_1).clicked());
+        assert!(!ui.widget(widget_2).clicked());
+
+        ui.update(-1.0);
+        assert!(!ui.widget(widget_1).clicked());
+        assert!(!ui.widget(widget_2).clicked());
+
+        ui.focus_next_widget();
+        ui.update(-1.0);
+        assert!(ui.widget(widget_1).clicked());
+        assert!(!ui.widget(widget_2).clicked());
+
+        ui.focus_next_widget();
+        ui.update(-1.0);
+        assert!(!ui.widget(widget_1).clicked());
+        assert!(ui.widget(widget_2).clicked());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn layout_hover_and_focus_queries() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        view.queue_event(Event {
+            data: EventType::MouseMove(MotionContext::default()),
+            context: EventContext { pos: Coord{ x: 21., y: 11.5 }, ..Default::default() }
+        });
+
+        let widget_size = Size { w: 42., h: 23. };
+
+        let ui = view.handle();
+        let strip_1 = ui.new_layouter::<VerticalLayouter>();
+        let strip_2 = ui.new_layouter::<VerticalLayouter>();
+        let widget_1 = ui.new_widget(Box::new(RectWidget {
+            min_size: widget_size,
+            ..Default::default()
+        }));
+        let widget_2 = ui.new_widget(Box::new(RectWidget {
+            min_size: widget_size,
+            ..Default::default()
+        }));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.layouter(strip_1).set_padding(0.).set_spacing(0.);
+        ui.layouter(strip_2).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(strip_1.widget(), ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(strip_2.widget(), ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(widget_1, strip_1, StackDirection::Back);
+        ui.pack_to_layout(widget_2, strip_2, StackDirection::Back);
+        ui.do_layout();
+
+        assert!(!ui.is_any_child_hovered(strip_1));
+        ui.update(-1.0);
+        assert!(ui.is_any_child_hovered(strip_1));
+        assert!(!ui.is_any_child_hovered(strip_2));
+        assert!(ui.is_any_child_hovered(ui.root_layout()));
+
+        assert!(!ui.contains_focus(strip_2));
+        ui.focus_widget(widget_2);
+        assert!(ui.contains_focus(strip_2));
+        assert!(!ui.contains_focus(strip_1));
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn local_event_coordinates() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let press_event = |x, y| Event {
+            data: EventType::MouseButtonPress(MouseButton { num: 1, modifiers: Modifiers::default() }),
+            context: EventContext { pos: Coord{ x, y }, ..Default::default() }
+        };
+        view.queue_event(press_event(21., 34.));
+        view.queue_event(press_event(21., 11.));
+
+        let widget_size = Size { w: 42., h: 23. };
+
+        let ui = view.handle();
+        let widget_1 = ui.new_widget(Box::new(RectWidget {
+            min_size: widget_size,
+            local_coordinates: true,
+            ..Default::default()
+        }));
+        let widget_2 = ui.new_widget(Box::new(RectWidget {
+            min_size: widget_size,
+            ..Default::default()
+        }));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(widget_2, ui.root_layout(), StackDirection::Back);
+        ui.pack_to_layout(widget_1, ui.root_layout(), StackDirection::Back);
+        ui.do_layout();
+        ui.update(-1.0);
+
+        assert_eq!(ui.widget(widget_1).last_press_pos, Coord { x: 21., y: 11. });
+        assert_eq!(ui.widget(widget_2).last_press_pos, Coord { x: 21., y: 11. });
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn two_widgets_hover() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let widget_1_hover_event = Event {
+            data: EventType::MouseMove(MotionContext::default()),
+            context: EventContext { pos: Coord{ x: 21., y: 36. }, ..Default::default() }
+        };
+        let widget_2_hover_event = Event {
+            data: EventType::MouseMove(MotionContext::default()),
+            context: EventContext { pos: Coord{ x: 21., y: 11.5 }, ..Default::default() }
+        };
+
+        view.queue_event(widget_1_hover_event);
+        view.queue_event(widget_2_hover_event);
+        view.queue_event(widget_1_hover_event);
+
+        let widget_size = Size { w: 42., h: 23. };
+
+        let ui = view.handle();
+        let widget_1 = ui.new_widget(Box::new(RectWidget {
+            min_size: widget_size,
+            ..Default::default()
+        }));
+        let widget_2 = ui.new_widget(Box::new(RectWidget {
+            min_size: widget_size,
+            ..Default::default()
+        }));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(widget_1, ui.root_layout(), StackDirection::Front);
+        ui.pack_to_layout(widget_2, ui.root_layout(), StackDirection::Front);
+        ui.do_layout();
+        ui.fit_window_size();
+        ui.fit_window_min_size();
+        ui.show_window();
+
+        assert!(!ui.widget(widget_1).is_hovered());
+        assert!(!ui.widget(widget_1).pointer_in());
+        assert!(!ui.widget(widget_2).is_hovered());
+        assert!(!ui.widget(widget_2).pointer_in());
+
+        ui.update(1.0);
+
+        assert!(ui.widget(widget_1).is_hovered());
+        assert!(ui.widget(widget_1).pointer_in());
+        assert!(!ui.widget(widget_2).is_hovered());
+        assert!(!ui.widget(widget_2).pointer_in());
+
+        ui.update(1.0);
+
+        assert!(!ui.widget(widget_1).is_hovered());
+        assert!(!ui.widget(widget_1).pointer_in());
+        assert!(ui.widget(widget_2).is_hovered());
+        assert!(ui.widget(widget_2).pointer_in());
+
+        ui.update(1.0);
+
+        assert!(ui.widget(widget_1).is_hovered());
+        assert!(ui.widget(widget_1).pointer_in());
+        assert!(!ui.widget(widget_2).is_hovered());
+        assert!(!ui.widget(widget_2).pointer_in());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn motion_events_coalesced() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let motion = Event {
+            data: EventType::MouseMove(MotionContext::default()),
+            context: EventContext { pos: Coord{ x: 21., y: 11.5 }, ..Default::default() }
+        };
+        let key = Event {
+            data: EventType::KeyRelease(Key {
+                key: KeyVal::Character('x'),
+                modifiers: Modifiers::default(),
+                code: 0
+            }),
+            context: EventContext::default()
+        };
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 42., h: 23. },
+            ..Default::default()
+        }));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Front);
+        ui.do_layout();
+
+        view.queue_event(motion);
+        view.queue_event(motion);
+        view.queue_event(motion);
+        view.handle().next_event(-1.0);
+        assert_eq!(view.handle().widget(widget).motion_events, 1);
+
+        view.queue_event(motion);
+        view.queue_event(motion);
+        view.queue_event(key);
+        view.queue_event(motion);
+        view.handle().next_event(-1.0);
+        assert_eq!(view.handle().widget(widget).motion_events, 3);
+
+        view.handle().widget(widget).wants_all_motion_events = true;
+        view.queue_event(motion);
+        view.queue_event(motion);
+        view.handle().next_event(-1.0);
+        assert_eq!(view.handle().widget(widget).motion_events, 5);
+
+        view.handle().widget(widget).wants_all_motion_events = false;
+        view.handle().set_motion_coalescing(false);
+        view.queue_event(motion);
+        view.queue_event(motion);
+        view.handle().next_event(-1.0);
+        assert_eq!(view.handle().widget(widget).motion_events, 7);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn tooltip_shown_after_delay_hidden_on_motion() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let hover_event = Event {
+            data: EventType::MouseMove(MotionContext::default()),
+            context: EventContext { pos: Coord{ x: 21., y: 11.5 }, ..Default::default() }
+        };
+
+        view.queue_event(hover_event);
+        view.queue_event(hover_event);
+
+        let ui = view.handle();
+        let widget = ui.new_widget(Box::new(RectWidget {
+            min_size: Size { w: 42., h: 23. },
+            tooltip: Some("Gain"),
+            ..Default::default()
+        }));
+        ui.layouter(ui.root_layout()).set_padding(0.).set_spacing(0.);
+        ui.pack_to_layout(widget, ui.root_layout(), StackDirection::Front);
+        ui.do_layout();
+        ui.fit_window_size();
+        ui.show_window();
+
+        ui.update(-1.0);
+        assert_eq!(ui.shown_tooltip(), None);
+
+        ui.timer_event(crate::tooltip::TOOLTIP_TIMER);
+        assert_eq!(ui.shown_tooltip(), Some("Gain"));
+
+        ui.update(-1.0);
+        assert_eq!(ui.shown_tooltip(), None);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn hover_pointer_enter_leave_window() {
+        let rw = Box::new(RootWidget::default());
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, rw, 1.));
+
+        let enter_event = Event {
+            data: EventType::PointerIn,
             context: EventContext { pos: Coord{ x: 21., y: 13. }, ..Default::default() }
         };
         let leave_event = Event {
@@ -1177,6 +5070,7 @@ mod tests {
             width_expandable: true,
             height_expandable: false,
             name: "flava",
+            cursor: Some(Cursor::Hand),
             ..Default::default()
         }));
 
@@ -1291,12 +5185,6 @@ mod tests {
                 println!("Click received red widget");
             }
 
-            if ui.widget(yellow).is_hovered() {
-                ui.set_cursor(pugl_sys::Cursor::Hand);
-            } else {
-                ui.set_cursor(pugl_sys::Cursor::Arrow);
-            }
-
             if ui.root_widget().focus_next() {
                 ui.focus_next_widget();
             }
@@ -0,0 +1,246 @@
+//! Glue to embed the `UI` into LV2 hosts
+//!
+//! Only available with the feature `lv2`.
+//!
+//! An [`LV2UIWrapper`](struct.LV2UIWrapper.html) owns the view of the
+//! `UI` and drives it through the idle and the show interface of the
+//! LV2 UI extension, so the plugin GUI does not need an event loop of
+//! its own. The C structs of the interfaces are defined here, so that
+//! no LV2 binding crate is needed.
+//!
+//! ```ignore
+//! // in LV2UI_Descriptor::instantiate
+//! let mut view = PuglView::new(parent, |pv| UI::new(pv, Box::new(RootWidget::default())));
+//! build_ui(view.handle());
+//! let handle = LV2UIWrapper::new(view).into_handle();
+//!
+//! // LV2UI_Descriptor::extension_data
+//! extern "C" fn extension_data(uri: *const c_char) -> *const c_void {
+//!     unsafe { lv2::extension_data(uri) }
+//! }
+//!
+//! // LV2UI_Descriptor::cleanup
+//! extern "C" fn cleanup(handle: LV2UIHandle) {
+//!     unsafe { lv2::cleanup(handle) }
+//! }
+//! ```
+use std::any::Any;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_void};
+
+use pugl_sys::*;
+
+use crate::ui::UI;
+use crate::widget::Widget;
+
+/// The URI of the LV2 UI idle interface
+pub const LV2_UI_IDLE_INTERFACE_URI: &str = "http://lv2plug.in/ns/extensions/ui#idleInterface";
+
+/// The URI of the LV2 UI show interface
+pub const LV2_UI_SHOW_INTERFACE_URI: &str = "http://lv2plug.in/ns/extensions/ui#showInterface";
+
+/// The opaque handle of an LV2 UI instance, `LV2UI_Handle` in C
+pub type LV2UIHandle = *mut c_void;
+
+/// `LV2UI_Idle_Interface` in C
+#[repr(C)]
+pub struct LV2UIIdleInterface {
+    pub idle: unsafe extern "C" fn(LV2UIHandle) -> c_int
+}
+
+/// `LV2UI_Show_Interface` in C
+#[repr(C)]
+pub struct LV2UIShowInterface {
+    pub show: unsafe extern "C" fn(LV2UIHandle) -> c_int,
+    pub hide: unsafe extern "C" fn(LV2UIHandle) -> c_int
+}
+
+static IDLE_INTERFACE: LV2UIIdleInterface = LV2UIIdleInterface { idle: idle_callback };
+
+static SHOW_INTERFACE: LV2UIShowInterface = LV2UIShowInterface {
+    show: show_callback,
+    hide: hide_callback
+};
+
+/// The wrapper behind an `LV2UIHandle`, independent of the root widget type
+trait Wrapped {
+    fn idle(&mut self) -> c_int;
+    fn show(&mut self) -> c_int;
+    fn hide(&mut self) -> c_int;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// Drives a `UI` by the LV2 UI idle and show interfaces
+pub struct LV2UIWrapper<RW: Widget + 'static> {
+    view: Box<PuglView<UI<RW>>>,
+    idle_hook: Option<Box<dyn FnMut(&mut UI<RW>)>>
+}
+
+impl<RW: Widget + 'static> LV2UIWrapper<RW> {
+    /// Wraps the view `view` of a `UI`.
+    pub fn new(view: Box<PuglView<UI<RW>>>) -> LV2UIWrapper<RW> {
+        LV2UIWrapper { view, idle_hook: None }
+    }
+
+    /// Returns the `UI`.
+    pub fn ui(&mut self) -> &mut UI<RW> {
+        self.view.handle()
+    }
+
+    /// Sets the application logic called after each idle cycle, for
+    /// example to write the values of changed widgets to the plugin's
+    /// control ports.
+    pub fn set_idle_hook<F: FnMut(&mut UI<RW>) + 'static>(&mut self, hook: F) {
+        self.idle_hook = Some(Box::new(hook));
+    }
+
+    /// Processes the pending events without blocking, as the host
+    /// calls it periodically.
+    ///
+    /// Returns `1` if the window has been closed and `0` otherwise,
+    /// as expected from `LV2UI_Idle_Interface::idle()`.
+    pub fn idle(&mut self) -> c_int {
+        let ui = self.view.handle();
+        ui.next_event(0.0);
+        if let Some(hook) = self.idle_hook.as_mut() {
+            hook(ui);
+        }
+        if ui.close_request_issued() { 1 } else { 0 }
+    }
+
+    /// Shows the window, as `LV2UI_Show_Interface::show()`.
+    pub fn show(&mut self) -> c_int {
+        let ui = self.view.handle();
+        ui.fit_window_size();
+        ui.show_window();
+        0
+    }
+
+    /// Hides the window, as `LV2UI_Show_Interface::hide()`.
+    pub fn hide(&mut self) -> c_int {
+        self.view.handle().hide_window();
+        0
+    }
+
+    /// Turns the wrapper into the handle to return from
+    /// `LV2UI_Descriptor::instantiate()`.
+    ///
+    /// The handle must be released by [`cleanup()`](fn.cleanup.html).
+    pub fn into_handle(self) -> LV2UIHandle {
+        let wrapped: Box<dyn Wrapped> = Box::new(self);
+        Box::into_raw(Box::new(wrapped)) as LV2UIHandle
+    }
+
+    /// Returns the wrapper behind `handle`, for example in
+    /// `LV2UI_Descriptor::port_event()`.
+    ///
+    /// Returns `None` if the root widget of the wrapped `UI` is not of
+    /// type `RW`.
+    ///
+    /// # Safety
+    ///
+    /// `handle` must have been returned by
+    /// [`into_handle()`](#method.into_handle) and must not have been
+    /// released yet.
+    pub unsafe fn from_handle<'a>(handle: LV2UIHandle) -> Option<&'a mut LV2UIWrapper<RW>> {
+        wrapped(handle).as_any_mut().downcast_mut::<LV2UIWrapper<RW>>()
+    }
+}
+
+impl<RW: Widget + 'static> Wrapped for LV2UIWrapper<RW> {
+    fn idle(&mut self) -> c_int { LV2UIWrapper::idle(self) }
+    fn show(&mut self) -> c_int { LV2UIWrapper::show(self) }
+    fn hide(&mut self) -> c_int { LV2UIWrapper::hide(self) }
+    fn as_any_mut(&mut self) -> &mut dyn Any { self }
+}
+
+unsafe fn wrapped<'a>(handle: LV2UIHandle) -> &'a mut Box<dyn Wrapped> {
+    &mut *(handle as *mut Box<dyn Wrapped>)
+}
+
+unsafe extern "C" fn idle_callback(handle: LV2UIHandle) -> c_int {
+    wrapped(handle).idle()
+}
+
+unsafe extern "C" fn show_callback(handle: LV2UIHandle) -> c_int {
+    wrapped(handle).show()
+}
+
+unsafe extern "C" fn hide_callback(handle: LV2UIHandle) -> c_int {
+    wrapped(handle).hide()
+}
+
+/// Returns the interface for the URI `uri`, to be called from
+/// `LV2UI_Descriptor::extension_data()`.
+///
+/// Provides the idle and the show interface and returns a null
+/// pointer for any other URI.
+///
+/// # Safety
+///
+/// `uri` must point to a null terminated string.
+pub unsafe fn extension_data(uri: *const c_char) -> *const c_void {
+    if uri.is_null() {
+        return std::ptr::null();
+    }
+    match CStr::from_ptr(uri).to_str() {
+        Ok(LV2_UI_IDLE_INTERFACE_URI) => &IDLE_INTERFACE as *const LV2UIIdleInterface as *const c_void,
+        Ok(LV2_UI_SHOW_INTERFACE_URI) => &SHOW_INTERFACE as *const LV2UIShowInterface as *const c_void,
+        _ => std::ptr::null()
+    }
+}
+
+/// Releases the handle and the wrapped `UI`, to be called from
+/// `LV2UI_Descriptor::cleanup()`.
+///
+/// # Safety
+///
+/// `handle` must have been returned by
+/// [`LV2UIWrapper::into_handle()`](struct.LV2UIWrapper.html#method.into_handle)
+/// and must not be used afterwards.
+pub unsafe fn cleanup(handle: LV2UIHandle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle as *mut Box<dyn Wrapped>));
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[derive(Default)]
+    struct RootWidget {
+        stub: crate::widget::WidgetStub
+    }
+
+    impl Widget for RootWidget {
+        widget_stub!();
+        fn min_size(&self) -> Size { Size { w: 10., h: 10. } }
+    }
+
+    #[test]
+    fn interfaces() {
+        let view = PuglView::new(std::ptr::null_mut(), |pv| UI::new(pv, Box::new(RootWidget::default())));
+        let mut wrapper = LV2UIWrapper::new(view);
+        wrapper.ui().do_layout();
+        let idled = std::rc::Rc::new(std::cell::Cell::new(0));
+        let counter = idled.clone();
+        wrapper.set_idle_hook(move |_| counter.set(counter.get() + 1));
+        let handle = wrapper.into_handle();
+
+        unsafe {
+            let uri = CString::new(LV2_UI_IDLE_INTERFACE_URI).unwrap();
+            let idle = &*(extension_data(uri.as_ptr()) as *const LV2UIIdleInterface);
+            assert_eq!((idle.idle)(handle), 0);
+            assert_eq!((idle.idle)(handle), 0);
+
+            let other = CString::new("http://lv2plug.in/ns/extensions/ui#resize").unwrap();
+            assert!(extension_data(other.as_ptr()).is_null());
+
+            assert!(LV2UIWrapper::<RootWidget>::from_handle(handle).is_some());
+            cleanup(handle);
+        }
+        assert_eq!(idled.get(), 2);
+    }
+}
@@ -0,0 +1,181 @@
+//! Drawing scalable panels as widget backgrounds
+//!
+//! A panel is either a rounded rectangle filled with the background
+//! color of the widget's [`Style`](../theme/struct.Style.html), or a
+//! nine-patch image: an image of the `UI`'s
+//! [`Assets`](../assets/struct.Assets.html) sliced by
+//! [`Insets`](struct.Insets.html) into corners that keep their size,
+//! edges stretched along one axis and a center stretched along both.
+//!
+//! The [`Theme`](../theme/struct.Theme.html) determines the
+//! [`Panel`](enum.Panel.html) drawn by
+//! [`PaintContext::draw_panel()`](../widget/struct.PaintContext.html#method.draw_panel),
+//! which is also used for the backgrounds of layout widgets, see
+//! [`LayoutWidget::set_panel()`](../layout/layoutwidget/struct.LayoutWidget.html#method.set_panel).
+//! Widgets can draw nine-patches of their own images by
+//! [`PaintContext::draw_nine_patch()`](../widget/struct.PaintContext.html#method.draw_nine_patch).
+//!
+//! The sliced and stretched images are cached per size and scale
+//! factor, so a nine-patch is only composed again if the widget has
+//! been resized.
+#[cfg(feature = "cairo-helpers")]
+use cairo::ImageSurface;
+use pugl_sys::*;
+
+use crate::widget::Layout;
+
+/// The widths of the borders of a nine-patch image that are not stretched
+///
+/// The widths are unscaled like the widget geometries.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Insets {
+    pub left: f64,
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64
+}
+
+impl Insets {
+    /// Insets of the same width `width` on all sides
+    pub fn uniform(width: f64) -> Insets {
+        Insets { left: width, top: width, right: width, bottom: width }
+    }
+}
+
+/// The kind of panels drawn as backgrounds
+#[derive(Clone, Debug, PartialEq)]
+pub enum Panel {
+    /// A rectangle with the background color and the border radius
+    /// of the widget's style
+    RoundedRect,
+    /// The image `image` of the `UI`'s assets, sliced by `insets`
+    NinePatch { image: String, insets: Insets }
+}
+
+impl Default for Panel {
+    fn default() -> Panel {
+        Panel::RoundedRect
+    }
+}
+
+/// Adds a rectangle with corners rounded by `radius` to the path of `cr`.
+pub fn rounded_rect(cr: &cairo::Context, layout: Layout, radius: f64) {
+    let (x, y, w, h) = (layout.pos.x, layout.pos.y, layout.size.w, layout.size.h);
+    let r = radius.min(w / 2.).min(h / 2.).max(0.);
+    if r == 0. {
+        cr.rectangle(x, y, w, h);
+        return;
+    }
+    let quarter = std::f64::consts::FRAC_PI_2;
+    cr.new_sub_path();
+    cr.arc(x + w - r, y + r, r, -quarter, 0.);
+    cr.arc(x + w - r, y + h - r, r, 0., quarter);
+    cr.arc(x + r, y + h - r, r, quarter, 2. * quarter);
+    cr.arc(x + r, y + r, r, 2. * quarter, 3. * quarter);
+    cr.close_path();
+}
+
+#[cfg(feature = "cairo-helpers")]
+/// Composes the nine-patch of `image` rasterized at `scale_factor`
+/// stretched to the unscaled size `size`.
+pub(crate) fn nine_patch(image: &ImageSurface, insets: Insets, size: Size, scale_factor: f64) -> Option<ImageSurface> {
+    let (target_w, target_h) = ((size.w * scale_factor).round(), (size.h * scale_factor).round());
+    let surface = ImageSurface::create(cairo::Format::ARgb32, (target_w as i32).max(1), (target_h as i32).max(1)).ok()?;
+    let (image_w, image_h) = (f64::from(image.get_width()), f64::from(image.get_height()));
+    let columns = slices(image_w, target_w, insets.left * scale_factor, insets.right * scale_factor);
+    let rows = slices(image_h, target_h, insets.top * scale_factor, insets.bottom * scale_factor);
+
+    let cr = cairo::Context::new(&surface);
+    for &((sx, sw), (dx, dw)) in columns.iter() {
+        for &((sy, sh), (dy, dh)) in rows.iter() {
+            if sw <= 0. || sh <= 0. || dw <= 0. || dh <= 0. {
+                continue;
+            }
+            let slice = copy_slice(image, sx, sy, sw, sh)?;
+            cr.save();
+            cr.rectangle(dx, dy, dw, dh);
+            cr.clip();
+            cr.translate(dx, dy);
+            cr.scale(dw / sw, dh / sh);
+            cr.set_source_surface(&slice, 0., 0.);
+            cr.get_source().set_extend(cairo::Extend::Pad);
+            cr.paint();
+            cr.restore();
+        }
+    }
+    drop(cr);
+    surface.flush();
+    Some(surface)
+}
+
+#[cfg(feature = "cairo-helpers")]
+/// Copies a slice out of `image`, so that stretching it does not
+/// blend in the pixels of the neighboring slices.
+fn copy_slice(image: &ImageSurface, x: f64, y: f64, w: f64, h: f64) -> Option<ImageSurface> {
+    let slice = ImageSurface::create(cairo::Format::ARgb32, w as i32, h as i32).ok()?;
+    let cr = cairo::Context::new(&slice);
+    cr.set_source_surface(image, -x, -y);
+    cr.paint();
+    drop(cr);
+    Some(slice)
+}
+
+#[cfg(feature = "cairo-helpers")]
+/// Returns the source and the destination `(start, length)` of the
+/// three slices along an axis.
+///
+/// If the target is smaller than the insets, the borders shrink
+/// proportionally.
+fn slices(source: f64, target: f64, start: f64, end: f64) -> [((f64, f64), (f64, f64)); 3] {
+    let start = start.round().min(source);
+    let end = end.round().min(source - start);
+    let shrink = if start + end > target && start + end > 0. { target / (start + end) } else { 1. };
+    let (dstart, dend) = (start * shrink, end * shrink);
+    [
+        ((0., start), (0., dstart)),
+        ((start, source - start - end), (dstart, target - dstart - dend)),
+        ((source - end, end), (target - dend, dend))
+    ]
+}
+
+#[cfg(all(test, feature = "cairo-helpers"))]
+mod tests {
+    use super::*;
+
+    fn pixel(surface: &mut ImageSurface, x: usize, y: usize) -> u32 {
+        let stride = surface.get_stride() as usize;
+        let data = surface.get_data().expect("surface data borrowed");
+        let p = &data[y * stride + 4 * x..y * stride + 4 * x + 4];
+        u32::from_ne_bytes([p[0], p[1], p[2], p[3]])
+    }
+
+    #[test]
+    fn slices_keep_borders() {
+        let s = slices(30., 100., 10., 5.);
+        assert_eq!(s[0], ((0., 10.), (0., 10.)));
+        assert_eq!(s[1], ((10., 15.), (10., 85.)));
+        assert_eq!(s[2], ((25., 5.), (95., 5.)));
+
+        let s = slices(30., 6., 10., 5.);
+        assert_eq!(s[0].1, (0., 4.));
+        assert_eq!(s[2].1, (4., 2.));
+    }
+
+    #[test]
+    fn corners_not_stretched() {
+        let image = ImageSurface::create(cairo::Format::ARgb32, 12, 12).unwrap();
+        {
+            let cr = cairo::Context::new(&image);
+            cr.set_source_rgb(0., 0., 1.);
+            cr.paint();
+            cr.set_source_rgb(1., 0., 0.);
+            cr.rectangle(0., 0., 4., 4.);
+            cr.fill();
+        }
+        let mut patch = nine_patch(&image, Insets::uniform(2.), Size { w: 20., h: 10. }, 2.).unwrap();
+        assert_eq!((patch.get_width(), patch.get_height()), (40, 20));
+        assert_eq!(pixel(&mut patch, 1, 1), 0xffff0000);
+        assert_eq!(pixel(&mut patch, 5, 5), 0xff0000ff);
+        assert_eq!(pixel(&mut patch, 38, 1), 0xff0000ff);
+    }
+}
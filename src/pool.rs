@@ -0,0 +1,122 @@
+//! Recycling of row widgets for list views
+//!
+//! A list showing thousands of rows should not have a widget for each
+//! row, but only for the rows that are currently visible. A
+//! [`WidgetPool`](struct.WidgetPool.html) keeps track of the row
+//! widgets. Widgets of rows that have been scrolled out of view are
+//! returned to the pool and rebound to the data of the rows scrolled
+//! into view, rather than being dropped and created anew. So the
+//! number of widgets, and with it the layouting cost, is bounded by
+//! the number of rows shown at a time.
+//!
+//! The row widgets must implement [`Recyclable`](trait.Recyclable.html).
+use std::ops::Range;
+
+use crate::ui::UI;
+use crate::widget::*;
+
+/// A widget that can be rebound to different data
+pub trait Recyclable: Widget {
+    /// The data of a row
+    type Item;
+
+    /// Supposed to make the widget show `item` from now on
+    ///
+    /// Called when the widget is taken from the pool and whenever
+    /// the rows are updated.
+    fn bind(&mut self, item: &Self::Item);
+
+    /// Called when the widget is returned to the pool
+    ///
+    /// Default implementation does nothing.
+    fn unbind(&mut self) {}
+}
+
+/// A pool of row widgets of type `W`
+pub struct WidgetPool<W: Recyclable> {
+    factory: Box<dyn Fn() -> Box<W>>,
+    widgets: Vec<WidgetHandle<W>>,
+    rows: Range<usize>
+}
+
+impl<W: Recyclable> WidgetPool<W> {
+    /// Creates an empty pool creating new widgets by `factory`
+    pub fn new<F>(factory: F) -> WidgetPool<W>
+    where F: Fn() -> Box<W> + 'static {
+        WidgetPool {
+            factory: Box::new(factory),
+            widgets: Vec::new(),
+            rows: 0..0
+        }
+    }
+
+    /// Binds the rows `rows` of `items` to widgets
+    ///
+    /// The rows are bound to the widgets in the order the widgets
+    /// have been created, so the first row of `rows` is shown by the
+    /// first widget, the second row by the second widget and so on.
+    /// Widgets rebound to another row are unbound before. The widgets
+    /// left over are unbound, hidden and stay in the pool. If there
+    /// are not enough widgets, new ones are created and passed to
+    /// `pack` to be packed into the list's layout. Then the layouting
+    /// is performed once.
+    ///
+    /// The widgets are never moved inside the layout, so if `pack`
+    /// appends them to the list's layout, their order in the layout
+    /// is the order of the rows they show.
+    pub fn show_rows<RW, F>(&mut self, ui: &mut UI<RW>, items: &[W::Item], rows: Range<usize>, mut pack: F)
+    where RW: Widget + 'static,
+          F: FnMut(&mut UI<RW>, WidgetHandle<W>) {
+        let rows = rows.start.min(items.len())..rows.end.min(items.len());
+        let mut changed = false;
+
+        while self.widgets.len() < rows.len() {
+            let handle = ui.new_widget((self.factory)());
+            pack(ui, handle);
+            self.widgets.push(handle);
+            changed = true;
+        }
+
+        for (i, handle) in self.widgets.iter().enumerate() {
+            let was_bound = i < self.rows.len();
+            let bound = i < rows.len();
+            let widget = ui.widget(*handle);
+            if was_bound && (!bound || self.rows.start != rows.start) {
+                widget.unbind();
+            }
+            if bound != was_bound {
+                widget.set_visible(bound);
+                changed = true;
+            }
+            if bound {
+                widget.bind(&items[rows.start + i]);
+            }
+        }
+        self.rows = rows;
+
+        if changed {
+            ui.visibility_changed();
+        }
+    }
+
+    /// Returns the handle of the widget bound to the row `row`, if any.
+    pub fn handle(&self, row: usize) -> Option<WidgetHandle<W>> {
+        if self.rows.contains(&row) {
+            Some(self.widgets[row - self.rows.start])
+        } else {
+            None
+        }
+    }
+
+    /// Returns the row the widget `widget` is bound to, if any.
+    pub fn row_of(&self, widget: WidgetHandle<W>) -> Option<usize> {
+        self.widgets[..self.rows.len()].iter()
+            .position(|h| h.id() == widget.id())
+            .map(|i| self.rows.start + i)
+    }
+
+    /// Returns the number of widgets the pool has created so far.
+    pub fn widget_count(&self) -> usize {
+        self.widgets.len()
+    }
+}
@@ -0,0 +1,67 @@
+//! Popup windows for menus extending beyond the window
+//!
+//! Overlays drawn inside the view are clipped by the window, which
+//! is a problem for the drop down lists of small plugin windows. A
+//! popup is a small window of its own with a `UI` of its own, which
+//! the `UI` of the main window opens by
+//! [`UI::open_popup()`](../ui/struct.UI.html#method.open_popup) and
+//! manages:
+//!
+//! * The events of the popup are processed by the
+//!   [`next_event()`](../ui/struct.UI.html#method.next_event) of the
+//!   main `UI`, which does not block longer than a 60th of a second
+//!   while a popup is open.
+//!
+//! * Key events the main window receives while a popup is open are
+//!   forwarded to the popup opened last, as the keyboard focus of a
+//!   plugin UI often stays in the host window. They pass the event
+//!   filters of the main `UI` before.
+//!
+//! * The popups are dismissed when they lose the focus, are
+//!   requested to close, or when a mouse button is pressed in the
+//!   main window. The application learns about it by
+//!   [`UI::dismissed_popups()`](../ui/struct.UI.html#method.dismissed_popups).
+//!
+//! ```ignore
+//! let list = ui.open_popup(Box::new(ListRoot::default()),
+//!                          WindowConfig::new("presets").position(below_button),
+//!                          |popup| {
+//!                              for name in presets.iter() {
+//!                                  let item = popup.new_widget(Box::new(Item::new(name)));
+//!                                  popup.pack_to_layout(item, popup.root_layout(), StackDirection::Back);
+//!                              }
+//!                          });
+//! ```
+//!
+//! Popup windows are undecorated and transient for the main window.
+//! Like for any window, their position is a hint of the
+//! [`WindowConfig`](../window/struct.WindowConfig.html).
+use std::any::Any;
+
+use pugl_sys::*;
+
+use crate::ui::UI;
+use crate::widget::Widget;
+
+/// The identifier of a popup window of a `UI`
+pub type PopupId = usize;
+
+/// The view of a popup, independent of the root widget type
+pub(crate) trait Popup {
+    fn next_event(&mut self);
+    fn forward_event(&mut self, ev: Event) -> Status;
+    /// Returns `true` iff the popup has lost the focus or has been
+    /// requested to close.
+    fn wants_dismissal(&mut self) -> bool;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<RW: Widget + 'static> Popup for Box<PuglView<UI<RW>>> {
+    fn next_event(&mut self) { self.handle().next_event(0.0) }
+    fn forward_event(&mut self, ev: Event) -> Status { self.handle().event(ev) }
+    fn wants_dismissal(&mut self) -> bool {
+        let ui = self.handle();
+        ui.window_focus_changed() == Some(false) || ui.close_request_issued()
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any { self }
+}
@@ -0,0 +1,102 @@
+//! Velocity based pointer prediction for drags
+use pugl_sys::*;
+
+/// Extrapolates the pointer position during a drag from its velocity
+///
+/// The velocity is smoothed over the motion events, so that jitter
+/// does not cause the predicted position to jump.
+pub(crate) struct DragPredictor {
+    lookahead: f64,
+    max_distance: f64,
+    last: Option<(Coord, f64)>,
+    velocity: Coord
+}
+
+impl DragPredictor {
+    pub(crate) fn new() -> DragPredictor {
+        DragPredictor {
+            lookahead: 1./60.,
+            max_distance: 16.0,
+            last: None,
+            velocity: Coord::default()
+        }
+    }
+
+    pub(crate) fn configure(&mut self, lookahead: f64, max_distance: f64) {
+        self.lookahead = lookahead;
+        self.max_distance = max_distance;
+    }
+
+    /// Forgets about the previous motion, to be called when a drag starts or ends
+    pub(crate) fn reset(&mut self) {
+        self.last = None;
+        self.velocity = Coord::default();
+    }
+
+    /// Returns the predicted position for the pointer at `pos` at `time` seconds
+    ///
+    /// The distance between `pos` and the predicted position is
+    /// clamped to `max_distance`.
+    pub(crate) fn predict(&mut self, pos: Coord, time: f64) -> Coord {
+        if let Some((last_pos, last_time)) = self.last {
+            let dt = time - last_time;
+            if dt > 0.0 {
+                let vx = (pos.x - last_pos.x) / dt;
+                let vy = (pos.y - last_pos.y) / dt;
+                self.velocity = Coord {
+                    x: 0.5 * (self.velocity.x + vx),
+                    y: 0.5 * (self.velocity.y + vy)
+                };
+            }
+        }
+        self.last = Some((pos, time));
+
+        let dx = self.velocity.x * self.lookahead;
+        let dy = self.velocity.y * self.lookahead;
+        let distance = (dx*dx + dy*dy).sqrt();
+        let clamp = if distance > self.max_distance {
+            self.max_distance / distance
+        } else {
+            1.0
+        };
+
+        Coord { x: pos.x + dx * clamp, y: pos.y + dy * clamp }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_motion_not_extrapolated() {
+        let mut pred = DragPredictor::new();
+        assert_eq!(pred.predict(Coord { x: 10., y: 20. }, 1.0), Coord { x: 10., y: 20. });
+    }
+
+    #[test]
+    fn constant_motion_extrapolated() {
+        let mut pred = DragPredictor::new();
+        pred.configure(0.1, 100.);
+        pred.predict(Coord { x: 0., y: 0. }, 0.0);
+        pred.predict(Coord { x: 10., y: 0. }, 1.0);
+        assert_eq!(pred.predict(Coord { x: 20., y: 0. }, 2.0), Coord { x: 20.75, y: 0. });
+    }
+
+    #[test]
+    fn extrapolation_clamped() {
+        let mut pred = DragPredictor::new();
+        pred.configure(1.0, 5.);
+        pred.predict(Coord { x: 0., y: 0. }, 0.0);
+        assert_eq!(pred.predict(Coord { x: 0., y: 100. }, 1.0), Coord { x: 0., y: 105. });
+    }
+
+    #[test]
+    fn reset_forgets_velocity() {
+        let mut pred = DragPredictor::new();
+        pred.predict(Coord { x: 0., y: 0. }, 0.0);
+        pred.predict(Coord { x: 10., y: 0. }, 1.0);
+        pred.reset();
+        assert_eq!(pred.predict(Coord { x: 50., y: 50. }, 2.0), Coord { x: 50., y: 50. });
+    }
+}
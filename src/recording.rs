@@ -0,0 +1,232 @@
+//! Recording and replaying the events of the windowing system
+//!
+//! The `UI` records the incoming events between
+//! [`UI::start_recording()`](../ui/struct.UI.html#method.start_recording)
+//! and [`UI::stop_recording()`](../ui/struct.UI.html#method.stop_recording)
+//! together with their time stamps. A recording can be saved to a
+//! file, for example by a debug option of the application, so that
+//! users can send in the interaction leading to a bug. With the
+//! feature `testing` it can be replayed through the mock view to
+//! reproduce the bug deterministically.
+//!
+//! The files are line based text, one event per line. Keyboard
+//! modifiers are not preserved in the files.
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use pugl_sys::*;
+
+#[cfg(feature = "testing")]
+use crate::ui::UI;
+#[cfg(feature = "testing")]
+use crate::widget::Widget;
+
+/// The special keys that can be saved to recording files
+const SPECIAL_KEYS: [SpecialKey; 20] = [
+    SpecialKey::F1, SpecialKey::F2, SpecialKey::F3, SpecialKey::F4,
+    SpecialKey::F5, SpecialKey::F6, SpecialKey::F7, SpecialKey::F8,
+    SpecialKey::F9, SpecialKey::F10, SpecialKey::F11, SpecialKey::F12,
+    SpecialKey::Left, SpecialKey::Up, SpecialKey::Right, SpecialKey::Down,
+    SpecialKey::PageUp, SpecialKey::PageDown, SpecialKey::Home, SpecialKey::End
+];
+
+/// The events recorded by the `UI` and their time stamps
+#[derive(Clone, Debug, Default)]
+pub struct EventRecording {
+    events: Vec<(f64, Event)>
+}
+
+impl EventRecording {
+    /// Returns the recorded events with their time in seconds since
+    /// the start of the recording.
+    pub fn events(&self) -> &[(f64, Event)] {
+        &self.events
+    }
+
+    /// Writes the recording to `writer`.
+    ///
+    /// Events which can not be represented in the file format, like
+    /// window events, are left out.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for (time, ev) in self.events.iter() {
+            if let Some(line) = format_event(ev) {
+                writeln!(writer, "{} {} {} {}", time, ev.context.pos.x, ev.context.pos.y, line)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a recording written by [`write_to()`](#method.write_to).
+    pub fn read_from<R: BufRead>(reader: R) -> io::Result<EventRecording> {
+        let mut events = Vec::new();
+        for (num, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event = parse_line(&line).ok_or_else(|| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("malformed event in line {}: {}", num + 1, line)
+            ))?;
+            events.push(event);
+        }
+        Ok(EventRecording { events })
+    }
+
+    /// Saves the recording to the file `path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        self.write_to(&mut file)
+    }
+
+    /// Loads a recording from the file `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<EventRecording> {
+        EventRecording::read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Replays the recording through the mock `view`.
+    ///
+    /// If `realtime` is `true`, the original time between the events
+    /// is kept, otherwise the events are processed as fast as possible.
+    #[cfg(feature = "testing")]
+    pub fn replay<RW: Widget + 'static>(&self, view: &mut PuglView<UI<RW>>, realtime: bool) {
+        let start = Instant::now();
+        for (time, ev) in self.events.iter() {
+            if realtime {
+                let elapsed = start.elapsed().as_secs_f64();
+                if *time > elapsed {
+                    view.handle().update(-1.0);
+                    std::thread::sleep(std::time::Duration::from_secs_f64(time - elapsed));
+                }
+            }
+            view.queue_event(*ev);
+        }
+        view.handle().update(-1.0);
+    }
+}
+
+/// Records events as they come in
+pub(crate) struct EventRecorder {
+    start: Instant,
+    recording: EventRecording
+}
+
+impl EventRecorder {
+    pub(crate) fn new() -> EventRecorder {
+        EventRecorder {
+            start: Instant::now(),
+            recording: EventRecording::default()
+        }
+    }
+
+    pub(crate) fn record(&mut self, ev: Event) {
+        self.recording.events.push((self.start.elapsed().as_secs_f64(), ev));
+    }
+
+    pub(crate) fn finish(self) -> EventRecording {
+        self.recording
+    }
+}
+
+fn format_key(key: &Key) -> String {
+    match key.key {
+        KeyVal::Character(c) => format!("{} char {}", key.code, c as u32),
+        KeyVal::Special(sk) => format!("{} special {:?}", key.code, sk)
+    }
+}
+
+fn format_event(ev: &Event) -> Option<String> {
+    let line = match ev.data {
+        EventType::MouseMove(_) => String::from("move"),
+        EventType::MouseButtonPress(btn) => format!("press {}", btn.num),
+        EventType::MouseButtonRelease(btn) => format!("release {}", btn.num),
+        EventType::Scroll(scroll) => format!("scroll {} {}", scroll.dx, scroll.dy),
+        EventType::PointerIn => String::from("enter"),
+        EventType::PointerOut => String::from("leave"),
+        EventType::KeyPress(key) => format!("keypress {}", format_key(&key)),
+        EventType::KeyRelease(key) => format!("keyrelease {}", format_key(&key)),
+        _ => return None
+    };
+    Some(line)
+}
+
+fn parse_key<'a>(mut fields: impl Iterator<Item = &'a str>) -> Option<Key> {
+    let code = fields.next()?.parse().ok()?;
+    let key = match fields.next()? {
+        "char" => KeyVal::Character(std::char::from_u32(fields.next()?.parse().ok()?)?),
+        "special" => {
+            let name = fields.next()?;
+            KeyVal::Special(*SPECIAL_KEYS.iter().find(|sk| format!("{:?}", sk) == name)?)
+        }
+        _ => return None
+    };
+    Some(Key { key, modifiers: Modifiers::default(), code })
+}
+
+fn parse_line(line: &str) -> Option<(f64, Event)> {
+    let mut fields = line.split_whitespace();
+    let time: f64 = fields.next()?.parse().ok()?;
+    let pos = Coord { x: fields.next()?.parse().ok()?, y: fields.next()?.parse().ok()? };
+    let button = |num: Option<&str>| num
+        .and_then(|n| n.parse().ok())
+        .map(|num| MouseButton { num, modifiers: Modifiers::default() });
+    let data = match fields.next()? {
+        "move" => EventType::MouseMove(MotionContext::default()),
+        "press" => EventType::MouseButtonPress(button(fields.next())?),
+        "release" => EventType::MouseButtonRelease(button(fields.next())?),
+        "scroll" => EventType::Scroll(Scroll {
+            dx: fields.next()?.parse().ok()?,
+            dy: fields.next()?.parse().ok()?,
+            modifiers: Modifiers::default()
+        }),
+        "enter" => EventType::PointerIn,
+        "leave" => EventType::PointerOut,
+        "keypress" => EventType::KeyPress(parse_key(fields)?),
+        "keyrelease" => EventType::KeyRelease(parse_key(fields)?),
+        _ => return None
+    };
+    Some((time, Event { data, context: EventContext { pos, ..Default::default() } }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(data: EventType, x: f64, y: f64) -> Event {
+        Event { data, context: EventContext { pos: Coord { x, y }, ..Default::default() } }
+    }
+
+    #[test]
+    fn write_and_read() {
+        let recording = EventRecording { events: vec![
+            (0.0, at(EventType::PointerIn, 1., 2.)),
+            (0.25, at(EventType::MouseButtonPress(MouseButton { num: 1, modifiers: Modifiers::default() }), 10.5, 20.)),
+            (0.5, at(EventType::Scroll(Scroll { dx: 0., dy: -1.5, modifiers: Modifiers::default() }), 10.5, 20.)),
+            (0.75, at(EventType::KeyPress(Key {
+                key: KeyVal::Character('ä'), modifiers: Modifiers::default(), code: 42
+            }), 0., 0.)),
+            (1.0, at(EventType::KeyRelease(Key {
+                key: KeyVal::Special(SpecialKey::Home), modifiers: Modifiers::default(), code: 0
+            }), 0., 0.))
+        ]};
+        let mut buffer = Vec::new();
+        recording.write_to(&mut buffer).unwrap();
+        let read = EventRecording::read_from(buffer.as_slice()).unwrap();
+
+        assert_eq!(read.events().len(), 5);
+        for ((t1, e1), (t2, e2)) in recording.events().iter().zip(read.events().iter()) {
+            assert_eq!(t1, t2);
+            assert_eq!(e1.data, e2.data);
+            assert_eq!(e1.context.pos, e2.context.pos);
+        }
+    }
+
+    #[test]
+    fn malformed_line() {
+        let err = EventRecording::read_from("0.5 1 2 move\n0.75 1 2 wiggle\n".as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("line 2"));
+    }
+}
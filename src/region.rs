@@ -0,0 +1,45 @@
+//! Regions of the window drawn by the host or an external renderer
+//!
+//! Some plugin UIs show a part that is not drawn by cairo, like a
+//! video view or an OpenGL sub-view composited into the plugin
+//! window by the host. A [`HostRegion`](struct.HostRegion.html)
+//! reserves the space for it in the layout. The UI does not paint
+//! anything into the region, not even the background of the parent
+//! widgets, and reports its geometry by
+//! [`UI::host_regions()`](../ui/struct.UI.html#method.host_regions).
+use pugl_sys::*;
+
+use crate::widget::*;
+
+/// A widget reserving a region of the layout not painted by the UI
+///
+/// Any other widget can do the same by returning `true` from
+/// [`Widget::host_drawn()`](../widget/trait.Widget.html#method.host_drawn).
+pub struct HostRegion {
+    stub: WidgetStub,
+    min_size: Size,
+    width_expandable: bool,
+    height_expandable: bool
+}
+
+impl HostRegion {
+    /// Creates a region of at least `min_size` which is expandable
+    /// in the directions given by `(width_expandable, height_expandable)`.
+    pub fn new(min_size: Size, (width_expandable, height_expandable): (bool, bool)) -> HostRegion {
+        HostRegion {
+            stub: WidgetStub::default(),
+            min_size,
+            width_expandable,
+            height_expandable
+        }
+    }
+}
+
+impl Widget for HostRegion {
+    widget_stub!();
+
+    fn min_size(&self) -> Size { self.min_size }
+    fn width_expandable(&self) -> bool { self.width_expandable }
+    fn height_expandable(&self) -> bool { self.height_expandable }
+    fn host_drawn(&self) -> bool { true }
+}
@@ -0,0 +1,318 @@
+//! The state machine routing input events to the widgets
+//!
+//! The [`UI`](../ui/struct.UI.html) is always in one of the states of
+//! [`InputState`](enum.InputState.html), which decides which widgets
+//! receive the events.
+//!
+//! * `Normal`: pointer events follow the event path under the
+//!   pointer, key events go to the focused widget.
+//!
+//! * `DragCapture`: the widget the primary mouse button has been
+//!   pressed on receives all events until the button is released.
+//!
+//! * `DragAndDrop`: the capturing widget has started to drag data,
+//!   see [`Widget::drag_data()`](../widget/trait.Widget.html#method.drag_data).
+//!   The pointer motions determine the drop target, the innermost
+//!   widget under the pointer accepting the data. The release of the
+//!   button drops the data on it, the Escape key cancels the drag.
+//!
+//! * `Modal`: only the modal widget and its descendants receive
+//!   events. Pointer events outside of the modal widget go to the
+//!   modal widget itself, so that for example a popup can close on a
+//!   click outside. Key events go to the focused widget if it is a
+//!   descendant of the modal widget, otherwise to the modal widget.
+//!
+//! The transitions are
+//!
+//! | state                        | input           | next state                   |
+//! |------------------------------|-----------------|------------------------------|
+//! | `Normal`                     | press on `w`    | `DragCapture(w, None)`       |
+//! | `Modal(m)`                   | press on `w`    | `DragCapture(w, Some(m))`    |
+//! | `DragCapture(_, None)`       | release         | `Normal`                     |
+//! | `DragCapture(_, Some(m))`    | release         | `Modal(m)`                   |
+//! | `DragCapture(w, m)`          | drag start      | `DragAndDrop(w, None, m)`    |
+//! | `DragAndDrop(s, _, m)`       | motion over `t` | `DragAndDrop(s, t, m)`       |
+//! | `DragAndDrop(_, _, None)`    | release, cancel | `Normal`                     |
+//! | `DragAndDrop(_, _, Some(m))` | release, cancel | `Modal(m)`                   |
+//! | `Normal`, `Modal(_)`         | grab by `m`     | `Modal(m)`                   |
+//! | `DragCapture(w, _)`          | grab by `m`     | `DragCapture(w, Some(m))`    |
+//! | `DragAndDrop(s, t, _)`       | grab by `m`     | `DragAndDrop(s, t, Some(m))` |
+//! | `Modal(_)`                   | ungrab          | `Normal`                     |
+//! | `DragCapture(w, _)`          | ungrab          | `DragCapture(w, None)`       |
+//! | `DragAndDrop(s, t, _)`       | ungrab          | `DragAndDrop(s, t, None)`    |
+//!
+//! The drop target `t` of a motion is `None` if no widget under the
+//! pointer accepts the data. A modal grab during a drag takes effect
+//! with the release of the mouse button and never interrupts the drag.
+//!
+//! In every state the machine also keeps the hover target, the
+//! innermost widget under the pointer that has received the pointer
+//! enter, or the root widget if there is none. It is changed by
+//! `hover`, and reset to the root widget if the hovered widget is
+//! removed or hidden. The transitions above keep it.
+
+use crate::widget::Id;
+
+/// The routing mode of an [`InputState`](struct.InputState.html)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Mode {
+    Normal,
+    DragCapture { widget: Id, modal: Option<Id> },
+    DragAndDrop { source: Id, target: Option<Id>, modal: Option<Id> },
+    Modal(Id)
+}
+
+/// The input routing state of the UI, see the [module docs](index.html)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct InputState {
+    mode: Mode,
+    hovered: Id
+}
+
+impl Default for Mode {
+    fn default() -> Mode {
+        Mode::Normal
+    }
+}
+
+impl InputState {
+    fn with_mode(self, mode: Mode) -> InputState {
+        InputState { mode, ..self }
+    }
+
+    /// Returns the routing mode.
+    pub(crate) fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Returns the hover target.
+    pub(crate) fn hovered(&self) -> Id {
+        self.hovered
+    }
+
+    /// The pointer has entered the widget `widget`.
+    pub(crate) fn hover(self, widget: Id) -> InputState {
+        InputState { hovered: widget, ..self }
+    }
+
+    /// Returns the widget capturing the events during a drag, if any.
+    pub(crate) fn capturing(&self) -> Option<Id> {
+        match self.mode {
+            Mode::DragCapture { widget, .. } => Some(widget),
+            _ => None
+        }
+    }
+
+    /// Returns the modal widget, if any.
+    ///
+    /// During a drag this is the widget that is modal after the drag.
+    pub(crate) fn modal(&self) -> Option<Id> {
+        match self.mode {
+            Mode::Normal => None,
+            Mode::DragCapture { modal, .. } => modal,
+            Mode::DragAndDrop { modal, .. } => modal,
+            Mode::Modal(m) => Some(m)
+        }
+    }
+
+    /// Returns the widget the dragged data comes from during a drag
+    /// and drop, if any.
+    pub(crate) fn drag_source(&self) -> Option<Id> {
+        match self.mode {
+            Mode::DragAndDrop { source, .. } => Some(source),
+            _ => None
+        }
+    }
+
+    /// Returns the widget the dragged data would be dropped on, if any.
+    pub(crate) fn drop_target(&self) -> Option<Id> {
+        match self.mode {
+            Mode::DragAndDrop { target, .. } => target,
+            _ => None
+        }
+    }
+
+    /// The primary mouse button is pressed on the widget `widget`.
+    pub(crate) fn press(self, widget: Id) -> InputState {
+        self.with_mode(Mode::DragCapture { widget, modal: self.modal() })
+    }
+
+    /// The primary mouse button is released.
+    pub(crate) fn release(self) -> InputState {
+        match self.mode {
+            Mode::DragCapture { modal: Some(m), .. } |
+            Mode::DragAndDrop { modal: Some(m), .. } => self.with_mode(Mode::Modal(m)),
+            Mode::DragCapture { modal: None, .. } |
+            Mode::DragAndDrop { modal: None, .. } => self.with_mode(Mode::Normal),
+            _ => self
+        }
+    }
+
+    /// The capturing widget starts to drag data.
+    pub(crate) fn start_drag(self) -> InputState {
+        match self.mode {
+            Mode::DragCapture { widget, modal } => self.with_mode(Mode::DragAndDrop { source: widget, target: None, modal }),
+            _ => self
+        }
+    }
+
+    /// The pointer is moved over the drop target `target` during a
+    /// drag and drop.
+    pub(crate) fn drag_over(self, target: Option<Id>) -> InputState {
+        match self.mode {
+            Mode::DragAndDrop { source, modal, .. } => self.with_mode(Mode::DragAndDrop { source, target, modal }),
+            _ => self
+        }
+    }
+
+    /// The drag and drop is cancelled.
+    pub(crate) fn cancel_drag(self) -> InputState {
+        match self.mode {
+            Mode::DragAndDrop { .. } => self.release(),
+            _ => self
+        }
+    }
+
+    /// The widget `widget` takes the modal grab.
+    pub(crate) fn grab(self, widget: Id) -> InputState {
+        self.with_mode(match self.mode {
+            Mode::DragCapture { widget: w, .. } => Mode::DragCapture { widget: w, modal: Some(widget) },
+            Mode::DragAndDrop { source, target, .. } => Mode::DragAndDrop { source, target, modal: Some(widget) },
+            _ => Mode::Modal(widget)
+        })
+    }
+
+    /// The modal grab is released.
+    pub(crate) fn ungrab(self) -> InputState {
+        self.with_mode(match self.mode {
+            Mode::DragCapture { widget, .. } => Mode::DragCapture { widget, modal: None },
+            Mode::DragAndDrop { source, target, .. } => Mode::DragAndDrop { source, target, modal: None },
+            _ => Mode::Normal
+        })
+    }
+
+    /// The widget `widget` is removed from the UI.
+    ///
+    /// A drag it captures or a drag and drop it is the source of is
+    /// cancelled and its modal grab released, the grabs of other
+    /// widgets are kept. If it is the drop target or the hover
+    /// target, there is none or the root widget afterwards.
+    pub(crate) fn forget(self, widget: Id) -> InputState {
+        let mut state = self;
+        if state.drag_source() == Some(widget) {
+            state = state.cancel_drag();
+        }
+        if state.capturing() == Some(widget) {
+            state = state.release();
+        }
+        if state.modal() == Some(widget) {
+            state = state.ungrab();
+        }
+        if state.drop_target() == Some(widget) {
+            state = state.drag_over(None);
+        }
+        if state.hovered == widget {
+            state.hovered = 0;
+        }
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NORMAL: InputState = InputState { mode: Mode::Normal, hovered: 0 };
+
+    fn modal(m: Id) -> InputState {
+        NORMAL.with_mode(Mode::Modal(m))
+    }
+
+    #[test]
+    fn drag_capture() {
+        let state = NORMAL.press(3);
+        assert_eq!(state.mode(), Mode::DragCapture { widget: 3, modal: None });
+        assert_eq!(state.capturing(), Some(3));
+        assert_eq!(state.modal(), None);
+        assert_eq!(state.release(), NORMAL);
+    }
+
+    #[test]
+    fn drag_inside_modal() {
+        let state = NORMAL.grab(2);
+        assert_eq!(state, modal(2));
+        assert_eq!(state.capturing(), None);
+
+        let state = state.press(5);
+        assert_eq!(state.capturing(), Some(5));
+        assert_eq!(state.modal(), Some(2));
+        assert_eq!(state.release(), modal(2));
+        assert_eq!(state.release().ungrab(), NORMAL);
+    }
+
+    #[test]
+    fn grab_during_drag_deferred_to_release() {
+        let state = NORMAL.press(4).grab(2);
+        assert_eq!(state.capturing(), Some(4));
+        assert_eq!(state.release(), modal(2));
+
+        let state = NORMAL.grab(2).press(4).ungrab();
+        assert_eq!(state.capturing(), Some(4));
+        assert_eq!(state.release(), NORMAL);
+    }
+
+    #[test]
+    fn drag_and_drop() {
+        let state = NORMAL.press(3).start_drag();
+        assert_eq!(state.mode(), Mode::DragAndDrop { source: 3, target: None, modal: None });
+        assert_eq!(state.capturing(), None);
+        assert_eq!(state.drag_source(), Some(3));
+
+        let state = state.drag_over(Some(7));
+        assert_eq!(state.drop_target(), Some(7));
+        assert_eq!(state.drag_over(None).drop_target(), None);
+        assert_eq!(state.release(), NORMAL);
+        assert_eq!(state.cancel_drag(), NORMAL);
+
+        assert_eq!(NORMAL.start_drag(), NORMAL);
+        assert_eq!(modal(2).drag_over(Some(7)), modal(2));
+        assert_eq!(NORMAL.press(3).cancel_drag(), NORMAL.press(3));
+    }
+
+    #[test]
+    fn drag_and_drop_inside_modal() {
+        let state = modal(2).press(5).start_drag().drag_over(Some(6));
+        assert_eq!(state.modal(), Some(2));
+        assert_eq!(state.release(), modal(2));
+        assert_eq!(state.cancel_drag(), modal(2));
+
+        let state = state.ungrab();
+        assert_eq!(state.mode(), Mode::DragAndDrop { source: 5, target: Some(6), modal: None });
+        assert_eq!(state.grab(4).release(), modal(4));
+    }
+
+    #[test]
+    fn hover_kept_by_transitions() {
+        let state = NORMAL.hover(3);
+        assert_eq!(state.hovered(), 3);
+        assert_eq!(state.grab(2).press(5).start_drag().release().ungrab().hovered(), 3);
+        assert_eq!(state.forget(4).hovered(), 3);
+        assert_eq!(state.forget(3).hovered(), 0);
+    }
+
+    #[test]
+    fn forget_keeps_grabs_of_others() {
+        assert_eq!(modal(2).press(5).forget(5), modal(2));
+        assert_eq!(modal(2).press(5).start_drag().drag_over(Some(6)).forget(5), modal(2));
+        assert_eq!(modal(2).press(5).forget(2).capturing(), Some(5));
+        assert_eq!(modal(2).press(5).forget(2).release(), NORMAL);
+        assert_eq!(NORMAL.press(5).start_drag().drag_over(Some(6)).forget(6).drop_target(), None);
+        assert_eq!(modal(2).forget(7), modal(2));
+    }
+
+    #[test]
+    fn release_without_press_ignored() {
+        assert_eq!(NORMAL.release(), NORMAL);
+        assert_eq!(modal(1).release(), modal(1));
+    }
+}
@@ -0,0 +1,158 @@
+//! Fixing quirks of event streams delivered by plugin hosts
+//!
+//! Plugin views embedded into a host's window sometimes receive
+//! messy event streams: duplicated enter and leave notifications,
+//! button presses without the matching release when the host steals
+//! the focus, or modifier states encoded in a host specific way. The
+//! [`InputSanitizer`](struct.InputSanitizer.html) of the
+//! [`UI`](../ui/struct.UI.html) fixes these before the events are
+//! passed to the widgets. All the fixes are off by default.
+//!
+//! ```
+//! # use pugl_sys::*;
+//! # use pugl_ui::sanitizer::*;
+//! let mut sanitizer = InputSanitizer::default();
+//! sanitizer
+//!     .set_dedupe_crossing(true)
+//!     .set_release_on_focus_out(true);
+//! ```
+use pugl_sys::*;
+
+/// Configuration and state of the input sanitizing
+///
+/// The instance used by the `UI` is accessed by
+/// [`UI::input_sanitizer()`](../ui/struct.UI.html#method.input_sanitizer).
+#[derive(Default)]
+pub struct InputSanitizer {
+    dedupe_crossing: bool,
+    release_on_focus_out: bool,
+    modifier_mapping: Option<fn(Modifiers) -> Modifiers>,
+
+    pointer_inside: Option<bool>,
+    pressed_buttons: Vec<u32>,
+    last_context: EventContext
+}
+
+impl InputSanitizer {
+    /// Drops `PointerIn` events while the pointer is already inside the
+    /// view and `PointerOut` events while it is already outside.
+    pub fn set_dedupe_crossing(&mut self, yn: bool) -> &mut InputSanitizer {
+        self.dedupe_crossing = yn;
+        self
+    }
+
+    /// Synthesizes a `MouseButtonRelease` for every pressed mouse
+    /// button when the view loses the focus, so that no drag is left
+    /// hanging.
+    pub fn set_release_on_focus_out(&mut self, yn: bool) -> &mut InputSanitizer {
+        self.release_on_focus_out = yn;
+        self
+    }
+
+    /// Sets a function that maps the modifiers of every incoming key,
+    /// mouse button, motion and scroll event to the encoding the
+    /// widgets expect.
+    ///
+    /// `None` leaves the modifiers untouched.
+    pub fn set_modifier_mapping(&mut self, mapping: Option<fn(Modifiers) -> Modifiers>) -> &mut InputSanitizer {
+        self.modifier_mapping = mapping;
+        self
+    }
+
+    /// Returns the sanitized event or `None` if the event is to be dropped.
+    pub(crate) fn sanitize(&mut self, mut ev: Event) -> Option<Event> {
+        match ev.data {
+            EventType::PointerIn | EventType::PointerOut => {
+                let inside = ev.data == EventType::PointerIn;
+                if self.dedupe_crossing && self.pointer_inside == Some(inside) {
+                    return None;
+                }
+                self.pointer_inside = Some(inside);
+            }
+            EventType::MouseButtonPress(btn) => {
+                if !self.pressed_buttons.contains(&btn.num) {
+                    self.pressed_buttons.push(btn.num);
+                }
+            }
+            EventType::MouseButtonRelease(btn) => {
+                self.pressed_buttons.retain(|&num| num != btn.num);
+            }
+            _ => {}
+        }
+        if let Some(mapping) = self.modifier_mapping {
+            match &mut ev.data {
+                EventType::KeyPress(key) |
+                EventType::KeyRelease(key) => key.modifiers = mapping(key.modifiers),
+                EventType::MouseButtonPress(btn) |
+                EventType::MouseButtonRelease(btn) => btn.modifiers = mapping(btn.modifiers),
+                EventType::MouseMove(motion) => motion.modifiers = mapping(motion.modifiers),
+                EventType::Scroll(scroll) => scroll.modifiers = mapping(scroll.modifiers),
+                _ => {}
+            }
+        }
+        self.last_context = ev.context;
+        Some(ev)
+    }
+
+    /// Returns the release events to be synthesized as the view has
+    /// lost the focus.
+    pub(crate) fn focus_lost(&mut self) -> Vec<Event> {
+        if !self.release_on_focus_out {
+            return Vec::new();
+        }
+        let context = self.last_context;
+        self.pressed_buttons.drain(..).map(|num| Event {
+            data: EventType::MouseButtonRelease(MouseButton { num, modifiers: Modifiers::default() }),
+            context
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn crossing(data: EventType) -> Event {
+        Event { data, context: EventContext::default() }
+    }
+
+    fn button(press: bool, num: u32) -> Event {
+        let btn = MouseButton { num, modifiers: Modifiers::default() };
+        Event {
+            data: if press { EventType::MouseButtonPress(btn) } else { EventType::MouseButtonRelease(btn) },
+            context: EventContext { pos: Coord { x: 3., y: 4. }, ..Default::default() }
+        }
+    }
+
+    #[test]
+    fn crossing_kept_by_default() {
+        let mut san = InputSanitizer::default();
+        assert!(san.sanitize(crossing(EventType::PointerIn)).is_some());
+        assert!(san.sanitize(crossing(EventType::PointerIn)).is_some());
+    }
+
+    #[test]
+    fn crossing_deduped() {
+        let mut san = InputSanitizer::default();
+        san.set_dedupe_crossing(true);
+        assert!(san.sanitize(crossing(EventType::PointerOut)).is_some());
+        assert!(san.sanitize(crossing(EventType::PointerOut)).is_none());
+        assert!(san.sanitize(crossing(EventType::PointerIn)).is_some());
+        assert!(san.sanitize(crossing(EventType::PointerIn)).is_none());
+        assert!(san.sanitize(crossing(EventType::PointerOut)).is_some());
+    }
+
+    #[test]
+    fn release_synthesized_on_focus_out() {
+        let mut san = InputSanitizer::default();
+        san.sanitize(button(true, 1));
+        assert!(san.focus_lost().is_empty());
+
+        san.set_release_on_focus_out(true);
+        san.sanitize(button(true, 1));
+        san.sanitize(button(true, 3));
+        san.sanitize(button(false, 1));
+        assert_eq!(san.focus_lost(), vec![button(false, 3)]);
+        assert!(san.focus_lost().is_empty());
+    }
+}
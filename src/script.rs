@@ -0,0 +1,166 @@
+//! Scripting user interaction for tests
+//!
+//! Only available with the feature `testing`.
+//!
+//! An [`EventScript`](struct.EventScript.html) records the sequences
+//! of pugl events a user interaction consists of, like the press, the
+//! motion and the release of a drag, and plays them into the mock
+//! view.
+//!
+//! ```ignore
+//! EventScript::new()
+//!     .click_at(Coord { x: 10., y: 10. })
+//!     .type_text("440")
+//!     .wait(100)
+//!     .drag(Coord { x: 50., y: 10. }, Coord { x: 50., y: 60. }, 5)
+//!     .play(&mut view);
+//! ```
+use std::time::Duration;
+
+use pugl_sys::*;
+
+use crate::ui::UI;
+use crate::widget::Widget;
+
+#[derive(Clone, Copy, Debug)]
+enum Step {
+    Event(Event),
+    Wait(Duration)
+}
+
+/// A builder of event sequences to be played into a mock view
+///
+/// The positions are window coordinates, that is they are scaled by
+/// the scale factor of the `UI`, like the positions of events coming
+/// from the windowing system. The mouse button is always the primary
+/// button.
+#[derive(Clone, Debug, Default)]
+pub struct EventScript {
+    steps: Vec<Step>
+}
+
+impl EventScript {
+    /// Creates an empty script.
+    pub fn new() -> EventScript {
+        EventScript::default()
+    }
+
+    /// Appends the event `ev`.
+    pub fn event(mut self, ev: Event) -> EventScript {
+        self.steps.push(Step::Event(ev));
+        self
+    }
+
+    /// Appends a pointer motion to `pos`.
+    pub fn move_to(self, pos: Coord) -> EventScript {
+        self.event(at(EventType::MouseMove(MotionContext::default()), pos))
+    }
+
+    /// Appends a motion to `pos` followed by a press and a release of
+    /// the mouse button at `pos`.
+    pub fn click_at(self, pos: Coord) -> EventScript {
+        self.move_to(pos)
+            .event(at(EventType::MouseButtonPress(primary_button()), pos))
+            .event(at(EventType::MouseButtonRelease(primary_button()), pos))
+    }
+
+    /// Appends a drag with the mouse button from `from` to `to`.
+    ///
+    /// The pointer is moved in `steps` equal steps between the press
+    /// at `from` and the release at `to`.
+    pub fn drag(self, from: Coord, to: Coord, steps: usize) -> EventScript {
+        let mut script = self.move_to(from)
+            .event(at(EventType::MouseButtonPress(primary_button()), from));
+        for i in 1..=steps {
+            let t = i as f64 / steps as f64;
+            let pos = Coord { x: from.x + (to.x - from.x) * t, y: from.y + (to.y - from.y) * t };
+            script = script.move_to(pos);
+        }
+        script.event(at(EventType::MouseButtonRelease(primary_button()), to))
+    }
+
+    /// Appends a press and a release of `key`.
+    pub fn key(self, key: KeyVal) -> EventScript {
+        let key = Key { key, modifiers: Modifiers::default(), code: 0 };
+        self.event(Event { data: EventType::KeyPress(key), context: EventContext::default() })
+            .event(Event { data: EventType::KeyRelease(key), context: EventContext::default() })
+    }
+
+    /// Appends a press and a release of the key of every character of `text`.
+    pub fn type_text(self, text: &str) -> EventScript {
+        text.chars().fold(self, |script, c| script.key(KeyVal::Character(c)))
+    }
+
+    /// Appends a pause of `ms` milliseconds.
+    ///
+    /// When played, the events before are processed and the script
+    /// sleeps before going on, so that timing dependent behavior like
+    /// tooltips or double clicks can be tested.
+    pub fn wait(mut self, ms: u64) -> EventScript {
+        self.steps.push(Step::Wait(Duration::from_millis(ms)));
+        self
+    }
+
+    /// Returns the events of the script, leaving out the pauses.
+    pub fn events(&self) -> Vec<Event> {
+        self.steps.iter().filter_map(|step| match step {
+            Step::Event(ev) => Some(*ev),
+            Step::Wait(_) => None
+        }).collect()
+    }
+
+    /// Queues the events into the mock `view` and lets the `UI`
+    /// process them.
+    pub fn play<RW: Widget + 'static>(&self, view: &mut PuglView<UI<RW>>) {
+        for step in self.steps.iter() {
+            match step {
+                Step::Event(ev) => view.queue_event(*ev),
+                Step::Wait(duration) => {
+                    view.handle().update(-1.0);
+                    std::thread::sleep(*duration);
+                }
+            }
+        }
+        view.handle().update(-1.0);
+    }
+}
+
+fn primary_button() -> MouseButton {
+    MouseButton { num: 1, modifiers: Modifiers::default() }
+}
+
+fn at(data: EventType, pos: Coord) -> Event {
+    Event { data, context: EventContext { pos, ..Default::default() } }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drag_steps() {
+        let events = EventScript::new()
+            .drag(Coord { x: 0., y: 10. }, Coord { x: 30., y: 40. }, 3)
+            .events();
+        assert_eq!(events.len(), 6);
+        assert!(matches!(events[1].data, EventType::MouseButtonPress(_)));
+        let positions: Vec<Coord> = events.iter().map(|ev| ev.pos()).collect();
+        assert_eq!(positions, vec![
+            Coord { x: 0., y: 10. }, Coord { x: 0., y: 10. },
+            Coord { x: 10., y: 20. }, Coord { x: 20., y: 30. }, Coord { x: 30., y: 40. },
+            Coord { x: 30., y: 40. }
+        ]);
+        assert!(matches!(events[5].data, EventType::MouseButtonRelease(_)));
+    }
+
+    #[test]
+    fn typed_text() {
+        let events = EventScript::new().type_text("ab").wait(10).events();
+        let keys: Vec<Option<char>> = events.iter()
+            .filter_map(|ev| ev.try_keypress())
+            .map(|key| key.try_char())
+            .collect();
+        assert_eq!(keys, vec![Some('a'), Some('b')]);
+        assert_eq!(events.len(), 4);
+    }
+}
@@ -0,0 +1,172 @@
+//! Resolution independent widget skins from SVG documents
+//!
+//! Only available with the feature `svg`.
+//!
+//! A designer ships the parts of a widget as elements of one SVG
+//! document, like the base and the pointer of a knob. An
+//! [`SvgSkin`](struct.SvgSkin.html) renders the elements by their ids
+//! into the region of a widget, rasterized at the current scale
+//! factor. The rasterizations are cached per element, size and scale
+//! factor, so that only changed geometries need rendering.
+//!
+//! The skins are added to the `UI`'s assets by
+//! [`Assets::add_svg_skin()`](../assets/struct.Assets.html#method.add_svg_skin)
+//! and drawn from `Widget::exposed()`:
+//!
+//! ```ignore
+//! ui.assets().add_svg_skin("knob", "skins/knob.svg")?;
+//!
+//! // in Widget::exposed()
+//! let skin = ctx.skin("knob").unwrap();
+//! skin.render_element(cr, "#knob_base", self.layout(), ctx.scale_factor());
+//! skin.render_element_rotated(cr, "#knob_pointer", self.layout(), self.angle, ctx.scale_factor());
+//! ```
+//!
+//! An element is rendered in its place within the document, with the
+//! whole document fitted into the widget's region. So a pointer drawn
+//! in the center of the document turns around the center of the
+//! widget.
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::Path;
+
+use cairo::ImageSurface;
+use pugl_sys::*;
+
+use crate::assets::AssetError;
+use crate::widget::Layout;
+
+/// The elements of an SVG document, rendered into widget regions
+pub struct SvgSkin {
+    handle: librsvg::SvgHandle,
+    cache: RefCell<HashMap<(String, u64, u64, u64), ImageSurface>>
+}
+
+impl SvgSkin {
+    /// Loads the SVG document file `path`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<SvgSkin, AssetError> {
+        let handle = librsvg::Loader::new().read_path(path).map_err(|err| AssetError::Svg(err.to_string()))?;
+        Ok(SvgSkin { handle, cache: RefCell::new(HashMap::new()) })
+    }
+
+    /// Returns `true` iff the document has an element with the id `id`.
+    ///
+    /// Ids are given with or without a leading `#`.
+    pub fn has_element(&self, id: &str) -> bool {
+        self.handle.has_element_with_id(&element_id(id)).unwrap_or(false)
+    }
+
+    /// Draws the element `id` of the document fitted into `layout`.
+    ///
+    /// `cr` is expected to be scaled by `scale_factor`, like the
+    /// context passed to `Widget::exposed()`. Returns `false` if the
+    /// element could not be rendered, for example if there is no
+    /// element `id`.
+    pub fn render_element(&self, cr: &cairo::Context, id: &str, layout: Layout, scale_factor: f64) -> bool {
+        self.render_element_rotated(cr, id, layout, 0., scale_factor)
+    }
+
+    /// Draws the element `id` of the document fitted into `layout`,
+    /// rotated by `angle` in radians clockwise around the center of
+    /// `layout`.
+    ///
+    /// See [`render_element()`](#method.render_element).
+    pub fn render_element_rotated(&self, cr: &cairo::Context, id: &str, layout: Layout, angle: f64, scale_factor: f64) -> bool {
+        let id = element_id(id);
+        let key = (id.clone(), scale_factor.to_bits(), layout.size.w.to_bits(), layout.size.h.to_bits());
+        let mut cache = self.cache.borrow_mut();
+        if !cache.contains_key(&key) {
+            match self.rasterize(&id, layout.size, scale_factor) {
+                Some(surface) => cache.insert(key.clone(), surface),
+                None => return false
+            };
+        }
+        let surface = &cache[&key];
+        let center = Coord { x: layout.pos.x + layout.size.w / 2., y: layout.pos.y + layout.size.h / 2. };
+        cr.save();
+        cr.translate(center.x, center.y);
+        cr.rotate(angle);
+        cr.scale(1./scale_factor, 1./scale_factor);
+        cr.set_source_surface(surface, -layout.size.w * scale_factor / 2., -layout.size.h * scale_factor / 2.);
+        cr.paint();
+        cr.restore();
+        true
+    }
+
+    /// Drops the rasterizations of all the elements.
+    pub fn clear_cache(&self) {
+        self.cache.borrow_mut().clear();
+    }
+
+    /// Drops the rasterizations at other scale factors than `scale_factor`.
+    pub(crate) fn retain_scale(&self, scale_factor: f64) {
+        let scale = scale_factor.to_bits();
+        self.cache.borrow_mut().retain(|(_, s, _, _), _| *s == scale);
+    }
+
+    fn rasterize(&self, id: &str, size: Size, scale_factor: f64) -> Option<ImageSurface> {
+        if !self.has_element(id) {
+            return None;
+        }
+        let surface = ImageSurface::create(cairo::Format::ARgb32,
+                                           ((size.w * scale_factor).ceil() as i32).max(1),
+                                           ((size.h * scale_factor).ceil() as i32).max(1)).ok()?;
+        let cr = cairo::Context::new(&surface);
+        let viewport = cairo::Rectangle { x: 0., y: 0., width: size.w * scale_factor, height: size.h * scale_factor };
+        librsvg::CairoRenderer::new(&self.handle).render_layer(&cr, Some(id), &viewport).ok()?;
+        Some(surface)
+    }
+}
+
+fn element_id(id: &str) -> String {
+    if id.starts_with('#') {
+        String::from(id)
+    } else {
+        format!("#{}", id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KNOB: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="20" viewBox="0 0 20 20">
+  <rect id="knob_base" x="0" y="0" width="20" height="20" fill="#0000ff"/>
+  <rect id="knob_pointer" x="9" y="0" width="2" height="10" fill="#ff0000"/>
+</svg>"##;
+
+    fn skin() -> SvgSkin {
+        let path = std::env::temp_dir().join(format!("pugl-ui-skin-{}.svg", std::process::id()));
+        std::fs::write(&path, KNOB).unwrap();
+        let skin = SvgSkin::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        skin
+    }
+
+    fn pixel(surface: &mut ImageSurface, x: usize, y: usize) -> u32 {
+        let stride = surface.get_stride() as usize;
+        let data = surface.get_data().expect("surface data borrowed");
+        let p = &data[y * stride + 4 * x..y * stride + 4 * x + 4];
+        u32::from_ne_bytes([p[0], p[1], p[2], p[3]])
+    }
+
+    #[test]
+    fn render_elements_by_id() {
+        let skin = skin();
+        assert!(skin.has_element("knob_base"));
+        assert!(skin.has_element("#knob_pointer"));
+        assert!(!skin.has_element("#knob_shadow"));
+
+        let mut surface = ImageSurface::create(cairo::Format::ARgb32, 80, 80).unwrap();
+        {
+            let cr = cairo::Context::new(&surface);
+            cr.scale(2., 2.);
+            let layout = Layout { pos: Coord::default(), size: Size { w: 40., h: 40. } };
+            assert!(skin.render_element(&cr, "#knob_base", layout, 2.));
+            assert!(skin.render_element_rotated(&cr, "#knob_pointer", layout, std::f64::consts::PI, 2.));
+            assert!(!skin.render_element(&cr, "#knob_shadow", layout, 2.));
+        }
+        assert_eq!(pixel(&mut surface, 40, 10), 0xff0000ff);
+        assert_eq!(pixel(&mut surface, 40, 70), 0xffff0000);
+    }
+}
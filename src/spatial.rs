@@ -0,0 +1,146 @@
+//! A grid of the widget geometries for hit testing large widget counts
+//!
+//! UIs with very many small widgets, like the steps of a step
+//! sequencer, make the `UI` visit many widgets for nothing when it
+//! searches the widget tree for the widget under the pointer or for
+//! the widgets inside an exposed area. If enabled by
+//! [`UI::set_spatial_index()`](../ui/struct.UI.html#method.set_spatial_index)
+//! the `UI` sorts the shown widgets into the cells of a grid after
+//! each layouting, so that only the widgets of the cells touched are
+//! tested.
+use std::collections::HashMap;
+
+use pugl_sys::*;
+
+use crate::widget::{Id, Layout};
+
+/// A widget of the index
+pub(crate) struct Entry {
+    /// The id of the widget
+    pub(crate) id: Id,
+    /// The entry of the parent widget, `None` for the root widget
+    pub(crate) parent: Option<usize>,
+    /// The index of the widget among the children of its parent
+    pub(crate) child_index: usize
+}
+
+/// The shown widgets in tree order sorted into grid cells
+///
+/// The entries of each cell are in tree order, the order of the
+/// widgets in the expose queue.
+pub(crate) struct SpatialIndex {
+    cell_size: f64,
+    entries: Vec<Entry>,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    valid: bool
+}
+
+impl SpatialIndex {
+    pub(crate) fn new(cell_size: f64) -> SpatialIndex {
+        SpatialIndex {
+            cell_size: cell_size.max(1.),
+            entries: Vec::new(),
+            cells: HashMap::new(),
+            valid: false
+        }
+    }
+
+    /// Drops all the entries, the index is valid afterwards
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.cells.clear();
+        self.valid = true;
+    }
+
+    /// Marks the index outdated until it is rebuilt.
+    pub(crate) fn invalidate(&mut self) {
+        self.valid = false;
+    }
+
+    /// Returns `false` if the widget tree has changed since the index
+    /// has been built.
+    pub(crate) fn is_valid(&self) -> bool {
+        self.valid
+    }
+
+    /// Adds the widget `id` covering `layout` and returns its entry.
+    ///
+    /// The widgets are supposed to be added in tree order, parents
+    /// before their children.
+    pub(crate) fn insert(&mut self, id: Id, parent: Option<usize>, child_index: usize, layout: Layout) -> usize {
+        let entry = self.entries.len();
+        self.entries.push(Entry { id, parent, child_index });
+        let ((left, top), (right, bottom)) = self.cell_range(layout.pos, layout.size);
+        for x in left..=right {
+            for y in top..=bottom {
+                self.cells.entry((x, y)).or_insert_with(Vec::new).push(entry);
+            }
+        }
+        entry
+    }
+
+    pub(crate) fn entry(&self, entry: usize) -> &Entry {
+        &self.entries[entry]
+    }
+
+    /// Returns the entries of the widgets that may contain `pos` in
+    /// tree order.
+    pub(crate) fn candidates_at(&self, pos: Coord) -> &[usize] {
+        let (cell, _) = self.cell_range(pos, Size::default());
+        self.cells.get(&cell).map_or(&[], |entries| entries.as_slice())
+    }
+
+    /// Returns the entries of the widgets that may intersect with the
+    /// rectangle `(pos, size)` in tree order.
+    pub(crate) fn candidates_in(&self, pos: Coord, size: Size) -> Vec<usize> {
+        let ((left, top), (right, bottom)) = self.cell_range(pos, size);
+        let mut candidates = Vec::new();
+        for x in left..=right {
+            for y in top..=bottom {
+                if let Some(entries) = self.cells.get(&(x, y)) {
+                    candidates.extend_from_slice(entries);
+                }
+            }
+        }
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+    }
+
+    /// Returns the first and the last cell covered by the rectangle
+    /// `(pos, size)`.
+    fn cell_range(&self, pos: Coord, size: Size) -> ((i64, i64), (i64, i64)) {
+        let cell = |v: f64| (v / self.cell_size).floor() as i64;
+        ((cell(pos.x), cell(pos.y)), (cell(pos.x + size.w.max(0.)), cell(pos.y + size.h.max(0.))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(x: f64, y: f64, w: f64, h: f64) -> Layout {
+        Layout { pos: Coord { x, y }, size: Size { w, h } }
+    }
+
+    #[test]
+    fn candidates_in_tree_order() {
+        let mut index = SpatialIndex::new(10.);
+        assert!(!index.is_valid());
+        index.clear();
+        let root = index.insert(0, None, 0, layout(0., 0., 100., 100.));
+        let left = index.insert(1, Some(root), 0, layout(0., 0., 15., 15.));
+        let right = index.insert(2, Some(root), 1, layout(50., 50., 5., 5.));
+        assert!(index.is_valid());
+
+        assert_eq!(index.candidates_at(Coord { x: 12., y: 3. }), &[root, left]);
+        assert_eq!(index.candidates_at(Coord { x: 52., y: 52. }), &[root, right]);
+        assert_eq!(index.candidates_at(Coord { x: 200., y: 0. }), &[] as &[usize]);
+        assert_eq!(index.candidates_in(Coord { x: 5., y: 5. }, Size { w: 50., h: 50. }), vec![root, left, right]);
+        assert_eq!(index.entry(right).parent, Some(root));
+        assert_eq!(index.entry(right).child_index, 1);
+
+        index.invalidate();
+        assert!(!index.is_valid());
+    }
+}
@@ -0,0 +1,151 @@
+//! Measuring texts without having a `cairo::Context` at hand
+//!
+//! Widgets showing texts usually need to know the size of the text
+//! to calculate their minimal size, so before they are drawn for the
+//! first time. The [`TextMetrics`](struct.TextMetrics.html) service
+//! of the `UI`, accessed by
+//! [`UI::text_metrics()`](../ui/struct.UI.html#method.text_metrics),
+//! measures texts using a shared pango context and caches the results.
+//!
+//! The [`Locale`](struct.Locale.html) of the `UI` configures the text
+//! direction and font fallbacks for translated UIs.
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use pugl_sys::*;
+
+/// The base direction of texts and layouts
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TextDirection {
+    LeftToRight,
+    RightToLeft
+}
+
+/// Locale dependent text settings of the `UI`
+///
+/// Set by [`UI::set_locale()`](../ui/struct.UI.html#method.set_locale).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Locale {
+    /// If `RightToLeft` texts are laid out right to left and the
+    /// layout direction of the `UI` is set to right to left, which
+    /// mirrors the whole widget layout horizontally.
+    pub direction: TextDirection,
+    /// Font families to be used for glyphs that the requested font
+    /// does not provide, like `"Noto Naskh Arabic"`
+    pub font_fallbacks: Vec<String>
+}
+
+impl Default for Locale {
+    fn default() -> Locale {
+        Locale {
+            direction: TextDirection::LeftToRight,
+            font_fallbacks: Vec::new()
+        }
+    }
+}
+
+impl Locale {
+    /// Returns the pango font description `font` with the font
+    /// fallbacks appended to its font families.
+    ///
+    /// ```
+    /// # use pugl_ui::text::*;
+    /// let locale = Locale {
+    ///     font_fallbacks: vec![String::from("Noto Sans Hebrew")],
+    ///     ..Default::default()
+    /// };
+    /// let desc = locale.font_description("Sans 12px");
+    /// assert_eq!(desc.get_family().unwrap().as_str(), "Sans,Noto Sans Hebrew");
+    /// ```
+    pub fn font_description(&self, font: &str) -> pango::FontDescription {
+        let mut desc = pango::FontDescription::from_string(font);
+        if !self.font_fallbacks.is_empty() {
+            let mut families: Vec<String> = desc.get_family().map(|f| vec![f.to_string()]).unwrap_or_default();
+            families.extend(self.font_fallbacks.iter().cloned());
+            desc.set_family(&families.join(","));
+        }
+        desc
+    }
+
+    fn pango_direction(&self) -> pango::Direction {
+        match self.direction {
+            TextDirection::LeftToRight => pango::Direction::Ltr,
+            TextDirection::RightToLeft => pango::Direction::Rtl
+        }
+    }
+}
+
+/// Measures texts using a shared pango context
+pub struct TextMetrics {
+    context: pango::Context,
+    locale: Locale,
+    cache: RefCell<HashMap<(String, String), Size>>
+}
+
+impl TextMetrics {
+    pub(crate) fn new() -> TextMetrics {
+        let sf = cairo::ImageSurface::create(cairo::Format::ARgb32, 8, 8)
+            .expect("Creation of text measuring surface failed");
+        let cr = cairo::Context::new(&sf);
+        let context = pangocairo::functions::create_context(&cr)
+            .expect("Creation of text measuring pango context failed");
+        TextMetrics {
+            context,
+            locale: Locale::default(),
+            cache: RefCell::new(HashMap::new())
+        }
+    }
+
+    /// The `Locale` used for measuring
+    pub fn locale(&self) -> &Locale {
+        &self.locale
+    }
+
+    pub(crate) fn set_locale(&mut self, locale: Locale) {
+        self.context.set_base_dir(locale.pango_direction());
+        self.locale = locale;
+        self.clear_cache();
+    }
+
+    /// Returns the size in pixels of `text` rendered with the pango
+    /// font description `font`, like `"Sans 24px"`.
+    pub fn measure(&self, text: &str, font: &str) -> Size {
+        let key = (String::from(text), String::from(font));
+        if let Some(size) = self.cache.borrow().get(&key) {
+            return *size;
+        }
+
+        let lyt = pango::Layout::new(&self.context);
+        lyt.set_font_description(Some(&self.locale.font_description(font)));
+        lyt.set_text(text);
+        let (w, h) = lyt.get_pixel_size();
+        let size = Size { w: w.into(), h: h.into() };
+
+        self.cache.borrow_mut().insert(key, size);
+        size
+    }
+
+    /// Forgets all the cached measurements, for example after new
+    /// fonts have been installed.
+    pub fn clear_cache(&self) {
+        self.cache.borrow_mut().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measure_cached() {
+        let tm = TextMetrics::new();
+        let size = tm.measure("Hello", "Sans 12px");
+        assert_eq!(tm.cache.borrow().len(), 1);
+        assert_eq!(tm.measure("Hello", "Sans 12px"), size);
+        assert_eq!(tm.cache.borrow().len(), 1);
+        tm.measure("Hello", "Sans 24px");
+        assert_eq!(tm.cache.borrow().len(), 2);
+        tm.clear_cache();
+        assert!(tm.cache.borrow().is_empty());
+    }
+}
@@ -0,0 +1,162 @@
+//! Colors and fonts of the `UI` and its widgets
+//!
+//! The [`Theme`](struct.Theme.html) describes the decorations the
+//! [`UI`](../ui/struct.UI.html) draws on top of the widgets, like
+//! highlights and tooltips, as well as the focus rings and panels the
+//! widgets can draw by their
+//! [`PaintContext`](../widget/struct.PaintContext.html). It does not
+//! take part in the styling of the widgets themselves.
+//!
+//! Widgets are supposed to draw themselves using the
+//! [`Style`](struct.Style.html) they get by the `PaintContext`. The
+//! `UI` owns a base style which can be partially overridden for
+//! individual widgets by a [`StyleOverride`](struct.StyleOverride.html).
+//! The overrides are inherited by the children of a layout, so that
+//! whole sections of the UI can be restyled at once. Changing the
+//! theme leaves the base style and the overrides untouched.
+
+use crate::panel::Panel;
+
+/// A color as `(red, green, blue)`, each component from `0.0` to `1.0`
+pub type Color = (f64, f64, f64);
+
+/// The colors and fonts used by the `UI`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    /// Color of the highlight frame around highlighted widgets
+    pub highlight_color: Color,
+    /// Line width of the highlight frame
+    pub highlight_width: f64,
+    /// Background color of tooltips
+    pub tooltip_background: Color,
+    /// Text and border color of tooltips
+    pub tooltip_foreground: Color,
+    /// Pango font description of the tooltip text
+    pub tooltip_font: String,
+    /// Color of the focus ring drawn by
+    /// [`PaintContext::draw_focus_ring()`](../widget/struct.PaintContext.html#method.draw_focus_ring)
+    pub focus_ring_color: Color,
+    /// Line width of the focus ring
+    pub focus_ring_width: f64,
+    /// Color of highlights and focus rings in high contrast mode
+    pub high_contrast_color: Color,
+    /// The panel drawn by
+    /// [`PaintContext::draw_panel()`](../widget/struct.PaintContext.html#method.draw_panel)
+    pub panel: Panel
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            highlight_color: (1.0, 0.6, 0.0),
+            highlight_width: 2.0,
+            tooltip_background: (1.0, 1.0, 0.85),
+            tooltip_foreground: (0.0, 0.0, 0.0),
+            tooltip_font: String::from("Sans 12px"),
+            focus_ring_color: (1.0, 1.0, 1.0),
+            focus_ring_width: 1.0,
+            high_contrast_color: (1.0, 1.0, 0.0),
+            panel: Panel::default()
+        }
+    }
+}
+
+impl Theme {
+    pub(crate) fn draw_highlight(&self, cr: &cairo::Context, pos: pugl_sys::Coord, size: pugl_sys::Size, high_contrast: bool) {
+        self.draw_frame(cr, pos, size, self.highlight_color, self.highlight_width, high_contrast);
+    }
+
+    pub(crate) fn draw_focus_ring(&self, cr: &cairo::Context, pos: pugl_sys::Coord, size: pugl_sys::Size, high_contrast: bool) {
+        self.draw_frame(cr, pos, size, self.focus_ring_color, self.focus_ring_width, high_contrast);
+    }
+
+    /// Returns the background and the foreground color of tooltips
+    pub(crate) fn tooltip_colors(&self, high_contrast: bool) -> (Color, Color) {
+        if high_contrast {
+            ((0.0, 0.0, 0.0), (1.0, 1.0, 1.0))
+        } else {
+            (self.tooltip_background, self.tooltip_foreground)
+        }
+    }
+
+    /// Draws a frame inside the rectangle `(pos, size)`
+    ///
+    /// In high contrast mode the frame is drawn twice as wide in
+    /// `high_contrast_color` on a black outline.
+    fn draw_frame(&self, cr: &cairo::Context, pos: pugl_sys::Coord, size: pugl_sys::Size, color: Color, width: f64, high_contrast: bool) {
+        let ((r, g, b), lw) = if high_contrast {
+            (self.high_contrast_color, 2. * width)
+        } else {
+            (color, width)
+        };
+        cr.save();
+        if high_contrast {
+            cr.set_source_rgb(0., 0., 0.);
+            cr.set_line_width(2. * lw);
+            cr.rectangle(pos.x + lw, pos.y + lw, size.w - 2.*lw, size.h - 2.*lw);
+            cr.stroke();
+        }
+        cr.set_source_rgb(r, g, b);
+        cr.set_line_width(lw);
+        cr.rectangle(pos.x + lw/2., pos.y + lw/2., size.w - lw, size.h - lw);
+        cr.stroke();
+        cr.restore();
+    }
+}
+
+/// The style widgets are supposed to draw themselves with
+#[derive(Clone, Debug, PartialEq)]
+pub struct Style {
+    /// Color of texts and lines
+    pub foreground: Color,
+    /// Background color
+    pub background: Color,
+    /// Color to emphasize things like active states
+    pub accent: Color,
+    /// Pango font description of texts
+    pub font: String,
+    /// Spacing between the widget's border and its content
+    pub padding: f64,
+    /// Radius of rounded corners
+    pub border_radius: f64
+}
+
+impl Default for Style {
+    fn default() -> Style {
+        Style {
+            foreground: (0.9, 0.9, 0.9),
+            background: (0.2, 0.2, 0.2),
+            accent: (1.0, 0.6, 0.0),
+            font: String::from("Sans 12px"),
+            padding: 4.0,
+            border_radius: 0.0
+        }
+    }
+}
+
+/// Overrides parts of the `Style` of a widget and its children
+///
+/// Fields that are `None` are inherited from the parent's style.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StyleOverride {
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+    pub accent: Option<Color>,
+    pub font: Option<String>,
+    pub padding: Option<f64>,
+    pub border_radius: Option<f64>
+}
+
+impl StyleOverride {
+    /// Returns the `style` with the overridden fields replaced.
+    pub fn apply(&self, style: &Style) -> Style {
+        Style {
+            foreground: self.foreground.unwrap_or(style.foreground),
+            background: self.background.unwrap_or(style.background),
+            accent: self.accent.unwrap_or(style.accent),
+            font: self.font.clone().unwrap_or_else(|| style.font.clone()),
+            padding: self.padding.unwrap_or(style.padding),
+            border_radius: self.border_radius.unwrap_or(style.border_radius)
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! Tooltips shown by the UI when the pointer rests on a widget
+//!
+//! The tooltips are only drawn with the feature `text`.
+use pugl_sys::*;
+
+#[cfg(feature = "text")]
+use crate::theme::Theme;
+use crate::widget::Layout;
+
+/// The timer id the `UI` uses for the tooltip hover delay.
+///
+/// Widget reminders use the widget's `Id` as timer id, so the timer
+/// id is taken from the other end of the range.
+pub(crate) const TOOLTIP_TIMER: usize = usize::MAX;
+
+#[cfg(feature = "text")]
+const PADDING: f64 = 4.0;
+#[cfg(feature = "text")]
+const POINTER_OFFSET: Coord = Coord { x: 12.0, y: 18.0 };
+
+/// Keeps track of the tooltip state of the `UI`
+pub(crate) struct TooltipManager {
+    delay: f64,
+    pending: bool,
+    text: Option<String>,
+    #[cfg_attr(not(feature = "text"), allow(dead_code))]
+    anchor: Coord,
+    drawn: Option<Layout>
+}
+
+impl TooltipManager {
+    pub(crate) fn new() -> TooltipManager {
+        TooltipManager {
+            delay: 0.6,
+            pending: false,
+            text: None,
+            anchor: Coord::default(),
+            drawn: None
+        }
+    }
+
+    pub(crate) fn delay(&self) -> f64 {
+        self.delay
+    }
+
+    pub(crate) fn set_delay(&mut self, delay: f64) {
+        self.delay = delay;
+    }
+
+    /// Marks the hover delay timer as running
+    pub(crate) fn set_pending(&mut self) {
+        self.pending = true;
+    }
+
+    /// Returns `true` iff the hover delay timer is running.
+    pub(crate) fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    /// Returns `true` iff the hover delay timer was running and marks it stopped.
+    pub(crate) fn take_pending(&mut self) -> bool {
+        let p = self.pending;
+        self.pending = false;
+        p
+    }
+
+    pub(crate) fn text(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+
+    /// Shows the tooltip `text` near the pointer position `anchor`
+    pub(crate) fn show(&mut self, text: String, anchor: Coord) {
+        self.text = Some(text);
+        self.anchor = anchor;
+    }
+
+    /// Hides the tooltip.
+    ///
+    /// Returns the rectangle, the tooltip has been drawn to, if any,
+    /// so that the `UI` can redraw it.
+    pub(crate) fn hide(&mut self) -> Option<Layout> {
+        self.text = None;
+        self.drawn.take()
+    }
+
+    /// Draws the tooltip inside the rectangle `(0, 0, bounds)`
+    #[cfg(feature = "text")]
+    pub(crate) fn draw(&mut self, cr: &cairo::Context, bounds: Size, theme: &Theme, high_contrast: bool) {
+        let text = match &self.text {
+            Some(text) => text,
+            None => return
+        };
+        let ctx = match pangocairo::functions::create_context(cr) {
+            Some(ctx) => ctx,
+            None => return
+        };
+        let lyt = pango::Layout::new(&ctx);
+        lyt.set_font_description(Some(&pango::FontDescription::from_string(&theme.tooltip_font)));
+        lyt.set_text(text);
+
+        let (w, h) = lyt.get_pixel_size();
+        let size = Size { w: f64::from(w) + 2.*PADDING, h: f64::from(h) + 2.*PADDING };
+
+        let mut pos = self.anchor + POINTER_OFFSET;
+        if pos.x + size.w > bounds.w {
+            pos.x = (bounds.w - size.w).max(0.0);
+        }
+        if pos.y + size.h > bounds.h {
+            pos.y = (self.anchor.y - size.h).max(0.0);
+        }
+
+        let ((br, bg, bb), (r, g, b)) = theme.tooltip_colors(high_contrast);
+        cr.save();
+        cr.set_source_rgb(br, bg, bb);
+        cr.rectangle(pos.x, pos.y, size.w, size.h);
+        cr.fill();
+        cr.set_source_rgb(r, g, b);
+        cr.set_line_width(1.);
+        cr.rectangle(pos.x + 0.5, pos.y + 0.5, size.w - 1., size.h - 1.);
+        cr.stroke();
+        cr.move_to(pos.x + PADDING, pos.y + PADDING);
+        pangocairo::functions::show_layout(cr, &lyt);
+        cr.restore();
+
+        self.drawn = Some(Layout { pos, size });
+    }
+}
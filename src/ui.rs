@@ -23,14 +23,134 @@
 //!   event, if the widget does not process the event, the event is
 //!   propagated to its parent.
 //!
-use std::collections::{VecDeque,HashMap};
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::collections::{VecDeque,HashMap,HashSet};
+use std::sync::mpsc::{self, Sender};
+use std::time::Instant;
+#[cfg(any(feature = "frame-stats", feature = "widget-stats"))]
+use std::time::Duration;
 
 use pugl_sys::*;
 
+use crate::accessibility::*;
+#[cfg(feature = "cairo-helpers")]
+use crate::assets::Assets;
+use crate::draw::CairoBackend;
+use crate::embed::ParentWindow;
 use crate::layout::*;
 use crate::layout::layoutwidget::*;
 use crate::layout::stacklayout::*;
+use crate::layout::tablayout::*;
+use crate::layout::panedlayout::*;
+use crate::layout::framelayout::*;
+use crate::theme::*;
+use crate::tooltip::*;
+use crate::gestures::*;
+use crate::group::POLL_PERIOD;
+use crate::kinetic::*;
+use crate::popup::{Popup, PopupId};
+use crate::prediction::DragPredictor;
+use crate::recording::{EventRecorder, EventRecording};
+use crate::routing::InputState;
+use crate::sanitizer::InputSanitizer;
+use crate::spatial::SpatialIndex;
+#[cfg(feature = "text")]
+use crate::text::{Locale, TextDirection, TextMetrics};
 use crate::widget::*;
+use crate::window::WindowConfig;
+
+/// Timer ids from `FLASH_TIMER_BASE + id` are used to end the
+/// highlight flash of the widget `id`.
+pub(crate) const FLASH_TIMER_BASE: usize = usize::MAX / 2;
+
+/// Timer ids from `WIDGET_TIMER_BASE` up to `FLASH_TIMER_BASE` are
+/// allocated for the timers requested by
+/// [`Widget::request_timer()`](../widget/trait.Widget.html#method.request_timer).
+pub(crate) const WIDGET_TIMER_BASE: usize = usize::MAX / 4;
+
+/// A timer requested by a widget
+struct WidgetTimer {
+    widget: Id,
+    tag: &'static str,
+    period: f64,
+    repeating: bool
+}
+
+/// Tolerance for floating point errors when rounding scaled sizes
+/// to pixels, so that `42. * 1.5` does not end up as 64 pixels.
+const PIXEL_EPSILON: f64 = 1e-6;
+
+/// Rounds the scaled window `size` up to full pixels
+fn window_pixels(size: Size) -> (i32, i32) {
+    ((size.w - PIXEL_EPSILON).ceil() as i32, (size.h - PIXEL_EPSILON).ceil() as i32)
+}
+
+/// Returns the scale factor the user has configured for the desktop,
+/// if any.
+///
+/// Before a window is opened there is no monitor to ask, so the scale
+/// is taken from the environment variables honored by the common
+/// toolkits: `GDK_SCALE` multiplied by `GDK_DPI_SCALE`, or else
+/// `QT_SCALE_FACTOR`. See
+/// [`UI::new_auto_scaled()`](struct.UI.html#method.new_auto_scaled).
+pub fn system_scale_factor() -> Option<f64> {
+    scale_from_env(|var| std::env::var(var).ok())
+}
+
+pub(crate) fn scale_from_env<F>(var: F) -> Option<f64>
+where F: Fn(&str) -> Option<String> {
+    let factor = |name| var(name)
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .filter(|f| f.is_finite() && *f > 0.0);
+    match (factor("GDK_SCALE"), factor("GDK_DPI_SCALE")) {
+        (None, None) => factor("QT_SCALE_FACTOR"),
+        (scale, dpi_scale) => Some(scale.unwrap_or(1.0) * dpi_scale.unwrap_or(1.0))
+    }
+}
+
+/// Merges overlapping rectangles until none of them overlap.
+pub(crate) fn merge_rects(mut rects: Vec<Layout>) -> Vec<Layout> {
+    let mut merged: Vec<Layout> = Vec::with_capacity(rects.len());
+    while let Some(mut rect) = rects.pop() {
+        while let Some(i) = merged.iter().position(|m| m.intersection(&rect).is_some()) {
+            rect = rect.union(&merged.swap_remove(i));
+        }
+        merged.push(rect);
+    }
+    merged
+}
+
+/// Returns the pixels of an offscreen surface of the scaled `size`,
+/// at least one in each direction.
+fn offscreen_pixels(size: Size) -> (i32, i32) {
+    let (w, h) = window_pixels(size);
+    (w.max(1), h.max(1))
+}
+
+/// Paints the `damage` of the widget at `layout` from its offscreen
+/// `surface` drawn at the scale factor `sf`.
+///
+/// `damage` is relative to the widget's position like in the
+/// `PaintContext`.
+fn blit_surface(surface: &cairo::ImageSurface, layout: Layout, damage: Layout, sf: f64, cr: &cairo::Context) {
+    cr.save();
+    cr.rectangle(layout.pos.x + damage.pos.x, layout.pos.y + damage.pos.y, damage.size.w, damage.size.h);
+    cr.clip();
+    cr.scale(1./sf, 1./sf);
+    cr.set_source_surface(surface, layout.pos.x * sf, layout.pos.y * sf);
+    cr.paint();
+    cr.restore();
+}
+
+/// Extends the scaled rectangle `(pos, size)` to full pixels
+fn pixel_bounds(pos: Coord, size: Size) -> (Coord, Size) {
+    let left = (pos.x + PIXEL_EPSILON).floor();
+    let top = (pos.y + PIXEL_EPSILON).floor();
+    let right = (pos.x + size.w - PIXEL_EPSILON).ceil();
+    let bottom = (pos.y + size.h - PIXEL_EPSILON).ceil();
+    (Coord { x: left, y: top }, Size { w: right - left, h: bottom - top })
+}
 
 /// Used to indicate if an event has been processed
 pub enum EventState {
@@ -48,13 +168,354 @@ impl EventState {
     }
 }
 
+/// How [`UI::next_event_auto()`](struct.UI.html#method.next_event_auto)
+/// chooses the timeout of the event loop
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum IdlePolicy {
+    /// Block until an event occurs when nothing is going on, wake up
+    /// at the frame rate `fps` while widgets are animating or
+    /// reminders or tooltips are pending. For standalone applications.
+    Adaptive { fps: f64 },
+    /// Never block, for plugin UIs idled by the host.
+    NonBlocking
+}
+
+impl Default for IdlePolicy {
+    fn default() -> IdlePolicy {
+        IdlePolicy::Adaptive { fps: 60.0 }
+    }
+}
+
+/// How the keyboard focus follows the pointer, see
+/// [`UI::set_focus_policy()`](struct.UI.html#method.set_focus_policy)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FocusPolicy {
+    /// The focus is only moved by the application and by the keyboard.
+    Manual,
+    /// Pressing the primary button on a widget focuses it, or its
+    /// innermost ancestor taking the focus, if it does not take the
+    /// focus itself.
+    ClickToFocus
+}
+
+impl Default for FocusPolicy {
+    fn default() -> FocusPolicy {
+        FocusPolicy::Manual
+    }
+}
+
+/// The phase of the delivery of an event along the event path
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Phase {
+    /// From the root towards the target, see `Widget::capture_event()`
+    Capture,
+    /// From the target towards the root, see `Widget::event()`
+    Bubble,
+    /// Towards the root after a widget has marked the event as
+    /// handled, see `Widget::observe_event()`
+    Observe
+}
+
+/// Metadata of a registered widget returned by
+/// [`UI::introspect()`](struct.UI.html#method.introspect)
+///
+/// Meant for external tooling like test drivers and UI inspectors.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WidgetInfo {
+    /// The id of the widget, see [`UI::widget_handle()`](struct.UI.html#method.widget_handle)
+    pub id: Id,
+    /// The widget's type as given by [`Widget::type_name()`](../widget/trait.Widget.html#method.type_name)
+    pub type_name: &'static str,
+    /// The name the widget has been registered with, if any
+    pub name: Option<String>,
+    /// The id of the layouting widget the widget is packed to, if any
+    pub parent: Option<Id>,
+    /// The geometry of the widget in window pixels
+    pub bounds: Layout,
+    /// `true` iff the widget can receive the keyboard focus
+    pub takes_focus: bool,
+    /// `true` iff the widget has the keyboard focus
+    pub has_focus: bool,
+    /// `true` iff the widget is sensitive
+    pub sensitive: bool,
+    /// `true` iff the widget is shown, see [`Widget::shown()`](../widget/trait.Widget.html#method.shown)
+    pub shown: bool
+}
+
+/// The user adjustable state of the layout returned by
+/// [`UI::snapshot_layout()`](struct.UI.html#method.snapshot_layout)
+///
+/// Host embedded plugin UIs can store it with the plugin state and
+/// restore it by [`UI::restore_layout()`](struct.UI.html#method.restore_layout)
+/// the next time the UI opens. Widgets are identified by their ids,
+/// so the snapshot can only be restored to a UI built the same way.
+/// Serializable by `serde` with the feature `serde`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayoutSnapshot {
+    /// The size `(w, h)` of the root widget, unscaled
+    pub size: (f64, f64),
+    /// The geometries `(x, y, w, h)` of the shown widgets, unscaled.
+    /// Informational, they follow from the other fields.
+    pub geometries: Vec<(Id, (f64, f64, f64, f64))>,
+    /// The focused widget, if any
+    pub focused: Option<Id>,
+    /// The scroll positions `(x, y)` of the widgets as given by
+    /// [`Widget::scroll_position()`](../widget/trait.Widget.html#method.scroll_position)
+    pub scroll_positions: Vec<(Id, (f64, f64))>,
+    /// The split ratios of the paned layouts
+    pub split_ratios: Vec<(Id, f64)>,
+    /// The active tabs of the tab layouts
+    pub active_tabs: Vec<(Id, usize)>
+}
+
+/// Constraints of the window size set by
+/// [`UI::set_size_hints()`](struct.UI.html#method.set_size_hints)
+///
+/// All lengths are unscaled, like the sizes of the widgets.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct SizeHints {
+    min: Option<Size>,
+    max: Option<Size>,
+    aspect_ratio: Option<f64>,
+    step: Option<f64>
+}
+
+impl SizeHints {
+    /// Returns the largest size fulfilling the hints that fits into `size`
+    ///
+    /// The minimal size takes precedence: if the aspect ratio or the
+    /// step would make the size smaller than it, the minimal size
+    /// wins, rounded up to the step.
+    fn constrain(&self, size: Size) -> Size {
+        let mut size = self.max.map_or(size, |max| Size { w: size.w.min(max.w), h: size.h.min(max.h) });
+        if let Some(ratio) = self.aspect_ratio {
+            if size.w > size.h * ratio {
+                size.w = size.h * ratio;
+            } else {
+                size.h = size.w / ratio;
+            }
+        }
+        let min = self.min.unwrap_or_default();
+        if let Some(step) = self.step {
+            let snap = |l: f64, min: f64| {
+                let snapped = (l / step + PIXEL_EPSILON).floor() * step;
+                if snapped < min - PIXEL_EPSILON {
+                    (min / step - PIXEL_EPSILON).ceil() * step
+                } else {
+                    snapped
+                }
+            };
+            size = Size { w: snap(size.w, min.w), h: snap(size.h, min.h) };
+        }
+        Size { w: size.w.max(min.w), h: size.h.max(min.h) }
+    }
+}
+
+/// Standard window shortcuts handled by the UI
+///
+/// The shortcuts are only handled if no widget has processed the key
+/// press. All of them are disabled by default, enable them by
+/// [`UI::set_window_shortcuts()`](struct.UI.html#method.set_window_shortcuts).
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct WindowShortcuts {
+    /// `Ctrl-W` and `Ctrl-Q` issue a close request, see
+    /// [`UI::close_request_issued()`](struct.UI.html#method.close_request_issued)
+    pub close: bool,
+    /// `F11` requests to toggle fullscreen, see
+    /// [`UI::fullscreen_toggle_requested()`](struct.UI.html#method.fullscreen_toggle_requested)
+    pub fullscreen: bool
+}
+
+/// The verdict of an event filter, see
+/// [`UI::add_event_filter()`](struct.UI.html#method.add_event_filter)
+#[derive(Copy, Clone, Debug)]
+pub enum FilterResult {
+    /// The event is passed on unchanged.
+    Pass,
+    /// The event is dropped.
+    Consume,
+    /// The event is replaced by the given event, which is passed on.
+    Replace(Event),
+    /// The event is delivered to the widget of the given id and
+    /// generation only, see [`FilterResult::redirect()`](#method.redirect).
+    Redirect(Id, u32)
+}
+
+impl FilterResult {
+    /// Redirects the event to the widget `widget`.
+    pub fn redirect<W: Widget>(widget: WidgetHandle<W>) -> FilterResult {
+        FilterResult::Redirect(widget.id(), widget.generation())
+    }
+}
+
+/// Identifies an event filter to remove it by
+/// [`UI::remove_event_filter()`](struct.UI.html#method.remove_event_filter)
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EventFilterId(usize);
+
+type EventFilter = Box<dyn FnMut(&Event) -> FilterResult>;
+
+/// A command sent into the `UI` from another thread, see
+/// [`UI::command_sender()`](struct.UI.html#method.command_sender)
+pub enum UiCommand {
+    /// Delivers the message to the widget of the given id and
+    /// generation by
+    /// [`Widget::message()`](../widget/trait.Widget.html#method.message),
+    /// see [`UiCommand::message()`](#method.message). The message is
+    /// dropped if the widget has been removed meanwhile.
+    Message(Id, u32, Box<dyn Any + Send>),
+    /// Redraws the whole window.
+    Redisplay
+}
+
+impl UiCommand {
+    /// Creates a command delivering `msg` to the widget `widget`.
+    pub fn message<W: Widget>(widget: WidgetHandle<W>, msg: Box<dyn Any + Send>) -> UiCommand {
+        UiCommand::Message(widget.id(), widget.generation(), msg)
+    }
+}
+
+/// A handle to a widget group, see [`UI::new_group()`](struct.UI.html#method.new_group)
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct WidgetGroup {
+    name: String
+}
+
+impl WidgetGroup {
+    /// Returns the name of the group.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl AsRef<str> for WidgetGroup {
+    fn as_ref(&self) -> &str {
+        &self.name
+    }
+}
+
+/// The horizontal direction in which the widgets are laid out
+///
+/// Set by [`UI::set_layout_direction()`](struct.UI.html#method.set_layout_direction)
+/// and, with the feature `text`, by the text direction of the
+/// [`UI::set_locale()`](struct.UI.html#method.set_locale).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LayoutDirection {
+    LeftToRight,
+    RightToLeft
+}
+
+impl Default for LayoutDirection {
+    fn default() -> LayoutDirection {
+        LayoutDirection::LeftToRight
+    }
+}
+
+/// The direction to move the focus to by
+/// [`UI::focus_direction()`](struct.UI.html#method.focus_direction)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FocusDirection {
+    Up,
+    Down,
+    Left,
+    Right
+}
+
+/// Returns the penalty of moving the focus from `from` to `to` in the
+/// direction `dir`, or `None` if `to` is not in that direction.
+///
+/// The distance across the direction counts twice, so that the
+/// focus is rather moved to a widget in the same row or column than
+/// to a nearer one diagonally.
+fn focus_distance(from: Layout, to: Layout, dir: FocusDirection) -> Option<f64> {
+    let center = |l: Layout| Coord { x: l.pos.x + l.size.w / 2., y: l.pos.y + l.size.h / 2. };
+    let (a, b) = (center(from), center(to));
+    let (along, across) = match dir {
+        FocusDirection::Up => (a.y - b.y, b.x - a.x),
+        FocusDirection::Down => (b.y - a.y, b.x - a.x),
+        FocusDirection::Left => (a.x - b.x, b.y - a.y),
+        FocusDirection::Right => (b.x - a.x, b.y - a.y)
+    };
+    if along > 0. { Some(along + 2. * across.abs()) } else { None }
+}
+
+/// The redisplays collected during `UI::batch()` or held back by
+/// `UI::set_max_fps()`
+#[derive(Default)]
+pub(crate) struct Damage {
+    full: bool,
+    rects: Vec<Layout>
+}
+
+impl Damage {
+    /// Notes a redisplay of the rectangle `rect`.
+    pub(crate) fn add(&mut self, rect: Layout) {
+        if !self.full {
+            self.rects.push(rect);
+        }
+    }
+
+    /// Notes a redisplay of the whole window.
+    pub(crate) fn add_full(&mut self) {
+        self.full = true;
+        self.rects.clear();
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        !self.full && self.rects.is_empty()
+    }
+
+    /// Empties the accumulator and returns the merged rectangles to
+    /// redisplay, or `None` if the whole window is to be redisplayed.
+    pub(crate) fn take(&mut self) -> Option<Vec<Layout>> {
+        let damage = std::mem::take(self);
+        if damage.full {
+            None
+        } else {
+            Some(merge_rects(damage.rects))
+        }
+    }
+}
+
+/// The redisplays and the layouting collected during `UI::batch()`
+#[derive(Default)]
+struct Batch {
+    depth: usize,
+    damage: Damage,
+    layout: bool
+}
+
+/// Takes the slot of a removed widget until it is reused
+#[derive(Default)]
+struct RemovedWidget {
+    stub: WidgetStub
+}
+
+impl Widget for RemovedWidget {
+    widget_stub!();
+}
+
+/// The location of a widget node
+///
+/// `tree` is `0` for the tree of the root widget, otherwise the id of
+/// the top node of an unlayouted or a parked tree. `path` are the
+/// indices of the children leading from the top node to the node.
+#[derive(Clone, Debug, Default)]
+struct NodePath {
+    tree: Id,
+    path: Vec<usize>
+}
+
 /// A node in the widget tree (internal use only)
 ///
 /// See ['layout'](../layout/index.html) for principles about widget layouting.
 pub struct WidgetNode {
     pub(crate) id: Id,
     pub(crate) layouter: Option<Box<dyn LayouterImpl>>,
-    pub(crate) children: Vec<WidgetNode>
+    pub(crate) constraints: Constraints,
+    pub(crate) children: Vec<WidgetNode>,
+    measured: Cell<Option<Size>>
 }
 
 impl WidgetNode {
@@ -62,7 +523,9 @@ impl WidgetNode {
         WidgetNode {
             id,
             layouter: None,
-            children: Vec::new()
+            constraints: Constraints::default(),
+            children: Vec::new(),
+            measured: Cell::new(None)
         }
     }
 
@@ -70,7 +533,9 @@ impl WidgetNode {
         WidgetNode {
             id,
             layouter: Some(L::new_implementor()),
-            children: Vec::new()
+            constraints: Constraints::default(),
+            children: Vec::new(),
+            measured: Cell::new(None)
         }
     }
 
@@ -78,39 +543,97 @@ impl WidgetNode {
         WidgetNode {
             id: 0,
             layouter: Some(L::new_implementor()),
-            children: Vec::new()
+            constraints: Constraints::default(),
+            children: Vec::new(),
+            measured: Cell::new(None)
         }
     }
 
-    /// Recursively completes the path to widget `id``
-    ///
-    /// The path is the way from `UI::root_widget` following by index
-    /// of `WidgetNode::children` Followed by `get_node_by_path()` it is
-    /// used to find the `WidgetNode` instance containing a certain
-    /// widget `Id`.
-    fn search(&self, mut path: VecDeque<usize>, id: Id) -> (VecDeque<usize>, bool) {
+    /// Returns the node at the end of `path`, the indices of the
+    /// children leading to it.
+    fn node_at(&self, path: &[usize]) -> &WidgetNode {
+        path.iter().fold(self, |node, &i| &node.children[i])
+    }
+
+    /// Returns the node at the end of `path` mutably.
+    fn node_at_mut(&mut self, path: &[usize]) -> &mut WidgetNode {
+        path.iter().fold(self, |node, &i| &mut node.children[i])
+    }
+
+    /// Inserts the locations of all the nodes of this subtree into
+    /// `index`, this node being at `path` in the tree `tree`.
+    fn collect_paths(&self, tree: Id, path: &mut Vec<usize>, index: &mut HashMap<Id, NodePath>) {
+        index.insert(self.id, NodePath { tree, path: path.clone() });
+        for (i, c) in self.children.iter().enumerate() {
+            path.push(i);
+            c.collect_paths(tree, path, index);
+            path.pop();
+        }
+    }
+
+    /// Returns `true` iff the widget `id` is in this subtree and it
+    /// and all its ancestors fulfill `cond`.
+    fn reaches(&self, id: Id, widgets: &[Box<dyn Widget>], cond: &dyn Fn(&dyn Widget) -> bool) -> bool {
+        if !cond(widgets[self.id].as_ref()) {
+            return false;
+        }
+        self.id == id || self.shown_children().any(|c| c.reaches(id, widgets, cond))
+    }
+
+    /// Returns the node of the widget `id` if it is in this subtree.
+    fn find(&self, id: Id) -> Option<&WidgetNode> {
         if self.id == id {
-            return (path, true);
+            return Some(self);
         }
-        for (i, c) in self.children.iter().enumerate() {
-            path.push_back(i);
-            let (p, found) = c.search(path, id);
-            if found {
-                return (p, true);
-            }
-            path = p;
-            path.pop_back();
+        self.children.iter().find_map(|c| c.find(id))
+    }
+
+    /// Calls `f` for each node of this subtree.
+    fn visit(&self, f: &mut dyn FnMut(&WidgetNode)) {
+        f(self);
+        for c in self.children.iter() {
+            c.visit(f);
+        }
+    }
+
+    /// Calls `f` for each node of this subtree.
+    fn visit_mut(&mut self, f: &mut dyn FnMut(&mut WidgetNode)) {
+        f(self);
+        for c in self.children.iter_mut() {
+            c.visit_mut(f);
+        }
+    }
+
+    /// Inserts the parent of each widget of this subtree into `parents`.
+    fn collect_parents(&self, parents: &mut HashMap<Id, Id>) {
+        for c in self.children.iter() {
+            parents.insert(c.id, self.id);
+            c.collect_parents(parents);
         }
-        (path, false)
     }
 
-    /// Recursively follows the path to the end finally returning the
-    /// final node. Takes a path set up by `search()`.
-    fn get_node_by_path(&mut self, mut path: VecDeque<usize>) -> &mut WidgetNode {
-        let index = path.pop_front();
-        match index {
-            None => self,
-            Some(i) => self.children[i].get_node_by_path(path)
+    /// Returns `true` iff any widget below this node fulfills `cond`.
+    fn any_descendant(&self, widgets: &[Box<dyn Widget>], cond: &dyn Fn(&dyn Widget) -> bool) -> bool {
+        self.shown_children().any(|c| cond(widgets[c.id].as_ref()) || c.any_descendant(widgets, cond))
+    }
+
+    /// Returns the children that the layouter shows at the moment,
+    /// regardless of their visibility.
+    fn shown_children(&self) -> impl Iterator<Item = &WidgetNode> {
+        self.children.iter().enumerate()
+            .filter(move |(i, _)| self.layouter.as_ref().map_or(true, |l| l.shows_child(*i)))
+            .map(|(_, c)| c)
+    }
+
+    /// Inserts the ids of all the widgets of this subtree that are
+    /// shown into `shown`.
+    fn collect_shown(&self, widgets: &[Box<dyn Widget>], shown: &mut HashSet<Id>) {
+        if !widgets[self.id].is_visible() {
+            return;
+        }
+        shown.insert(self.id);
+        for c in self.shown_children() {
+            c.collect_shown(widgets, shown);
         }
     }
 
@@ -120,6 +643,12 @@ impl WidgetNode {
             .downcast_mut::<L::Implementor>().expect("downcast of layouter failed")
     }
 
+    pub(crate) fn layouter_impl_ref<L: Layouter>(&self) -> &L::Implementor {
+        self.layouter
+            .as_deref().expect("no layouter found")
+            .downcast_ref::<L::Implementor>().expect("downcast of layouter failed")
+    }
+
     pub(crate) fn pack<L: Layouter, W: Widget>(&mut self, widget: Id, mut parent: LayoutWidgetHandle<L, W>, target: L::Target) {
         let subnode_id = match self.children.iter().position(|ref node| node.id == widget) {
             Some(id) => id,
@@ -139,10 +668,24 @@ impl WidgetNode {
         }
     }
 
+    /// Calculates the minimal sizes of the widgets of this subtree.
+    ///
+    /// The sizes of subtrees that have been measured before and have
+    /// not been invalidated since are taken from the last measurement.
     pub(crate) fn calc_widget_sizes (&self, widgets: &mut Vec<Box<dyn Widget>>) -> Size {
+        if let Some(size) = self.measured.get() {
+            self.restore_measured(widgets);
+            return size;
+        }
+        let size = self.measure(widgets);
+        self.measured.set(Some(size));
+        size
+    }
+
+    fn measure(&self, widgets: &mut Vec<Box<dyn Widget>>) -> Size {
         if self.children.is_empty() {
             let wgt = &mut widgets[self.id];
-            let size = wgt.min_size();
+            let size = self.constraints.apply_min(wgt.min_size());
             wgt.set_size(&size);
 
             return size;
@@ -152,12 +695,31 @@ impl WidgetNode {
             .as_ref()
             .expect("::calc_widget_sizes() no layouter found")
             .calc_size(widgets, &self.children);
+        let size = self.constraints.apply_min(size);
 
         widgets[self.id].set_size(&size);
 
         size
     }
 
+    /// Sets the sizes of the visible widgets of this subtree to
+    /// their last measurement.
+    fn restore_measured(&self, widgets: &mut Vec<Box<dyn Widget>>) {
+        if let Some(size) = self.measured.get() {
+            widgets[self.id].set_size(&size);
+        }
+        for c in self.shown_children() {
+            if widgets[c.id].is_visible() {
+                c.restore_measured(widgets);
+            }
+        }
+    }
+
+    /// Drops the measurements of all the widgets of this subtree.
+    fn invalidate_measures(&self) {
+        self.visit(&mut |n| n.measured.set(None));
+    }
+
     pub(crate) fn detect_expandables(&self, widgets: &mut Vec<Box<dyn Widget>>) -> (bool, bool) {
         if self.children.is_empty() {
             let wgt = &widgets[self.id];
@@ -167,7 +729,10 @@ impl WidgetNode {
         let mut width_exp = false;
         let mut height_exp = false;
 
-        for c in self.children.iter() {
+        for c in self.shown_children() {
+            if !widgets[c.id].is_visible() {
+                continue;
+            }
             let (we, he) = c.detect_expandables(widgets);
             width_exp = we || width_exp;
             height_exp = he || height_exp;
@@ -181,6 +746,52 @@ impl WidgetNode {
     }
 }
 
+/// Time spent in the phases of a frame
+///
+/// Only available with the feature `frame-stats`. A frame ends with
+/// the drawing of the window, so `layouting` and `event_dispatch` are
+/// the times spent since the end of the previous frame. Retrieved by
+/// [`UI::frame_stats()`](struct.UI.html#method.frame_stats).
+#[cfg(feature = "frame-stats")]
+#[derive(Clone, Debug, Default)]
+pub struct FrameStats {
+    /// Time spent in `UI::do_layout()`
+    pub layouting: Duration,
+    /// Time spent dispatching events to the widgets
+    pub event_dispatch: Duration,
+    /// Time spent drawing the window
+    pub exposing: Duration,
+    /// Time spent in each widget's `exposed()` in the order of the calls
+    pub widget_exposes: Vec<(Id, Duration)>
+}
+
+/// Counters and times of a widget for profiling interactions
+///
+/// Only available with the feature `widget-stats`. Retrieved by
+/// [`UI::widget_stats()`](struct.UI.html#method.widget_stats).
+#[cfg(feature = "widget-stats")]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WidgetStats {
+    /// Number of the events passed to the widget's `event()`
+    pub events_received: u64,
+    /// Number of the events the widget has processed
+    pub events_processed: u64,
+    /// Number of the widget's repaint requests, counted only while
+    /// the view is mapped
+    pub repaints_requested: u64,
+    /// Number of the calls of the widget's `exposed()`
+    pub exposes: u64,
+    /// Time spent in the widget's `event()`
+    pub event_time: Duration,
+    /// Time spent in the widget's `exposed()`
+    pub expose_time: Duration
+}
+
+type DeferredJob<RW> = Box<dyn FnOnce(&mut UI<RW>)>;
+
+/// Drains a command channel and dispatches the commands
+type CommandDrain<RW> = Box<dyn FnMut(&mut UI<RW>)>;
+
 /// The central interface between application, widgets and the windowing system
 ///
 /// The `UI` has the following responsibilities.
@@ -228,17 +839,161 @@ impl WidgetNode {
 /// dragging started, receives, mouse events and key events first,
 /// until the dragging stops.
 ///
+/// ## Highlights
+///
+/// The `UI` can draw a highlight frame on top of a widget using
+/// [`set_highlighted()`](#method.set_highlighted) for transient
+/// emphasis, for example to mark a MIDI learn target. Using
+/// [`flash_highlight()`](#method.flash_highlight) the highlight is
+/// removed after a given time. The color is taken from the
+/// [`Theme`](../theme/struct.Theme.html).
+///
+/// ## Tooltips
+///
+/// When the pointer rests on a widget that returns a text by
+/// [`Widget::tooltip()`](../widget/trait.Widget.html#method.tooltip)
+/// for the time set by
+/// [`set_tooltip_delay()`](#method.set_tooltip_delay), the `UI`
+/// shows the text in a tooltip near the pointer. The tooltip is
+/// hidden as soon as the pointer moves or leaves the widget.
+///
+/// ## Mouse cursor
+///
+/// Whenever the widget under the pointer changes, the `UI` sets the
+/// mouse cursor to the one returned by the widget's
+/// [`Widget::cursor()`](../widget/trait.Widget.html#method.cursor),
+/// see [`cursor()`](#method.cursor).
+///
+/// # Widget groups
+///
+/// Widgets can be added to named groups using
+/// [`add_to_group()`](#method.add_to_group). Then the visibility,
+/// the sensitivity or the highlight of all the widgets of the group
+/// can be changed in one call, for example to hide all the
+/// "advanced" controls. The group methods take the name of the group
+/// or a [`WidgetGroup`](struct.WidgetGroup.html) handle returned by
+/// [`new_group()`](#method.new_group).
+///
+/// # Named widgets
+///
+/// Widgets registered by [`new_named_widget()`](#method.new_named_widget)
+/// can be looked up by their name using
+/// [`widget_by_name()`](#method.widget_by_name), so that generated
+/// UIs can reference widgets without passing `WidgetHandle`s around.
+///
+/// # Deferred construction
+///
+/// Big UIs can take a long time to be constructed. In order not to
+/// block the host, the construction of the widgets can be split into
+/// jobs queued by [`defer()`](#method.defer). They are run one after
+/// another in the subsequent calls of
+/// [`next_event()`](#method.next_event), while the window shows what
+/// has been constructed so far, for example a "loading" root widget.
+///
+/// # Swapping pages
+///
+/// Using [`swap_root_layout()`](#method.swap_root_layout) the whole
+/// content of the root layout can be replaced by another layout at
+/// runtime, for example to switch between the pages of a wizard.
+/// The widgets of the swapped out content stay registered and can be
+/// swapped in again later.
 pub struct UI<RW: Widget + 'static> {
     widgets: Vec<Box<dyn Widget>>,
+    generations: Vec<u32>,
+    free_slots: Vec<Id>,
+    batch: RefCell<Batch>,
     root_widget_node: WidgetNode,
     unlayouted_nodes: HashMap<Id, WidgetNode>,
+    parked_nodes: HashMap<Id, WidgetNode>,
+    node_index: HashMap<Id, NodePath>,
+    shown_widgets: Option<HashSet<Id>>,
+    realized_widgets: HashSet<Id>,
+    groups: HashMap<String, Vec<Id>>,
+    names: HashMap<String, Id>,
+    highlighted: HashSet<Id>,
+    theme: Theme,
+    style: Style,
+    style_overrides: HashMap<Id, StyleOverride>,
+    interaction: InteractionSettings,
+    #[cfg(feature = "text")]
+    text_metrics: TextMetrics,
     root_widget_handle: LayoutWidgetHandle<VerticalLayouter, RW>,
     view: PuglViewFFI,
     focused_widget: Id,
-    widget_under_pointer: Id,
-    drag_ongoing: bool,
+    pointer_pos: Coord,
+    cursor: Cell<Cursor>,
+    relayout_pending: bool,
+    fit_window_on_resize: bool,
+    window_config: Option<WindowConfig>,
+    popups: Vec<(PopupId, Box<dyn Popup>)>,
+    next_popup_id: PopupId,
+    dismissed_popups: Vec<PopupId>,
+    hit_path: RefCell<Option<Vec<(usize, Id)>>>,
+    spatial_index: Option<SpatialIndex>,
+    tooltip: TooltipManager,
+    input_state: InputState,
+    drag_data: Option<Box<dyn Any>>,
+    gesture_widget: Option<Id>,
+    pressed_widget: Option<Id>,
+    event_marked_handled: bool,
+    pointer_gestures: GestureRecognizer,
+    kinetic_scroller: KineticScroller,
+    dragged_divider: Option<Id>,
+    drag_predictor: DragPredictor,
+    sanitizer: InputSanitizer,
     have_focus: bool,
+    focus_change: Option<bool>,
+    keyboard_mode: bool,
+    focus_overlay: bool,
+    arrow_navigation: bool,
+    high_contrast: bool,
     close_request_issued: bool,
+    close_veto_cycles: usize,
+    pending_close_request: Option<usize>,
+    window_shortcuts: WindowShortcuts,
+    size_hints: SizeHints,
+    fullscreen_toggle_request: bool,
+    clipboard: Option<String>,
+    scale_query: Option<Box<dyn FnMut() -> Option<f64>>>,
+    follow_monitor_scale: bool,
+    start_time: Instant,
+    mapped: bool,
+    running_reminders: HashMap<Id, f64>,
+    widget_timers: HashMap<usize, WidgetTimer>,
+    next_widget_timer: usize,
+    animating_widgets: HashSet<Id>,
+    last_frame: Option<Instant>,
+    painted_hashes: HashMap<Id, (u64, VisualState)>,
+    render_caches: HashMap<Id, cairo::ImageSurface>,
+    retained_surfaces: HashMap<Id, cairo::ImageSurface>,
+    #[cfg(feature = "cairo-helpers")]
+    assets: Assets,
+    frame_time: f64,
+    previous_frame_time: f64,
+    frame_exposed: bool,
+    max_fps: Option<f64>,
+    throttled: RefCell<Damage>,
+    deferred_jobs: VecDeque<DeferredJob<RW>>,
+    messages: VecDeque<(Id, Box<dyn Any>)>,
+    command_drains: Vec<CommandDrain<RW>>,
+    command_sender: Option<Sender<UiCommand>>,
+    in_update: bool,
+    motion_coalescing: bool,
+    pending_motion: Option<Event>,
+    expired_timers: Vec<usize>,
+    deferred_budget: f64,
+    idle_policy: IdlePolicy,
+    focus_policy: FocusPolicy,
+    layout_direction: LayoutDirection,
+    recorder: Option<EventRecorder>,
+    event_filters: Vec<(EventFilterId, EventFilter)>,
+    next_event_filter: usize,
+    #[cfg(feature = "frame-stats")]
+    current_frame_stats: FrameStats,
+    #[cfg(feature = "frame-stats")]
+    last_frame_stats: FrameStats,
+    #[cfg(feature = "widget-stats")]
+    widget_stats: HashMap<Id, WidgetStats>,
 
     scale_factor: f64
 }
@@ -254,18 +1009,119 @@ impl<RW: Widget + 'static> UI<RW> {
             view,
             root_widget_node: WidgetNode::root::<VerticalLayouter>(),
             unlayouted_nodes: HashMap::new(),
+            parked_nodes: HashMap::new(),
+            node_index: std::iter::once((0, NodePath::default())).collect(),
+            shown_widgets: None,
+            realized_widgets: HashSet::new(),
+            groups: HashMap::new(),
+            names: HashMap::new(),
+            highlighted: HashSet::new(),
+            theme: Theme::default(),
+            style: Style::default(),
+            style_overrides: HashMap::new(),
+            interaction: InteractionSettings::default(),
+            #[cfg(feature = "text")]
+            text_metrics: TextMetrics::new(),
             root_widget_handle: LayoutWidgetHandle::<VerticalLayouter, RW>::new(WidgetHandle::new(0)),
             focused_widget: 0,
             widgets: vec![root_widget],
-            drag_ongoing: false,
+            generations: vec![0],
+            free_slots: Vec::new(),
+            batch: RefCell::new(Batch::default()),
+            input_state: InputState::default(),
+            drag_data: None,
+            gesture_widget: None,
+            pressed_widget: None,
+            relayout_pending: false,
+            fit_window_on_resize: false,
+            window_config: None,
+            popups: Vec::new(),
+            next_popup_id: 0,
+            dismissed_popups: Vec::new(),
+            hit_path: RefCell::new(None),
+            spatial_index: None,
+            event_marked_handled: false,
+            pointer_gestures: GestureRecognizer::new(),
+            kinetic_scroller: KineticScroller::new(),
+            dragged_divider: None,
+            drag_predictor: DragPredictor::new(),
+            sanitizer: InputSanitizer::default(),
             have_focus: false,
-            widget_under_pointer: 0,
+            focus_change: None,
+            keyboard_mode: false,
+            focus_overlay: false,
+            arrow_navigation: false,
+            high_contrast: false,
+            pointer_pos: Coord::default(),
+            cursor: Cell::new(Cursor::Arrow),
+            tooltip: TooltipManager::new(),
             close_request_issued: false,
+            close_veto_cycles: 0,
+            pending_close_request: None,
+            window_shortcuts: WindowShortcuts::default(),
+            size_hints: SizeHints::default(),
+            fullscreen_toggle_request: false,
+            clipboard: None,
+            scale_query: None,
+            follow_monitor_scale: false,
+            start_time: Instant::now(),
+            mapped: true,
+            running_reminders: HashMap::new(),
+            widget_timers: HashMap::new(),
+            next_widget_timer: WIDGET_TIMER_BASE,
+            animating_widgets: HashSet::new(),
+            last_frame: None,
+            painted_hashes: HashMap::new(),
+            render_caches: HashMap::new(),
+            retained_surfaces: HashMap::new(),
+            #[cfg(feature = "cairo-helpers")]
+            assets: Assets::new(1.0),
+            frame_time: 0.0,
+            previous_frame_time: 0.0,
+            frame_exposed: false,
+            max_fps: None,
+            throttled: RefCell::new(Damage::default()),
+            deferred_jobs: VecDeque::new(),
+            messages: VecDeque::new(),
+            command_drains: Vec::new(),
+            command_sender: None,
+            in_update: false,
+            motion_coalescing: true,
+            pending_motion: None,
+            expired_timers: Vec::new(),
+            deferred_budget: 0.01,
+            idle_policy: IdlePolicy::default(),
+            focus_policy: FocusPolicy::default(),
+            layout_direction: LayoutDirection::default(),
+            recorder: None,
+            event_filters: Vec::new(),
+            next_event_filter: 0,
+            #[cfg(feature = "frame-stats")]
+            current_frame_stats: FrameStats::default(),
+            #[cfg(feature = "frame-stats")]
+            last_frame_stats: FrameStats::default(),
+            #[cfg(feature = "widget-stats")]
+            widget_stats: HashMap::new(),
 
             scale_factor: 1.0
         }
     }
 
+    /// Creates the view of a new `UI` with the root widget
+    /// `root_widget` embedded into the window `parent`
+    ///
+    /// This is the way to go for plugin GUIs, as CLAP, VST3 and LV2
+    /// hosts pass the native handle of the window the GUI is to be
+    /// embedded into. See the [`embed`](../embed/index.html) module.
+    ///
+    /// ```ignore
+    /// let mut view = UI::embed_into(ParentWindow::from_x11(window), Box::new(RootWidget::default()));
+    /// let ui = view.handle();
+    /// ```
+    pub fn embed_into(parent: ParentWindow, root_widget: Box<RW>) -> Box<PuglView<UI<RW>>> {
+        PuglView::new(parent.as_ptr(), |pv| UI::new(pv, root_widget))
+    }
+
     /// Creates a new `UI` which is scaled by the `scale_factor`
     ///
     /// Widgets don't know about the scale factor. They can do their
@@ -275,15 +1131,142 @@ impl<RW: Widget + 'static> UI<RW> {
     pub fn new_scaled(view: PuglViewFFI, root_widget: Box<RW>, scale_factor: f64) -> UI<RW> {
         let mut ui = UI::new(view, root_widget);
         ui.scale_factor = scale_factor;
+        #[cfg(feature = "cairo-helpers")]
+        ui.assets.prepare(scale_factor);
+        ui
+    }
+
+    /// Creates a new `UI` which is scaled by the scale factor of the
+    /// desktop as detected by [`system_scale_factor()`](fn.system_scale_factor.html),
+    /// or `1.0` if none is detected.
+    ///
+    /// Once the window is configured, the `UI` follows the scale
+    /// factor pugl reports for the monitor the window is on. The
+    /// application can still override the scale by
+    /// [`set_scale_factor()`](#method.set_scale_factor), for example
+    /// from a user setting.
+    pub fn new_auto_scaled(view: PuglViewFFI, root_widget: Box<RW>) -> UI<RW> {
+        let mut ui = UI::new_scaled(view, root_widget, system_scale_factor().unwrap_or(1.0));
+        ui.follow_monitor_scale = true;
         ui
     }
 
-    fn push_widget<W: Widget>(&mut self, widget: Box<W>) -> Id {
+    fn push_widget<W: Widget>(&mut self, mut widget: Box<W>) -> Id {
+        widget.set_interaction_settings(self.interaction);
+        widget.on_added();
+        if let Some(id) = self.free_slots.pop() {
+            self.widgets[id] = widget;
+            return id;
+        }
         let id = self.widgets.len();
         self.widgets.push(widget);
+        self.generations.push(0);
         id
     }
 
+    /// Returns a handle to the widget `id` of the current generation
+    /// of its slot.
+    fn handle_of<W: Widget>(&self, id: Id) -> WidgetHandle<W> {
+        WidgetHandle::with_generation(id, self.generations[id])
+    }
+
+    /// Returns the id of the widget of `handle`.
+    ///
+    /// Panics if the widget has been removed.
+    fn live_id<W: Widget>(&self, handle: WidgetHandle<W>) -> Id {
+        if !self.is_alive(handle) {
+            panic!("stale widget handle: widget {} has been removed", handle.id());
+        }
+        handle.id()
+    }
+
+    /// Returns `true` iff the widget of `handle` has not been removed
+    /// by [`remove_widget()`](#method.remove_widget).
+    pub fn is_alive<W: Widget>(&self, handle: WidgetHandle<W>) -> bool {
+        self.slot_alive(handle.id(), handle.generation())
+    }
+
+    /// Returns `true` iff the slot `id` is in its generation `generation`.
+    fn slot_alive(&self, id: Id, generation: u32) -> bool {
+        self.generations.get(id) == Some(&generation)
+    }
+
+    /// Removes the widget `widget` and all the widgets packed into it
+    /// from the `UI`.
+    ///
+    /// The widget must not be packed into a layout, but it may have
+    /// been parked by [`swap_root_layout()`](#method.swap_root_layout).
+    /// The slots of the removed widgets are reused by widgets
+    /// registered later. Handles to the removed widgets become stale:
+    /// [`is_alive()`](#method.is_alive) returns `false` for them and
+    /// using them with [`widget()`](#method.widget) panics.
+    pub fn remove_widget<W: Widget>(&mut self, widget: WidgetHandle<W>) {
+        let id = self.live_id(widget);
+        let node = self.unlayouted_nodes.remove(&id)
+            .or_else(|| self.parked_nodes.remove(&id))
+            .expect("only widgets not packed into a layout can be removed");
+        let mut removed = Vec::new();
+        node.visit(&mut |n| removed.push(n.id));
+        for id in removed {
+            self.widgets[id] = Box::new(RemovedWidget::default());
+            self.generations[id] += 1;
+            self.free_slots.push(id);
+            self.forget_widget(id);
+        }
+    }
+
+    /// Drops all the references of the `UI` to the widget `id`.
+    fn forget_widget(&mut self, id: Id) {
+        self.node_index.remove(&id);
+        self.names.retain(|_, wid| *wid != id);
+        for members in self.groups.values_mut() {
+            members.retain(|wid| *wid != id);
+        }
+        self.highlighted.remove(&id);
+        self.style_overrides.remove(&id);
+        self.realized_widgets.remove(&id);
+        if let Some(shown) = self.shown_widgets.as_mut() {
+            shown.remove(&id);
+        }
+        self.painted_hashes.remove(&id);
+        self.render_caches.remove(&id);
+        self.retained_surfaces.remove(&id);
+        self.animating_widgets.remove(&id);
+        self.messages.retain(|(wid, _)| *wid != id);
+        if self.running_reminders.remove(&id).is_some() {
+            self.stop_timer(id);
+        }
+        let timers: Vec<usize> = self.widget_timers.iter()
+            .filter(|(_, timer)| timer.widget == id)
+            .map(|(timer_id, _)| *timer_id)
+            .collect();
+        for timer_id in timers {
+            self.widget_timers.remove(&timer_id);
+            self.stop_timer(timer_id);
+        }
+        #[cfg(feature = "widget-stats")]
+        self.widget_stats.remove(&id);
+        if self.focused_widget == id {
+            self.focused_widget = 0;
+        }
+        if self.gesture_widget == Some(id) {
+            self.gesture_widget = None;
+        }
+        if self.pressed_widget == Some(id) {
+            self.pressed_widget = None;
+        }
+        if self.kinetic_scroller.widget() == Some(id) {
+            self.kinetic_scroller.cancel();
+        }
+        if self.dragged_divider == Some(id) {
+            self.dragged_divider = None;
+        }
+        self.input_state = self.input_state.forget(id);
+        if self.input_state.drag_source().is_none() {
+            self.drag_data = None;
+        }
+    }
+
     /// Registers a new widget in the `UI`.
     ///
     /// The instance of the widget must be passed heap allocated in a `Box`.
@@ -291,8 +1274,68 @@ impl<RW: Widget + 'static> UI<RW> {
     pub fn new_widget<W: Widget>(&mut self, widget: Box<W>) -> WidgetHandle<W> {
         let id = self.push_widget(widget);
         self.unlayouted_nodes.insert(id, WidgetNode::new_leaf(id));
+        self.node_index.insert(id, NodePath { tree: id, path: Vec::new() });
+
+        self.handle_of(id)
+    }
+
+    /// Registers a new widget constructed by `construct` in the `UI`.
+    ///
+    /// The closure gets a [`UiContext`](../widget/struct.UiContext.html)
+    /// giving access to the theme, the scale factor and text
+    /// measuring, for example to calculate the minimum size of the
+    /// widget. Returns a `WidgetHandle` to the widget.
+    pub fn new_widget_with_ctx<W, F>(&mut self, construct: F) -> WidgetHandle<W>
+    where W: Widget,
+          F: FnOnce(&UiContext) -> Box<W> {
+        let widget = {
+            let ctx = UiContext {
+                theme: &self.theme,
+                style: &self.style,
+                scale_factor: self.scale_factor,
+                #[cfg(feature = "text")]
+                text_metrics: &self.text_metrics
+            };
+            construct(&ctx)
+        };
+        self.new_widget(widget)
+    }
+
+    /// Registers a new widget in the `UI` under the name `name`.
+    ///
+    /// Like [`new_widget()`](#method.new_widget) but the widget can
+    /// also be looked up by [`widget_by_name()`](#method.widget_by_name).
+    ///
+    /// Panics if there is already a widget of the same name.
+    pub fn new_named_widget<W: Widget>(&mut self, name: &str, widget: Box<W>) -> WidgetHandle<W> {
+        if self.names.contains_key(name) {
+            panic!("widget name {} already taken", name);
+        }
+        let handle = self.new_widget(widget);
+        self.names.insert(String::from(name), handle.id());
+        handle
+    }
 
-        WidgetHandle::<W>::new(id)
+    /// Returns the handle of the widget named `name`.
+    ///
+    /// Returns `None` if there is no widget of that name or if it is
+    /// not of type `W`.
+    pub fn widget_handle_by_name<W: Widget>(&self, name: &str) -> Option<WidgetHandle<W>> {
+        let id = *self.names.get(name)?;
+        if self.widgets[id].downcast_ref::<W>().is_some() {
+            Some(self.handle_of(id))
+        } else {
+            None
+        }
+    }
+
+    /// Returns a mutable reference to the widget named `name`.
+    ///
+    /// Returns `None` if there is no widget of that name or if it is
+    /// not of type `W`.
+    pub fn widget_by_name<W: Widget>(&mut self, name: &str) -> Option<&mut W> {
+        let id = *self.names.get(name)?;
+        self.widgets[id].downcast_mut::<W>()
     }
 
     /// Creates a new `LayoutingWidget` for a `Layouter` of type `L` and registers it to the UI/
@@ -302,7 +1345,25 @@ impl<RW: Widget + 'static> UI<RW> {
     where L: Layouter {
         let id = self.push_widget(Box::new(LayoutWidget::default()));
         self.unlayouted_nodes.insert(id, WidgetNode::new_node::<L>(id));
-        LayoutWidgetHandle::<L, LayoutWidget>::new(WidgetHandle::new(id))
+        self.node_index.insert(id, NodePath { tree: id, path: Vec::new() });
+        LayoutWidgetHandle::<L, LayoutWidget>::new(self.handle_of(id))
+    }
+
+    /// Creates a new frame drawing a border with the caption `caption`
+    /// around its child, see [`FrameLayouter`](../layout/framelayout/struct.FrameLayouter.html).
+    ///
+    /// The space for the caption is measured with the font of the
+    /// `UI`'s style. Without the feature `text` the caption is not
+    /// drawn and no space is reserved for it.
+    pub fn new_frame(&mut self, caption: &str) -> LayoutWidgetHandle<FrameLayouter, LayoutWidget> {
+        let frame = self.new_layouter::<FrameLayouter>();
+        #[cfg(feature = "text")]
+        {
+            let height = self.text_metrics.measure(caption, &self.style.font).h;
+            self.layouter(frame).set_caption_height(height);
+        }
+        self.widget(frame.widget()).set_caption(caption);
+        frame
     }
 
     /// Adds a spacing widget to a layouter.
@@ -320,14 +1381,65 @@ impl<RW: Widget + 'static> UI<RW> {
     where L: Layouter,
           W: Widget,
           PW: Widget {
+        self.pack_to_layout_with(widget, parent, target, Constraints::default());
+    }
 
-        let id = widget.id();
+    /// Adds the `widget` to a `layout` according to the layout
+    /// `target` applying the size [`Constraints`](../layout/struct.Constraints.html)
+    /// `constraints` to the widget.
+    pub fn pack_to_layout_with<L, W, PW>(&mut self,
+                                         widget: WidgetHandle<W>,
+                                         parent: LayoutWidgetHandle<L, PW>,
+                                         target: L::Target,
+                                         constraints: Constraints)
+    where L: Layouter,
+          W: Widget,
+          PW: Widget {
+        let id = self.live_id(widget);
+        self.pack_id_to_layout(id, parent, target, constraints);
+    }
 
-        let new_node = self.unlayouted_nodes.remove(&id).expect("widget already layouted?");
-        let node = self.find_node(parent.widget().id());
+    pub(crate) fn pack_id_to_layout<L, PW>(&mut self, id: Id, parent: LayoutWidgetHandle<L, PW>, target: L::Target, constraints: Constraints)
+    where L: Layouter,
+          PW: Widget {
+        let mut new_node = self.unlayouted_nodes.remove(&id)
+            .or_else(|| self.parked_nodes.remove(&id))
+            .expect("widget already layouted?");
+        new_node.constraints = constraints;
+        new_node.invalidate_measures();
+        let parent_id = self.live_id(parent.widget());
+        self.invalidate_measure_of(parent_id);
+        let location = self.node_index[&parent_id].clone();
+        let node = self.find_node(parent_id);
 
         node.children.push(new_node);
         node.pack(id, parent, target);
+
+        let index = node.children.len() - 1;
+        let mut path = location.path;
+        path.push(index);
+        let mut paths = HashMap::new();
+        node.children[index].collect_paths(location.tree, &mut path, &mut paths);
+        self.node_index.extend(paths);
+        if let Some(index) = self.spatial_index.as_mut() {
+            index.invalidate();
+        }
+    }
+
+    /// Drops the measurements of the widget `id` and its ancestors,
+    /// so that they are measured at the next layouting.
+    fn invalidate_measure_of(&self, id: Id) {
+        let location = match self.node_index.get(&id) {
+            Some(location) => location,
+            None => return
+        };
+        if let Some(mut node) = self.tree(location.tree) {
+            node.measured.set(None);
+            for &i in location.path.iter() {
+                node = &node.children[i];
+                node.measured.set(None);
+            }
+        }
     }
 
     /// Performs the layouting of the widgets.
@@ -335,10 +1447,40 @@ impl<RW: Widget + 'static> UI<RW> {
     /// This must be done before the view is realized (or window is
     /// shown). All registered widgets should have been packed to a
     /// layout before.
+    ///
+    /// All the widgets are measured. When the window is resized,
+    /// only the widgets that have asked for it by
+    /// [`Widget::ask_for_relayout()`](../widget/trait.Widget.html#method.ask_for_relayout),
+    /// the ones that have been packed since and their ancestors are
+    /// measured again. During a [`batch()`](#method.batch) the
+    /// layouting is postponed to the end of the batch.
     pub fn do_layout(&mut self) {
+        self.root_widget_node.invalidate_measures();
+        {
+            let mut batch = self.batch.borrow_mut();
+            if batch.depth > 0 {
+                batch.layout = true;
+                return;
+            }
+        }
+        self.relayout();
+    }
+
+    /// Performs the layouting re-measuring only the invalidated subtrees.
+    fn relayout(&mut self) {
+        self.relayout_pending = false;
+        for id in 0..self.widgets.len() {
+            if self.widgets[id].needs_relayout() {
+                self.invalidate_measure_of(id);
+            }
+        }
+        #[cfg(feature = "frame-stats")]
+        let start = Instant::now();
         if !self.unlayouted_nodes.is_empty() {
             eprintln!("WARNING: Rendering layout with {} unlayouted widgets!", self.unlayouted_nodes.len());
         }
+        self.render_caches.clear();
+        let orig_layouts: Vec<Layout> = self.widgets.iter().map(|w| w.layout()).collect();
         let orig_size = self.widgets[0].size();
         let new_size = {
             let widgets = &mut self.widgets;
@@ -355,183 +1497,3139 @@ impl<RW: Widget + 'static> UI<RW> {
             new_size
         };
         self.widgets[0].set_layout(&Layout { pos: Default::default(), size: new_size });
+        self.notify_shown_changes();
+        if self.layout_direction == LayoutDirection::RightToLeft {
+            let shown = self.shown_widgets.as_ref().expect("shown widgets not collected");
+            for &id in shown.iter().filter(|&&id| id != 0) {
+                let mut layout = self.widgets[id].layout();
+                layout.pos.x = new_size.w - layout.pos.x - layout.size.w;
+                self.widgets[id].set_layout(&layout);
+            }
+        }
+        self.notify_layout_changes(&orig_layouts);
+        self.rebuild_spatial_index();
+        #[cfg(feature = "frame-stats")]
+        {
+            self.current_frame_stats.layouting += start.elapsed();
+        }
+    }
+
+    /// Performs the layouting and posts redisplays of the areas the
+    /// widgets have left or moved to.
+    fn relayout_damaged(&mut self) {
+        let orig_layouts: Vec<Layout> = self.widgets.iter().map(|w| w.layout()).collect();
+        self.relayout();
+        let damage = self.widgets.iter().zip(orig_layouts.iter())
+            .filter(|(wgt, orig)| wgt.layout() != **orig)
+            .map(|(wgt, orig)| wgt.layout().union(orig))
+            .collect();
+        for rect in merge_rects(damage) {
+            self.post_layout_redisplay(rect);
+        }
+    }
+
+    /// Notifies the widgets that have been shown or hidden since the
+    /// last layouting.
+    ///
+    /// Nothing is notified on the first layouting.
+    fn notify_shown_changes(&mut self) {
+        let mut shown = HashSet::new();
+        self.root_widget_node.collect_shown(&self.widgets, &mut shown);
+        if let Some(previous) = self.shown_widgets.take() {
+            for id in shown.difference(&previous) {
+                self.widgets[*id].shown();
+            }
+            for id in previous.difference(&shown) {
+                self.widgets[*id].hidden();
+            }
+        }
+        self.shown_widgets = Some(shown);
+    }
+
+    /// Notifies the shown widgets whose layout differs from
+    /// `orig_layouts`.
+    fn notify_layout_changes(&mut self, orig_layouts: &[Layout]) {
+        let shown = self.shown_widgets.as_ref().expect("shown widgets not collected");
+        for (id, wgt) in self.widgets.iter_mut().enumerate() {
+            if shown.contains(&id) && wgt.layout() != orig_layouts[id] {
+                wgt.on_layout();
+            }
+        }
+    }
+
+    /// Returns the statistics of the last frame drawn
+    ///
+    /// Only available with the feature `frame-stats`. See [`FrameStats`](struct.FrameStats.html).
+    #[cfg(feature = "frame-stats")]
+    pub fn frame_stats(&self) -> &FrameStats {
+        &self.last_frame_stats
+    }
+
+    /// Returns the statistics of the widget `widget` since the
+    /// widget's registration or the last
+    /// [`reset_widget_stats()`](#method.reset_widget_stats).
+    ///
+    /// Only available with the feature `widget-stats`. See
+    /// [`WidgetStats`](struct.WidgetStats.html).
+    #[cfg(feature = "widget-stats")]
+    pub fn widget_stats<W: Widget>(&self, widget: WidgetHandle<W>) -> WidgetStats {
+        if !self.is_alive(widget) {
+            return WidgetStats::default();
+        }
+        self.widget_stats.get(&widget.id()).cloned().unwrap_or_default()
+    }
+
+    /// Resets the statistics of all widgets.
+    ///
+    /// Only available with the feature `widget-stats`.
+    #[cfg(feature = "widget-stats")]
+    pub fn reset_widget_stats(&mut self) {
+        self.widget_stats.clear();
+    }
+
+    /// Returns a table of the statistics of all widgets that have
+    /// received an event or have been exposed, the most expensive one
+    /// first.
+    ///
+    /// Named widgets are listed by their names, the others by their
+    /// ids. Only available with the feature `widget-stats`.
+    #[cfg(feature = "widget-stats")]
+    pub fn dump_widget_stats(&self) -> String {
+        let mut stats: Vec<(&Id, &WidgetStats)> = self.widget_stats.iter().collect();
+        stats.sort_by(|(ida, a), (idb, b)| {
+            (b.event_time + b.expose_time).cmp(&(a.event_time + a.expose_time)).then(ida.cmp(idb))
+        });
+        let mut dump = String::from("widget           events processed repaints exposes event_us expose_us\n");
+        for (id, st) in stats {
+            let name = self.names.iter()
+                .find(|(_, nid)| *nid == id)
+                .map_or_else(|| format!("#{}", id), |(name, _)| name.clone());
+            dump.push_str(&format!("{:16} {:6} {:9} {:8} {:7} {:8} {:9}\n",
+                                   name, st.events_received, st.events_processed,
+                                   st.repaints_requested, st.exposes,
+                                   st.event_time.as_micros(), st.expose_time.as_micros()));
+        }
+        dump
+    }
+
+    /// Creates the widget group `name`, if it does not exist yet, and
+    /// returns a handle to it.
+    ///
+    /// The handle can be passed to the group methods instead of the
+    /// name, like `ui.set_group_sensitive(&envelope, false)`.
+    pub fn new_group(&mut self, name: &str) -> WidgetGroup {
+        self.groups.entry(String::from(name)).or_insert_with(Vec::new);
+        WidgetGroup { name: String::from(name) }
+    }
+
+    /// Returns the handle to the widget group `name` if it exists.
+    pub fn group(&self, name: &str) -> Option<WidgetGroup> {
+        self.groups.get_key_value(name).map(|(name, _)| WidgetGroup { name: name.clone() })
+    }
+
+    /// Returns the ids of the members of the widget group `group`.
+    ///
+    /// The list is empty if the group does not exist.
+    pub fn group_members<G: AsRef<str>>(&self, group: G) -> Vec<Id> {
+        self.groups.get(group.as_ref()).cloned().unwrap_or_default()
+    }
+
+    /// Redraws all the widgets of the widget group `group`, for
+    /// example after the application changed the state all of them
+    /// display.
+    ///
+    /// Nothing happens if the group does not exist.
+    pub fn redisplay_group<G: AsRef<str>>(&mut self, group: G) {
+        let members = match self.groups.get(group.as_ref()) {
+            Some(members) => members.clone(),
+            None => return
+        };
+        for id in members {
+            self.invalidate_render_caches_of(id);
+            if self.is_shown(id) {
+                self.post_widget_redisplay(id);
+            }
+        }
+    }
+
+    /// Adds the widget specified by `widget` to the widget group `group`.
+    ///
+    /// If the group does not exist yet, it is created. A widget can
+    /// be member of several groups.
+    pub fn add_to_group<G: AsRef<str>, W: Widget>(&mut self, group: G, widget: WidgetHandle<W>) {
+        let id = self.live_id(widget);
+        let members = self.groups.entry(group.as_ref().to_string()).or_insert_with(Vec::new);
+        if !members.contains(&id) {
+            members.push(id);
+        }
+    }
+
+    /// Removes the widget specified by `widget` from the widget group `group`.
+    pub fn remove_from_group<G: AsRef<str>, W: Widget>(&mut self, group: G, widget: WidgetHandle<W>) {
+        if !self.is_alive(widget) {
+            return;
+        }
+        if let Some(members) = self.groups.get_mut(group.as_ref()) {
+            members.retain(|&id| id != widget.id());
+        }
+    }
+
+    /// Shows or hides all the widgets of the widget group `group`.
+    ///
+    /// If the visibility of any widget has changed, the layouting is
+    /// performed once afterwards and the whole window is redrawn.
+    /// Nothing happens if the group does not exist.
+    pub fn set_group_visible<G: AsRef<str>>(&mut self, group: G, visible: bool) {
+        let members = match self.groups.get(group.as_ref()) {
+            Some(members) => members,
+            None => return
+        };
+        let mut changed = false;
+        for id in members.iter() {
+            let wgt = &mut self.widgets[*id];
+            if wgt.is_visible() != visible {
+                wgt.set_visible(visible);
+                changed = true;
+            }
+        }
+        if changed {
+            self.visibility_changed();
+        }
+    }
+
+    /// Shows or hides the widget specified by `widget`.
+    ///
+    /// A hidden widget stays packed but is skipped by the layouting,
+    /// it does not receive events and is not drawn. Hiding a layout
+    /// widget hides all its children. If the visibility has changed,
+    /// the layouting is performed and the whole window is redrawn.
+    pub fn set_visible<W: Widget>(&mut self, widget: WidgetHandle<W>, visible: bool) {
+        let id = self.live_id(widget);
+        let wgt = &mut self.widgets[id];
+        if wgt.is_visible() != visible {
+            wgt.set_visible(visible);
+            self.visibility_changed();
+        }
+    }
+
+    /// Relayouts and redraws the window after a visibility change.
+    ///
+    /// If the focused or the hovered widget are no longer shown, they
+    /// lose the focus or the hover state respectively.
+    pub(crate) fn visibility_changed(&mut self) {
+        self.do_layout();
+        self.redisplay();
+
+        if !self.accepts_input(self.focused_widget) {
+            self.widgets[self.focused_widget].set_focus(false);
+            self.focused_widget = 0;
+        }
+        let hovered = self.input_state.hovered();
+        if !self.is_shown(hovered) {
+            self.hide_tooltip();
+            self.widgets[hovered].pointer_leave_wrap();
+            self.input_state = self.input_state.hover(0);
+            self.update_cursor();
+        }
+    }
+
+    /// Sets the sensitivity of all the widgets of the widget group `group`.
+    ///
+    /// See [`set_sensitive()`](#method.set_sensitive). Nothing
+    /// happens if the group does not exist.
+    pub fn set_group_sensitive<G: AsRef<str>>(&mut self, group: G, sensitive: bool) {
+        if let Some(members) = self.groups.get(group.as_ref()) {
+            for id in members.iter() {
+                self.widgets[*id].set_sensitive(sensitive);
+            }
+            self.sensitivity_changed();
+        }
+    }
+
+    /// Sets the sensitivity of the widget specified by `widget`.
+    ///
+    /// Insensitive widgets and their children do not receive any
+    /// events, the events go to the closest sensitive parent
+    /// instead. They are skipped in the focus traversal and lose the
+    /// focus when made insensitive. If the sensitivity changes, the
+    /// widget is repainted, so that it can gray itself out.
+    pub fn set_sensitive<W: Widget>(&mut self, widget: WidgetHandle<W>, sensitive: bool) {
+        let id = self.live_id(widget);
+        self.widgets[id].set_sensitive(sensitive);
+        self.sensitivity_changed();
+    }
+
+    fn sensitivity_changed(&mut self) {
+        if !self.accepts_input(self.focused_widget) {
+            self.widgets[self.focused_widget].set_focus(false);
+            self.focused_widget = 0;
+        }
+    }
+
+    /// Returns `true` iff the widget `id` and all its ancestors are visible.
+    fn is_shown(&self, id: Id) -> bool {
+        self.root_widget_node.reaches(id, &self.widgets, &|w| w.is_visible())
+    }
+
+    /// Returns the geometries of the shown host drawn regions in
+    /// window pixels.
+    ///
+    /// The UI does not paint anything into these regions, see
+    /// [`HostRegion`](../region/struct.HostRegion.html). The regions
+    /// change with the layout, so the application should query them
+    /// after [`do_layout()`](#method.do_layout) and after the window
+    /// has been resized.
+    pub fn host_regions(&self) -> Vec<Layout> {
+        let sf = self.scale_factor;
+        self.host_drawn_layouts().iter()
+            .map(|l| Layout { pos: l.pos.scale(sf), size: l.size.scale(sf) })
+            .collect()
+    }
+
+    /// Returns the geometry of the widget `widget` in window pixels,
+    /// or `None` if it is not shown.
+    ///
+    /// Useful to position an external renderer at a
+    /// [`HostRegion`](../region/struct.HostRegion.html).
+    pub fn host_region<W: Widget>(&self, widget: WidgetHandle<W>) -> Option<Layout> {
+        if !self.is_alive(widget) || !self.is_shown(widget.id()) {
+            return None;
+        }
+        let l = self.widgets[widget.id()].layout();
+        Some(Layout { pos: l.pos.scale(self.scale_factor), size: l.size.scale(self.scale_factor) })
+    }
+
+    /// Returns the metadata of all registered widgets in the order
+    /// of their ids.
+    ///
+    /// This is a stable programmatic surface for external tooling.
+    /// Widgets can be looked up by the `id` of their
+    /// [`WidgetInfo`](struct.WidgetInfo.html) using
+    /// [`widget_handle()`](#method.widget_handle).
+    pub fn introspect(&self) -> Vec<WidgetInfo> {
+        let mut shown = HashSet::new();
+        self.root_widget_node.collect_shown(&self.widgets, &mut shown);
+        let mut parents = HashMap::new();
+        self.root_widget_node.collect_parents(&mut parents);
+        self.widgets.iter().enumerate().map(|(id, wgt)| {
+            WidgetInfo {
+                id,
+                type_name: wgt.type_name(),
+                name: self.names.iter().find(|(_, nid)| **nid == id).map(|(name, _)| name.clone()),
+                parent: parents.get(&id).cloned(),
+                bounds: self.window_bounds(id),
+                takes_focus: wgt.takes_focus(),
+                has_focus: wgt.has_focus(),
+                sensitive: wgt.is_sensitive(),
+                shown: shown.contains(&id)
+            }
+        }).collect()
+    }
+
+    /// Returns the descriptions of the shown widgets for assistive
+    /// technologies, see [`accessibility`](../accessibility/index.html).
+    ///
+    /// The root of the tree describes the window. It takes the
+    /// description of the root widget, or if it has none, a nameless
+    /// description of the role `Role::Window`.
+    pub fn accessibility_tree(&self) -> AccessibleNode {
+        let root = &self.widgets[0];
+        let mut tree = AccessibleNode {
+            id: 0,
+            accessible: root.accessible().unwrap_or_else(|| Accessible::new(Role::Window, "")),
+            bounds: self.window_bounds(0),
+            focused: root.has_focus(),
+            sensitive: root.is_sensitive(),
+            children: Vec::new()
+        };
+        for c in self.root_widget_node.shown_children() {
+            self.collect_accessibles(c, &mut tree.children);
+        }
+        tree
+    }
+
+    fn collect_accessibles(&self, node: &WidgetNode, siblings: &mut Vec<AccessibleNode>) {
+        let wgt = &self.widgets[node.id];
+        if !wgt.is_visible() {
+            return;
+        }
+        match wgt.accessible() {
+            Some(accessible) => {
+                let mut acc_node = AccessibleNode {
+                    id: node.id,
+                    accessible,
+                    bounds: self.window_bounds(node.id),
+                    focused: wgt.has_focus(),
+                    sensitive: wgt.is_sensitive(),
+                    children: Vec::new()
+                };
+                for c in node.shown_children() {
+                    self.collect_accessibles(c, &mut acc_node.children);
+                }
+                siblings.push(acc_node);
+            }
+            None => for c in node.shown_children() {
+                self.collect_accessibles(c, siblings);
+            }
+        }
+    }
+
+    fn window_bounds(&self, id: Id) -> Layout {
+        let l = self.widgets[id].layout();
+        Layout { pos: l.pos.scale(self.scale_factor), size: l.size.scale(self.scale_factor) }
+    }
+
+    /// Performs the action `action` on the widget `id` as requested by
+    /// an assistive technology.
+    ///
+    /// `Action::Focus` focuses the widget, the other actions are
+    /// passed to [`Widget::accessible_action()`](../widget/trait.Widget.html#method.accessible_action).
+    /// Returns `true` iff the action was performed. Actions are not
+    /// performed on widgets that are not shown, are insensitive or
+    /// do not list the action in their description.
+    pub fn perform_accessible_action(&mut self, id: Id, action: Action) -> bool {
+        if id >= self.widgets.len() || !self.is_shown(id) || !self.widgets[id].is_sensitive() {
+            return false;
+        }
+        let listed = self.widgets[id].accessible().map_or(false, |acc| acc.actions.contains(&action));
+        if !listed {
+            return false;
+        }
+        if action == Action::Focus {
+            if !self.widgets[id].takes_focus() {
+                return false;
+            }
+            self.focus_id(id);
+            return true;
+        }
+        let performed = self.widgets[id].accessible_action(action);
+        if performed {
+            self.post_widget_redisplay(id);
+        }
+        performed
+    }
+
+    /// Returns the user adjustable state of the layout, see
+    /// [`LayoutSnapshot`](struct.LayoutSnapshot.html).
+    pub fn snapshot_layout(&self) -> LayoutSnapshot {
+        let size = self.widgets[0].size();
+        let mut shown: Vec<Id> = {
+            let mut shown = HashSet::new();
+            self.root_widget_node.collect_shown(&self.widgets, &mut shown);
+            shown.into_iter().collect()
+        };
+        shown.sort_unstable();
+        let mut snapshot = LayoutSnapshot {
+            size: (size.w, size.h),
+            geometries: shown.into_iter().map(|id| (id, self.widgets[id].rect())).collect(),
+            focused: Some(self.focused_widget).filter(|id| *id != 0),
+            scroll_positions: self.widgets.iter().enumerate()
+                .filter_map(|(id, w)| w.scroll_position().map(|p| (id, (p.x, p.y))))
+                .collect(),
+            ..Default::default()
+        };
+        self.root_widget_node.visit(&mut |node| {
+            let layouter = match node.layouter.as_deref() {
+                Some(l) => l,
+                None => return
+            };
+            if let Some(paned) = layouter.downcast_ref::<PanedLayouterImpl>() {
+                snapshot.split_ratios.push((node.id, paned.split_ratio()));
+            }
+            if let Some(tabs) = layouter.downcast_ref::<TabLayouterImpl>() {
+                snapshot.active_tabs.push((node.id, tabs.active_tab()));
+            }
+        });
+        snapshot
+    }
+
+    /// Restores the state of the layout from `snapshot` taken by
+    /// [`snapshot_layout()`](#method.snapshot_layout).
+    ///
+    /// The layout is performed and the default window size is set to
+    /// the size of the snapshot, so this is best called after the
+    /// widgets have been packed and before the window is shown.
+    /// Entries of widgets that do not exist are ignored.
+    pub fn restore_layout(&mut self, snapshot: &LayoutSnapshot) {
+        let (w, h) = snapshot.size;
+        self.root_widget_node.visit_mut(&mut |node| {
+            let layouter = match node.layouter.as_deref_mut() {
+                Some(l) => l,
+                None => return
+            };
+            if let Some(paned) = layouter.downcast_mut::<PanedLayouterImpl>() {
+                if let Some((_, ratio)) = snapshot.split_ratios.iter().find(|(id, _)| *id == node.id) {
+                    paned.set_split_ratio(*ratio);
+                }
+            }
+            if let Some(tabs) = layouter.downcast_mut::<TabLayouterImpl>() {
+                if let Some((_, tab)) = snapshot.active_tabs.iter().find(|(id, _)| *id == node.id) {
+                    tabs.set_active_tab(*tab);
+                }
+            }
+        });
+        for (id, (x, y)) in snapshot.scroll_positions.iter() {
+            if let Some(wgt) = self.widgets.get_mut(*id) {
+                wgt.set_scroll_position(Coord { x: *x, y: *y });
+            }
+        }
+        self.widgets[0].set_size(&Size { w, h });
+        self.visibility_changed();
+        if let Some(id) = snapshot.focused.filter(|id| *id < self.widgets.len()) {
+            self.focus_id(id);
+        }
+        if self.widgets[0].size().w * self.widgets[0].size().h > 0.0 {
+            self.fit_window_size();
+        }
+    }
+
+    /// Returns the handle of the widget with the id `id`.
+    ///
+    /// Returns `None` if there is no such widget or if it is not of
+    /// type `W`. Meant for tooling using [`introspect()`](#method.introspect).
+    pub fn widget_handle<W: Widget>(&self, id: Id) -> Option<WidgetHandle<W>> {
+        self.widgets.get(id)?.downcast_ref::<W>()?;
+        Some(self.handle_of(id))
+    }
+
+    fn host_drawn_layouts(&self) -> Vec<Layout> {
+        let mut shown = HashSet::new();
+        self.root_widget_node.collect_shown(&self.widgets, &mut shown);
+        let mut ids: Vec<Id> = shown.into_iter().filter(|id| self.widgets[*id].host_drawn()).collect();
+        ids.sort_unstable();
+        ids.into_iter().map(|id| self.widgets[id].layout()).collect()
+    }
+
+    /// Returns `true` iff the widget `id` and all its ancestors are
+    /// visible and sensitive.
+    fn accepts_input(&self, id: Id) -> bool {
+        self.root_widget_node.reaches(id, &self.widgets, &|w| w.is_visible() && w.is_sensitive())
+    }
+
+    /// Highlights or unhighlights all the widgets of the widget group `group`.
+    ///
+    /// Nothing happens if the group does not exist.
+    pub fn set_group_highlighted<G: AsRef<str>>(&mut self, group: G, highlighted: bool) {
+        let members = match self.groups.get(group.as_ref()) {
+            Some(members) => members.clone(),
+            None => return
+        };
+        for id in members {
+            self.set_highlight(id, highlighted);
+        }
+    }
+
+    /// Returns a mutable reference to the [`Theme`](../theme/struct.Theme.html) of the `UI`
+    pub fn theme(&mut self) -> &mut Theme {
+        &mut self.theme
+    }
+
+    /// Sets the [`Theme`](../theme/struct.Theme.html) of the `UI`
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+        self.redisplay();
+    }
+
+    /// Returns a mutable reference to the [`Assets`](../assets/struct.Assets.html)
+    /// of the `UI`, the images the widgets draw by the key
+    ///
+    /// Only available with the feature `cairo-helpers`.
+    #[cfg(feature = "cairo-helpers")]
+    pub fn assets(&mut self) -> &mut Assets {
+        &mut self.assets
+    }
+
+    /// Highlights or unhighlights the widget specified by `widget`.
+    pub fn set_highlighted<W: Widget>(&mut self, widget: WidgetHandle<W>, highlighted: bool) {
+        let id = self.live_id(widget);
+        self.set_highlight(id, highlighted);
+    }
+
+    /// Returns `true` iff the widget specified by `widget` is highlighted.
+    pub fn is_highlighted<W: Widget>(&self, widget: WidgetHandle<W>) -> bool {
+        self.is_alive(widget) && self.highlighted.contains(&widget.id())
+    }
+
+    /// Highlights the widget specified by `widget` for `duration` seconds.
+    pub fn flash_highlight<W: Widget>(&mut self, widget: WidgetHandle<W>, duration: f64) {
+        let id = self.live_id(widget);
+        self.set_highlight(id, true);
+        self.start_timer(FLASH_TIMER_BASE + id, duration);
+    }
+
+    fn set_highlight(&mut self, id: Id, highlighted: bool) {
+        let changed = if highlighted {
+            self.highlighted.insert(id)
+        } else {
+            self.highlighted.remove(&id)
+        };
+        if changed {
+            self.post_widget_redisplay(id);
+        }
+    }
+
+    /// Posts a redisplay of the whole window, or notes it during a
+    /// [`batch()`](#method.batch).
+    fn redisplay(&self) {
+        if !self.mapped {
+            return;
+        }
+        let mut batch = self.batch.borrow_mut();
+        if batch.depth > 0 {
+            batch.damage.add_full();
+        } else if self.throttle_period().is_some() {
+            self.throttled.borrow_mut().add_full();
+        } else {
+            self.post_redisplay();
+        }
+    }
+
+    /// Runs `updates` on the `UI` collecting the redisplays and the
+    /// layouting they cause.
+    ///
+    /// Afterwards the layouting requested by
+    /// [`do_layout()`](#method.do_layout) is done once and the damaged
+    /// areas are merged and posted at once, so that an application
+    /// updating dozens of widgets, for example on a message from the
+    /// audio thread, does not cause dozens of redisplays. Batches can
+    /// be nested, the collected work is done at the end of the
+    /// outermost one.
+    ///
+    /// The layout of the widgets is not updated during the batch.
+    pub fn batch<F: FnOnce(&mut UI<RW>)>(&mut self, updates: F) {
+        self.batch.borrow_mut().depth += 1;
+        updates(self);
+        let (damage, layout) = {
+            let mut batch = self.batch.borrow_mut();
+            batch.depth -= 1;
+            if batch.depth > 0 {
+                return;
+            }
+            (batch.damage.take(), std::mem::take(&mut batch.layout))
+        };
+        if layout {
+            self.do_layout();
+        }
+        self.post_damage(damage);
+    }
+
+    /// Limits the redisplays to `fps` frames per second, or lifts the
+    /// limit if `fps` is `None`. Unlimited by default.
+    ///
+    /// Redisplays requested less than `1/fps` seconds after the last
+    /// expose are held back and merged, and posted by
+    /// [`next_event()`](#method.next_event) once the period has
+    /// passed. So continuous animations like meters do not redraw
+    /// faster than the target rate, however often they ask for a
+    /// repaint. Widgets can interpolate their drawings by the
+    /// [`frame_time()`](../widget/struct.PaintContext.html#method.frame_time)
+    /// and the
+    /// [`frame_delta()`](../widget/struct.PaintContext.html#method.frame_delta)
+    /// of the `PaintContext`.
+    pub fn set_max_fps(&mut self, fps: Option<f64>) {
+        self.max_fps = fps.filter(|fps| *fps > 0.0);
+        self.flush_throttled_redisplays();
+    }
+
+    /// Returns the maximum frame rate set by
+    /// [`set_max_fps()`](#method.set_max_fps).
+    pub fn max_fps(&self) -> Option<f64> {
+        self.max_fps
+    }
+
+    /// Returns the time in seconds until the next redisplay may be
+    /// posted, or `None` if redisplays are not held back right now.
+    fn throttle_period(&self) -> Option<f64> {
+        let fps = self.max_fps?;
+        if !self.frame_exposed {
+            return None;
+        }
+        let remaining = self.frame_time + 1.0 / fps - self.start_time.elapsed().as_secs_f64();
+        if remaining > 0.0 { Some(remaining) } else { None }
+    }
+
+    /// Posts the redisplays held back by the frame rate limit if the
+    /// frame period has passed.
+    fn flush_throttled_redisplays(&self) {
+        if self.throttle_period().is_some() {
+            return;
+        }
+        let damage = self.throttled.borrow_mut().take();
+        self.post_damage(damage);
+    }
+
+    /// Posts the redisplays taken from a `Damage` accumulator.
+    fn post_damage(&self, damage: Option<Vec<Layout>>) {
+        match damage {
+            None if self.mapped => self.post_redisplay(),
+            None => {}
+            Some(rects) => {
+                for rect in rects {
+                    self.post_layout_redisplay(rect);
+                }
+            }
+        }
+    }
+
+    /// Shortens `timeout` so that held back redisplays are posted
+    /// in time.
+    fn throttled_timeout(&self, timeout: f64) -> f64 {
+        let pending = !self.throttled.borrow().is_empty();
+        match self.throttle_period().filter(|_| pending) {
+            Some(remaining) if timeout < 0.0 || remaining < timeout => remaining,
+            _ => timeout
+        }
+    }
+
+    /// Returns `true` iff a [`batch()`](#method.batch) is running.
+    pub fn in_batch(&self) -> bool {
+        self.batch.borrow().depth > 0
+    }
+
+    fn post_widget_redisplay(&self, id: Id) {
+        self.post_layout_redisplay(self.widgets[id].layout());
+    }
+
+    /// Posts a redisplay of the rectangle `rect` in unscaled window coordinates
+    ///
+    /// During a [`batch()`](#method.batch) the rectangle is collected.
+    fn post_layout_redisplay(&self, rect: Layout) {
+        if !self.mapped {
+            return;
+        }
+        {
+            let mut batch = self.batch.borrow_mut();
+            if batch.depth > 0 {
+                batch.damage.add(rect);
+                return;
+            }
+        }
+        if self.throttle_period().is_some() {
+            self.throttled.borrow_mut().add(rect);
+            return;
+        }
+        let pos = rect.pos.scale(self.scale_factor);
+        let size = rect.size.scale(self.scale_factor);
+        let (pos, size) = pixel_bounds(pos, size);
+        self.post_redisplay_rect(pos, size);
     }
 
     /// Sets the default window size, so that the widget layout fits into it.
+    ///
+    /// At fractional scale factors the scaled size is rounded up to
+    /// full pixels, so that the bottom and right most widgets are not
+    /// cut off.
     pub fn fit_window_size(&self) {
         let size = self.widgets[0].size().scale(self.scale_factor);
         if size.h * size.w == 0.0 {
             panic!("Root window size zero. Have you forgotten ui::UI::do_layout()?");
         }
-        self.set_default_size(size.w as i32, size.h as i32);
+        let (w, h) = window_pixels(size);
+        self.set_default_size(w, h);
     }
 
-    /// Sets the minimal window size, so that the widget layout fits into it.
-    pub fn fit_window_min_size(&self) {
-        let size = self.widgets[0].size().scale(self.scale_factor);
-        if size.h * size.w == 0.0 {
-            panic!("Minimal root size zero. Have you forgotten ui::UI::do_layout()?");
+    /// Configures the window by `config` and shows it.
+    ///
+    /// To be called after the layouting, as a window without an
+    /// explicit size is fitted to the layout like by
+    /// [`fit_window_size()`](#method.fit_window_size). See
+    /// [`window`](../window/index.html) for the hints that are only
+    /// kept in the configuration.
+    pub fn open_window(&mut self, config: WindowConfig) {
+        self.set_window_title(&config.title);
+        if config.resizable {
+            self.make_resizable();
+        }
+        if let Some(parent) = config.transient_for {
+            self.set_transient_for(parent.as_ptr());
+        }
+        match config.size {
+            Some(size) => {
+                let (w, h) = window_pixels(size.scale(self.scale_factor));
+                self.set_default_size(w, h);
+            }
+            None => self.fit_window_size()
+        }
+        self.window_config = Some(config);
+        self.show_window();
+    }
+
+    /// Returns the configuration the window has been opened with by
+    /// [`open_window()`](#method.open_window).
+    pub fn window_config(&self) -> Option<&WindowConfig> {
+        self.window_config.as_ref()
+    }
+
+    /// Opens a popup window with a `UI` of the root widget
+    /// `root_widget` and returns its id, see [`popup`](../popup/index.html).
+    ///
+    /// `build` is to register and pack the widgets of the popup. The
+    /// layouting is performed afterwards and the window is opened
+    /// undecorated by `config` at the scale factor of this `UI`. Unless
+    /// `config` names another one, the window of this `UI` becomes the
+    /// transient parent of the popup.
+    pub fn open_popup<PRW, F>(&mut self, root_widget: Box<PRW>, config: WindowConfig, build: F) -> PopupId
+    where PRW: Widget + 'static,
+          F: FnOnce(&mut UI<PRW>) {
+        let config = match config.transient_for {
+            Some(_) => config,
+            None => config.transient_for(ParentWindow::from_ptr(self.get_native_window()))
+        };
+        let scale_factor = self.scale_factor;
+        let mut view = PuglView::new(std::ptr::null_mut(), |pv| UI::new_scaled(pv, root_widget, scale_factor));
+        let ui = view.handle();
+        build(ui);
+        ui.do_layout();
+        ui.open_window(config.decorated(false));
+
+        let id = self.next_popup_id;
+        self.next_popup_id += 1;
+        self.popups.push((id, Box::new(view)));
+        id
+    }
+
+    /// Returns the `UI` of the popup `id`.
+    ///
+    /// Returns `None` if the popup `id` is not open or if the root
+    /// widget of its `UI` is not of type `PRW`.
+    pub fn popup<PRW: Widget + 'static>(&mut self, id: PopupId) -> Option<&mut UI<PRW>> {
+        let (_, popup) = self.popups.iter_mut().find(|(pid, _)| *pid == id)?;
+        popup.as_any_mut().downcast_mut::<Box<PuglView<UI<PRW>>>>().map(|view| view.handle())
+    }
+
+    /// Returns `true` iff the popup `id` is open.
+    pub fn is_popup_open(&self, id: PopupId) -> bool {
+        self.popups.iter().any(|(pid, _)| *pid == id)
+    }
+
+    /// Closes the popup `id`.
+    ///
+    /// Returns `false` if the popup `id` is not open. Popups closed by
+    /// the application are not reported by
+    /// [`dismissed_popups()`](#method.dismissed_popups).
+    pub fn close_popup(&mut self, id: PopupId) -> bool {
+        let count = self.popups.len();
+        self.popups.retain(|(pid, _)| *pid != id);
+        self.popups.len() != count
+    }
+
+    /// Returns the popups that have been dismissed since the last call.
+    ///
+    /// The notification is consumed by the call.
+    pub fn dismissed_popups(&mut self) -> Vec<PopupId> {
+        std::mem::take(&mut self.dismissed_popups)
+    }
+
+    /// Closes all the popups and reports them as dismissed.
+    fn dismiss_popups(&mut self) {
+        for (id, _) in self.popups.drain(..) {
+            self.dismissed_popups.push(id);
+        }
+    }
+
+    /// Processes the events of the popups and dismisses the popups
+    /// that have lost the focus or have been requested to close.
+    fn pump_popups(&mut self) {
+        for (_, popup) in self.popups.iter_mut() {
+            popup.next_event();
+        }
+        let mut i = 0;
+        while i < self.popups.len() {
+            if self.popups[i].1.wants_dismissal() {
+                let (id, _) = self.popups.remove(i);
+                self.dismissed_popups.push(id);
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    /// Forwards key events to the popup opened last and dismisses
+    /// the popups on button presses.
+    ///
+    /// `ev` has been sanitized and filtered already. Returns the
+    /// status of a forwarded event.
+    fn forward_to_popup(&mut self, ev: Event) -> Option<Status> {
+        match ev.data {
+            EventType::KeyPress(_) | EventType::KeyRelease(_) => {
+                let ev = ev.scale_pos(self.scale_factor);
+                self.popups.last_mut().map(|(_, popup)| popup.forward_event(ev))
+            }
+            EventType::MouseButtonPress(_) => {
+                self.dismiss_popups();
+                None
+            }
+            _ => None
+        }
+    }
+
+    /// Lets the `UI` fit the minimal window size to the layout after
+    /// widgets have requested a resize by
+    /// [`Widget::request_resize()`](../widget/trait.Widget.html#method.request_resize),
+    /// so that the window grows instead of cutting off the widgets.
+    /// Off by default.
+    pub fn set_fit_window_on_resize(&mut self, yn: bool) {
+        self.fit_window_on_resize = yn;
+    }
+
+    /// Sets the minimal window size, so that the widget layout fits into it.
+    ///
+    /// The size is rounded up like in [`fit_window_size()`](#method.fit_window_size).
+    pub fn fit_window_min_size(&self) {
+        let size = self.widgets[0].size().scale(self.scale_factor);
+        if size.h * size.w == 0.0 {
+            panic!("Minimal root size zero. Have you forgotten ui::UI::do_layout()?");
+        }
+        let (w, h) = window_pixels(size);
+        self.set_min_size(w, h);
+    }
+
+    /// Returns the scale factor of the `UI`.
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// Changes the scale factor of the `UI` to `scale_factor`, for
+    /// example when the user changes the HiDPI settings while the
+    /// window is open.
+    ///
+    /// The widgets are notified by
+    /// [`Widget::scale_changed()`](../widget/trait.Widget.html#method.scale_changed),
+    /// the layout is redone and the window size fitting the layout at
+    /// the new scale and the minimal size of the
+    /// [size hints](#method.set_size_hints) are passed to the
+    /// windowing system. The sizes of the widgets do not change, as
+    /// they are unscaled.
+    ///
+    /// A `UI` created by [`new_auto_scaled()`](#method.new_auto_scaled)
+    /// stops following the scale of the monitor then.
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        self.follow_monitor_scale = false;
+        if !self.change_scale_factor(scale_factor) {
+            return;
+        }
+        self.do_layout();
+        let size = self.widgets[0].size();
+        if size.w * size.h > 0.0 {
+            self.fit_window_size();
+        }
+        self.redisplay();
+    }
+
+    /// Sets a query for the scale factor of the monitor the window is on.
+    ///
+    /// Whenever the window is configured, that is moved or resized,
+    /// `query` is called. If it returns a scale factor different from
+    /// the current one, the scale factor is changed like by
+    /// [`set_scale_factor()`](#method.set_scale_factor), except that
+    /// the window size is left to the windowing system, which usually
+    /// resizes windows moved to another monitor itself. The root
+    /// layout is fitted into the window at the new scale.
+    ///
+    /// The query takes precedence over the scale factor pugl reports,
+    /// which a `UI` created by
+    /// [`new_auto_scaled()`](#method.new_auto_scaled) follows
+    /// otherwise. This is the hook for plugin hosts which know the
+    /// scale better than the windowing system, for example as they
+    /// scale their plugin windows themselves.
+    pub fn set_scale_query<Q>(&mut self, query: Q)
+    where Q: FnMut() -> Option<f64> + 'static {
+        self.scale_query = Some(Box::new(query));
+    }
+
+    /// Returns the scale factor of the monitor the window is on, if the
+    /// `UI` is to follow it.
+    fn monitor_scale_factor(&mut self) -> Option<f64> {
+        if let Some(query) = self.scale_query.as_mut() {
+            return query();
+        }
+        if !self.follow_monitor_scale {
+            return None;
+        }
+        Some(PuglViewTrait::get_scale_factor(self)).filter(|f| f.is_finite() && *f > 0.0)
+    }
+
+    /// Sets the scale factor and notifies the widgets.
+    ///
+    /// Returns `true` iff the scale factor changed.
+    fn change_scale_factor(&mut self, scale_factor: f64) -> bool {
+        if scale_factor == self.scale_factor {
+            return false;
+        }
+        self.scale_factor = scale_factor;
+        for wgt in self.widgets.iter_mut() {
+            wgt.scale_changed(scale_factor);
+        }
+        #[cfg(feature = "cairo-helpers")]
+        self.assets.prepare(scale_factor);
+        self.invalidate_render_caches();
+        self.root_widget_node.invalidate_measures();
+        self.forward_size_hints();
+        true
+    }
+
+    /// Sets the constraints of the window size.
+    ///
+    /// * `min`: the minimal size
+    /// * `max`: the maximal size
+    /// * `aspect_ratio`: the fixed ratio of width divided by height
+    /// * `step`: the granularity, the width and the height are whole
+    ///   multiples of it
+    ///
+    /// The lengths are unscaled like the widget sizes, so a `step` of
+    /// `1.0` keeps the window size at whole multiples of the scale
+    /// factor. The hints are forwarded to the windowing system and
+    /// also enforced on the window content, as not all windowing
+    /// systems respect them: when the window is resized the root
+    /// widget gets the largest size fulfilling them that fits into the
+    /// window.
+    ///
+    /// Panics if `aspect_ratio` or `step` is not positive.
+    pub fn set_size_hints(&mut self, min: Option<Size>, max: Option<Size>, aspect_ratio: Option<f64>, step: Option<f64>) {
+        if aspect_ratio.map_or(false, |ratio| !(ratio > 0.)) {
+            panic!("aspect ratio {:?} not positive", aspect_ratio);
+        }
+        if step.map_or(false, |step| !(step > 0.)) {
+            panic!("size step {:?} not positive", step);
+        }
+        self.size_hints = SizeHints { min, max, aspect_ratio, step };
+        self.forward_size_hints();
+    }
+
+    /// Passes the size hints scaled by the scale factor to pugl.
+    fn forward_size_hints(&mut self) {
+        let hints = self.size_hints;
+        if let Some(min) = hints.min {
+            let (w, h) = window_pixels(min.scale(self.scale_factor));
+            self.set_min_size(w, h);
+        }
+        if let Some(max) = hints.max {
+            let (w, h) = window_pixels(max.scale(self.scale_factor));
+            self.set_max_size(w, h);
+        }
+        if let Some(ratio) = hints.aspect_ratio {
+            self.set_aspect_ratio(ratio);
+        }
+        if let Some(step) = hints.step {
+            let (w, h) = window_pixels(Size { w: step, h: step }.scale(self.scale_factor));
+            self.set_size_increment(w, h);
+        }
+    }
+
+    /// Returns `true` iff a the window has been requested to close by the windowing system
+    /// or by the close shortcut, see [`set_window_shortcuts()`](#method.set_window_shortcuts).
+    ///
+    /// The application should check for this at every cycle of the
+    /// event loop and terminate the event loop if `true` is returned.
+    pub fn close_request_issued(&self) -> bool {
+        self.close_request_issued
+    }
+
+    /// Makes close requests from the windowing system vetoable.
+    ///
+    /// If `cycles` is not zero, a close request of the windowing
+    /// system does not immediately make
+    /// [`close_request_issued()`](#method.close_request_issued) return
+    /// `true`. Instead [`close_request_pending()`](#method.close_request_pending)
+    /// returns `true` and the application can for example ask the
+    /// user about unsaved changes and then answer by
+    /// [`confirm_close()`](#method.confirm_close) or
+    /// [`veto_close()`](#method.veto_close). An unanswered request is
+    /// confirmed after `cycles` calls of
+    /// [`next_event()`](#method.next_event), so that the window can
+    /// always be closed. Zero, the default, disables the veto.
+    pub fn set_close_veto(&mut self, cycles: usize) {
+        self.close_veto_cycles = cycles;
+    }
+
+    /// Returns `true` iff a close request is waiting to be confirmed
+    /// or vetoed, see [`set_close_veto()`](#method.set_close_veto).
+    pub fn close_request_pending(&self) -> bool {
+        self.pending_close_request.is_some()
+    }
+
+    /// Confirms a pending close request, so that
+    /// [`close_request_issued()`](#method.close_request_issued)
+    /// returns `true`.
+    pub fn confirm_close(&mut self) {
+        if self.pending_close_request.take().is_some() {
+            self.close_request_issued = true;
+        }
+    }
+
+    /// Vetoes a pending close request, the window stays open.
+    pub fn veto_close(&mut self) {
+        self.pending_close_request = None;
+    }
+
+    fn count_down_close_request(&mut self) {
+        match self.pending_close_request {
+            Some(0) => self.confirm_close(),
+            Some(n) => self.pending_close_request = Some(n - 1),
+            None => {}
+        }
+    }
+
+    /// Enables or disables the standard window shortcuts
+    ///
+    /// Standalone applications get the usual behavior of windows
+    /// this way without matching keys in their root widget.
+    pub fn set_window_shortcuts(&mut self, shortcuts: WindowShortcuts) {
+        self.window_shortcuts = shortcuts;
+    }
+
+    /// Returns the enabled standard window shortcuts.
+    pub fn window_shortcuts(&self) -> WindowShortcuts {
+        self.window_shortcuts
+    }
+
+    /// Returns `true` once after the fullscreen shortcut has been pressed.
+    ///
+    /// The application is supposed to toggle the fullscreen state of
+    /// its window then.
+    pub fn fullscreen_toggle_requested(&mut self) -> bool {
+        let req = self.fullscreen_toggle_request;
+        self.fullscreen_toggle_request = false;
+        req
+    }
+
+    /// Returns the text of the clipboard of the windowing system, if any.
+    ///
+    /// The clipboard is filled by other applications, by
+    /// [`set_clipboard_text()`](#method.set_clipboard_text) and by
+    /// widgets by
+    /// [`Widget::copy_to_clipboard()`](../widget/trait.Widget.html#method.copy_to_clipboard).
+    /// Its content is pasted into focused text entries on `Ctrl-V`,
+    /// see [`Widget::paste()`](../widget/trait.Widget.html#method.paste).
+    pub fn clipboard_text(&mut self) -> Option<String> {
+        let text = PuglViewTrait::get_clipboard(self);
+        if text.is_some() {
+            self.clipboard = text.clone();
+        }
+        text
+    }
+
+    /// Puts `text` into the clipboard of the windowing system.
+    pub fn set_clipboard_text(&mut self, text: String) {
+        PuglViewTrait::set_clipboard(self, &text);
+        self.clipboard = Some(text);
+    }
+
+    /// Returns the text last copied to or pasted from the clipboard by
+    /// the `UI`, an empty string if there is none.
+    #[deprecated(note = "use `clipboard_text()`, which reads the clipboard of the windowing system")]
+    pub fn clipboard(&self) -> &str {
+        self.clipboard.as_deref().unwrap_or("")
+    }
+
+    /// Puts `text` into the clipboard of the windowing system.
+    #[deprecated(note = "use `set_clipboard_text()`")]
+    pub fn set_clipboard(&mut self, text: &str) {
+        self.set_clipboard_text(String::from(text));
+    }
+
+    /// Returns `true` iff the focused widget is a text entry accepting input.
+    fn text_input_focused(&self) -> bool {
+        let fw = self.focused_widget;
+        fw != 0 && self.widgets[fw].takes_text_input() && self.accepts_input(fw)
+            && self.input_state.modal().map_or(true, |m| self.is_descendant(fw, m))
+    }
+
+    /// Passes the key event `ev` to the focused text entry and the
+    /// part not processed to the root widget. `Ctrl-V` pastes the clipboard.
+    fn deliver_text_key(&mut self, ev: Event, key: Key) {
+        let fw = self.focused_widget;
+        if let KeyVal::Character(c) = key.key {
+            if key.modifiers.ctrl() && crate::input::ctrl_letter(c) == 'v' {
+                if let EventType::KeyPress(_) = ev.data {
+                    if let Some(text) = self.clipboard_text() {
+                        self.widgets[fw].paste(&text);
+                    }
+                }
+                return;
+            }
+        }
+        if let Some(ev) = self.deliver_event(fw, ev) {
+            self.deliver_event(0, ev);
+        }
+    }
+
+    /// Handles the key press `key` not processed by any widget if it
+    /// is an enabled window shortcut or, with arrow key navigation
+    /// on, an arrow key.
+    fn handle_window_shortcut(&mut self, key: Key) {
+        match key.key {
+            KeyVal::Character(c) if self.window_shortcuts.close && key.modifiers.ctrl() => {
+                if let 'w' | 'W' | 'q' | 'Q' | '\u{17}' | '\u{11}' = c {
+                    self.close_request();
+                }
+            }
+            KeyVal::Special(SpecialKey::F11) if self.window_shortcuts.fullscreen => {
+                self.fullscreen_toggle_request = true;
+            }
+            KeyVal::Special(sk) if self.arrow_navigation => {
+                let dir = match sk {
+                    SpecialKey::Up => FocusDirection::Up,
+                    SpecialKey::Down => FocusDirection::Down,
+                    SpecialKey::Left => FocusDirection::Left,
+                    SpecialKey::Right => FocusDirection::Right,
+                    _ => return
+                };
+                self.focus_direction(dir);
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns a mutable reference to the `Layouter` of the passed `LayoutWidgetHandle`.
+    ///
+    /// This can be used to borrow a handle to the layouter in order
+    /// to change layouting parameters. Changes after the layouting
+    /// take effect at the next call of
+    /// [`next_event()`](#method.next_event), which re-layouts and
+    /// redraws the widgets whose layout has changed, unless
+    /// [`do_layout()`](#method.do_layout) is called before.
+    ///
+    /// As the layouter may be changed, it is remeasured at the next
+    /// layouting. Use [`layouter_ref()`](#method.layouter_ref) to
+    /// only read the layouting parameters.
+    pub fn layouter<L, W>(&mut self, layouter: LayoutWidgetHandle<L, W>) -> &mut L::Implementor
+    where L: Layouter, W: Widget {
+        let id = self.live_id(layouter.widget());
+        self.invalidate_measure_of(id);
+        self.relayout_pending = true;
+        self.find_node(id).layouter_impl::<L>()
+    }
+
+    /// Returns a reference to the `Layouter` of the passed
+    /// `LayoutWidgetHandle`, without causing a relayout like
+    /// [`layouter()`](#method.layouter).
+    pub fn layouter_ref<L, W>(&self, layouter: LayoutWidgetHandle<L, W>) -> &L::Implementor
+    where L: Layouter, W: Widget {
+        let id = self.live_id(layouter.widget());
+        self.find_node_ref(id).expect("widget not registered").layouter_impl_ref::<L>()
+    }
+
+    /// Returns the interaction settings of the UI
+    pub fn interaction_settings(&self) -> InteractionSettings {
+        self.interaction
+    }
+
+    /// Sets the user's interaction settings for all the widgets.
+    ///
+    /// See [`InteractionSettings`](../widget/struct.InteractionSettings.html).
+    pub fn set_interaction_settings(&mut self, settings: InteractionSettings) {
+        self.interaction = settings;
+        for w in self.widgets.iter_mut() {
+            w.set_interaction_settings(settings);
+        }
+    }
+
+    /// Returns the `TextMetrics` service of the UI
+    ///
+    /// Widgets showing text can use it to measure the text to determine
+    /// their minimal size.
+    #[cfg(feature = "text")]
+    pub fn text_metrics(&self) -> &TextMetrics {
+        &self.text_metrics
+    }
+
+    /// Returns the `Locale` of the UI
+    #[cfg(feature = "text")]
+    pub fn locale(&self) -> &Locale {
+        self.text_metrics.locale()
+    }
+
+    /// Sets the `Locale` of the UI
+    ///
+    /// The locale is used by the [`TextMetrics`](../text/struct.TextMetrics.html).
+    /// The layout direction is set to the text direction of the
+    /// locale, see [`set_layout_direction()`](#method.set_layout_direction).
+    /// The layouting is performed and the whole window is redrawn.
+    #[cfg(feature = "text")]
+    pub fn set_locale(&mut self, locale: Locale) {
+        self.layout_direction = match locale.direction {
+            TextDirection::LeftToRight => LayoutDirection::LeftToRight,
+            TextDirection::RightToLeft => LayoutDirection::RightToLeft
+        };
+        self.text_metrics.set_locale(locale);
+        self.do_layout();
+        self.redisplay();
+    }
+
+    /// Returns the direction in which the widgets are laid out.
+    pub fn layout_direction(&self) -> LayoutDirection {
+        self.layout_direction
+    }
+
+    /// Sets the direction in which the widgets are laid out.
+    ///
+    /// If it is right to left, the whole layout is mirrored
+    /// horizontally. The layouting is performed and the whole window
+    /// is redrawn.
+    pub fn set_layout_direction(&mut self, direction: LayoutDirection) {
+        self.layout_direction = direction;
+        self.do_layout();
+        self.redisplay();
+    }
+
+    /// Returns the base `Style` of the UI
+    pub fn style(&self) -> &Style {
+        &self.style
+    }
+
+    /// Sets the base `Style` of the UI and redraws the whole window.
+    ///
+    /// Widgets without a style override, neither of themselves nor of
+    /// any of their ancestors, are drawn with this style.
+    pub fn set_style(&mut self, style: Style) {
+        self.style = style;
+        self.invalidate_render_caches();
+        self.redisplay();
+    }
+
+    /// Overrides parts of the style of the widget `widget` and its children.
+    ///
+    /// If `widget` is a layout widget, the override is inherited
+    /// by all the widgets inside the layout unless they override the
+    /// style themselves.
+    pub fn set_style_override<W: Widget>(&mut self, widget: WidgetHandle<W>, style: StyleOverride) {
+        let id = self.live_id(widget);
+        self.style_overrides.insert(id, style);
+        self.invalidate_render_caches();
+        self.redisplay();
+    }
+
+    /// Removes the style override of the widget `widget`.
+    pub fn clear_style_override<W: Widget>(&mut self, widget: WidgetHandle<W>) {
+        let id = self.live_id(widget);
+        if self.style_overrides.remove(&id).is_some() {
+            self.invalidate_render_caches();
+            self.redisplay();
+        }
+    }
+
+    /// Returns the effective style of the widget `widget`.
+    ///
+    /// That is the base style with all the overrides of the widget
+    /// and its ancestors applied; the style the widget gets by the
+    /// [`PaintContext`](../widget/struct.PaintContext.html).
+    pub fn widget_style<W: Widget>(&self, widget: WidgetHandle<W>) -> Style {
+        let path = self.root_path(self.live_id(widget)).unwrap_or(&[]);
+        let mut node = &self.root_widget_node;
+        let mut style = self.apply_style_override(node.id, self.style.clone());
+        for &i in path {
+            node = &node.children[i];
+            style = self.apply_style_override(node.id, style);
+        }
+        style
+    }
+
+    /// Renders the widget `widget` and its children into a new image
+    /// surface, for example for screenshots, preset thumbnails or
+    /// image based regression tests.
+    ///
+    /// The surface covers the widget's layout at the scale factor of
+    /// the `UI`, so its size is the widget's size in pixels. The
+    /// widgets are drawn in full with their effective styles, but
+    /// without highlights and tooltips. The widget needs to be
+    /// layouted, so [`do_layout()`](#method.do_layout) needs to be
+    /// called before.
+    pub fn render_to_surface<W: Widget>(&mut self, widget: WidgetHandle<W>) -> cairo::ImageSurface {
+        let id = self.live_id(widget);
+        let layout = self.widgets[id].layout();
+        let sf = self.scale_factor;
+        let (surface, cr) = self.offscreen_target(layout).expect("Creation of offscreen surface failed");
+        let expose = ExposeArea { pos: layout.pos.scale(sf), size: layout.size.scale(sf) };
+
+        let mut styles = vec![self.widget_style(widget)];
+        let mut queue = Vec::new();
+        if let Some(node) = self.find_node_ref(id) {
+            self.make_expose_queue(node, None, &mut queue, &mut styles, 0);
+        }
+        #[cfg(feature = "cairo-helpers")]
+        self.assets.prepare(sf);
+        let painted_hashes = self.painted_hashes.clone();
+        self.paint_queue(&queue, &mut styles, &expose, true, &cr);
+        drop(cr);
+        self.painted_hashes = painted_hashes;
+        surface.flush();
+        surface
+    }
+
+    fn apply_style_override(&self, id: Id, style: Style) -> Style {
+        match self.style_overrides.get(&id) {
+            Some(so) => so.apply(&style),
+            None => style
+        }
+    }
+
+    /// Returns `true` iff any widget inside the layout `layout` is hovered.
+    ///
+    /// This can be used to highlight a whole section of the UI, when
+    /// the pointer is hovering any of its widgets.
+    pub fn is_any_child_hovered<L, W>(&self, layout: LayoutWidgetHandle<L, W>) -> bool
+    where L: Layouter, W: Widget {
+        self.is_alive(layout.widget()) && self.find_node_ref(layout.widget().id())
+            .map_or(false, |node| node.any_descendant(&self.widgets, &|w| w.is_hovered()))
+    }
+
+    /// Returns `true` iff any widget inside the layout `layout` has the focus.
+    pub fn contains_focus<L, W>(&self, layout: LayoutWidgetHandle<L, W>) -> bool
+    where L: Layouter, W: Widget {
+        self.is_alive(layout.widget()) && self.find_node_ref(layout.widget().id())
+            .map_or(false, |node| node.any_descendant(&self.widgets, &|w| w.has_focus()))
+    }
+
+    /// Returns a mutable reference to the `Layouter` of root Layouter.
+    ///
+    /// This can be used to borrow a handle to the layouter in order
+    /// to change layouting parameters.
+    pub fn root_layout(&self) -> LayoutWidgetHandle<VerticalLayouter, RW> {
+        self.root_widget_handle
+    }
+
+    /// Replaces the content of the root layout by the layout `layout`.
+    ///
+    /// All the widgets packed directly into the root layout so far are
+    /// unpacked along with their children. They stay registered in the
+    /// `UI`, so that any of them can be swapped in again later or be
+    /// packed into another layout. Until then they are neither drawn
+    /// nor do they receive events or the focus.
+    ///
+    /// `layout` must not be packed into a layout, unless it is the
+    /// current content of the root layout, in which case nothing
+    /// happens. The layout parameters of the root layout are kept. The
+    /// layout is redone and the whole window is redrawn.
+    pub fn swap_root_layout<L: Layouter>(&mut self, layout: LayoutWidgetHandle<L, LayoutWidget>) {
+        let id = self.live_id(layout.widget());
+        if self.root_widget_node.children.len() == 1 && self.root_widget_node.children[0].id == id {
+            return;
+        }
+        let new_node = self.unlayouted_nodes.remove(&id)
+            .or_else(|| self.parked_nodes.remove(&id))
+            .expect("layout already packed?");
+
+        let old_nodes = std::mem::replace(&mut self.root_widget_node.children, vec![new_node]);
+        for node in old_nodes {
+            self.parked_nodes.insert(node.id, node);
+        }
+        self.rebuild_node_index();
+        let root_impl = self.root_widget_node.layouter_impl::<VerticalLayouter>();
+        root_impl.clear();
+        VerticalLayouter.pack(root_impl, 0, StackDirection::Back);
+
+        self.invalidate_render_caches();
+        self.visibility_changed();
+    }
+
+    /// Makes the tab `index` of the tab layout `layout` the active tab.
+    ///
+    /// The layouting is performed and the whole window is redrawn.
+    /// Widgets of the previously active tab lose the focus and the
+    /// hover state. See [`TabLayouter`](../layout/tablayout/struct.TabLayouter.html).
+    pub fn set_active_tab<W: Widget>(&mut self, layout: LayoutWidgetHandle<TabLayouter, W>, index: usize) {
+        if self.layouter_ref(layout).active_tab() != index {
+            self.layouter(layout).set_active_tab(index);
+            self.visibility_changed();
+        }
+    }
+
+    /// Returns a mutable reference to the root widget.
+    pub fn root_widget(&mut self) -> &mut RW {
+        self.widgets[0].downcast_mut::<RW>().expect("Root Widget cast failed")
+    }
+
+    /// Returns a mutable reference to the root widget, or `None` if
+    /// the downcast fails, which is a logic error of the `UI`.
+    ///
+    /// Meant for code that must not panic, like the code of a plugin
+    /// GUI running inside a host.
+    pub fn try_root_widget(&mut self) -> Option<&mut RW> {
+        self.widgets[0].downcast_mut::<RW>()
+    }
+
+    /// Returns a mutable reference to the specified by `widget`.
+    ///
+    /// It returns a reference to the actual widget instance, so type specific
+    /// methods of the widget can be used.
+    ///
+    /// Panics if the widget has been removed by
+    /// [`remove_widget()`](#method.remove_widget). See
+    /// [`try_widget()`](#method.try_widget) for a non panicking variant.
+    pub fn widget<W: Widget>(&mut self, widget: WidgetHandle<W>) -> &mut W {
+        let id = self.live_id(widget);
+        self.widgets[id].downcast_mut::<W>().expect("Widget cast failed!")
+    }
+
+    /// Returns an iterator over all the widgets of type `W` together
+    /// with their ids, for example to reset all the dials at once.
+    ///
+    /// The ids can be turned into handles by
+    /// [`widget_handle()`](#method.widget_handle).
+    pub fn widgets_of_type<W: Widget>(&mut self) -> impl Iterator<Item = (&mut W, Id)> {
+        self.widgets.iter_mut()
+            .enumerate()
+            .filter_map(|(id, wgt)| wgt.downcast_mut::<W>().map(|w| (w, id)))
+    }
+
+    /// Returns mutable references to the two widgets `first` and
+    /// `second` at the same time.
+    ///
+    /// Useful to transfer state between widgets, like copying the
+    /// value of one dial into another. Panics if both handles refer to
+    /// the same widget or if one of the widgets has been removed.
+    pub fn widgets2<W1, W2>(&mut self, first: WidgetHandle<W1>, second: WidgetHandle<W2>) -> (&mut W1, &mut W2)
+    where W1: Widget, W2: Widget {
+        let (i, j) = (self.live_id(first), self.live_id(second));
+        assert_ne!(i, j, "widgets2() needs two different widgets");
+        let (a, b) = if i < j {
+            let (low, high) = self.widgets.split_at_mut(j);
+            (&mut low[i], &mut high[0])
+        } else {
+            let (low, high) = self.widgets.split_at_mut(i);
+            (&mut high[0], &mut low[j])
+        };
+        (a.downcast_mut::<W1>().expect("Widget cast failed!"),
+         b.downcast_mut::<W2>().expect("Widget cast failed!"))
+    }
+
+    /// Returns mutable references to all the widgets of `handles` at
+    /// the same time, in the order of the handles.
+    ///
+    /// The widgets have to be of the same type. Panics if a widget is
+    /// passed more than once or if one of the widgets has been removed.
+    pub fn widgets_n<W: Widget>(&mut self, handles: &[WidgetHandle<W>]) -> Vec<&mut W> {
+        let ids: Vec<Id> = handles.iter().map(|h| self.live_id(*h)).collect();
+        let mut slots: Vec<Option<&mut Box<dyn Widget>>> = self.widgets.iter_mut().map(Some).collect();
+        ids.into_iter()
+            .map(|id| slots[id].take().expect("widgets_n() needs different widgets"))
+            .map(|wgt| wgt.downcast_mut::<W>().expect("Widget cast failed!"))
+            .collect()
+    }
+
+    /// Returns a mutable reference to the widget `widget`, or `None`
+    /// if it has been removed or if it is not of type `W`.
+    ///
+    /// The latter can only happen if the handle comes from another `UI`.
+    pub fn try_widget<W: Widget>(&mut self, widget: WidgetHandle<W>) -> Option<&mut W> {
+        if !self.is_alive(widget) {
+            return None;
+        }
+        self.widgets.get_mut(widget.id())?.downcast_mut::<W>()
+    }
+
+    /// Performs a step in the cycle of the widget focus.
+    ///
+    /// Can be called when the root widget received a TAB key press event.
+    pub fn focus_next_widget(&mut self) {
+        let mut fw = self.focused_widget;
+        loop {
+            fw += 1;
+            if fw == self.widgets.len() {
+                fw = 0;
+            }
+            if (self.widgets[fw].takes_focus() && self.accepts_input(fw))
+                || (fw == self.focused_widget) {
+                break;
+            }
+        }
+
+        self.move_focus(fw);
+    }
+
+    /// Moves the focus to the nearest widget in the direction `dir`
+    /// from the focused widget, judging by the widget geometries.
+    ///
+    /// Only shown widgets that take the focus and accept input are
+    /// considered. Returns `true` iff the focus has been moved.
+    /// Applications can call this on arrow key presses, or let the
+    /// `UI` do it by [`set_arrow_key_navigation()`](#method.set_arrow_key_navigation).
+    pub fn focus_direction(&mut self, dir: FocusDirection) -> bool {
+        let from = self.widgets[self.focused_widget].layout();
+        let mut shown = HashSet::new();
+        self.root_widget_node.collect_shown(&self.widgets, &mut shown);
+        let target = shown.into_iter()
+            .filter(|&id| id != self.focused_widget && self.widgets[id].takes_focus() && self.accepts_input(id))
+            .filter_map(|id| focus_distance(from, self.widgets[id].layout(), dir).map(|d| (id, d)))
+            .min_by(|(ia, a), (ib, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal).then(ia.cmp(ib)))
+            .map(|(id, _)| id);
+        match target {
+            Some(id) => {
+                self.move_focus(id);
+                true
+            }
+            None => false
+        }
+    }
+
+    /// Lets the arrow keys move the focus by
+    /// [`focus_direction()`](#method.focus_direction) if no widget
+    /// has processed the key press. Off by default.
+    pub fn set_arrow_key_navigation(&mut self, yn: bool) {
+        self.arrow_navigation = yn;
+    }
+
+    /// Lets the `UI` draw the focus ring around the focused widget
+    /// on top of the widgets, so that widgets do not need to draw it
+    /// themselves by
+    /// [`PaintContext::draw_focus_ring()`](../widget/struct.PaintContext.html#method.draw_focus_ring).
+    /// Off by default.
+    ///
+    /// Like the focus ring of the widgets it is only drawn while
+    /// [`focus_visible()`](#method.focus_visible) is `true`. The
+    /// color and the line width are taken from the
+    /// [`Theme`](../theme/struct.Theme.html).
+    pub fn set_focus_overlay(&mut self, yn: bool) {
+        if self.focus_overlay != yn {
+            self.focus_overlay = yn;
+            self.post_widget_redisplay(self.focused_widget);
+        }
+    }
+
+    /// Sorts the shown widgets into a grid of cells of `cell_size`
+    /// after each layouting, or stops doing so if `cell_size` is
+    /// `None`. Off by default.
+    ///
+    /// The grid is used to find the widget under the pointer and the
+    /// widgets to be drawn into an exposed area. It pays off for
+    /// windows with very many small widgets, `cell_size` being a few
+    /// times the size of them.
+    ///
+    /// Widgets are found only within their layout, so
+    /// [`Widget::is_hit_by()`](../widget/trait.Widget.html#method.is_hit_by)
+    /// must not be `true` outside of it. Between packing new widgets
+    /// and the next [`do_layout()`](#method.do_layout) the widget
+    /// tree is searched as without the grid.
+    pub fn set_spatial_index(&mut self, cell_size: Option<f64>) {
+        self.spatial_index = cell_size.map(SpatialIndex::new);
+        self.rebuild_spatial_index();
+    }
+
+    /// Sorts the shown widgets into the spatial index if there is one.
+    fn rebuild_spatial_index(&mut self) {
+        let mut index = match self.spatial_index.take() {
+            Some(index) => index,
+            None => return
+        };
+        index.clear();
+        let mut pending = vec![(&self.root_widget_node, None, 0)];
+        while let Some((node, parent, child_index)) = pending.pop() {
+            let wgt = &self.widgets[node.id];
+            if parent.is_some() && !wgt.is_visible() {
+                continue;
+            }
+            let entry = index.insert(node.id, parent, child_index, wgt.layout());
+            let children = node.children.iter().enumerate().rev()
+                .filter(|(i, _)| node.layouter.as_ref().map_or(true, |l| l.shows_child(*i)));
+            for (i, c) in children {
+                pending.push((c, Some(entry), i));
+            }
+        }
+        self.spatial_index = Some(index);
+    }
+
+    fn move_focus(&mut self, id: Id) {
+        let old = self.focused_widget;
+        self.widgets[old].set_focus(false);
+        self.focused_widget = id;
+        self.widgets[id].set_focus(true);
+        if self.focus_overlay && self.keyboard_mode && old != id {
+            self.post_widget_redisplay(old);
+            self.post_widget_redisplay(id);
+        }
+    }
+
+    /// Focuses the widget specified by `widget`
+    ///
+    pub fn focus_widget<W: Widget>(&mut self, widget: WidgetHandle<W>) {
+        let id = self.live_id(widget);
+        self.focus_id(id);
+    }
+
+    fn focus_id(&mut self, id: Id) {
+        if self.widgets[id].takes_focus() && self.accepts_input(id) {
+            self.move_focus(id);
+        }
+    }
+
+    /// Sets the policy by which the focus follows the pointer.
+    /// Default is `FocusPolicy::Manual`.
+    pub fn set_focus_policy(&mut self, policy: FocusPolicy) {
+        self.focus_policy = policy;
+    }
+
+    /// Returns the policy by which the focus follows the pointer.
+    pub fn focus_policy(&self) -> FocusPolicy {
+        self.focus_policy
+    }
+
+    /// Makes the widget `widget` modal.
+    ///
+    /// From now on only the widget and its descendants receive
+    /// events. Pointer events outside of the widget are passed to the
+    /// widget itself, so that a popup can for example close itself
+    /// on a click outside of it. Key events go to the focused widget
+    /// if it is a descendant of the modal widget, otherwise to the
+    /// modal widget. Only one widget can be modal at a time, the
+    /// previous one loses its modality.
+    ///
+    /// If a drag is ongoing, the modality takes effect when the drag
+    /// ends.
+    pub fn set_modal<W: Widget>(&mut self, widget: WidgetHandle<W>) {
+        let id = self.live_id(widget);
+        self.input_state = self.input_state.grab(id);
+    }
+
+    /// Ends the modality set by [`set_modal()`](#method.set_modal).
+    pub fn release_modal(&mut self) {
+        self.input_state = self.input_state.ungrab();
+    }
+
+    /// Returns `true` iff the widget `widget` is modal.
+    pub fn is_modal<W: Widget>(&self, widget: WidgetHandle<W>) -> bool {
+        self.is_alive(widget) && self.input_state.modal() == Some(widget.id())
+    }
+
+    fn is_descendant(&self, id: Id, ancestor: Id) -> bool {
+        self.find_node_ref(ancestor).map_or(false, |node| node.find(id).is_some())
+    }
+
+    /// Returns `true` iff the last user interaction was by the keyboard.
+    ///
+    /// Widgets are supposed to draw their focus ring only if this is
+    /// `true`, so that the focus ring shows up when navigating by
+    /// keyboard but not after a mouse click. Widgets get it also by
+    /// [`PaintContext::focus_visible()`](../widget/struct.PaintContext.html#method.focus_visible).
+    /// The focused widget is repainted when it changes.
+    pub fn focus_visible(&self) -> bool {
+        self.keyboard_mode
+    }
+
+    /// Returns `true` iff the high contrast rendering mode is on.
+    pub fn high_contrast(&self) -> bool {
+        self.high_contrast
+    }
+
+    /// Switches the high contrast rendering mode on or off and redraws
+    /// the whole window.
+    ///
+    /// Other than switching the `Theme`, the high contrast mode is
+    /// meant as an accessibility setting on top of any theme. Widgets
+    /// get it by
+    /// [`PaintContext::high_contrast()`](../widget/struct.PaintContext.html#method.high_contrast).
+    /// The highlighting, focus rings and tooltips drawn by the `UI` use
+    /// stronger colors and thicker lines.
+    pub fn set_high_contrast(&mut self, yn: bool) {
+        if self.high_contrast != yn {
+            self.high_contrast = yn;
+            self.invalidate_render_caches();
+            self.redisplay();
+        }
+    }
+
+    fn set_keyboard_mode(&mut self, yn: bool) {
+        if self.keyboard_mode != yn {
+            self.keyboard_mode = yn;
+            self.invalidate_render_caches_of(self.focused_widget);
+            self.post_widget_redisplay(self.focused_widget);
+        }
+    }
+
+    /// Called on the map and unmap events of the view.
+    ///
+    /// While the view is unmapped (hidden or minimized) no redisplays
+    /// are posted and the widgets' reminders are suspended. When the
+    /// view is mapped again, the reminders are resumed and the whole
+    /// view is repainted.
+    fn set_mapped(&mut self, mapped: bool) {
+        if mapped == self.mapped {
+            return;
+        }
+        self.mapped = mapped;
+        if mapped {
+            for (id, timeout) in self.running_reminders.iter() {
+                self.start_timer(*id, *timeout);
+            }
+            for (id, timer) in self.widget_timers.iter() {
+                self.start_timer(*id, timer.period);
+            }
+            self.invalidate_render_caches();
+            self.redisplay();
+        } else {
+            for id in self.running_reminders.keys().chain(self.widget_timers.keys()) {
+                self.stop_timer(*id);
+            }
+            self.last_frame = None;
+        }
+    }
+
+    /// Returns `true` iff the view is mapped.
+    ///
+    /// The `UI` follows the map and unmap events of the view. While
+    /// the view is unmapped (hidden or minimized) no redisplays are
+    /// posted and the widgets' reminders are suspended. When the view
+    /// is mapped again, the reminders are resumed and the whole view
+    /// is repainted. The view is considered mapped initially.
+    pub fn is_mapped(&self) -> bool {
+        self.mapped
+    }
+
+    /// Sets the time in seconds the pointer has to rest on a widget
+    /// before its tooltip is shown.
+    pub fn set_tooltip_delay(&mut self, delay: f64) {
+        self.tooltip.set_delay(delay);
+    }
+
+    /// Configures the pointer prediction during drags.
+    ///
+    /// For widgets that opt in by
+    /// [`Widget::predicts_drag()`](../widget/trait.Widget.html#method.predicts_drag)
+    /// the pointer position of `MouseMove` events during a drag is
+    /// extrapolated `lookahead` seconds from the pointer's
+    /// velocity. The distance of the predicted position to the actual
+    /// one is clamped to `max_distance`.
+    ///
+    /// Default: `lookahead` is `1/60` seconds, `max_distance` is `16.0`.
+    pub fn set_drag_prediction(&mut self, lookahead: f64, max_distance: f64) {
+        self.drag_predictor.configure(lookahead, max_distance);
+    }
+
+    /// Returns a mutable reference to the `InputSanitizer`
+    ///
+    /// The [`InputSanitizer`](../sanitizer/struct.InputSanitizer.html)
+    /// fixes quirks of the event stream some plugin hosts deliver.
+    pub fn input_sanitizer(&mut self) -> &mut InputSanitizer {
+        &mut self.sanitizer
+    }
+
+    /// Returns the mouse cursor the `UI` has set for the widget under
+    /// the pointer.
+    pub fn cursor(&self) -> Cursor {
+        self.cursor.get()
+    }
+
+    /// Returns the text of the tooltip currently shown, if any.
+    pub fn shown_tooltip(&self) -> Option<&str> {
+        self.tooltip.text()
+    }
+
+    fn hide_tooltip(&mut self) {
+        if self.tooltip.take_pending() {
+            self.stop_timer(TOOLTIP_TIMER);
+        }
+        if let Some(rect) = self.tooltip.hide() {
+            self.post_layout_redisplay(rect);
+        }
+    }
+
+    fn handle_timer(&mut self, id: usize) {
+        if id == KINETIC_TIMER {
+            self.kinetic_step();
+            return;
+        }
+        if id == LONG_PRESS_TIMER {
+            self.stop_timer(id);
+            if let Some((widget, gesture)) = self.pointer_gestures.long_press() {
+                self.widgets[widget].pointer_gesture(gesture);
+            }
+            return;
+        }
+        if id == TOOLTIP_TIMER {
+            self.tooltip_timer_event();
+            return;
+        }
+        if id >= FLASH_TIMER_BASE {
+            self.stop_timer(id);
+            self.set_highlight(id - FLASH_TIMER_BASE, false);
+            return;
+        }
+        if id >= WIDGET_TIMER_BASE {
+            let (widget, tag, repeating) = match self.widget_timers.get(&id) {
+                Some(timer) => (timer.widget, timer.tag, timer.repeating),
+                None => return
+            };
+            if !repeating {
+                self.widget_timers.remove(&id);
+                self.stop_timer(id);
+            }
+            self.widgets[widget].timer(tag);
+            return;
+        }
+        if !self.widgets[id].reminder_handler() {
+            self.running_reminders.remove(&id);
+            self.stop_timer(id);
+        }
+    }
+
+    fn tooltip_timer_event(&mut self) {
+        if self.tooltip.take_pending() {
+            self.stop_timer(TOOLTIP_TIMER);
+        }
+        let wgt = &self.widgets[self.input_state.hovered()];
+        if !wgt.is_hovered() {
+            return;
+        }
+        if let Some(text) = wgt.tooltip() {
+            self.tooltip.show(text, self.pointer_pos);
+            self.redisplay();
+        }
+    }
+
+    /// Returns `true` iff the window has the focus.
+    pub fn has_focus(&self) -> bool {
+        self.have_focus
+    }
+
+    /// Returns `Some(has_focus)` iff the window focus changed since the last call.
+    ///
+    /// The notification is consumed by the call. This can be used to
+    /// pause expensive animations like meters while the window is not
+    /// focused.
+    pub fn window_focus_changed(&mut self) -> Option<bool> {
+        self.focus_change.take()
+    }
+
+    /// Initiates the next cycle of the event loop
+    ///
+    /// The application should call it at the beginning of the event loop.
+    ///
+    /// From `pugl` documentation:
+    /// If `timeout` is zero, then this function will not block. Plugins
+    /// should always use a timeout of zero to avoid blocking the
+    /// host.
+    ///
+    /// If a positive `timeout` is given, then events will be processed
+    /// for that amount of time, starting from when this function was
+    /// called.
+    ///
+    /// If a `negative` timeout is given, this function will block
+    /// indefinitely until an event occurs.
+    ///
+    /// For continuously animating programs, a timeout that is a
+    /// reasonable fraction of the ideal frame period should be used,
+    /// to minimize input latency by ensuring that as many input
+    /// events are consumed as possible before drawing.
+    ///
+    /// While the view is unmapped, repaint, reminder and timer
+    /// requests of the widgets are kept pending until the view is
+    /// mapped again.
+    ///
+    /// If there are deferred jobs pending (see [`defer()`](#method.defer)),
+    /// some of them are run first and the function does not block.
+    ///
+    /// # Order of delivery
+    ///
+    /// Within one call things happen in the following order:
+    ///
+    /// 1. A close request held back by
+    ///    [`set_close_veto()`](#method.set_close_veto) is counted down.
+    ///
+    /// 2. The commands sent through
+    ///    [`command_sender()`](#method.command_sender) and
+    ///    [`command_channel()`](#method.command_channel) are
+    ///    dispatched in the order they have been sent.
+    ///
+    /// 3. The messages queued by [`send_message()`](#method.send_message)
+    ///    and by commands are delivered in the order they have been
+    ///    sent. Messages sent during the delivery are delivered in the
+    ///    next call.
+    ///
+    /// 4. Deferred jobs are run in the order they have been queued.
+    ///
+    /// 5. If widgets have requested a resize or a layouter has been
+    ///    changed, the layouting is redone and the areas of the
+    ///    widgets that have moved are redisplayed.
+    ///
+    /// 6. While the view is mapped, the frame callbacks (see
+    ///    [`request_frame_callbacks()`](#method.request_frame_callbacks))
+    ///    are called, and then the repaint, reminder and timer
+    ///    requests of the widgets are processed, both in the order of
+    ///    the widget ids.
+    ///
+    /// 7. Redisplays held back by [`set_max_fps()`](#method.set_max_fps)
+    ///    are posted if the frame period has passed.
+    ///
+    /// 8. The events of the open popups are processed, see
+    ///    [`open_popup()`](#method.open_popup).
+    ///
+    /// 9. The input events from the windowing system are dispatched in
+    ///    the order in which they are reported. Consecutive motion
+    ///    events may be coalesced, see
+    ///    [`set_motion_coalescing()`](#method.set_motion_coalescing).
+    ///
+    /// 10. A coalesced motion event still held back is dispatched.
+    ///     Then the timers that have expired during 9., that is the
+    ///     widgets' reminders and timers, tooltips and flashing
+    ///     highlights, are handled in the order they have expired.
+    pub fn next_event(&mut self, timeout: f64) {
+        self.count_down_close_request();
+        self.drain_commands();
+        self.deliver_messages();
+        let timeout = if self.deferred_jobs.is_empty() {
+            timeout
+        } else {
+            self.run_deferred_jobs();
+            if self.deferred_jobs.is_empty() { timeout } else { 0.0 }
+        };
+        let resize_requested = self.widgets.iter_mut()
+            .fold(false, |requested, wgt| wgt.resize_requested() || requested);
+        if resize_requested || self.relayout_pending {
+            self.relayout_damaged();
+            if resize_requested && self.fit_window_on_resize {
+                self.fit_window_min_size();
+            }
+        }
+        if self.mapped {
+            self.call_frame_callbacks();
+            let mut damage = Vec::new();
+            for id in 0..self.widgets.len() {
+                let full = self.widgets[id].needs_repaint();
+                let rects = self.widgets[id].dirty_rects();
+                let invalidated = self.widgets[id].buffer_invalidated();
+                if invalidated {
+                    self.retained_surfaces.remove(&id);
+                }
+                #[cfg(feature = "widget-stats")]
+                {
+                    if full || !rects.is_empty() {
+                        self.widget_stats.entry(id).or_default().repaints_requested += 1;
+                    }
+                }
+                if (full || !rects.is_empty()) && (invalidated || self.state_changed(id)) {
+                    self.invalidate_render_caches_of(id);
+                    let wl = self.widgets[id].layout();
+                    if full {
+                        damage.push(wl);
+                    } else {
+                        damage.extend(rects.iter().filter_map(|r| {
+                            wl.intersection(&Layout { pos: r.pos + wl.pos, size: r.size })
+                        }));
+                    }
+                }
+                let w = &mut self.widgets[id];
+                if let Some(timeout) = w.reminder_request() {
+                    self.running_reminders.insert(id, timeout);
+                    self.start_timer(id, timeout);
+                }
+                for request in self.widgets[id].timer_requests() {
+                    self.process_timer_request(id, request);
+                }
+            }
+            for rect in merge_rects(damage) {
+                self.post_layout_redisplay(rect);
+            }
+        }
+        self.flush_throttled_redisplays();
+        let timeout = self.throttled_timeout(timeout);
+        let timeout = if self.popups.is_empty() {
+            timeout
+        } else {
+            self.pump_popups();
+            if timeout < 0.0 { POLL_PERIOD } else { timeout.min(POLL_PERIOD) }
+        };
+        self.in_update = true;
+        self.update(timeout);
+        self.in_update = false;
+        self.flush_motion();
+        for id in std::mem::take(&mut self.expired_timers) {
+            self.handle_timer(id);
+        }
+    }
+
+    /// Requests a call of [`Widget::frame()`](../widget/trait.Widget.html#method.frame)
+    /// of the widget `widget` in every call of [`next_event()`](#method.next_event)
+    /// until it returns `false`.
+    ///
+    /// Meant for continuous animations like meters and scopes. The
+    /// widget is repainted after each frame, and
+    /// [`auto_timeout()`](#method.auto_timeout) wakes up the event
+    /// loop at the frame rate as long as a widget is animating. While
+    /// the view is unmapped, the frame callbacks are paused.
+    pub fn request_frame_callbacks<W: Widget>(&mut self, widget: WidgetHandle<W>) {
+        let id = self.live_id(widget);
+        self.animating_widgets.insert(id);
+    }
+
+    /// Returns `true` iff the widget `widget` has frame callbacks requested.
+    pub fn is_animating<W: Widget>(&self, widget: WidgetHandle<W>) -> bool {
+        self.is_alive(widget) && self.animating_widgets.contains(&widget.id())
+    }
+
+    fn call_frame_callbacks(&mut self) {
+        if self.animating_widgets.is_empty() {
+            self.last_frame = None;
+            return;
+        }
+        let now = Instant::now();
+        let dt = self.last_frame.map_or(0.0, |last| now.duration_since(last).as_secs_f64());
+        self.last_frame = Some(now);
+        let mut ids: Vec<Id> = self.animating_widgets.iter().cloned().collect();
+        ids.sort_unstable();
+        for id in ids {
+            if !self.widgets[id].frame(dt) {
+                self.animating_widgets.remove(&id);
+            }
+            self.post_widget_redisplay(id);
+        }
+    }
+
+    fn process_timer_request(&mut self, widget: Id, request: TimerRequest) {
+        let tag = match request {
+            TimerRequest::Start { tag, .. } | TimerRequest::Cancel(tag) => tag
+        };
+        let running = self.widget_timers.iter()
+            .find(|(_, t)| t.widget == widget && t.tag == tag)
+            .map(|(id, _)| *id);
+        if let Some(id) = running {
+            self.widget_timers.remove(&id);
+            self.stop_timer(id);
+        }
+        if let TimerRequest::Start { tag, period, repeating } = request {
+            let id = self.next_widget_timer;
+            self.next_widget_timer = if id + 1 < FLASH_TIMER_BASE { id + 1 } else { WIDGET_TIMER_BASE };
+            self.widget_timers.insert(id, WidgetTimer { widget, tag, period, repeating });
+            self.start_timer(id, period);
+        }
+    }
+
+    /// Enables or disables the coalescing of pointer motion events.
+    ///
+    /// Fast pointer movements cause streams of `MouseMove` events,
+    /// each of which would run through the event path and often cause
+    /// a redundant repaint. So by default, motion events arriving
+    /// during one call of [`next_event()`](#method.next_event) are
+    /// compressed to the latest one. Other events in between are
+    /// passed in the right order. Widgets that need every single
+    /// motion event, like for freehand drawing, can opt out by
+    /// [`Widget::wants_all_motion_events()`](../widget/trait.Widget.html#method.wants_all_motion_events).
+    pub fn set_motion_coalescing(&mut self, yn: bool) {
+        self.motion_coalescing = yn;
+    }
+
+    /// Returns `true` iff motion events are to be held back to be
+    /// coalesced with the following ones.
+    fn coalesces_motion(&self) -> bool {
+        if !self.motion_coalescing || !self.in_update {
+            return false;
+        }
+        let target = self.input_state.capturing().unwrap_or(self.input_state.hovered());
+        !self.widgets[target].wants_all_motion_events()
+    }
+
+    /// Dispatches the motion event held back for coalescing, if any.
+    fn flush_motion(&mut self) {
+        if let Some(ev) = self.pending_motion.take() {
+            self.dispatch_event(ev);
+        }
+    }
+
+    /// Returns the thresholds of the recognition of
+    /// [pointer gestures](../gestures/index.html).
+    pub fn gesture_settings(&self) -> GestureSettings {
+        self.pointer_gestures.settings()
+    }
+
+    /// Sets the thresholds of the recognition of
+    /// [pointer gestures](../gestures/index.html).
+    pub fn set_gesture_settings(&mut self, settings: GestureSettings) {
+        self.pointer_gestures.set_settings(settings);
+    }
+
+    /// Feeds the pointer event `ev` to the gesture recognition.
+    ///
+    /// A recognized swipe is reported before the release event is
+    /// dispatched, so that the widget can tell a swipe from a click.
+    fn recognize_gestures(&mut self, ev: &Event) {
+        let time = self.start_time.elapsed().as_secs_f64();
+        match ev.data {
+            EventType::MouseButtonPress(btn) if btn.num == 1 => {
+                let path = self.pointer_path(ev.pos());
+                let widget = path.iter().rev().find(|&&id| self.widgets[id].recognizes_gestures()).cloned();
+                match widget {
+                    Some(id) => {
+                        self.pointer_gestures.press(id, ev.pos(), time);
+                        self.start_timer(LONG_PRESS_TIMER, self.pointer_gestures.settings().long_press_delay);
+                    }
+                    None => self.pointer_gestures.cancel()
+                }
+            }
+            EventType::MouseMove(_) => {
+                if self.pointer_gestures.motion(ev.pos(), time) {
+                    self.stop_timer(LONG_PRESS_TIMER);
+                }
+            }
+            EventType::MouseButtonRelease(btn) if btn.num == 1 => {
+                self.stop_timer(LONG_PRESS_TIMER);
+                if let Some((widget, gesture)) = self.pointer_gestures.release(ev.pos(), time) {
+                    if self.accepts_input(widget) {
+                        self.widgets[widget].pointer_gesture(gesture);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Feeds the pointer event `ev` to the kinetic scrolling, see
+    /// [`Widget::kinetic_scrolling()`](../widget/trait.Widget.html#method.kinetic_scrolling).
+    fn track_kinetic_scrolling(&mut self, ev: &Event) {
+        let time = self.start_time.elapsed().as_secs_f64();
+        let scroll_position = |ui: &UI<RW>| ui.kinetic_scroller.widget()
+            .and_then(|id| ui.widgets[id].scroll_position());
+        match ev.data {
+            EventType::MouseButtonPress(btn) => {
+                self.stop_timer(KINETIC_TIMER);
+                self.kinetic_scroller.cancel();
+                if btn.num != 1 {
+                    return;
+                }
+                let path = self.pointer_path(ev.pos());
+                let container = path.iter().rev()
+                    .find(|&&id| self.widgets[id].kinetic_scrolling())
+                    .and_then(|&id| self.widgets[id].scroll_position().map(|pos| (id, pos)));
+                if let Some((id, pos)) = container {
+                    self.kinetic_scroller.begin(id, pos, time);
+                }
+            }
+            EventType::MouseMove(_) => {
+                if let Some(pos) = scroll_position(self) {
+                    self.kinetic_scroller.track(pos, time);
+                }
+            }
+            EventType::MouseButtonRelease(btn) if btn.num == 1 => {
+                if let Some(pos) = scroll_position(self) {
+                    if self.kinetic_scroller.release(pos, time) {
+                        self.start_timer(KINETIC_TIMER, KINETIC_PERIOD);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Moves the scroll container on in the kinetic scrolling.
+    fn kinetic_step(&mut self) {
+        let time = self.start_time.elapsed().as_secs_f64();
+        match self.kinetic_scroller.step(time, self.interaction.kinetic_friction) {
+            Some((id, pos)) if self.accepts_input(id) => {
+                let wgt = &mut self.widgets[id];
+                wgt.set_scroll_position(pos);
+                if let Some(pos) = wgt.scroll_position() {
+                    self.kinetic_scroller.clamped(pos);
+                }
+                self.invalidate_render_caches_of(id);
+                self.post_widget_redisplay(id);
+            }
+            _ => {
+                self.kinetic_scroller.cancel();
+                self.stop_timer(KINETIC_TIMER);
+            }
+        }
+    }
+
+    /// Installs the event filter `filter`, which sees every event
+    /// before it is dispatched to the widgets.
+    ///
+    /// The filters run in the order they have been added, each one
+    /// getting the event as passed on by the previous one. By the
+    /// returned [`FilterResult`](enum.FilterResult.html) a filter can
+    /// consume, modify or redirect the event, for example to
+    /// implement global gestures, debugging overlays or modal
+    /// behavior outside of widgets. The positions of the events are
+    /// unscaled like the widget geometries.
+    pub fn add_event_filter(&mut self, filter: Box<dyn FnMut(&Event) -> FilterResult>) -> EventFilterId {
+        let id = EventFilterId(self.next_event_filter);
+        self.next_event_filter += 1;
+        self.event_filters.push((id, filter));
+        id
+    }
+
+    /// Removes the event filter `id`. Returns `true` iff it was installed.
+    pub fn remove_event_filter(&mut self, id: EventFilterId) -> bool {
+        let len = self.event_filters.len();
+        self.event_filters.retain(|(fid, _)| *fid != id);
+        self.event_filters.len() != len
+    }
+
+    /// Runs the event filters on `ev`.
+    ///
+    /// Returns the event to be dispatched, or `None` if it has been
+    /// consumed or redirected.
+    fn filter_event(&mut self, mut ev: Event) -> Option<Event> {
+        let mut redirect = None;
+        for (_, filter) in self.event_filters.iter_mut() {
+            match filter(&ev) {
+                FilterResult::Pass => {}
+                FilterResult::Consume => return None,
+                FilterResult::Replace(new_ev) => ev = new_ev,
+                FilterResult::Redirect(id, generation) => {
+                    redirect = Some((id, generation));
+                    break;
+                }
+            }
+        }
+        match redirect {
+            Some((id, generation)) => {
+                if self.slot_alive(id, generation) {
+                    self.deliver_event(id, ev);
+                }
+                None
+            }
+            None => Some(ev)
+        }
+    }
+
+    /// Starts recording the incoming events, see
+    /// [`recording`](../recording/index.html).
+    ///
+    /// A recording already running is discarded.
+    pub fn start_recording(&mut self) {
+        self.recorder = Some(EventRecorder::new());
+    }
+
+    /// Stops recording the incoming events and returns the recording,
+    /// or `None` if no recording was running.
+    pub fn stop_recording(&mut self) -> Option<EventRecording> {
+        self.recorder.take().map(EventRecorder::finish)
+    }
+
+    /// Returns `true` iff the incoming events are being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Sets the policy by which [`next_event_auto()`](#method.next_event_auto)
+    /// chooses the timeout. Default is `IdlePolicy::Adaptive { fps: 60.0 }`.
+    pub fn set_idle_policy(&mut self, policy: IdlePolicy) {
+        self.idle_policy = policy;
+    }
+
+    /// Returns the timeout [`next_event_auto()`](#method.next_event_auto)
+    /// would pass to [`next_event()`](#method.next_event) now.
+    ///
+    /// The timeout is zero with `IdlePolicy::NonBlocking` and while
+    /// deferred jobs or messages are pending. Otherwise it is the
+    /// frame period while widgets are animating or reminders, timers or
+    /// tooltips are pending and negative, that is blocking, if the UI
+    /// is idle.
+    pub fn auto_timeout(&self) -> f64 {
+        let fps = match self.idle_policy {
+            IdlePolicy::NonBlocking => return 0.0,
+            IdlePolicy::Adaptive { fps } => fps
+        };
+        if !self.deferred_jobs.is_empty() || !self.messages.is_empty() {
+            return 0.0;
+        }
+        if !self.animating_widgets.is_empty() || !self.running_reminders.is_empty()
+            || !self.widget_timers.is_empty() || self.tooltip.is_pending() {
+            return 1.0 / fps;
+        }
+        -1.0
+    }
+
+    /// Initiates the next cycle of the event loop with the timeout
+    /// chosen by the [`IdlePolicy`](enum.IdlePolicy.html), see
+    /// [`auto_timeout()`](#method.auto_timeout).
+    pub fn next_event_auto(&mut self) {
+        let timeout = self.auto_timeout();
+        self.next_event(timeout);
+    }
+
+    /// Queues the message `msg` to be delivered to the widget `widget`
+    /// at the beginning of the next call of [`next_event()`](#method.next_event).
+    ///
+    /// The widget receives it by
+    /// [`Widget::message()`](../widget/trait.Widget.html#method.message).
+    /// Messages are delivered in the order they have been sent, also
+    /// to invisible and insensitive widgets.
+    pub fn send_message<W: Widget>(&mut self, widget: WidgetHandle<W>, msg: Box<dyn Any>) {
+        let id = self.live_id(widget);
+        self.messages.push_back((id, msg));
+    }
+
+    /// Returns a sender of [`UiCommand`](enum.UiCommand.html)s, that
+    /// can be moved to another thread, like a DSP or a worker thread
+    /// of a plugin.
+    ///
+    /// The commands are processed in the order they have been sent at
+    /// the beginning of the next call of
+    /// [`next_event()`](#method.next_event) on the GUI thread. All
+    /// the senders returned feed the same channel.
+    pub fn command_sender(&mut self) -> Sender<UiCommand> {
+        if let Some(sender) = self.command_sender.as_ref() {
+            return sender.clone();
+        }
+        let sender = self.command_channel(|ui: &mut UI<RW>, command| match command {
+            UiCommand::Message(id, generation, msg) => {
+                if ui.slot_alive(id, generation) {
+                    ui.messages.push_back((id, msg));
+                }
+            }
+            UiCommand::Redisplay => ui.redisplay()
+        });
+        self.command_sender = Some(sender.clone());
+        sender
+    }
+
+    /// Creates a channel of application defined commands of type `T`.
+    ///
+    /// The commands sent to the returned sender are passed to
+    /// `dispatch` on the GUI thread at the beginning of the next call
+    /// of [`next_event()`](#method.next_event). For commands beyond
+    /// what [`command_sender()`](#method.command_sender) offers, like
+    /// setting the values of parameters.
+    pub fn command_channel<T, F>(&mut self, mut dispatch: F) -> Sender<T>
+    where T: Send + 'static,
+          F: FnMut(&mut UI<RW>, T) + 'static {
+        let (sender, receiver) = mpsc::channel();
+        self.command_drains.push(Box::new(move |ui| {
+            while let Ok(command) = receiver.try_recv() {
+                dispatch(ui, command);
+            }
+        }));
+        sender
+    }
+
+    fn drain_commands(&mut self) {
+        let mut drains = std::mem::take(&mut self.command_drains);
+        for drain in drains.iter_mut() {
+            drain(self);
+        }
+        drains.append(&mut self.command_drains);
+        self.command_drains = drains;
+    }
+
+    fn deliver_messages(&mut self) {
+        for (id, msg) in std::mem::take(&mut self.messages) {
+            self.widgets[id].message(msg);
+        }
+    }
+
+    /// Queues the job `job` to be run in one of the next calls of
+    /// [`next_event()`](#method.next_event).
+    ///
+    /// The job typically creates some widgets and packs them into
+    /// layouts. The jobs are run in the order they have been queued.
+    /// In each call of `next_event()` jobs are run until the time
+    /// budget set by [`set_deferred_budget()`](#method.set_deferred_budget)
+    /// is used up, but at least one. After the last job the layout is
+    /// done and the window is redrawn, so the jobs themselves should
+    /// not call [`do_layout()`](#method.do_layout).
+    ///
+    /// ```
+    /// # use pugl_sys::*;
+    /// # #[macro_use] extern crate pugl_ui;
+    /// # use pugl_ui::ui::*;
+    /// # use pugl_ui::widget::*;
+    /// # use pugl_ui::layout::stacklayout::*;
+    /// # #[derive(Default)] struct Knob { stub: WidgetStub }
+    /// # impl Widget for Knob { widget_stub!(); }
+    /// # fn main() {}
+    /// fn build_section<RW: Widget + 'static>(ui: &mut UI<RW>) {
+    ///     let panel = ui.new_layouter::<HorizontalLayouter>();
+    ///     ui.pack_to_layout(panel.widget(), ui.root_layout(), StackDirection::Back);
+    ///     for _ in 0..16 {
+    ///         let knob = ui.new_widget(Box::new(Knob::default()));
+    ///         ui.pack_to_layout(knob, panel, StackDirection::Back);
+    ///     }
+    /// }
+    ///
+    /// fn build_ui<RW: Widget + 'static>(ui: &mut UI<RW>) {
+    ///     for _ in 0..8 {
+    ///         ui.defer(build_section);
+    ///     }
+    /// }
+    /// ```
+    pub fn defer<F>(&mut self, job: F)
+    where F: FnOnce(&mut UI<RW>) + 'static {
+        self.deferred_jobs.push_back(Box::new(job));
+    }
+
+    /// Sets the time in seconds that deferred jobs may take in
+    /// one call of `next_event()`. The default is `0.01`.
+    pub fn set_deferred_budget(&mut self, budget: f64) {
+        self.deferred_budget = budget;
+    }
+
+    /// Returns `true` iff there are deferred jobs pending.
+    pub fn is_loading(&self) -> bool {
+        !self.deferred_jobs.is_empty()
+    }
+
+    fn run_deferred_jobs(&mut self) {
+        let start = Instant::now();
+        while let Some(job) = self.deferred_jobs.pop_front() {
+            job(self);
+            if start.elapsed().as_secs_f64() >= self.deferred_budget {
+                break;
+            }
+        }
+        if self.deferred_jobs.is_empty() {
+            self.do_layout();
+            self.redisplay();
+        }
+    }
+
+    /// Returns `false` iff the widget `id` provides a state hash that
+    /// has not changed since it has been drawn the last time.
+    fn state_changed(&self, id: Id) -> bool {
+        match self.widgets[id].state_hash() {
+            Some(hash) => self.painted_hashes.get(&id) != Some(&(hash, self.widgets[id].state())),
+            None => true
+        }
+    }
+
+    /// Collects the widgets to be exposed.
+    ///
+    /// Along with each widget the index into `styles` of its
+    /// effective style is pushed. New styles are only added to
+    /// `styles` for widgets having a style override. If `area` is
+    /// `None` all the visible widgets are collected. The children of
+    /// cacheable widgets are not collected, as they are drawn into
+    /// the render cache.
+    fn make_expose_queue(&self, node: &WidgetNode, area: Option<&ExposeArea>, expose_queue: &mut Vec<(Id, usize)>, styles: &mut Vec<Style>, style: usize) {
+        let wgt = &self.widgets[node.id];
+        if !wgt.is_visible() {
+            return;
+        }
+        if let Some(area) = area {
+            let pos = area.pos.scale(1./self.scale_factor);
+            let size = area.size.scale(1./self.scale_factor);
+            if !wgt.intersects_with(pos, size) {
+                return;
+            }
+        }
+        let style = match self.style_overrides.get(&node.id) {
+            Some(so) => {
+                let new_style = so.apply(&styles[style]);
+                styles.push(new_style);
+                styles.len() - 1
+            }
+            None => style
+        };
+        expose_queue.push((node.id, style));
+        if wgt.cacheable() {
+            return;
+        }
+        for c in node.shown_children() {
+            self.make_expose_queue(c, area, expose_queue, styles, style);
+        }
+    }
+
+    /// Collects the widgets to be exposed in the `area` like
+    /// `make_expose_queue()` among the widgets the spatial `index`
+    /// has for the `area`.
+    ///
+    /// A widget is collected only if its parent is, so the queue is
+    /// the same as the one of the tree search.
+    fn make_indexed_expose_queue(&self, index: &SpatialIndex, area: &ExposeArea, expose_queue: &mut Vec<(Id, usize)>, styles: &mut Vec<Style>) {
+        let pos = area.pos.scale(1./self.scale_factor);
+        let size = area.size.scale(1./self.scale_factor);
+        let mut collected: HashMap<usize, usize> = HashMap::new();
+        for e in index.candidates_in(pos, size) {
+            let entry = index.entry(e);
+            let style = match entry.parent {
+                Some(parent) => match collected.get(&parent) {
+                    Some(&style) if !self.widgets[index.entry(parent).id].cacheable() => style,
+                    _ => continue
+                },
+                None => 0
+            };
+            let wgt = &self.widgets[entry.id];
+            if !wgt.is_visible() || !wgt.intersects_with(pos, size) {
+                continue;
+            }
+            let style = match self.style_overrides.get(&entry.id) {
+                Some(so) => {
+                    let new_style = so.apply(&styles[style]);
+                    styles.push(new_style);
+                    styles.len() - 1
+                }
+                None => style
+            };
+            expose_queue.push((entry.id, style));
+            collected.insert(e, style);
+        }
+    }
+
+    /// Drops the widgets from the expose `queue` whose exposed part is
+    /// covered entirely by an opaque widget later in the queue, see
+    /// [`Widget::opaque()`](../widget/trait.Widget.html#method.opaque).
+    fn cull_covered_widgets(&self, queue: &mut Vec<(Id, usize)>, area: &ExposeArea) {
+        let area = Layout {
+            pos: area.pos.scale(1./self.scale_factor),
+            size: area.size.scale(1./self.scale_factor)
+        };
+        let covers: Vec<(usize, Layout)> = queue.iter().enumerate()
+            .map(|(i, &(id, _))| (i, &self.widgets[id]))
+            .filter(|(_, wgt)| wgt.opaque() && wgt.transform().is_none() && !wgt.host_drawn())
+            .map(|(i, wgt)| (i, wgt.layout()))
+            .collect();
+        if covers.is_empty() {
+            return;
+        }
+        let mut index = 0;
+        queue.retain(|&(id, _)| {
+            index += 1;
+            let wgt = &self.widgets[id];
+            if wgt.transform().is_some() {
+                return true;
+            }
+            let exposed = match wgt.layout().intersection(&area) {
+                Some(exposed) => exposed,
+                None => return true
+            };
+            !covers.iter().any(|&(i, layout)| i >= index && layout.contains(&exposed))
+        });
+    }
+
+    /// Draws the widgets of the `queue` made by `make_expose_queue()`
+    ///
+    /// If `full_damage` is `true` the whole widgets are drawn,
+    /// otherwise only the parts inside the `expose` area.
+    fn paint_queue(&mut self, queue: &[(Id, usize)], styles: &mut Vec<Style>, expose: &ExposeArea, full_damage: bool, cr: &cairo::Context) {
+        for &(id, style) in queue {
+            let damage = if full_damage {
+                Layout { pos: Coord::default(), size: self.widgets[id].size() }
+            } else {
+                self.local_damage(id, expose)
+            };
+            if self.widgets[id].cacheable() {
+                self.paint_cached(id, style, styles, expose, damage, cr);
+            } else if self.widgets[id].double_buffered() {
+                self.paint_retained(id, &styles[style], expose, damage, cr);
+            } else {
+                self.draw_widget(id, &styles[style], expose, damage, cr);
+            }
+        }
+    }
+
+    fn draw_widget(&mut self, id: Id, style: &Style, expose: &ExposeArea, damage: Layout, cr: &cairo::Context) {
+        let ctx = PaintContext {
+            expose,
+            theme: &self.theme,
+            style,
+            #[cfg(feature = "cairo-helpers")]
+            assets: &self.assets,
+            scale_factor: self.scale_factor,
+            frame_time: self.frame_time,
+            previous_frame_time: self.previous_frame_time,
+            focus_visible: self.keyboard_mode,
+            high_contrast: self.high_contrast,
+            damage
+        };
+        #[cfg(feature = "frame-stats")]
+        let start = Instant::now();
+        #[cfg(feature = "widget-stats")]
+        let widget_start = Instant::now();
+        let wgt = &mut self.widgets[id];
+        if self.realized_widgets.insert(id) {
+            wgt.on_realize();
+        }
+        let transform = wgt.transform();
+        if let Some(transform) = transform {
+            let layout = wgt.layout();
+            let center = Coord { x: layout.pos.x + layout.size.w / 2., y: layout.pos.y + layout.size.h / 2. };
+            cr.save();
+            transform.apply(center, cr);
+        }
+        let backend = CairoBackend::new(cr);
+        if wgt.local_coordinates() {
+            let origin = wgt.pos();
+            cr.save();
+            cr.translate(origin.x, origin.y);
+            wgt.draw(&ctx, &backend);
+            cr.restore();
+        } else {
+            wgt.draw(&ctx, &backend);
+        }
+        if transform.is_some() {
+            cr.restore();
+        }
+        // Only a complete paint shows the state everywhere, a partial
+        // one leaves parts of an earlier state on screen.
+        let whole = Layout { pos: Coord::default(), size: wgt.size() };
+        match wgt.state_hash() {
+            Some(hash) if damage.contains(&whole) => { self.painted_hashes.insert(id, (hash, wgt.state())); }
+            _ => { self.painted_hashes.remove(&id); }
+        }
+        #[cfg(feature = "frame-stats")]
+        self.current_frame_stats.widget_exposes.push((id, start.elapsed()));
+        #[cfg(feature = "widget-stats")]
+        {
+            let stats = self.widget_stats.entry(id).or_default();
+            stats.exposes += 1;
+            stats.expose_time += widget_start.elapsed();
+        }
+    }
+
+    /// Draws the cacheable widget `id` from its render cache.
+    ///
+    /// If there is no valid render cache, the widget and its children
+    /// are drawn into a new one before. Only the `damage` of the
+    /// widget is painted from the cache.
+    fn paint_cached(&mut self, id: Id, style: usize, styles: &mut Vec<Style>, expose: &ExposeArea, damage: Layout, cr: &cairo::Context) {
+        let layout = self.widgets[id].layout();
+        if !self.render_caches.contains_key(&id) {
+            let (surface, ccr) = match self.offscreen_target(layout) {
+                Some(target) => target,
+                None => return self.paint_uncached(id, style, styles, expose, cr)
+            };
+            let full = Layout { pos: Coord::default(), size: layout.size };
+            self.draw_widget(id, &styles[style], expose, full, &ccr);
+
+            let mut queue = Vec::new();
+            if let Some(node) = self.find_node_ref(id) {
+                for c in node.shown_children() {
+                    self.make_expose_queue(c, None, &mut queue, styles, style);
+                }
+            }
+            self.paint_queue(&queue, styles, expose, true, &ccr);
+            drop(ccr);
+            self.render_caches.insert(id, surface);
         }
-        self.set_min_size(size.w as i32, size.h as i32);
+        blit_surface(&self.render_caches[&id], layout, damage, self.scale_factor, cr);
     }
 
-    /// Returns `true` iff a the window has been requested to close by the windowing system
+    /// Draws the double buffered widget `id` from its retained surface.
     ///
-    /// The application should check for this at every cycle of the
-    /// event loop and terminate the event loop if `true` is returned.
-    pub fn close_request_issued(&self) -> bool {
-        self.close_request_issued
+    /// The surface is drawn again if the widget has invalidated it or
+    /// its size has changed. Only the `damage` of the widget is
+    /// painted from the surface.
+    fn paint_retained(&mut self, id: Id, style: &Style, expose: &ExposeArea, damage: Layout, cr: &cairo::Context) {
+        let layout = self.widgets[id].layout();
+        let (width, height) = offscreen_pixels(layout.size.scale(self.scale_factor));
+        let valid = self.retained_surfaces.get(&id)
+            .map_or(false, |surface| surface.get_width() == width && surface.get_height() == height);
+        if !valid {
+            let (surface, rcr) = match self.offscreen_target(layout) {
+                Some(target) => target,
+                None => return self.draw_widget(id, style, expose, damage, cr)
+            };
+            let full = Layout { pos: Coord::default(), size: layout.size };
+            self.draw_widget(id, style, expose, full, &rcr);
+            drop(rcr);
+            self.retained_surfaces.insert(id, surface);
+        }
+        blit_surface(&self.retained_surfaces[&id], layout, damage, self.scale_factor, cr);
     }
 
-    /// Returns a mutable reference to the `Layouter` of the passed `LayoutWidgetHandle`.
+    /// Creates a surface covering `layout` at the scale factor and a
+    /// context on it, on which widgets are drawn at their window
+    /// positions.
     ///
-    /// This can be used to borrow a handle to the layouter in order
-    /// to change layouting parameters.
-    pub fn layouter<L, W>(&mut self, layouter: LayoutWidgetHandle<L, W>) -> &mut L::Implementor
-    where L: Layouter, W: Widget {
-        self.find_node(layouter.widget().id()).layouter_impl::<L>()
+    /// Returns `None` if the surface can not be created.
+    fn offscreen_target(&self, layout: Layout) -> Option<(cairo::ImageSurface, cairo::Context)> {
+        let sf = self.scale_factor;
+        let (w, h) = offscreen_pixels(layout.size.scale(sf));
+        let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, w, h).ok()?;
+        let cr = cairo::Context::new(&surface);
+        cr.scale(sf, sf);
+        cr.translate(-layout.pos.x, -layout.pos.y);
+        Some((surface, cr))
     }
 
-    /// Returns a mutable reference to the `Layouter` of root Layouter.
-    ///
-    /// This can be used to borrow a handle to the layouter in order
-    /// to change layouting parameters.
-    pub fn root_layout(&self) -> LayoutWidgetHandle<VerticalLayouter, RW> {
-        self.root_widget_handle
+    /// Draws the cacheable widget `id` and its children directly, if
+    /// the render cache can not be created.
+    fn paint_uncached(&mut self, id: Id, style: usize, styles: &mut Vec<Style>, expose: &ExposeArea, cr: &cairo::Context) {
+        let damage = self.local_damage(id, expose);
+        self.draw_widget(id, &styles[style], expose, damage, cr);
+        let mut queue = Vec::new();
+        if let Some(node) = self.find_node_ref(id) {
+            for c in node.shown_children() {
+                self.make_expose_queue(c, Some(expose), &mut queue, styles, style);
+            }
+        }
+        self.paint_queue(&queue, styles, expose, false, cr);
     }
 
-    /// Returns a mutable reference to the root widget.
-    pub fn root_widget(&mut self) -> &mut RW {
-        self.widgets[0].downcast_mut::<RW>().expect("Root Widget cast failed")
+    /// Drops all the render caches and retained surfaces, for example
+    /// when the style has changed.
+    fn invalidate_render_caches(&mut self) {
+        self.render_caches.clear();
+        self.retained_surfaces.clear();
     }
 
-    /// Returns a mutable reference to the specified by `widget`.
+    /// Drops the render caches of the widget `id` and its ancestors.
+    fn invalidate_render_caches_of(&mut self, id: Id) {
+        if self.render_caches.is_empty() {
+            return;
+        }
+        let path = match self.root_path(id) {
+            Some(path) => path.to_vec(),
+            None => return
+        };
+        let mut node = &self.root_widget_node;
+        self.render_caches.remove(&node.id);
+        for i in path {
+            node = &node.children[i];
+            self.render_caches.remove(&node.id);
+        }
+    }
+
+    /// Returns the event path from the root widget to the widget at `pos`.
     ///
-    /// It returns a reference to the actual widget instance, so type specific
-    /// methods of the widget can be used.
-    pub fn widget<W: Widget>(&mut self, widget: WidgetHandle<W>) -> &mut W {
-        self.widgets[widget.id()].downcast_mut::<W>().expect("Widget cast failed!")
+    /// The path found last is cached. As long as the pointer stays on
+    /// the leaf of the cached path, only the widgets of the path and
+    /// the children of the leaf are hit tested, rather than searching
+    /// the whole widget tree.
+    fn pointer_path(&self, pos: Coord) -> VecDeque<Id> {
+        if let Some(path) = self.cached_pointer_path(pos) {
+            return path;
+        }
+        if let Some(index) = self.spatial_index.as_ref().filter(|index| index.is_valid()) {
+            return self.indexed_pointer_path(index, pos);
+        }
+        let mut node = &self.root_widget_node;
+        let mut path = VecDeque::from(vec![node.id]);
+        let mut hit_path = Vec::new();
+        'descend: loop {
+            for (i, c) in node.children.iter().enumerate() {
+                if !node.layouter.as_ref().map_or(true, |l| l.shows_child(i)) {
+                    continue;
+                }
+                let wgt = &self.widgets[c.id];
+                if wgt.is_visible() && wgt.is_hit_by(pos) {
+                    if !wgt.is_sensitive() {
+                        break 'descend;
+                    }
+                    path.push_back(c.id);
+                    hit_path.push((i, c.id));
+                    node = c;
+                    continue 'descend;
+                }
+            }
+            break;
+        }
+        self.hit_path.replace(Some(hit_path));
+        path
     }
 
-    /// Performs a step in the cycle of the widget focus.
+    /// Finds the event path to the widget at `pos` among the widgets
+    /// the spatial `index` has for `pos`.
     ///
-    /// Can be called when the root widget received a TAB key press event.
-    pub fn focus_next_widget(&mut self) {
-        let mut fw = self.focused_widget;
-        loop {
-            fw += 1;
-            if fw == self.widgets.len() {
-                fw = 0;
+    /// The candidates are in tree order, so the first hit child of
+    /// the path's end is the one the tree search would find.
+    fn indexed_pointer_path(&self, index: &SpatialIndex, pos: Coord) -> VecDeque<Id> {
+        let root = 0;
+        let mut entries = vec![root];
+        for &e in index.candidates_at(pos) {
+            let entry = index.entry(e);
+            if entry.parent != entries.last().cloned() {
+                continue;
             }
-            if self.widgets[fw].takes_focus() || (fw == self.focused_widget) {
-                break;
+            let wgt = &self.widgets[entry.id];
+            if wgt.is_visible() && wgt.is_hit_by(pos) {
+                if !wgt.is_sensitive() {
+                    break;
+                }
+                entries.push(e);
             }
         }
-
-        self.widgets[self.focused_widget].set_focus(false);
-        self.focused_widget = fw;
-        self.widgets[self.focused_widget].set_focus(true);
+        let hit_path = entries[1..].iter()
+            .map(|&e| (index.entry(e).child_index, index.entry(e).id))
+            .collect();
+        self.hit_path.replace(Some(hit_path));
+        entries.iter().map(|&e| index.entry(e).id).collect()
     }
 
-    /// Focuses the widget specified by `widget`
+    /// Returns the cached event path if it still leads to the widget
+    /// at `pos`.
     ///
-    pub fn focus_widget<W: Widget>(&mut self, widget: WidgetHandle<W>) {
-        let id = widget.id();
-        if self.widgets[id].takes_focus() {
-            self.widgets[self.focused_widget].set_focus(false);
-            self.focused_widget = id;
-            self.widgets[id].set_focus(true);
+    /// The siblings before a widget of the path need to be hit tested
+    /// only if the children of the parent may overlap.
+    fn cached_pointer_path(&self, pos: Coord) -> Option<VecDeque<Id>> {
+        let hit_path = self.hit_path.borrow();
+        let mut node = &self.root_widget_node;
+        let mut path = VecDeque::from(vec![node.id]);
+        for &(i, id) in hit_path.as_ref()? {
+            let child = node.children.get(i).filter(|c| c.id == id)?;
+            if let Some(layouter) = &node.layouter {
+                if !layouter.shows_child(i) {
+                    return None;
+                }
+                if layouter.children_may_overlap() && node.children[..i].iter().enumerate()
+                    .any(|(j, c)| layouter.shows_child(j) && self.widgets[c.id].is_visible() && self.widgets[c.id].is_hit_by(pos)) {
+                    return None;
+                }
+            }
+            let wgt = &self.widgets[id];
+            if !(wgt.is_visible() && wgt.is_sensitive() && wgt.is_hit_by(pos)) {
+                return None;
+            }
+            path.push_back(id);
+            node = child;
+        }
+        let hit_child = node.shown_children()
+            .map(|c| &self.widgets[c.id])
+            .find(|wgt| wgt.is_visible() && wgt.is_hit_by(pos));
+        match hit_child {
+            Some(wgt) if wgt.is_sensitive() => None,
+            _ => Some(path)
         }
     }
 
-    /// Returns `true` iff the window has the focus.
-    pub fn has_focus(&self) -> bool {
-        self.have_focus
+    fn event_path(&self, widget: &WidgetNode, pos: Coord, mut path: VecDeque<usize>) -> VecDeque<usize> {
+        path.push_back(widget.id);
+        for c in widget.shown_children() {
+            let wgt = &self.widgets[c.id];
+            if wgt.is_visible() && wgt.is_hit_by(pos) {
+                if !wgt.is_sensitive() {
+                    return path;
+                }
+                return self.event_path(c, pos, path);
+            }
+        }
+        path
     }
 
-    /// Initiates the next cycle of the event loop
-    ///
-    /// The application should call it at the beginning of the event loop.
-    ///
-    /// From `pugl` documentation:
-    /// If `timeout` is zero, then this function will not block. Plugins
-    /// should always use a timeout of zero to avoid blocking the
-    /// host.
-    ///
-    /// If a positive `timeout` is given, then events will be processed
-    /// for that amount of time, starting from when this function was
-    /// called.
-    ///
-    /// If a `negative` timeout is given, this function will block
-    /// indefinitely until an event occurs.
-    ///
-    /// For continuously animating programs, a timeout that is a
-    /// reasonable fraction of the ideal frame period should be used,
-    /// to minimize input latency by ensuring that as many input
-    /// events are consumed as possible before drawing.
-    pub fn next_event(&mut self, timeout: f64) {
-        for id in 0..self.widgets.len() {
-            let w = &mut self.widgets[id]; if w.needs_repaint() {
-                let pos = w.pos().scale(self.scale_factor);
-                let size = w.size().scale(self.scale_factor);
-                self.post_redisplay_rect(pos, size);
-            }
-            let w = &mut self.widgets[id];
-            if let Some(timeout) = w.reminder_request() {
-                self.start_timer(id, timeout);
-            }
+    fn update_cursor(&self) {
+        let id = self.input_state.hovered();
+        let cursor = match self.paned_layouter(id) {
+            Some(paned) => paned.cursor(),
+            None => self.widgets[id].cursor().unwrap_or(Cursor::Arrow)
+        };
+        self.cursor.set(cursor);
+        self.set_cursor(cursor);
+    }
+
+    /// Returns the `PanedLayouterImpl` if the widget `id` is a paned layout.
+    fn paned_layouter(&self, id: Id) -> Option<&PanedLayouterImpl> {
+        self.find_node_ref(id)?.layouter.as_deref()?.downcast_ref::<PanedLayouterImpl>()
+    }
+
+    /// Returns `true` iff `pos` is on the divider of the paned layout `id`.
+    fn hits_divider(&self, id: Id, pos: Coord) -> bool {
+        match self.find_node_ref(id) {
+            Some(node) => node.layouter.as_deref()
+                .and_then(|l| l.downcast_ref::<PanedLayouterImpl>())
+                .map_or(false, |paned| paned.divider_hit(&self.widgets, &node.children, pos)),
+            None => false
+        }
+    }
+
+    /// Moves the divider of the paned layout `id` to `pos` and
+    /// re-applies the layout.
+    fn drag_divider(&mut self, id: Id, pos: Coord) {
+        let layout = self.widgets[id].layout();
+        let paned = self.find_node(id).layouter_impl::<PanedLayouter>();
+        let ratio = paned.split_ratio();
+        paned.drag_divider(layout, pos);
+        if paned.split_ratio() != ratio {
+            self.do_layout();
+            self.redisplay();
         }
-        self.update(timeout);
     }
 
-    fn make_expose_queue(&self, node: &WidgetNode, area: &ExposeArea, expose_queue: &mut Vec<Id>) {
+    /// Returns the part of the `area` (unscaled window coordinates)
+    /// covered by the widget `id` relative to the widget's position.
+    fn local_damage(&self, id: Id, area: &ExposeArea) -> Layout {
+        let wl = self.widgets[id].layout();
         let pos = area.pos.scale(1./self.scale_factor);
         let size = area.size.scale(1./self.scale_factor);
-        if !self.widgets[node.id].intersects_with(pos, size) {
-            return;
-        }
-        expose_queue.push(node.id);
-        for c in node.children.iter() {
-            self.make_expose_queue(c, area, expose_queue);
+
+        let left = pos.x.max(wl.pos.x);
+        let top = pos.y.max(wl.pos.y);
+        let right = (pos.x + size.w).min(wl.pos.x + wl.size.w);
+        let bottom = (pos.y + size.h).min(wl.pos.y + wl.size.h);
+
+        Layout {
+            pos: Coord { x: left - wl.pos.x, y: top - wl.pos.y },
+            size: Size { w: (right - left).max(0.0), h: (bottom - top).max(0.0) }
         }
     }
 
-    fn event_path(&self, widget: &WidgetNode, pos: Coord, mut path: VecDeque<usize>) -> VecDeque<usize> {
-        path.push_back(widget.id);
-        for c in widget.children.iter() {
-            if self.widgets[c.id].is_hit_by(pos) {
-                return self.event_path(c, pos, path);
+    /// Passes the event `ev` to the widget `id`
+    ///
+    /// If the widget uses local coordinates or a transform, the event's
+    /// position is translated to them and a returned event is
+    /// translated back.
+    fn deliver_event(&mut self, id: Id, ev: Event) -> Option<Event> {
+        self.deliver_event_in(id, ev, Phase::Bubble)
+    }
+
+    fn deliver_event_in(&mut self, id: Id, ev: Event, phase: Phase) -> Option<Event> {
+        #[cfg(feature = "widget-stats")]
+        let start = Instant::now();
+        let result = self.deliver_event_unrecorded(id, ev, phase);
+        if let Some(text) = self.widgets[id].clipboard_request() {
+            self.set_clipboard_text(text);
+        }
+        #[cfg(feature = "widget-stats")]
+        {
+            let stats = self.widget_stats.entry(id).or_default();
+            stats.events_received += 1;
+            if result.is_none() {
+                stats.events_processed += 1;
             }
+            stats.event_time += start.elapsed();
         }
-        path
+        result
     }
 
-    fn find_node(&mut self, id: Id) -> &mut WidgetNode {
-        match self.unlayouted_nodes.get_mut(&id) {
-            Some(l) => l,
-            None => {
-                let path = VecDeque::new();
-                let (path, _) = self.root_widget_node.search(path, id);
-                self.root_widget_node.get_node_by_path(path)
+    fn deliver_event_unrecorded(&mut self, id: Id, mut ev: Event, phase: Phase) -> Option<Event> {
+        let wgt = &mut self.widgets[id];
+        let layout = wgt.layout();
+        let center = Coord { x: layout.pos.x + layout.size.w / 2., y: layout.pos.y + layout.size.h / 2. };
+        let transform = wgt.transform();
+        if let Some(transform) = transform {
+            ev.context.pos = transform.map_back(center, ev.context.pos);
+        }
+        let origin = if wgt.local_coordinates() { layout.pos } else { Coord::default() };
+        ev.context.pos = Coord { x: ev.context.pos.x - origin.x, y: ev.context.pos.y - origin.y };
+        let result = match phase {
+            Phase::Capture => wgt.capture_event(ev),
+            Phase::Bubble => wgt.event(ev),
+            Phase::Observe => {
+                wgt.observe_event(ev);
+                Some(ev)
+            }
+        };
+        self.event_marked_handled = wgt.take_event_handled();
+        result.map(|mut ev| {
+            ev.context.pos = ev.context.pos + origin;
+            if let Some(transform) = transform {
+                ev.context.pos = transform.map(center, ev.context.pos);
+            }
+            ev
+        })
+    }
+
+    /// Dispatches the event `ev` from the windowing system to the widgets
+    fn dispatch_event(&mut self, ev: Event) -> Status {
+        let ev = match self.sanitizer.sanitize(ev) {
+            Some(ev) => ev.scale_pos(1./self.scale_factor),
+            None => return Status::Success
+        };
+        let ev = match self.filter_event(ev) {
+            Some(ev) => ev,
+            None => return Status::Success
+        };
+        if !self.popups.is_empty() {
+            if let Some(status) = self.forward_to_popup(ev) {
+                return status;
+            }
+        }
+        if let EventType::MouseMove(_) = ev.data {
+            self.track_pressed_widget(ev.pos());
+        }
+        let status = self.route_event(ev);
+        if let EventType::MouseButtonRelease(btn) = ev.data {
+            if btn.num == 1 {
+                self.release_pressed_widget(ev.pos());
             }
         }
+        status
     }
-}
 
+    /// Arms the pressed widget iff the pointer at `pos` is over it,
+    /// as the release is then to click it.
+    fn track_pressed_widget(&mut self, pos: Coord) {
+        if let Some(id) = self.pressed_widget {
+            let wgt = &mut self.widgets[id];
+            let hit = wgt.is_hit_by(pos);
+            wgt.set_armed(hit);
+        }
+    }
 
+    /// Releases the pressed widget by the release of the primary
+    /// button at `pos`, and clicks it if it is still armed.
+    fn release_pressed_widget(&mut self, pos: Coord) {
+        let id = match self.pressed_widget.take() {
+            Some(id) => id,
+            None => return
+        };
+        let wgt = &mut self.widgets[id];
+        let click = wgt.is_armed() && wgt.is_hit_by(pos) && wgt.is_sensitive();
+        wgt.set_armed(false);
+        wgt.set_pressed(false);
+        if click {
+            wgt.clicked();
+        }
+    }
 
-impl<RW: Widget> PuglViewTrait for UI<RW> {
-    fn exposed (&mut self, expose: &ExposeArea, cr: &cairo::Context) {
-        let mut expose_queue: Vec<Id> = Vec::with_capacity(self.widgets.len());
-        cr.scale(self.scale_factor, self.scale_factor);
-        self.make_expose_queue(&self.root_widget_node, expose, &mut expose_queue);
-        for wid in expose_queue {
-            self.widgets[wid].exposed(expose, cr);
+    /// Turns the drag of the capturing widget into a drag and drop of
+    /// `data`, see [`Widget::drag_data()`](../widget/trait.Widget.html#method.drag_data).
+    fn start_drag_and_drop(&mut self, data: Box<dyn Any>, pos: Coord) {
+        if let Some(id) = self.pressed_widget.take() {
+            self.widgets[id].set_armed(false);
+            self.widgets[id].set_pressed(false);
+        }
+        if let Some(id) = self.gesture_widget.take() {
+            self.widgets[id].gesture_end_wrap();
         }
+        let target = self.drop_target_at(pos, &*data);
+        self.input_state = self.input_state.start_drag().drag_over(target);
+        self.drag_data = Some(data);
     }
 
-    fn event (&mut self, ev: Event) -> Status {
-        let ev = ev.scale_pos(1./self.scale_factor);
+    /// Ends the drag and drop and drops the dragged data on the drop
+    /// target iff `drop` is `true`.
+    fn end_drag_and_drop(&mut self, drop: bool) {
+        let target = self.input_state.drop_target();
+        self.input_state = if drop { self.input_state.release() } else { self.input_state.cancel_drag() };
+        let data = self.drag_data.take();
+        if let (true, Some(target), Some(data)) = (drop, target, data) {
+            self.widgets[target].dropped(data);
+        }
+    }
+
+    /// Returns the innermost widget at `pos` accepting the dragged `data`.
+    fn drop_target_at(&self, pos: Coord, data: &dyn Any) -> Option<Id> {
+        let path = match self.input_state.modal().and_then(|m| self.find_node_ref(m)) {
+            Some(node) => self.event_path(node, pos, VecDeque::new()),
+            None => self.pointer_path(pos)
+        };
+        path.iter().rev().copied().find(|&id| self.accepts_input(id) && self.widgets[id].accepts_drop(data))
+    }
+
+    /// Delivers the sanitized and filtered event `ev` to the widgets.
+    fn route_event(&mut self, ev: Event) -> Status {
+        self.recognize_gestures(&ev);
+        self.track_kinetic_scrolling(&ev);
+        match ev.data {
+            EventType::KeyPress(_) => self.set_keyboard_mode(true),
+            EventType::MouseButtonPress(_) => self.set_keyboard_mode(false),
+            _ => {}
+        }
+        match ev.data {
+            EventType::MouseMove(_) |
+            EventType::PointerIn => {
+                self.pointer_pos = ev.pos();
+                self.hide_tooltip();
+            }
+            EventType::PointerOut |
+            EventType::MouseButtonPress(_) |
+            EventType::KeyPress(_) |
+            EventType::Scroll(_) => self.hide_tooltip(),
+            _ => {}
+        }
+        if self.input_state.drag_source().is_some() {
+            match ev.data {
+                EventType::MouseMove(_) => {
+                    let target = self.drag_data.as_ref().and_then(|data| self.drop_target_at(ev.pos(), &**data));
+                    self.input_state = self.input_state.drag_over(target);
+                    return Status::Success;
+                }
+                EventType::MouseButtonRelease(btn) if btn.num == 1 => {
+                    self.end_drag_and_drop(true);
+                    return Status::Success;
+                }
+                // pugl reports the Escape key as character
+                EventType::KeyPress(Key { key: KeyVal::Character('\u{1b}'), .. }) => {
+                    self.end_drag_and_drop(false);
+                    return Status::Success;
+                }
+                _ => {}
+            }
+        }
+        if let EventType::KeyPress(key) | EventType::KeyRelease(key) = ev.data {
+            if self.input_state.capturing().is_none() && self.text_input_focused() {
+                self.deliver_text_key(ev, key);
+                return Status::Success;
+            }
+        }
         let ev = match self.widgets[0].event(ev) {
             Some(ev) => ev,
             None => return Status::Success
@@ -539,100 +4637,361 @@ impl<RW: Widget> PuglViewTrait for UI<RW> {
         let ev = match ev.data {
             EventType::KeyPress (_) |
             EventType::KeyRelease (_) => {
-                if self.drag_ongoing {
-                    self.widgets[self.widget_under_pointer].event(ev);
+                if let Some(id) = self.input_state.capturing() {
+                    self.deliver_event(id, ev);
                     return Status::Success
                 }
-                match self.widgets[self.focused_widget].event(ev) {
-                    Some(ev) => ev,
-                    None => return Status::Success
+                let target = match self.input_state.modal() {
+                    Some(m) if !self.is_descendant(self.focused_widget, m) => m,
+                    _ => self.focused_widget
+                };
+                if !self.accepts_input(target) {
+                    ev
+                } else {
+                    match self.deliver_event(target, ev) {
+                        Some(ev) => ev,
+                        None => return Status::Success
+                    }
                 }
             }
             EventType::MouseButtonPress(btn) => {
                 if btn.num == 1 {
-                    self.drag_ongoing = true;
+                    self.drag_predictor.reset();
                 }
                 ev
             }
             EventType::MouseButtonRelease(btn) => {
-                if btn.num == 1 && self.drag_ongoing {
-                    self.drag_ongoing = false;
-                    let wgt = &mut self.widgets[self.widget_under_pointer];
-                    let pev = wgt.event(ev);
-                    if !wgt.is_hit_by(ev.pos()) {
-                        wgt.pointer_leave_wrap();
-                    }
-                    match pev {
-                        Some(ev) => ev,
-                        None => return Status::Success
+                match self.input_state.capturing() {
+                    Some(id) if btn.num == 1 => {
+                        self.input_state = self.input_state.release();
+                        self.dragged_divider = None;
+                        let pev = self.deliver_event(id, ev);
+                        if let Some(id) = self.gesture_widget.take() {
+                            self.widgets[id].gesture_end_wrap();
+                        }
+                        let wgt = &mut self.widgets[id];
+                        if !wgt.is_hit_by(ev.pos()) {
+                            wgt.pointer_leave_wrap();
+                        }
+                        match pev {
+                            Some(ev) => ev,
+                            None => return Status::Success
+                        }
                     }
-                } else {
-                    ev
+                    _ => ev
                 }
             }
+            EventType::MouseMove(_) => match self.input_state.capturing() {
+                Some(id) => {
+                    if self.dragged_divider == Some(id) {
+                        self.drag_divider(id, ev.pos());
+                        return Status::Success;
+                    }
+                    if let Some(data) = self.widgets[id].drag_data() {
+                        self.start_drag_and_drop(data, ev.pos());
+                        return Status::Success;
+                    }
+                    let mut ev = ev;
+                    if self.widgets[id].predicts_drag() {
+                        let time = self.start_time.elapsed().as_secs_f64();
+                        ev.context.pos = self.drag_predictor.predict(ev.pos(), time);
+                    }
+                    self.deliver_event(id, ev);
+                    return Status::Success;
+                }
+                None => ev
+            },
             _ => {
-                if self.drag_ongoing {
-                    self.widgets[self.widget_under_pointer].event(ev);
+                if let Some(id) = self.input_state.capturing() {
+                    self.deliver_event(id, ev);
                     return Status::Success;
                 }
                 ev
             }
         };
 
-        let mut event_path = self.event_path(&self.root_widget_node, ev.pos(), VecDeque::new());
+        let mut event_path = match self.input_state.modal().and_then(|m| self.find_node_ref(m)) {
+            Some(node) => self.event_path(node, ev.pos(), VecDeque::new()),
+            None => self.pointer_path(ev.pos())
+        };
         let mut evop = Some(ev);
 
         if let Some(id) = event_path.back() {
-            if self.widget_under_pointer != *id {
-                self.widgets[self.widget_under_pointer].pointer_leave_wrap();
+            let mut hover_changed = false;
+            if self.input_state.hovered() != *id {
+                self.widgets[self.input_state.hovered()].pointer_leave_wrap();
                 self.widgets[*id].pointer_enter_wrap();
-                self.widget_under_pointer = *id;
+                self.input_state = self.input_state.hover(*id);
+                hover_changed = true;
             }
             if ev.data == EventType::PointerIn {
                 self.widgets[*id].pointer_enter_wrap();
-                self.widget_under_pointer = *id;
+                self.input_state = self.input_state.hover(*id);
+                hover_changed = true;
+            }
+            if hover_changed {
+                self.update_cursor();
+            }
+            if let EventType::MouseButtonPress(btn) = ev.data {
+                if btn.num == 1 {
+                    if self.focus_policy == FocusPolicy::ClickToFocus {
+                        let focus = event_path.iter().rev().copied()
+                            .find(|&wid| self.widgets[wid].takes_focus() && self.accepts_input(wid));
+                        if let Some(wid) = focus {
+                            self.move_focus(wid);
+                        }
+                    }
+                    self.input_state = self.input_state.press(*id);
+                    if let Some(pressed) = self.pressed_widget.take() {
+                        self.widgets[pressed].set_armed(false);
+                        self.widgets[pressed].set_pressed(false);
+                    }
+                    if self.widgets[*id].is_sensitive() {
+                        self.widgets[*id].set_pressed(true);
+                        self.widgets[*id].set_armed(true);
+                        self.pressed_widget = Some(*id);
+                    }
+                    if self.hits_divider(*id, ev.pos()) {
+                        self.dragged_divider = Some(*id);
+                    }
+                    if self.widgets[*id].reports_gestures() && self.widgets[*id].is_sensitive() {
+                        self.widgets[*id].gesture_begin_wrap();
+                        self.gesture_widget = Some(*id);
+                    }
+                }
+            }
+            match ev.data {
+                EventType::MouseMove(_) |
+                EventType::PointerIn => {
+                    if self.widgets[*id].tooltip().is_some() {
+                        self.tooltip.set_pending();
+                        self.start_timer(TOOLTIP_TIMER, self.tooltip.delay());
+                    }
+                }
+                _ => {}
             }
             if ev.data == EventType::PointerOut {
-                self.widgets[self.widget_under_pointer].pointer_leave_wrap();
+                self.widgets[self.input_state.hovered()].pointer_leave_wrap();
             }
         }
 
+        let target = event_path.back().cloned();
+        for &id in event_path.iter().take(event_path.len().saturating_sub(1)) {
+            if !self.widgets[id].captures_events() {
+                continue;
+            }
+            evop = match evop {
+                Some(ev) => self.deliver_event_in(id, ev, Phase::Capture),
+                None => break
+            }
+        }
+
+        let is_scroll = matches!(ev.data, EventType::Scroll(_));
+        let mut handled = false;
         while let Some(id) = event_path.pop_back() {
+            if is_scroll && Some(id) != target && !self.widgets[id].scroll_container() {
+                continue;
+            }
             evop = match evop {
+                Some(ev) if handled => self.deliver_event_in(id, ev, Phase::Observe),
                 Some(ev) => {
-                    self.widgets[id].event(ev)
+                    let result = self.deliver_event(id, ev);
+                    handled = self.event_marked_handled;
+                    result
                 },
                 None => break
             }
         }
+        if handled {
+            evop = None;
+        }
+
+        if let Some(Event { data: EventType::KeyPress(key), .. }) = evop {
+            self.handle_window_shortcut(key);
+        }
 
         Status::Success
     }
 
+    /// Returns the top node of the tree `tree`, see `NodePath`.
+    fn tree(&self, tree: Id) -> Option<&WidgetNode> {
+        if tree == 0 {
+            return Some(&self.root_widget_node);
+        }
+        self.unlayouted_nodes.get(&tree).or_else(|| self.parked_nodes.get(&tree))
+    }
+
+    /// Returns the path of the widget `id` in the tree of the root
+    /// widget, if it is in there.
+    fn root_path(&self, id: Id) -> Option<&[usize]> {
+        self.node_index.get(&id)
+            .filter(|location| location.tree == 0)
+            .map(|location| location.path.as_slice())
+    }
+
+    fn find_node_ref(&self, id: Id) -> Option<&WidgetNode> {
+        let location = self.node_index.get(&id)?;
+        self.tree(location.tree).map(|tree| tree.node_at(&location.path))
+    }
+
+    fn find_node(&mut self, id: Id) -> &mut WidgetNode {
+        let location = self.node_index.get(&id).expect("widget not registered");
+        let tree = match location.tree {
+            0 => &mut self.root_widget_node,
+            tree => match self.unlayouted_nodes.get_mut(&tree) {
+                Some(node) => node,
+                None => self.parked_nodes.get_mut(&tree).expect("widget tree not found")
+            }
+        };
+        tree.node_at_mut(&location.path)
+    }
+
+    /// Rebuilds the index of the node locations from scratch.
+    fn rebuild_node_index(&mut self) {
+        self.node_index.clear();
+        if let Some(index) = self.spatial_index.as_mut() {
+            index.invalidate();
+        }
+        self.root_widget_node.collect_paths(0, &mut Vec::new(), &mut self.node_index);
+        for (&tree, node) in self.unlayouted_nodes.iter().chain(self.parked_nodes.iter()) {
+            node.collect_paths(tree, &mut Vec::new(), &mut self.node_index);
+        }
+    }
+}
+
+
+
+impl<RW: Widget> PuglViewTrait for UI<RW> {
+    fn exposed (&mut self, expose: &ExposeArea, cr: &cairo::Context) {
+        #[cfg(feature = "frame-stats")]
+        let start = Instant::now();
+        let mut expose_queue: Vec<(Id, usize)> = Vec::with_capacity(self.widgets.len());
+        let mut styles = vec![self.style.clone()];
+        #[cfg(feature = "cairo-helpers")]
+        self.assets.prepare(self.scale_factor);
+        cr.scale(self.scale_factor, self.scale_factor);
+        match self.spatial_index.as_ref().filter(|index| index.is_valid()) {
+            Some(index) => self.make_indexed_expose_queue(index, expose, &mut expose_queue, &mut styles),
+            None => self.make_expose_queue(&self.root_widget_node, Some(expose), &mut expose_queue, &mut styles, 0)
+        }
+        self.cull_covered_widgets(&mut expose_queue, expose);
+        let now = self.start_time.elapsed().as_secs_f64();
+        self.previous_frame_time = if self.frame_exposed { self.frame_time } else { now };
+        self.frame_time = now;
+        self.frame_exposed = true;
+
+        let pos = expose.pos.scale(1./self.scale_factor);
+        let size = expose.size.scale(1./self.scale_factor);
+        let holes = self.host_drawn_layouts();
+        cr.save();
+        cr.set_fill_rule(cairo::FillRule::EvenOdd);
+        cr.rectangle(pos.x, pos.y, size.w, size.h);
+        for hole in holes.iter() {
+            cr.rectangle(hole.pos.x, hole.pos.y, hole.size.w, hole.size.h);
+        }
+        cr.clip();
+        cr.set_fill_rule(cairo::FillRule::Winding);
+        self.paint_queue(&expose_queue, &mut styles, expose, false, cr);
+
+        for wid in self.highlighted.iter() {
+            let w = &self.widgets[*wid];
+            if w.intersects_with(pos, size) && self.is_shown(*wid) {
+                self.theme.draw_highlight(cr, w.pos(), w.size(), self.high_contrast);
+            }
+        }
+        let fw = self.focused_widget;
+        if self.focus_overlay && self.keyboard_mode && fw != 0 && self.is_shown(fw) {
+            let w = &self.widgets[fw];
+            if w.intersects_with(pos, size) {
+                self.theme.draw_focus_ring(cr, w.pos(), w.size(), self.high_contrast);
+            }
+        }
+        cr.restore();
+        #[cfg(feature = "text")]
+        {
+            let bounds = self.widgets[0].size();
+            self.tooltip.draw(cr, bounds, &self.theme, self.high_contrast);
+        }
+
+        #[cfg(feature = "frame-stats")]
+        {
+            self.current_frame_stats.exposing = start.elapsed();
+            self.last_frame_stats = std::mem::take(&mut self.current_frame_stats);
+        }
+    }
+
+    fn event (&mut self, ev: Event) -> Status {
+        #[cfg(feature = "frame-stats")]
+        let start = Instant::now();
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(ev);
+        }
+        let status = match ev.data {
+            EventType::Map | EventType::Unmap => {
+                self.set_mapped(ev.data == EventType::Map);
+                Status::Success
+            }
+            EventType::MouseMove(_) if self.coalesces_motion() => {
+                self.pending_motion = Some(ev);
+                Status::Success
+            }
+            _ => {
+                self.flush_motion();
+                self.dispatch_event(ev)
+            }
+        };
+        #[cfg(feature = "frame-stats")]
+        {
+            self.current_frame_stats.event_dispatch += start.elapsed();
+        }
+        status
+    }
+
     fn focus_in(&mut self) -> Status {
+        if !self.have_focus {
+            self.focus_change = Some(true);
+        }
         self.have_focus = true;
         self.widgets[self.focused_widget].set_focus(true);
         Status::Success
     }
 
     fn focus_out(&mut self) -> Status {
+        if self.have_focus {
+            self.focus_change = Some(false);
+        }
         self.have_focus = false;
         self.widgets[self.focused_widget].set_focus(false);
+        for ev in self.sanitizer.focus_lost() {
+            self.event(ev);
+        }
         Status::Success
     }
 
     fn resize (&mut self, size: Size) {
-        self.widgets[0].set_size(&size.scale(1./self.scale_factor));
-        self.do_layout();
+        if let Some(scale_factor) = self.monitor_scale_factor() {
+            if self.change_scale_factor(scale_factor) {
+                self.redisplay();
+            }
+        }
+        let size = self.size_hints.constrain(size.scale(1./self.scale_factor));
+        self.widgets[0].set_size(&size);
+        self.relayout();
     }
 
     fn close_request (&mut self) {
-        self.close_request_issued = true;
+        if self.close_veto_cycles == 0 {
+            self.close_request_issued = true;
+        } else if self.pending_close_request.is_none() {
+            self.pending_close_request = Some(self.close_veto_cycles);
+        }
     }
 
     fn timer_event(&mut self, id: usize) -> Status {
-        if !self.widgets[id].reminder_handler() {
-            self.stop_timer(id);
+        if self.in_update {
+            self.expired_timers.push(id);
+        } else {
+            self.handle_timer(id);
         }
         Status::Success
     }
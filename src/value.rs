@@ -0,0 +1,172 @@
+//! Formatting and parsing of parameter values
+//!
+//! A [`ValueFormat`](struct.ValueFormat.html) defines how the value
+//! of a parameter is shown as text and how text entered by the user
+//! is parsed back into a value. Defined once by the application next
+//! to the parameter's range, it can be shared by display widgets,
+//! tooltips and text entries editing the value, so that all of them
+//! agree on the units.
+//!
+//! ```
+//! # use pugl_ui::value::*;
+//! let gain = ValueFormat::decibel();
+//! assert_eq!(gain.format(-6.02), "-6.0 dB");
+//! assert_eq!(gain.parse("-12 dB"), Some(-12.0));
+//!
+//! let cutoff = ValueFormat::frequency();
+//! assert_eq!(cutoff.format(1500.), "1.50 kHz");
+//! assert_eq!(cutoff.parse("1.5k"), Some(1500.));
+//! ```
+use std::fmt;
+use std::sync::Arc;
+
+const NOTE_NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+
+/// The formatter and parser of a parameter value
+///
+/// Cloning is cheap, the closures are shared.
+#[derive(Clone)]
+pub struct ValueFormat {
+    formatter: Arc<dyn Fn(f64) -> String + Send + Sync>,
+    parser: Arc<dyn Fn(&str) -> Option<f64> + Send + Sync>
+}
+
+impl fmt::Debug for ValueFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ValueFormat")
+    }
+}
+
+impl Default for ValueFormat {
+    /// Plain numbers with two decimals
+    fn default() -> ValueFormat {
+        ValueFormat::new(|v| format!("{:.2}", v), |s| s.trim().parse().ok())
+    }
+}
+
+impl ValueFormat {
+    /// Creates a format from a `formatter` and a `parser`.
+    ///
+    /// The parser is supposed to return `None` if the text cannot be
+    /// parsed.
+    pub fn new<F, P>(formatter: F, parser: P) -> ValueFormat
+    where F: Fn(f64) -> String + Send + Sync + 'static,
+          P: Fn(&str) -> Option<f64> + Send + Sync + 'static {
+        ValueFormat {
+            formatter: Arc::new(formatter),
+            parser: Arc::new(parser)
+        }
+    }
+
+    /// Values in decibels, like `-6.0 dB`
+    ///
+    /// Values below -90 dB are shown as `-inf dB`, which parses to
+    /// negative infinity.
+    pub fn decibel() -> ValueFormat {
+        ValueFormat::new(
+            |v| if v < -90. { String::from("-inf dB") } else { format!("{:.1} dB", v) },
+            |s| {
+                let s = strip_unit(s, "dB");
+                if s == "-inf" {
+                    Some(f64::NEG_INFINITY)
+                } else {
+                    s.parse().ok()
+                }
+            }
+        )
+    }
+
+    /// Frequencies in Hz, like `440 Hz` or `1.50 kHz`
+    ///
+    /// The parser accepts the `k` suffix for kHz.
+    pub fn frequency() -> ValueFormat {
+        ValueFormat::new(
+            |v| if v.abs() >= 1000. { format!("{:.2} kHz", v / 1000.) } else { format!("{:.0} Hz", v) },
+            |s| {
+                let s = strip_unit(s, "Hz");
+                match s.strip_suffix('k') {
+                    Some(k) => k.trim().parse::<f64>().ok().map(|v| v * 1000.),
+                    None => s.parse().ok()
+                }
+            }
+        )
+    }
+
+    /// MIDI note numbers as note names, like `A4` for 69
+    pub fn note_name() -> ValueFormat {
+        ValueFormat::new(
+            |v| {
+                let note = v.round() as i32;
+                format!("{}{}", NOTE_NAMES[note.rem_euclid(12) as usize], note.div_euclid(12) - 1)
+            },
+            |s| {
+                let s = s.trim();
+                let split = s.find(|c: char| c == '-' || c.is_ascii_digit())?;
+                let (name, octave) = s.split_at(split);
+                let index = NOTE_NAMES.iter().position(|n| n.eq_ignore_ascii_case(name))?;
+                let octave: i32 = octave.parse().ok()?;
+                Some(f64::from((octave + 1) * 12 + index as i32))
+            }
+        )
+    }
+
+    /// Returns `value` as text.
+    pub fn format(&self, value: f64) -> String {
+        (self.formatter)(value)
+    }
+
+    /// Returns the value of `text`, or `None` if it cannot be parsed.
+    pub fn parse(&self, text: &str) -> Option<f64> {
+        (self.parser)(text)
+    }
+}
+
+fn strip_unit<'a>(s: &'a str, unit: &str) -> &'a str {
+    let s = s.trim();
+    s.strip_suffix(unit).unwrap_or(s).trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decibel() {
+        let f = ValueFormat::decibel();
+        assert_eq!(f.format(0.), "0.0 dB");
+        assert_eq!(f.format(-100.), "-inf dB");
+        assert_eq!(f.parse("3.5"), Some(3.5));
+        assert_eq!(f.parse(" -inf dB"), Some(f64::NEG_INFINITY));
+        assert_eq!(f.parse("loud"), None);
+    }
+
+    #[test]
+    fn frequency() {
+        let f = ValueFormat::frequency();
+        assert_eq!(f.format(440.), "440 Hz");
+        assert_eq!(f.parse("440 Hz"), Some(440.));
+        assert_eq!(f.parse("1.5 kHz"), Some(1500.));
+    }
+
+    #[test]
+    fn note_name() {
+        let f = ValueFormat::note_name();
+        assert_eq!(f.format(69.), "A4");
+        assert_eq!(f.format(61.), "C#4");
+        assert_eq!(f.format(0.), "C-1");
+        assert_eq!(f.parse("A4"), Some(69.));
+        assert_eq!(f.parse("c#4"), Some(61.));
+        assert_eq!(f.parse("C-1"), Some(0.));
+        assert_eq!(f.parse("H4"), None);
+    }
+
+    #[test]
+    fn custom_format_shared() {
+        let percent = ValueFormat::new(|v| format!("{:.0} %", v * 100.), |s| {
+            strip_unit(s, "%").parse::<f64>().ok().map(|v| v / 100.)
+        });
+        let shared = percent.clone();
+        assert_eq!(shared.format(0.5), "50 %");
+        assert_eq!(percent.parse("25%"), Some(0.25));
+    }
+}
@@ -1,12 +1,28 @@
 //! Everything to describe an access a widget
+use std::any::Any;
 use std::marker::PhantomData;
 use downcast_rs::DowncastSync;
 
 use pugl_sys::*;
 
+use crate::accessibility::{Accessible, Action};
+#[cfg(feature = "cairo-helpers")]
+use crate::assets::Assets;
+use crate::panel;
+#[cfg(feature = "cairo-helpers")]
+use crate::panel::{Insets, Panel};
+use crate::draw::DrawBackend;
+use crate::gestures::PointerGesture;
+use crate::theme::{Style, Theme};
+#[cfg(feature = "text")]
+use crate::text::TextMetrics;
+
 /// The unique Id of a widget.
 ///
 /// The Id is the way, widgets can be accessed by a [`WidgetHandle`](struct.WidgetHandle.html).
+/// The Id of a widget removed by
+/// [`UI::remove_widget()`](../ui/struct.UI.html#method.remove_widget)
+/// is reused by widgets registered later.
 pub type Id = usize;
 
 /// The `Widget` trait.
@@ -55,16 +71,63 @@ pub trait Widget : DowncastSync {
         Some (ev)
     }
 
+    /// Supposed to return `true` if the widget wants to see pointer
+    /// events for its descendants before them in a capture phase,
+    /// see [`capture_event()`](#method.capture_event).
+    ///
+    /// Default: `false`
+    fn captures_events(&self) -> bool { false }
+
+    /// Called with the pointer events targeted at a descendant of the
+    /// widget, if the widget [captures events](#method.captures_events).
+    ///
+    /// The capture phase runs from the root towards the target before
+    /// the events are passed by [`event()`](#method.event) from the
+    /// target towards the root. As there, returning `None` stops the
+    /// delivery, so that neither the target nor any widget in between
+    /// gets the event.
+    ///
+    /// Default implementation passes the event on.
+    fn capture_event(&mut self, ev: Event) -> Option<Event> {
+        Some(ev)
+    }
+
+    /// Called for widgets on the way to the root after a descendant
+    /// has [marked the event as handled](#method.mark_event_handled).
+    ///
+    /// Allows decorating widgets to observe the interactions of their
+    /// children without acting on events a child has acted on.
+    ///
+    /// Default implementation does nothing.
+    fn observe_event(&mut self, _ev: Event) {}
+
     /// Called when the widget has to draw itself.
     ///
     /// # Parameters
     ///
-    /// * `expose: &ExposeArea` – a pugl_sys::pugl::ExposeArea
+    /// * `ctx: &PaintContext` – a [`PaintContext`](struct.PaintContext.html)
     /// carrying the information which rectangle of the widget
-    /// actually needs to be redrawn.
+    /// actually needs to be redrawn and further information useful
+    /// for drawing.
+    ///
+    /// * `cr: &cairo::Context` – the cairo context to draw to.
     ///
     /// Default implementation does nothing.
-    fn exposed(&mut self, _expose: &ExposeArea, _cr: &cairo::Context) {}
+    fn exposed(&mut self, _ctx: &PaintContext, _cr: &cairo::Context) {}
+
+    /// Called by the `UI` when the widget has to draw itself with the
+    /// drawing backend `backend`.
+    ///
+    /// Only needs to be implemented by widgets making use of other
+    /// backends than cairo, see the [`draw`](../draw/index.html) module.
+    ///
+    /// Default implementation calls [`exposed()`](#method.exposed)
+    /// with the cairo context of the backend, if there is one.
+    fn draw(&mut self, ctx: &PaintContext, backend: &dyn DrawBackend) {
+        if let Some(cr) = backend.cairo() {
+            self.exposed(ctx, cr);
+        }
+    }
 
     /// Supposed to return the minimum size of the widget.
     ///
@@ -88,6 +151,250 @@ pub trait Widget : DowncastSync {
         false
     }
 
+    /// Supposed to return the mouse cursor to be shown while the
+    /// pointer is hovering the widget.
+    ///
+    /// The UI sets the cursor when the hovered widget changes. If
+    /// `None` is returned `Cursor::Arrow` is used.
+    ///
+    /// Default: `None`
+    fn cursor(&self) -> Option<Cursor> { None }
+
+    /// Supposed to return the text of the widget's tooltip.
+    ///
+    /// The UI shows the tooltip when the pointer rests on the widget.
+    ///
+    /// Default: `None`
+    fn tooltip(&self) -> Option<String> { None }
+
+    /// Supposed to return the description of the widget for
+    /// assistive technologies like screen readers, see
+    /// [`accessibility`](../accessibility/index.html).
+    ///
+    /// Widgets returning `None` are left out of the accessibility
+    /// tree, their accessible children become children of their
+    /// accessible parent. That is usually wanted for layouts.
+    ///
+    /// Default: `None`
+    fn accessible(&self) -> Option<Accessible> { None }
+
+    /// Called when an assistive technology requests to perform the
+    /// action `action` listed by [`accessible()`](#method.accessible).
+    ///
+    /// Supposed to return `true` iff the action was performed.
+    /// `Action::Focus` is performed by the UI and does not reach the
+    /// widget.
+    ///
+    /// Default: `false`
+    fn accessible_action(&mut self, _action: Action) -> bool { false }
+
+    /// Supposed to return `true` if the widget wants the pointer
+    /// position of `MouseMove` events to be extrapolated during drags.
+    ///
+    /// On hosts with a high frame latency knobs and sliders feel
+    /// sluggish when following the pointer. The UI can then predict
+    /// the pointer position from its velocity, see
+    /// [`UI::set_drag_prediction()`](../ui/struct.UI.html#method.set_drag_prediction).
+    ///
+    /// Default: `false`
+    fn predicts_drag(&self) -> bool { false }
+
+    /// Supposed to return `true` if the widget is a text entry, see
+    /// [`input`](../input/index.html).
+    ///
+    /// While such a widget is focused, it receives the key events
+    /// before anybody else, even before the root widget. Only the key
+    /// events it does not process are passed to the root widget.
+    /// `Ctrl-V` is not passed as key event but delivers the content of
+    /// the UI's clipboard to [`paste()`](#method.paste).
+    ///
+    /// Default: `false`
+    fn takes_text_input(&self) -> bool { false }
+
+    /// Called with the content of the UI's clipboard when `Ctrl-V` is
+    /// pressed while the widget is a focused text entry, see
+    /// [`takes_text_input()`](#method.takes_text_input).
+    ///
+    /// Default implementation does nothing.
+    fn paste(&mut self, _text: &str) {}
+
+    /// Supposed to return `true` if the widget is a scroll container,
+    /// like a scroll area or a list.
+    ///
+    /// A `Scroll` event that is not processed by the widget under the
+    /// pointer is offered only to the ancestors that are scroll
+    /// containers, from the nearest to the root. So a dial inside a
+    /// scroll area can take the scroll events, while scrolling over a
+    /// label scrolls the area, and not some other ancestor.
+    ///
+    /// Default: `false`
+    fn scroll_container(&self) -> bool { false }
+
+    /// Supposed to return `true` if a scroll container wants the
+    /// scrolling to go on after it has been dragged.
+    ///
+    /// The UI tracks the [`scroll_position()`](#method.scroll_position)
+    /// while the primary button is held down on the widget or its
+    /// children. If it was changing fast enough at the release, the
+    /// UI continues to move it by
+    /// [`set_scroll_position()`](#method.set_scroll_position) with
+    /// the velocity decaying by the `kinetic_friction` of the
+    /// [`InteractionSettings`](struct.InteractionSettings.html). The
+    /// widget is supposed to clamp the position to its scroll range,
+    /// which stops the motion. A click anywhere stops it as well.
+    ///
+    /// Default: `false`
+    fn kinetic_scrolling(&self) -> bool { false }
+
+    /// Supposed to return `true` if the area of the widget is drawn
+    /// by the host or an external renderer.
+    ///
+    /// Then the UI does not paint anything into the widget's area,
+    /// see [`HostRegion`](../region/struct.HostRegion.html).
+    ///
+    /// Default: `false`
+    fn host_drawn(&self) -> bool { false }
+
+    /// Supposed to return `true` if the widget needs every single
+    /// `MouseMove` event, for example for freehand drawing.
+    ///
+    /// Otherwise motion events may be coalesced, see
+    /// [`UI::set_motion_coalescing()`](../ui/struct.UI.html#method.set_motion_coalescing).
+    ///
+    /// Default: `false`
+    fn wants_all_motion_events(&self) -> bool { false }
+
+    /// Supposed to return `true` if the widget is bound to a plugin
+    /// parameter and wants its user gestures to be reported.
+    ///
+    /// A gesture begins when the primary mouse button is pressed on
+    /// the widget and ends when it is released. Hosts need the
+    /// begin-edit and end-edit notifications to record automation
+    /// correctly. The application retrieves them by
+    /// [`gestures()`](#method.gestures).
+    ///
+    /// Default: `false`
+    fn reports_gestures(&self) -> bool { false }
+
+    /// Supposed to return `true` if the UI should recognize long
+    /// presses and swipes on the widget, see
+    /// [`gestures`](../gestures/index.html).
+    ///
+    /// Default: `false`
+    fn recognizes_gestures(&self) -> bool { false }
+
+    /// Called when the UI has recognized the pointer gesture `gesture`
+    /// on the widget.
+    ///
+    /// Only called if [`recognizes_gestures()`](#method.recognizes_gestures)
+    /// returns `true`.
+    fn pointer_gesture(&mut self, _gesture: PointerGesture) {}
+
+    /// Supposed to return `true` if the widget draws and processes
+    /// events in widget local coordinates.
+    ///
+    /// Then the UI translates the `cairo::Context` to the widget's
+    /// origin before calling [`exposed()`](#method.exposed) and the
+    /// position of the events passed to [`event()`](#method.event) is
+    /// relative to the widget's origin. Otherwise window coordinates
+    /// are used, which is the default.
+    ///
+    /// Default: `false`
+    fn local_coordinates(&self) -> bool { false }
+
+    /// Supposed to return the transform with which the widget is to
+    /// be drawn, like a rotation for a vertical label.
+    ///
+    /// The UI applies the [`Transform`](struct.Transform.html) around
+    /// the center of the widget to the `cairo::Context` before calling
+    /// [`exposed()`](#method.exposed) and maps the position of the
+    /// events passed to [`event()`](#method.event) back by the
+    /// inverse. The layout of the widget is not affected, so the widget
+    /// should keep its drawing within the area that the transformed
+    /// drawing covers inside its layout, typically by drawing around
+    /// its center. A quarter turned widget, for example, has to draw
+    /// its contents into a box with width and height swapped.
+    ///
+    /// Default: `None`
+    fn transform(&self) -> Option<Transform> { None }
+
+    /// Supposed to return `true` if the widget and its children are
+    /// to be drawn from a render cache.
+    ///
+    /// The UI draws such a widget and its children once into an
+    /// offscreen surface and copies the surface to the window on the
+    /// following exposes until the widget or any of its children asks
+    /// for a repaint. This saves a lot of CPU for complex but mostly
+    /// static parts of the UI like plugin backgrounds.
+    ///
+    /// Default: `false`
+    fn cacheable(&self) -> bool { false }
+
+    /// Supposed to return `true` if the widget is to be drawn from a
+    /// retained surface of its own.
+    ///
+    /// The UI draws such a widget once into an offscreen surface and
+    /// copies the surface to the window on the following exposes, for
+    /// example when a playhead moving over a waveform view exposes
+    /// parts of it. The widget is only drawn again after it has called
+    /// [`invalidate_buffer()`](#method.invalidate_buffer), or when its
+    /// size, the scale factor or the style changes. So a widget whose
+    /// drawing depends on its hover or focus state needs to invalidate
+    /// the buffer itself when the state changes. Unlike
+    /// [`cacheable()`](#method.cacheable) the children of the widget
+    /// are drawn as usual.
+    ///
+    /// Default: `false`
+    fn double_buffered(&self) -> bool { false }
+
+    /// Supposed to return `true` if the widget paints every pixel of
+    /// its layout with opaque colors.
+    ///
+    /// The UI does not draw the widgets drawn before an opaque widget
+    /// whose exposed parts it covers entirely, like the parts of a
+    /// background hidden by an overlay. Widgets with a
+    /// [`transform()`](#method.transform) are never taken as opaque.
+    ///
+    /// Default: `false`
+    fn opaque(&self) -> bool { false }
+
+    /// Supposed to return a cheap hash of the widget's visual state.
+    ///
+    /// If the widget returns a hash, the UI skips repaint requests
+    /// of the widget as long as the hash is the same as when the
+    /// widget has been drawn the last time. This cuts redundant
+    /// drawing when many widgets ask for a repaint at once, like in
+    /// dense meter bridges. The hash must cover everything the
+    /// widget's drawing depends on. The
+    /// [`VisualState`](struct.VisualState.html) is taken into account
+    /// by the UI, so the hash does not need to cover it.
+    ///
+    /// Exposes from the windowing system, like when the window has
+    /// been covered, are always drawn.
+    ///
+    /// Default: `None`, the widget is repainted whenever asked.
+    fn state_hash(&self) -> Option<u64> { None }
+
+    /// Called when the widget has been clicked: the primary button
+    /// has been pressed on the widget and released on it again.
+    ///
+    /// If the pointer is dragged out of the widget and the button is
+    /// released outside, there is no click. Called after the release
+    /// event has been delivered. Insensitive widgets are not clicked.
+    ///
+    /// Default implementation does nothing.
+    fn clicked(&mut self) {}
+
+    /// Called when the widget becomes armed or disarmed, see
+    /// [`is_armed()`](#method.is_armed).
+    ///
+    /// The widget is armed when the primary button is pressed on it
+    /// and disarmed when the pointer leaves it while pressed or the
+    /// button is released. Useful to draw the pressed look of buttons.
+    ///
+    /// Default implementation does nothing.
+    fn pressed_changed(&mut self, _armed: bool) {}
+
     /// Called when the mouse pointer is entering the widget's layout.
     ///
     /// Default implementation does nothing.
@@ -98,6 +405,120 @@ pub trait Widget : DowncastSync {
     /// Default implementation does nothing.
     fn pointer_leave(&mut self) {}
 
+    /// Called when a message sent by
+    /// [`UI::send_message()`](../ui/struct.UI.html#method.send_message)
+    /// is delivered to the widget.
+    ///
+    /// The widget can downcast the message to the types it knows.
+    /// Default implementation drops the message.
+    fn message(&mut self, _msg: Box<dyn Any>) {}
+
+    /// Supposed to return the data to be dragged, if the widget is a
+    /// source of drag and drop.
+    ///
+    /// Called on the pointer motions while the primary button is
+    /// pressed on the widget until data is returned. Then the drag
+    /// and drop starts: the widget is no longer pressed and does not
+    /// receive the further events of the drag. When the button is
+    /// released, the data is dropped on the innermost widget under the
+    /// pointer accepting it, see [`accepts_drop()`](#method.accepts_drop).
+    /// The Escape key cancels the drag and drop.
+    ///
+    /// Default: `None`
+    fn drag_data(&mut self) -> Option<Box<dyn Any>> { None }
+
+    /// Supposed to return `true` if the widget accepts the dragged
+    /// `data` to be dropped on it.
+    ///
+    /// Default: `false`
+    fn accepts_drop(&self, _data: &dyn Any) -> bool { false }
+
+    /// Called when the dragged `data` is dropped on the widget.
+    ///
+    /// Default implementation drops the data.
+    fn dropped(&mut self, _data: Box<dyn Any>) {}
+
+    /// Returns the scroll position of a scrolling widget.
+    ///
+    /// Used by [`UI::snapshot_layout()`](../ui/struct.UI.html#method.snapshot_layout)
+    /// to persist the scroll position. Default implementation returns
+    /// `None`, that is the widget does not scroll.
+    fn scroll_position(&self) -> Option<Coord> { None }
+
+    /// Sets the scroll position of a scrolling widget restored by
+    /// [`UI::restore_layout()`](../ui/struct.UI.html#method.restore_layout).
+    ///
+    /// Default implementation does nothing.
+    fn set_scroll_position(&mut self, _pos: Coord) {}
+
+    /// Called when the scale factor of the UI has changed to
+    /// `scale_factor` by
+    /// [`UI::set_scale_factor()`](../ui/struct.UI.html#method.set_scale_factor).
+    ///
+    /// Widgets caching pixel data, like rendered fonts or images,
+    /// should rebuild their caches. Default implementation does
+    /// nothing.
+    fn scale_changed(&mut self, _scale_factor: f64) {}
+
+    /// Called when the widget is shown after having been hidden.
+    ///
+    /// A widget is hidden if it or one of its ancestors is invisible
+    /// or if a layouter does not show it, like an inactive tab of a
+    /// [`TabLayouter`](../layout/tablayout/struct.TabLayouter.html).
+    /// The notifications are sent by the layouting.
+    ///
+    /// Default implementation does nothing.
+    fn shown(&mut self) {}
+
+    /// Called when the widget is hidden after having been shown.
+    ///
+    /// See [`shown()`](#method.shown). Default implementation does nothing.
+    fn hidden(&mut self) {}
+
+    /// Called when the widget has been registered in the UI.
+    ///
+    /// The root widget is not notified. Default implementation does
+    /// nothing.
+    fn on_added(&mut self) {}
+
+    /// Called right before the widget is exposed for the first time.
+    ///
+    /// The widget is layouted by then and can set up resources for
+    /// drawing. Default implementation does nothing.
+    fn on_realize(&mut self) {}
+
+    /// Called after a layouting has assigned a new size or position
+    /// to the widget.
+    ///
+    /// The widget can allocate its cairo surfaces or text layouts for
+    /// the final size here. Only widgets that are shown are notified.
+    /// Default implementation does nothing.
+    fn on_layout(&mut self) {}
+
+    /// Returns the name of the widget's type.
+    ///
+    /// Used by [`UI::introspect()`](../ui/struct.UI.html#method.introspect).
+    /// The default implementation returns the full path of the type
+    /// and should not be overridden.
+    fn type_name(&self) -> &'static str { std::any::type_name::<Self>() }
+
+    /// Called once per event loop cycle while the widget has frame
+    /// callbacks requested by
+    /// [`UI::request_frame_callbacks()`](../ui/struct.UI.html#method.request_frame_callbacks).
+    ///
+    /// `dt` is the time in seconds since the last call, `0.0` for the
+    /// first one. The widget is repainted after each call. Supposed to
+    /// return true, iff the animation is still going on.
+    ///
+    /// Default implementation does nothing and returns false.
+    fn frame(&mut self, _dt: f64) -> bool { false }
+
+    /// Called when the timer identified by `tag` requested by
+    /// [`request_timer()`](#method.request_timer) has expired.
+    ///
+    /// Default implementation does nothing.
+    fn timer(&mut self, _tag: &'static str) {}
+
     /// Called when the requested reminding time is passed
     ///
     /// Supposed to return true, iff the reminder is still needed
@@ -119,6 +540,72 @@ pub trait Widget : DowncastSync {
         self.stub_mut().needs_repaint = true;
     }
 
+    /// Asks for drawing the retained surface of a
+    /// [`double_buffered()`](#method.double_buffered) widget again
+    /// and repainting the widget.
+    ///
+    /// A mere [`ask_for_repaint()`](#method.ask_for_repaint) of a
+    /// double buffered widget copies the retained surface to the
+    /// window again. Usually not to be reimplemented.
+    /// ```
+    /// # use pugl_sys::*;
+    /// # #[macro_use] extern crate pugl_ui;
+    /// # use pugl_ui::widget::*;
+    /// # #[derive(Default)] struct DummyWidget { stub: WidgetStub }
+    /// # impl Widget for DummyWidget { widget_stub!(); }
+    /// # fn main() {
+    /// let mut widget = DummyWidget::default();
+    /// widget.invalidate_buffer();
+    /// assert!(widget.buffer_invalidated());
+    /// assert!(!widget.buffer_invalidated());
+    /// assert!(widget.needs_repaint());
+    /// # }
+    /// ```
+    fn invalidate_buffer(&mut self) {
+        self.stub_mut().buffer_invalid = true;
+        self.stub_mut().needs_repaint = true;
+    }
+
+    /// Hands the invalidation of the retained surface over to the UI
+    ///
+    /// Only to be called by the UI. Usually not to be reimplemented.
+    fn buffer_invalidated(&mut self) -> bool {
+        std::mem::take(&mut self.stub_mut().buffer_invalid)
+    }
+
+    /// Asks for repainting only the rectangle `rect` of the widget.
+    ///
+    /// The position of `rect` is relative to the widget's
+    /// position. The UI merges the rectangles of all the widgets
+    /// before posting the redisplays, and the widget gets the part to
+    /// be drawn by [`PaintContext::damage()`](struct.PaintContext.html#method.damage).
+    ///
+    /// Usually not to be reimplemented.
+    /// ```
+    /// # use pugl_sys::*;
+    /// # #[macro_use] extern crate pugl_ui;
+    /// # use pugl_ui::widget::*;
+    /// # #[derive(Default)] struct DummyWidget { stub: WidgetStub }
+    /// # impl Widget for DummyWidget { widget_stub!(); }
+    /// # fn main() {
+    /// let mut widget = DummyWidget::default();
+    /// let rect = Layout { pos: Coord { x: 2., y: 3. }, size: Size { w: 4., h: 5. } };
+    /// widget.ask_for_repaint_rect(rect);
+    /// assert_eq!(widget.dirty_rects(), vec![rect]);
+    /// assert!(widget.dirty_rects().is_empty());
+    /// # }
+    /// ```
+    fn ask_for_repaint_rect(&mut self, rect: Layout) {
+        self.stub_mut().dirty_rects.push(rect);
+    }
+
+    /// Hands the rectangles requested to be repainted over to the UI
+    ///
+    /// Only to be called by the UI. Usually not to be reimplemented.
+    fn dirty_rects(&mut self) -> Vec<Layout> {
+        std::mem::take(&mut self.stub_mut().dirty_rects)
+    }
+
     /// The widget can request a reminder after `timeout`
     /// seconds. When the time has passed `reminder_handler() is
     /// called.
@@ -162,6 +649,52 @@ pub trait Widget : DowncastSync {
         self.stub_mut().reminder_request.take()
     }
 
+    /// The widget can request a timer identified by `tag`, which
+    /// calls [`timer()`](#method.timer) after `period` seconds and,
+    /// if `repeating` is `true`, every `period` seconds after that.
+    ///
+    /// Unlike [`request_reminder()`](#method.request_reminder) a
+    /// widget can have several timers running, one per tag. A request
+    /// for a tag that is already running restarts the timer with the
+    /// new period.
+    ///
+    /// Usually not to be reimplemented.
+    /// ```
+    /// # use pugl_sys::*;
+    /// # #[macro_use] extern crate pugl_ui;
+    /// # use pugl_ui::widget::*;
+    /// # #[derive(Default)] struct DummyWidget { stub: WidgetStub }
+    /// # impl Widget for DummyWidget { widget_stub!(); }
+    /// # fn main() {
+    /// let mut widget = DummyWidget::default();
+    /// widget.request_timer("blink", 0.5, true);
+    /// widget.cancel_timer("fade");
+    /// assert_eq!(widget.timer_requests(), vec![
+    ///     TimerRequest::Start { tag: "blink", period: 0.5, repeating: true },
+    ///     TimerRequest::Cancel("fade")
+    /// ]);
+    /// assert!(widget.timer_requests().is_empty());
+    /// # }
+    /// ```
+    fn request_timer(&mut self, tag: &'static str, period: f64, repeating: bool) {
+        self.stub_mut().timer_requests.push(TimerRequest::Start { tag, period, repeating });
+    }
+
+    /// Cancels the timer identified by `tag`, if it is running.
+    ///
+    /// Usually not to be reimplemented.
+    fn cancel_timer(&mut self, tag: &'static str) {
+        self.stub_mut().timer_requests.push(TimerRequest::Cancel(tag));
+    }
+
+    /// Hands the timer requests over to the UI
+    ///
+    /// Only to be called by the UI as it consumes the timer requests.
+    /// Usually not to be reimplemented.
+    fn timer_requests(&mut self) -> Vec<TimerRequest> {
+        std::mem::take(&mut self.stub_mut().timer_requests)
+    }
+
     /// Returns true iff the widget is currently focused.
     ///
     /// Usually not to be reimplemented.
@@ -473,11 +1006,91 @@ pub trait Widget : DowncastSync {
 
     /// Returns true iff the widget is sensitive to user evnets.
     ///
+    /// The UI does not pass events to insensitive widgets, see
+    /// [`UI::set_sensitive()`](../ui/struct.UI.html#method.set_sensitive).
     /// Usually not to be reimplemented.
     fn is_sensitive(&self) -> bool {
         self.stub().sensitive
     }
 
+    /// Sets the widget's sensitivity to `yn`.
+    ///
+    /// Usually not to be reimplemented.
+    /// ```
+    /// # use pugl_sys::*;
+    /// # #[macro_use] extern crate pugl_ui;
+    /// # use pugl_ui::widget::*;
+    /// # #[derive(Default)] struct DummyWidget { stub: WidgetStub }
+    /// # impl Widget for DummyWidget { widget_stub!(); }
+    /// # fn main() {
+    /// let mut widget = DummyWidget::default();
+    /// assert!(widget.is_sensitive());
+    /// widget.set_sensitive(false);
+    /// assert!(!widget.is_sensitive());
+    /// # }
+    /// ```
+    fn set_sensitive(&mut self, yn: bool) {
+        let s = self.stub().sensitive;
+        self.stub_mut().sensitive = yn;
+        if s != yn {
+            self.stub_mut().needs_repaint = true;
+        }
+    }
+
+    /// Returns true iff the widget is visible.
+    ///
+    /// Invisible widgets are skipped by the layouters, are not drawn
+    /// and do not receive events.
+    ///
+    /// Usually not to be reimplemented.
+    /// ```
+    /// # use pugl_sys::*;
+    /// # #[macro_use] extern crate pugl_ui;
+    /// # use pugl_ui::widget::*;
+    /// # #[derive(Default)] struct DummyWidget { stub: WidgetStub }
+    /// # impl Widget for DummyWidget { widget_stub!(); }
+    /// # fn main() {
+    /// let mut widget = DummyWidget::default();
+    /// assert!(widget.is_visible());
+    /// widget.set_visible(false);
+    /// assert!(!widget.is_visible());
+    /// # }
+    /// ```
+    fn is_visible(&self) -> bool {
+        self.stub().visible
+    }
+
+    /// Sets the widget's visibility to `yn`.
+    ///
+    /// The layout is not changed until the next layouting. Use
+    /// [`UI::set_visible()`](../ui/struct.UI.html#method.set_visible)
+    /// to relayout immediately. Usually not to be reimplemented.
+    fn set_visible(&mut self, yn: bool) {
+        if self.stub().visible != yn {
+            self.stub_mut().needs_relayout = true;
+        }
+        self.stub_mut().visible = yn;
+    }
+
+    /// Returns the user's interaction settings.
+    ///
+    /// Widgets are supposed to use them to scale their reactions on
+    /// scroll and drag events, so that the user can tune the feel of
+    /// all the widgets at once by
+    /// [`UI::set_interaction_settings()`](../ui/struct.UI.html#method.set_interaction_settings).
+    ///
+    /// Usually not to be reimplemented.
+    fn interaction_settings(&self) -> InteractionSettings {
+        self.stub().interaction
+    }
+
+    /// Sets the interaction settings.
+    ///
+    /// Only to be called by the UI. Usually not to be reimplemented.
+    fn set_interaction_settings(&mut self, settings: InteractionSettings) {
+        self.stub_mut().interaction = settings;
+    }
+
     /// Returns true iff the widget is currently hovered.
     ///
     /// Usually not to be reimplemented.
@@ -500,6 +1113,77 @@ pub trait Widget : DowncastSync {
         self.stub().hovered
     }
 
+    /// Returns true iff the primary button has been pressed on the
+    /// widget and is still held down.
+    ///
+    /// Usually not to be reimplemented.
+    fn is_pressed(&self) -> bool {
+        self.stub().pressed
+    }
+
+    /// Sets the widget's pressed state to `yn`.
+    ///
+    /// Usually only called by the UI.
+    /// Usually not to be reimplemented.
+    fn set_pressed(&mut self, yn: bool) {
+        if self.stub().pressed != yn {
+            self.stub_mut().pressed = yn;
+            self.stub_mut().needs_repaint = true;
+        }
+    }
+
+    /// Returns true iff the widget is pressed and the pointer is
+    /// over it, so that releasing the primary button clicks it.
+    ///
+    /// Usually not to be reimplemented.
+    fn is_armed(&self) -> bool {
+        self.stub().armed
+    }
+
+    /// Sets the widget's armed state to `yn` and notifies the widget
+    /// by [`pressed_changed()`](#method.pressed_changed) if it changes.
+    ///
+    /// Usually only called by the UI.
+    /// Usually not to be reimplemented.
+    fn set_armed(&mut self, yn: bool) {
+        if self.stub().armed != yn {
+            self.stub_mut().armed = yn;
+            self.stub_mut().needs_repaint = true;
+            self.pressed_changed(yn);
+        }
+    }
+
+    /// Returns the [`VisualState`](struct.VisualState.html) the widget
+    /// is supposed to draw itself in.
+    ///
+    /// The states are maintained by the UI, and the widget is
+    /// repainted when they change.
+    ///
+    /// Usually not to be reimplemented.
+    /// ```
+    /// # use pugl_sys::*;
+    /// # #[macro_use] extern crate pugl_ui;
+    /// # use pugl_ui::widget::*;
+    /// # #[derive(Default)] struct DummyWidget { stub: WidgetStub }
+    /// # impl Widget for DummyWidget { widget_stub!(); }
+    /// # fn main() {
+    /// let mut widget = DummyWidget::default();
+    /// assert!(widget.state().is_normal());
+    /// widget.pointer_enter_wrap();
+    /// widget.set_sensitive(false);
+    /// assert_eq!(widget.state(), VisualState { hovered: true, disabled: true, ..Default::default() });
+    /// # }
+    /// ```
+    fn state(&self) -> VisualState {
+        VisualState {
+            hovered: self.is_hovered(),
+            pressed: self.is_pressed(),
+            armed: self.is_armed(),
+            disabled: !self.is_sensitive(),
+            focused: self.has_focus()
+        }
+    }
+
     /// Returns true iff the widget's Layout is containing `pos`.
     ///
     /// Usually not to be reimplemented.
@@ -613,6 +1297,62 @@ pub trait Widget : DowncastSync {
         self.stub_mut().needs_repaint()
     }
 
+    /// Asks for measuring the widget again at the next layouting,
+    /// for example because its minimal size has changed.
+    ///
+    /// The layouting is not done until the next
+    /// [`UI::do_layout()`](../ui/struct.UI.html#method.do_layout)
+    /// or the next resizing of the window. Usually not to be reimplemented.
+    fn ask_for_relayout(&mut self) {
+        self.stub_mut().needs_relayout = true;
+    }
+
+    /// Asks for measuring the widget again and layouting the widgets
+    /// at the next call of
+    /// [`UI::next_event()`](../ui/struct.UI.html#method.next_event),
+    /// for example because its minimal size has grown like the one of
+    /// a label whose text got longer.
+    ///
+    /// Unlike [`ask_for_relayout()`](#method.ask_for_relayout) the
+    /// layouting is done automatically, and the minimal window size
+    /// is fitted to the new layout if the `UI` is set to, see
+    /// [`UI::set_fit_window_on_resize()`](../ui/struct.UI.html#method.set_fit_window_on_resize).
+    /// Usually not to be reimplemented.
+    /// ```
+    /// # use pugl_sys::*;
+    /// # #[macro_use] extern crate pugl_ui;
+    /// # use pugl_ui::widget::*;
+    /// # #[derive(Default)] struct DummyWidget { stub: WidgetStub }
+    /// # impl Widget for DummyWidget { widget_stub!(); }
+    /// # fn main() {
+    /// let mut widget = DummyWidget::default();
+    /// widget.request_resize();
+    /// assert!(widget.resize_requested());
+    /// assert!(!widget.resize_requested());
+    /// assert!(widget.needs_relayout());
+    /// # }
+    /// ```
+    fn request_resize(&mut self) {
+        self.stub_mut().needs_relayout = true;
+        self.stub_mut().resize_requested = true;
+    }
+
+    /// Returns true iff the widget has requested a resize and resets
+    /// the request.
+    ///
+    /// Only to be called by the UI. Usually not to be reimplemented.
+    fn resize_requested(&mut self) -> bool {
+        std::mem::take(&mut self.stub_mut().resize_requested)
+    }
+
+    /// Returns true iff the widget has asked to be measured again
+    /// and resets the request.
+    ///
+    /// Usually not to be reimplemented.
+    fn needs_relayout(&mut self) -> bool {
+        std::mem::replace(&mut self.stub_mut().needs_relayout, false)
+    }
+
     /// Wrapper for the `pointer_enter()` event function.
     ///
     /// Usually only called by the UI.
@@ -632,9 +1372,128 @@ pub trait Widget : DowncastSync {
         self.ask_for_repaint();
         self.pointer_leave();
     }
+
+    /// Marks the event currently being processed by
+    /// [`event()`](#method.event) as handled, but to be passed on.
+    ///
+    /// If `event()` returns the event, the UI passes it on to the
+    /// ancestors by [`observe_event()`](#method.observe_event) rather
+    /// than by `event()`. Usually not to be reimplemented.
+    fn mark_event_handled(&mut self) {
+        self.stub_mut().event_handled = true;
+    }
+
+    /// Returns true iff the widget has marked the last event as
+    /// handled and resets the mark.
+    ///
+    /// Usually only called by the UI. Usually not to be reimplemented.
+    fn take_event_handled(&mut self) -> bool {
+        std::mem::replace(&mut self.stub_mut().event_handled, false)
+    }
+
+    /// Passes `text` to the UI's clipboard, for example when text is
+    /// copied from a text entry.
+    ///
+    /// The UI takes the text after the current event has been
+    /// processed, see
+    /// [`UI::set_clipboard_text()`](../ui/struct.UI.html#method.set_clipboard_text).
+    ///
+    /// Usually not to be reimplemented.
+    fn copy_to_clipboard(&mut self, text: String) {
+        self.stub_mut().clipboard_request = Some(text);
+    }
+
+    /// Returns the text passed by [`copy_to_clipboard()`](#method.copy_to_clipboard)
+    /// since the last call, if any.
+    ///
+    /// Usually only called by the UI.
+    /// Usually not to be reimplemented.
+    fn clipboard_request(&mut self) -> Option<String> {
+        self.stub_mut().clipboard_request.take()
+    }
+
+    /// Returns true iff a user gesture on the widget is ongoing.
+    ///
+    /// Usually not to be reimplemented.
+    fn in_gesture(&self) -> bool {
+        self.stub().in_gesture
+    }
+
+    /// Returns the gestures begun and ended since the last call in
+    /// the order they happened.
+    ///
+    /// A quick click can begin and end a gesture between two calls,
+    /// so both are reported in that case.
+    ///
+    /// Usually not to be reimplemented.
+    fn gestures(&mut self) -> Vec<Gesture> {
+        std::mem::take(&mut self.stub_mut().gestures)
+    }
+
+    /// Begins a user gesture on the widget.
+    ///
+    /// Usually only called by the UI.
+    /// Usually not to be reimplemented.
+    fn gesture_begin_wrap(&mut self) {
+        self.stub_mut().in_gesture = true;
+        self.stub_mut().gestures.push(Gesture::Begin);
+    }
+
+    /// Ends the ongoing user gesture on the widget.
+    ///
+    /// Usually only called by the UI.
+    /// Usually not to be reimplemented.
+    fn gesture_end_wrap(&mut self) {
+        if self.stub().in_gesture {
+            self.stub_mut().in_gesture = false;
+            self.stub_mut().gestures.push(Gesture::End);
+        }
+    }
 }
 impl_downcast!(sync Widget);
 
+/// The begin or the end of a user gesture on a widget, see
+/// [`Widget::reports_gestures()`](trait.Widget.html#method.reports_gestures)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Gesture {
+    Begin,
+    End
+}
+
+/// A request of a widget to start or cancel a timer, see
+/// [`Widget::request_timer()`](trait.Widget.html#method.request_timer)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimerRequest {
+    Start { tag: &'static str, period: f64, repeating: bool },
+    Cancel(&'static str)
+}
+
+/// The visual state of a widget, see
+/// [`Widget::state()`](trait.Widget.html#method.state)
+///
+/// The states are independent from each other, so a widget can for
+/// example be hovered and focused at the same time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct VisualState {
+    /// The mouse pointer is over the widget
+    pub hovered: bool,
+    /// The primary button has been pressed on the widget and is held down
+    pub pressed: bool,
+    /// The widget is pressed and the pointer is over it
+    pub armed: bool,
+    /// The widget is insensitive to user events
+    pub disabled: bool,
+    /// The widget has the keyboard focus
+    pub focused: bool
+}
+
+impl VisualState {
+    /// Returns `true` iff none of the states applies.
+    pub fn is_normal(&self) -> bool {
+        *self == VisualState::default()
+    }
+}
+
 /// The rectangle the widget is covering
 #[derive(Copy, Clone, Default, Debug, PartialEq)]
 pub struct Layout {
@@ -642,6 +1501,344 @@ pub struct Layout {
     pub size: Size
 }
 
+impl Layout {
+    /// Returns the overlap of `self` and `other`, if they overlap.
+    /// ```
+    /// # use pugl_sys::*;
+    /// # use pugl_ui::widget::*;
+    /// let a = Layout { pos: Coord { x: 0., y: 0. }, size: Size { w: 4., h: 4. } };
+    /// let b = Layout { pos: Coord { x: 2., y: 1. }, size: Size { w: 4., h: 4. } };
+    /// let c = Layout { pos: Coord { x: 5., y: 0. }, size: Size { w: 4., h: 4. } };
+    /// assert_eq!(a.intersection(&b), Some(Layout { pos: Coord { x: 2., y: 1. }, size: Size { w: 2., h: 3. } }));
+    /// assert_eq!(a.intersection(&c), None);
+    /// ```
+    pub fn intersection(&self, other: &Layout) -> Option<Layout> {
+        let left = self.pos.x.max(other.pos.x);
+        let top = self.pos.y.max(other.pos.y);
+        let right = (self.pos.x + self.size.w).min(other.pos.x + other.size.w);
+        let bottom = (self.pos.y + self.size.h).min(other.pos.y + other.size.h);
+        if right <= left || bottom <= top {
+            return None;
+        }
+        Some(Layout { pos: Coord { x: left, y: top }, size: Size { w: right - left, h: bottom - top } })
+    }
+
+    /// Returns `true` iff `other` lies entirely within `self`.
+    /// ```
+    /// # use pugl_sys::*;
+    /// # use pugl_ui::widget::*;
+    /// let a = Layout { pos: Coord { x: 0., y: 0. }, size: Size { w: 4., h: 4. } };
+    /// let b = Layout { pos: Coord { x: 1., y: 1. }, size: Size { w: 3., h: 2. } };
+    /// assert!(a.contains(&b));
+    /// assert!(!b.contains(&a));
+    /// ```
+    pub fn contains(&self, other: &Layout) -> bool {
+        other.pos.x >= self.pos.x && other.pos.y >= self.pos.y
+            && other.pos.x + other.size.w <= self.pos.x + self.size.w
+            && other.pos.y + other.size.h <= self.pos.y + self.size.h
+    }
+
+    /// Returns the smallest rectangle containing `self` and `other`.
+    /// ```
+    /// # use pugl_sys::*;
+    /// # use pugl_ui::widget::*;
+    /// let a = Layout { pos: Coord { x: 0., y: 0. }, size: Size { w: 4., h: 4. } };
+    /// let b = Layout { pos: Coord { x: 2., y: 1. }, size: Size { w: 4., h: 4. } };
+    /// assert_eq!(a.union(&b), Layout { pos: Coord { x: 0., y: 0. }, size: Size { w: 6., h: 5. } });
+    /// ```
+    pub fn union(&self, other: &Layout) -> Layout {
+        let left = self.pos.x.min(other.pos.x);
+        let top = self.pos.y.min(other.pos.y);
+        let right = (self.pos.x + self.size.w).max(other.pos.x + other.size.w);
+        let bottom = (self.pos.y + self.size.h).max(other.pos.y + other.size.h);
+        Layout { pos: Coord { x: left, y: top }, size: Size { w: right - left, h: bottom - top } }
+    }
+}
+
+/// A transform of the drawing of a widget, see
+/// [`Widget::transform()`](trait.Widget.html#method.transform)
+///
+/// The widget is first mirrored and then rotated around its center.
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct Transform {
+    /// The clockwise rotation in radians
+    pub rotation: f64,
+    /// Mirrors the widget at its vertical axis
+    pub mirror_horizontally: bool,
+    /// Mirrors the widget at its horizontal axis
+    pub mirror_vertically: bool
+}
+
+impl Transform {
+    /// Creates a clockwise rotation by `angle` radians.
+    pub fn rotation(angle: f64) -> Transform {
+        Transform { rotation: angle, ..Default::default() }
+    }
+
+    fn mirror(&self) -> (f64, f64) {
+        (if self.mirror_horizontally { -1. } else { 1. },
+         if self.mirror_vertically { -1. } else { 1. })
+    }
+
+    /// Applies the transform around `center` to the `cairo::Context`.
+    pub fn apply(&self, center: Coord, cr: &cairo::Context) {
+        let (sx, sy) = self.mirror();
+        cr.translate(center.x, center.y);
+        cr.rotate(self.rotation);
+        cr.scale(sx, sy);
+        cr.translate(-center.x, -center.y);
+    }
+
+    /// Maps the point `pos` of the untransformed drawing to where it
+    /// appears in the window.
+    /// ```
+    /// # use pugl_sys::*;
+    /// # use pugl_ui::widget::*;
+    /// let t = Transform::rotation(std::f64::consts::FRAC_PI_2);
+    /// let p = t.map(Coord { x: 10., y: 10. }, Coord { x: 20., y: 10. });
+    /// assert!((p.x - 10.).abs() < 1e-9 && (p.y - 20.).abs() < 1e-9);
+    /// ```
+    pub fn map(&self, center: Coord, pos: Coord) -> Coord {
+        let (sx, sy) = self.mirror();
+        let (dx, dy) = ((pos.x - center.x) * sx, (pos.y - center.y) * sy);
+        let (sin, cos) = self.rotation.sin_cos();
+        Coord { x: center.x + dx * cos - dy * sin, y: center.y + dx * sin + dy * cos }
+    }
+
+    /// Maps the point `pos` in the window back to the untransformed
+    /// drawing, the inverse of [`map()`](#method.map).
+    pub fn map_back(&self, center: Coord, pos: Coord) -> Coord {
+        let (sx, sy) = self.mirror();
+        let (dx, dy) = (pos.x - center.x, pos.y - center.y);
+        let (sin, cos) = self.rotation.sin_cos();
+        Coord { x: center.x + (dx * cos + dy * sin) * sx, y: center.y + (dy * cos - dx * sin) * sy }
+    }
+}
+
+/// User preferences of the interaction with widgets
+///
+/// Set for all widgets by
+/// [`UI::set_interaction_settings()`](../ui/struct.UI.html#method.set_interaction_settings)
+/// and queried by widgets by
+/// [`Widget::interaction_settings()`](trait.Widget.html#method.interaction_settings).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct InteractionSettings {
+    /// Multiplier for the steps of scroll events
+    pub scroll_step: f64,
+    /// Number of pixels to drag to change a value by one unit (like
+    /// the whole range of a dial)
+    pub drag_pixels_per_unit: f64,
+    /// Maximum time in seconds between two clicks to be considered a double click
+    pub double_click_time: f64,
+    /// Decay rate per second of the velocity of kinetic scrolling, see
+    /// [`Widget::kinetic_scrolling()`](trait.Widget.html#method.kinetic_scrolling)
+    pub kinetic_friction: f64
+}
+
+impl Default for InteractionSettings {
+    fn default() -> InteractionSettings {
+        InteractionSettings {
+            scroll_step: 1.0,
+            drag_pixels_per_unit: 200.0,
+            double_click_time: 0.4,
+            kinetic_friction: 4.0
+        }
+    }
+}
+
+/// Information passed to [`Widget::exposed()`](trait.Widget.html#method.exposed)
+///
+/// Widgets get all the information about the drawing, apart from the
+/// `cairo::Context`, by the `PaintContext`. Information that might
+/// be useful for future rendering features will be added here, so
+/// that the signature of `Widget::exposed()` can stay stable.
+pub struct PaintContext<'a> {
+    pub(crate) expose: &'a ExposeArea,
+    pub(crate) theme: &'a Theme,
+    pub(crate) style: &'a Style,
+    #[cfg(feature = "cairo-helpers")]
+    pub(crate) assets: &'a Assets,
+    pub(crate) scale_factor: f64,
+    pub(crate) frame_time: f64,
+    pub(crate) previous_frame_time: f64,
+    pub(crate) focus_visible: bool,
+    pub(crate) high_contrast: bool,
+    pub(crate) damage: Layout
+}
+
+impl<'a> PaintContext<'a> {
+    /// The expose area as reported by the windowing system
+    ///
+    /// The area is in window coordinates and not scaled by the scale factor.
+    pub fn expose_area(&self) -> &ExposeArea {
+        self.expose
+    }
+
+    /// The [`Theme`](../theme/struct.Theme.html) of the `UI`
+    pub fn theme(&self) -> &Theme {
+        self.theme
+    }
+
+    /// The image `key` of the `UI`'s [`Assets`](../assets/struct.Assets.html)
+    /// rasterized at the current scale factor
+    ///
+    /// Only available with the feature `cairo-helpers`.
+    #[cfg(feature = "cairo-helpers")]
+    pub fn image(&self, key: &str) -> Option<&cairo::ImageSurface> {
+        self.assets.surface(key, self.scale_factor)
+    }
+
+    /// Draws the image `key` of the `UI`'s [`Assets`](../assets/struct.Assets.html)
+    /// with its upper left corner at `pos`.
+    ///
+    /// Returns `false` if there is no image `key`. Only available with
+    /// the feature `cairo-helpers`.
+    #[cfg(feature = "cairo-helpers")]
+    pub fn paint_image(&self, cr: &cairo::Context, key: &str, pos: Coord) -> bool {
+        self.assets.paint(cr, key, pos, self.scale_factor)
+    }
+
+    /// The skin `key` of the `UI`'s [`Assets`](../assets/struct.Assets.html)
+    ///
+    /// Only available with the feature `svg`.
+    #[cfg(feature = "svg")]
+    pub fn skin(&self, key: &str) -> Option<&crate::skin::SvgSkin> {
+        self.assets.skin(key)
+    }
+
+    /// Draws the panel of the theme into `layout`, see the
+    /// [`panel`](../panel/index.html) module.
+    ///
+    /// A rounded rectangle is filled with the background color of the
+    /// style. A nine-patch falls back to the rounded rectangle, if its
+    /// image is missing in the assets or if the feature `cairo-helpers`
+    /// is disabled.
+    pub fn draw_panel(&self, cr: &cairo::Context, layout: Layout) {
+        #[cfg(feature = "cairo-helpers")]
+        if let Panel::NinePatch { image, insets } = &self.theme.panel {
+            if self.draw_nine_patch(cr, image, *insets, layout) {
+                return;
+            }
+        }
+        let (r, g, b) = self.style.background;
+        cr.save();
+        cr.set_source_rgb(r, g, b);
+        panel::rounded_rect(cr, layout, self.style.border_radius);
+        cr.fill();
+        cr.restore();
+    }
+
+    /// Draws the image `key` of the `UI`'s [`Assets`](../assets/struct.Assets.html)
+    /// as nine-patch sliced by `insets` into `layout`.
+    ///
+    /// Returns `false` if there is no image `key`. Only available with
+    /// the feature `cairo-helpers`.
+    #[cfg(feature = "cairo-helpers")]
+    pub fn draw_nine_patch(&self, cr: &cairo::Context, key: &str, insets: Insets, layout: Layout) -> bool {
+        self.assets.paint_nine_patch(cr, key, insets, layout, self.scale_factor)
+    }
+
+    /// The [`Style`](../theme/struct.Style.html) the widget is supposed to draw itself with
+    ///
+    /// That is the `UI`'s base style with all the overrides of the
+    /// widget and its ancestors applied.
+    pub fn style(&self) -> &Style {
+        self.style
+    }
+
+    /// The scale factor the `UI` applies to the `cairo::Context`
+    ///
+    /// Usually widgets don't need to know it, as the scaling is
+    /// transparent. It can be useful to align lines to device pixels.
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// The time in seconds since the creation of the `UI` when the current frame started
+    pub fn frame_time(&self) -> f64 {
+        self.frame_time
+    }
+
+    /// The time in seconds since the previous frame started, `0.0` in the first frame
+    ///
+    /// Lets widgets advance animations by the actual frame interval,
+    /// see [`UI::set_max_fps()`](../ui/struct.UI.html#method.set_max_fps).
+    pub fn frame_delta(&self) -> f64 {
+        self.frame_time - self.previous_frame_time
+    }
+
+    /// `true` iff focused widgets should draw a focus ring
+    ///
+    /// See [`UI::focus_visible()`](../ui/struct.UI.html#method.focus_visible).
+    pub fn focus_visible(&self) -> bool {
+        self.focus_visible
+    }
+
+    /// `true` iff the `UI` is in high contrast mode
+    ///
+    /// Widgets should then draw with strong contrasts and thick lines.
+    /// See [`UI::set_high_contrast()`](../ui/struct.UI.html#method.set_high_contrast).
+    pub fn high_contrast(&self) -> bool {
+        self.high_contrast
+    }
+
+    /// Draws a focus ring inside the rectangle `(pos, size)` if
+    /// focus rings are to be visible
+    ///
+    /// The ring is drawn according to the theme and respects the high
+    /// contrast mode. Call it from `exposed()` if the widget has the focus.
+    pub fn draw_focus_ring(&self, cr: &cairo::Context, pos: Coord, size: Size) {
+        if self.focus_visible {
+            self.theme.draw_focus_ring(cr, pos, size, self.high_contrast);
+        }
+    }
+
+    /// The part of the widget that actually needs to be redrawn
+    ///
+    /// The position is relative to the widget's position.
+    pub fn damage(&self) -> Layout {
+        self.damage
+    }
+}
+
+/// Services of the `UI` available when a widget is constructed
+///
+/// Passed to the constructing closure of
+/// [`UI::new_widget_with_ctx()`](../ui/struct.UI.html#method.new_widget_with_ctx),
+/// so that widgets can for example measure their texts to determine
+/// their minimal size without creating cairo surfaces of their own.
+pub struct UiContext<'a> {
+    pub(crate) theme: &'a Theme,
+    pub(crate) style: &'a Style,
+    pub(crate) scale_factor: f64,
+    #[cfg(feature = "text")]
+    pub(crate) text_metrics: &'a TextMetrics
+}
+
+impl<'a> UiContext<'a> {
+    /// The [`Theme`](../theme/struct.Theme.html) of the `UI`
+    pub fn theme(&self) -> &Theme {
+        self.theme
+    }
+
+    /// The global [`Style`](../theme/struct.Style.html) of the `UI`
+    pub fn style(&self) -> &Style {
+        self.style
+    }
+
+    /// The scale factor of the `UI`
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// The [`TextMetrics`](../text/struct.TextMetrics.html) of the
+    /// `UI`. Only available with the feature `text`.
+    #[cfg(feature = "text")]
+    pub fn text_metrics(&self) -> &TextMetrics {
+        self.text_metrics
+    }
+}
+
 /// The stub of a widget.
 ///
 /// Contains all the data common to all widgets.
@@ -649,9 +1846,22 @@ pub struct WidgetStub {
     pub layout: Layout,
     has_focus: bool,
     needs_repaint: bool,
+    needs_relayout: bool,
+    resize_requested: bool,
+    buffer_invalid: bool,
     sensitive: bool,
+    visible: bool,
     hovered: bool,
-    reminder_request: Option<f64>
+    pressed: bool,
+    armed: bool,
+    event_handled: bool,
+    interaction: InteractionSettings,
+    dirty_rects: Vec<Layout>,
+    reminder_request: Option<f64>,
+    timer_requests: Vec<TimerRequest>,
+    in_gesture: bool,
+    gestures: Vec<Gesture>,
+    clipboard_request: Option<String>
 }
 
 impl Default for WidgetStub {
@@ -660,9 +1870,22 @@ impl Default for WidgetStub {
             layout: Layout::default(),
             has_focus: false,
             needs_repaint: false,
+            needs_relayout: false,
+            resize_requested: false,
+            buffer_invalid: false,
             sensitive: true,
+            visible: true,
             hovered: false,
-            reminder_request: None
+            pressed: false,
+            armed: false,
+            event_handled: false,
+            interaction: InteractionSettings::default(),
+            dirty_rects: Vec::new(),
+            reminder_request: None,
+            timer_requests: Vec::new(),
+            in_gesture: false,
+            gestures: Vec::new(),
+            clipboard_request: None
         }
     }
 }
@@ -689,6 +1912,7 @@ impl WidgetStub {
 /// deduce and downcast the widget to the actual `T`.
 pub struct WidgetHandle<W: Widget> {
     id: Id,
+    generation: u32,
     widget_type: PhantomData<W>
 }
 
@@ -698,6 +1922,7 @@ impl<W: Widget> Clone for WidgetHandle<W> {
     fn clone(&self) -> WidgetHandle<W> {
         WidgetHandle::<W> {
             id: self.id,
+            generation: self.generation,
             widget_type: PhantomData::<W>
         }
     }
@@ -705,13 +1930,22 @@ impl<W: Widget> Clone for WidgetHandle<W> {
 
 impl<W: Widget> WidgetHandle<W> {
     pub(crate) fn new(id: Id) -> Self {
+        WidgetHandle::with_generation(id, 0)
+    }
+
+    /// Creates a handle to the widget `id` valid as long as the slot
+    /// `id` is in its generation `generation`.
+    pub(crate) fn with_generation(id: Id, generation: u32) -> Self {
         WidgetHandle::<W> {
             id,
+            generation,
             widget_type: PhantomData::<W>
         }
     }
 
     pub(crate) fn id(&self) -> Id { self.id }
+
+    pub(crate) fn generation(&self) -> u32 { self.generation }
 }
 
 /// Implements [`Widget::stub()`](widget/trait.Widget.html#tymethod.stub)
@@ -783,6 +2017,17 @@ mod tests {
         assert!(!widget.needs_repaint());
     }
 
+    #[test]
+    fn widget_set_sensitive_repaint() {
+        let mut widget = DummyWidget::default();
+        widget.set_sensitive(true);
+        assert!(!widget.needs_repaint());
+        widget.set_sensitive(false);
+        assert!(widget.needs_repaint());
+        widget.set_sensitive(true);
+        assert!(widget.needs_repaint());
+    }
+
     #[test]
     fn widget_pointer_enter_repaint() {
         let mut widget = DummyWidget::default();
@@ -796,4 +2041,16 @@ mod tests {
         widget.pointer_leave_wrap();
         assert!(widget.needs_repaint());
     }
+
+    #[test]
+    fn transform_map_back() {
+        let center = Coord { x: 50., y: 25. };
+        let t = Transform { rotation: 0.7, mirror_horizontally: true, mirror_vertically: false };
+        for pos in [Coord { x: 0., y: 0. }, Coord { x: 80., y: 10. }, center].iter() {
+            let back = t.map_back(center, t.map(center, *pos));
+            assert!((back.x - pos.x).abs() < 1e-9 && (back.y - pos.y).abs() < 1e-9);
+        }
+        let mirrored = Transform { mirror_horizontally: true, ..Default::default() };
+        assert_eq!(mirrored.map(center, Coord { x: 60., y: 5. }), Coord { x: 40., y: 5. });
+    }
 }
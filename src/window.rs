@@ -0,0 +1,130 @@
+//! Configuration of the window of a standalone `UI`
+//!
+//! Instead of calling the window setup functions one by one, an
+//! application describes its window by a
+//! [`WindowConfig`](struct.WindowConfig.html) and opens it by
+//! [`UI::open_window()`](../ui/struct.UI.html#method.open_window)
+//! after the layouting:
+//!
+//! ```ignore
+//! ui.do_layout();
+//! ui.open_window(WindowConfig::new("Synth").class_name("synth").resizable(true));
+//! ```
+//!
+//! The title, the resizability, the size and the transient parent are
+//! applied by pugl. The class name, the initial position and the
+//! decoration are hints that `pugl-sys` does not pass on to the
+//! windowing system yet. The `UI` keeps them along with the rest of
+//! the configuration, see
+//! [`UI::window_config()`](../ui/struct.UI.html#method.window_config),
+//! so that platform specific code can apply them to the native window.
+use pugl_sys::*;
+
+use crate::embed::ParentWindow;
+
+/// The properties of a window, see the [module documentation](index.html)
+#[derive(Clone, Debug, PartialEq)]
+pub struct WindowConfig {
+    /// The title shown in the title bar
+    pub title: String,
+    /// The class name by which window managers identify the application
+    pub class_name: Option<String>,
+    /// Whether the user can resize the window
+    pub resizable: bool,
+    /// The initial size, unscaled like the widget sizes; `None` fits
+    /// the window to the layout
+    pub size: Option<Size>,
+    /// The position of the window on the screen, `None` leaves it to
+    /// the windowing system
+    pub position: Option<Coord>,
+    /// The window the window belongs to, like a dialog to its main window
+    pub transient_for: Option<ParentWindow>,
+    /// Whether the window has a title bar and borders
+    pub decorated: bool
+}
+
+impl Default for WindowConfig {
+    fn default() -> WindowConfig {
+        WindowConfig {
+            title: String::new(),
+            class_name: None,
+            resizable: false,
+            size: None,
+            position: None,
+            transient_for: None,
+            decorated: true
+        }
+    }
+}
+
+impl WindowConfig {
+    /// A decorated window titled `title` of the size of the layout,
+    /// which is not resizable
+    pub fn new(title: &str) -> WindowConfig {
+        WindowConfig { title: String::from(title), ..Default::default() }
+    }
+
+    /// Sets the class name of the window.
+    pub fn class_name(mut self, class_name: &str) -> WindowConfig {
+        self.class_name = Some(String::from(class_name));
+        self
+    }
+
+    /// Makes the window resizable or not.
+    pub fn resizable(mut self, yn: bool) -> WindowConfig {
+        self.resizable = yn;
+        self
+    }
+
+    /// Sets the initial size of the window.
+    pub fn size(mut self, size: Size) -> WindowConfig {
+        self.size = Some(size);
+        self
+    }
+
+    /// Sets the initial position of the window.
+    pub fn position(mut self, pos: Coord) -> WindowConfig {
+        self.position = Some(pos);
+        self
+    }
+
+    /// Makes the window transient for the window `parent`.
+    pub fn transient_for(mut self, parent: ParentWindow) -> WindowConfig {
+        self.transient_for = Some(parent);
+        self
+    }
+
+    /// Gives the window a title bar and borders or not.
+    pub fn decorated(mut self, yn: bool) -> WindowConfig {
+        self.decorated = yn;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder() {
+        let config = WindowConfig::new("Synth");
+        assert_eq!(config.title, "Synth");
+        assert!(config.decorated);
+        assert!(!config.resizable);
+        assert_eq!(config.size, None);
+
+        let config = config
+            .class_name("synth")
+            .resizable(true)
+            .size(Size { w: 300., h: 200. })
+            .position(Coord { x: 10., y: 20. })
+            .transient_for(ParentWindow::from_x11(42))
+            .decorated(false);
+        assert_eq!(config.class_name.as_deref(), Some("synth"));
+        assert!(config.resizable);
+        assert_eq!(config.size, Some(Size { w: 300., h: 200. }));
+        assert_eq!(config.position, Some(Coord { x: 10., y: 20. }));
+        assert_eq!(config.transient_for, Some(ParentWindow::from_x11(42)));
+        assert!(!config.decorated);
+    }
+}